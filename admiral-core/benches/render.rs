@@ -0,0 +1,62 @@
+// Benchmarks for the message render path: cargo bench -p admiral-core --bench render
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::HashMap;
+use std::sync::Arc;
+use twitch_irc::message::{IRCMessage, PrivmsgMessage};
+
+use admiral_core::emotes::{parse_message_html, RenderOptions};
+
+fn emote_map(size: usize) -> Arc<HashMap<String, (String, bool)>> {
+    let map = (0..size)
+        .map(|i| {
+            (
+                format!("Emote{}", i),
+                (format!("https://cdn.7tv.app/emote/{}/1x.webp", i), i % 50 == 0),
+            )
+        })
+        .collect();
+    Arc::new(map)
+}
+
+fn privmsg(text: &str) -> PrivmsgMessage {
+    let raw = format!(
+        "@badge-info=;badges=subscriber/12;color=#1E90FF;display-name=BenchUser;emotes=;id=bench-1;room-id=1;tmi-sent-ts=1700000000000;user-id=2 :benchuser!benchuser@benchuser.tmi.twitch.tv PRIVMSG #bench :{}",
+        text
+    );
+    PrivmsgMessage::try_from(IRCMessage::parse(&raw).unwrap()).unwrap()
+}
+
+fn bench_parse_message_html(c: &mut Criterion) {
+    let map = emote_map(2000);
+    let options = RenderOptions::default();
+    let messages = [
+        ("text", privmsg("this chat moves way too fast, how long has the stream been going?")),
+        ("emotes", privmsg("Emote1 Emote2 Emote50 Emote3 Emote4 Emote100 Emote5")),
+        ("mixed", privmsg("no way Emote7 that was insane Emote8 Emote9 clip it")),
+    ];
+    let mut group = c.benchmark_group("parse_message_html");
+    for (name, msg) in &messages {
+        group.bench_with_input(BenchmarkId::from_parameter(name), msg, |b, msg| {
+            b.iter(|| parse_message_html(black_box(msg), &map, &options))
+        });
+    }
+    group.finish();
+}
+
+fn bench_emote_lookup(c: &mut Criterion) {
+    let words: Vec<String> = (0..64)
+        .map(|i| if i % 3 == 0 { format!("Emote{}", i * 7) } else { format!("word{}", i) })
+        .collect();
+    let mut group = c.benchmark_group("emote_map_lookup");
+    for size in [100, 1000, 5000] {
+        let map = emote_map(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &map, |b, map| {
+            b.iter(|| words.iter().filter(|word| map.contains_key(word.as_str())).count())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse_message_html, bench_emote_lookup);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+// badges.rs
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::emotes::source_room_id;
+use crate::markup::escape_html;
+use crate::state::RwLockExt;
+
+/// Scope of the badges every channel shares, like moderator and VIP
+pub const GLOBAL_BADGES: &str = "global";
+
+#[derive(Debug, Clone)]
+pub struct BadgeImage {
+    pub url: String,
+    pub title: String,
+}
+
+// Badge images by scope (a channel id or GLOBAL_BADGES), each keyed "set_id/version".
+// Filled from Helix by the app; channels override global badges of the same set, like
+// custom subscriber badges do.
+static BADGE_MAPS: Lazy<RwLock<HashMap<String, Arc<HashMap<String, BadgeImage>>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+pub fn badge_key(set_id: &str, version: &str) -> String {
+    format!("{}/{}", set_id, version)
+}
+
+pub fn store_badges(scope: &str, badges: HashMap<String, BadgeImage>) {
+    BADGE_MAPS.write_locked().insert(scope.to_string(), Arc::new(badges));
+}
+
+pub fn find_badge(scopes: &[&str], set_id: &str, version: &str) -> Option<BadgeImage> {
+    let maps = BADGE_MAPS.read_locked();
+    let key = badge_key(set_id, version);
+    scopes
+        .iter()
+        .chain(&[GLOBAL_BADGES])
+        .find_map(|scope| maps.get(*scope).and_then(|badges| badges.get(&key)).cloned())
+}
+
+/// Icons for the sender's badges, in the order Twitch lists them. Badges that haven't
+/// been fetched yet are left out. A message shared from another channel looks in that
+/// channel's badges first.
+pub fn badges_html(msg: &PrivmsgMessage) -> String {
+    let scopes: Vec<&str> = source_room_id(msg).into_iter().chain([msg.channel_id.as_str()]).collect();
+    let mut html = String::new();
+    for badge in &msg.badges {
+        let Some(image) = find_badge(&scopes, &badge.name, &badge.version) else {
+            continue;
+        };
+        let title = escape_html(&image.title);
+        html.push_str(&format!(
+            r#"<img class="badge" width="18" height="18" src="{}" alt="{}" title="{}"/>"#,
+            escape_html(&image.url),
+            title,
+            title
+        ));
+    }
+    html
+}
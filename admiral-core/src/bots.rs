@@ -0,0 +1,108 @@
+// bots.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum BotDisplay {
+    Normal,
+    #[default]
+    Dimmed,
+    Collapsed, // Sender line only, click to expand
+    Hidden,
+}
+
+impl BotDisplay {
+    pub const ALL: [BotDisplay; 4] = [
+        BotDisplay::Normal,
+        BotDisplay::Dimmed,
+        BotDisplay::Collapsed,
+        BotDisplay::Hidden,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BotDisplay::Normal => "Normal",
+            BotDisplay::Dimmed => "Dimmed",
+            BotDisplay::Collapsed => "Collapsed",
+            BotDisplay::Hidden => "Hidden",
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        Self::ALL.iter().position(|d| *d == self).unwrap_or(0) as u32
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+
+    pub fn css_class(self) -> Option<&'static str> {
+        match self {
+            BotDisplay::Dimmed => Some("bot-dimmed"),
+            BotDisplay::Collapsed => Some("bot-collapsed"),
+            BotDisplay::Normal | BotDisplay::Hidden => None,
+        }
+    }
+}
+
+// Stored under [bots] in favorites.toml
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BotSettings {
+    pub known_bots: Vec<String>, // Lowercase logins
+    pub display: BotDisplay,
+    pub channel_overrides: HashMap<String, BotDisplay>, // channel login -> display
+}
+
+impl Default for BotSettings {
+    fn default() -> Self {
+        Self {
+            known_bots: [
+                "nightbot",
+                "streamelements",
+                "fossabot",
+                "moobot",
+                "streamlabs",
+                "wizebot",
+                "sery_bot",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+            display: BotDisplay::Dimmed,
+            channel_overrides: HashMap::new(),
+        }
+    }
+}
+
+impl BotSettings {
+    pub fn is_bot(&self, sender_login: &str) -> bool {
+        self.known_bots
+            .iter()
+            .any(|bot| bot.eq_ignore_ascii_case(sender_login))
+    }
+
+    /// How messages from `sender_login` should render in `channel`
+    pub fn display_for(&self, channel: &str, sender_login: &str) -> BotDisplay {
+        if !self.is_bot(sender_login) {
+            return BotDisplay::Normal;
+        }
+        self.channel_overrides
+            .get(&channel.to_lowercase())
+            .copied()
+            .unwrap_or(self.display)
+    }
+}
+
+/// Parses a comma or whitespace separated list of logins
+pub fn parse_bot_list(text: &str) -> Vec<String> {
+    let mut bots: Vec<String> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|s| s.trim().trim_start_matches('@').to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+    bots.sort();
+    bots.dedup();
+    bots
+}
@@ -0,0 +1,66 @@
+// cheermotes.rs
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::markup::escape_html;
+use crate::state::RwLockExt;
+
+/// One cheer prefix, like "Cheer" or a channel's own, with its tiers lowest first
+#[derive(Debug, Clone)]
+pub struct Cheermote {
+    pub prefix: String,
+    pub tiers: Vec<CheerTier>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheerTier {
+    pub min_bits: u64,
+    pub color: String, // "#9c3ee8"
+    pub url: String,   // Animated, for dark backgrounds
+}
+
+// Cheermotes usable in each channel by channel id, keyed by lowercase prefix. Filled from
+// Helix by the app; a channel's set includes the global ones.
+static CHEERMOTES: Lazy<RwLock<HashMap<String, Arc<HashMap<String, Cheermote>>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+pub fn store_cheermotes(channel_id: &str, cheermotes: Vec<Cheermote>) {
+    let by_prefix = cheermotes
+        .into_iter()
+        .map(|mut cheermote| {
+            cheermote.tiers.sort_by_key(|tier| tier.min_bits);
+            (cheermote.prefix.to_lowercase(), cheermote)
+        })
+        .collect();
+    CHEERMOTES.write_locked().insert(channel_id.to_string(), Arc::new(by_prefix));
+}
+
+/// Splits a cheer like "Cheer100" into its prefix and amount
+fn split_cheer(word: &str) -> Option<(&str, u64)> {
+    let digits = word.len() - word.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || digits == word.len() {
+        return None;
+    }
+    let (prefix, amount) = word.split_at(word.len() - digits);
+    Some((prefix, amount.parse().ok().filter(|amount| *amount > 0)?))
+}
+
+/// The image and tinted amount for `word` if it's a cheer in the channel, None for any
+/// other word or before the channel's cheermotes have been fetched
+pub fn cheer_html(channel_id: &str, word: &str) -> Option<String> {
+    let (prefix, amount) = split_cheer(word)?;
+    let cheermotes = CHEERMOTES.read_locked().get(channel_id).cloned()?;
+    let cheermote = cheermotes.get(&prefix.to_lowercase())?;
+    let tier = cheermote.tiers.iter().rev().find(|tier| tier.min_bits <= amount)?;
+    let word_escaped = escape_html(word);
+    Some(format!(
+        r#"<span class="cheer"><img class="cheermote" height="28" src="{}" alt="{}" title="{}"/><span class="cheer-amount" style="color: {};">{}</span></span>"#,
+        escape_html(&tier.url),
+        word_escaped,
+        word_escaped,
+        escape_html(&tier.color),
+        amount
+    ))
+}
@@ -0,0 +1,97 @@
+// emoji.rs
+
+use crate::markup::escape_html;
+
+// Twemoji's artwork as maintained after Twitter dropped it, named by code points
+const EMOJI_BASE_URL: &str = "https://cdn.jsdelivr.net/gh/jdecked/twemoji@15.1.0/assets/svg/";
+
+const VARIATION_SELECTOR: char = '\u{FE0F}';
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+const KEYCAP: char = '\u{20E3}';
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+// Pictographs that are emoji on their own
+fn is_emoji_presentation(c: char) -> bool {
+    matches!(c as u32, 0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF)
+}
+
+// Symbols like © or ↔ that are only emoji when followed by the variation selector
+fn is_text_presentation(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xA9 | 0xAE | 0x203C | 0x2049 | 0x2122 | 0x2139 | 0x2194..=0x21AA | 0x231A..=0x23FF | 0x24C2 | 0x25AA..=0x25FE | 0x3030 | 0x303D | 0x3297 | 0x3299
+    )
+}
+
+fn is_modifier(c: char) -> bool {
+    matches!(c as u32, 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F) || c == VARIATION_SELECTOR || c == KEYCAP
+}
+
+// Length in chars of the emoji sequence starting at chars[0], if one does
+fn emoji_len(chars: &[char]) -> Option<usize> {
+    let first = *chars.first()?;
+    let next = chars.get(1).copied();
+    let mut len = if is_regional_indicator(first) {
+        // Flags are pairs; a lone indicator stays text
+        return next.filter(|c| is_regional_indicator(*c)).map(|_| 2);
+    } else if first.is_ascii_digit() || first == '#' || first == '*' {
+        return (next == Some(VARIATION_SELECTOR) && chars.get(2) == Some(&KEYCAP)).then_some(3);
+    } else if is_emoji_presentation(first) || (is_text_presentation(first) && next == Some(VARIATION_SELECTOR)) {
+        1
+    } else {
+        return None;
+    };
+    loop {
+        match chars.get(len) {
+            Some(c) if is_modifier(*c) => len += 1,
+            // Joined sequences like family or profession emoji render as one picture
+            Some(&ZERO_WIDTH_JOINER) if chars.get(len + 1).is_some_and(|c| is_emoji_presentation(*c)) => len += 2,
+            _ => return Some(len),
+        }
+    }
+}
+
+// Twemoji file name: code points in hex, without the variation selector unless joined
+fn emoji_file_name(sequence: &[char]) -> String {
+    let joined = sequence.contains(&ZERO_WIDTH_JOINER);
+    sequence
+        .iter()
+        .filter(|c| joined || **c != VARIATION_SELECTOR)
+        .map(|c| format!("{:x}", *c as u32))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Appends `text` escaped, with emoji turned into images the size of emotes.
+/// An emoji the image set lacks falls back to the character itself.
+pub fn push_text_with_emoji(html: &mut String, text: &str) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut plain = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let Some(len) = emoji_len(&chars[i..]) else {
+            plain.push(chars[i]);
+            i += 1;
+            continue;
+        };
+        if !plain.is_empty() {
+            html.push_str(&escape_html(&plain));
+            plain.clear();
+        }
+        let sequence = &chars[i..i + len];
+        let emoji: String = sequence.iter().collect();
+        html.push_str(r#"<img class="emoji" width="28" height="28" src=""#);
+        html.push_str(EMOJI_BASE_URL);
+        html.push_str(&emoji_file_name(sequence));
+        html.push_str(r#".svg" alt=""#);
+        html.push_str(&emoji);
+        html.push_str(r#"" onerror="this.replaceWith(this.alt)"/>"#);
+        i += len;
+    }
+    if !plain.is_empty() {
+        html.push_str(&escape_html(&plain));
+    }
+}
@@ -0,0 +1,902 @@
+// emotes.rs
+
+use chrono::Local;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex, RwLock};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use std::collections::HashSet;
+use twitch_irc::message::PrivmsgMessage; // Import the message struct
+use twitch_irc::message::RGBColor;
+use url::Url;
+
+use crate::badges::badges_html;
+use crate::bots::BotDisplay;
+use crate::cheermotes::cheer_html;
+use crate::emoji::push_text_with_emoji;
+use crate::fetch_scheduler::{FetchError, FetchScheduler};
+use crate::hype_chat::PaidMessage;
+use crate::markup::escape_html;
+use crate::network::{http_client, is_offline};
+use crate::profile::cache_dir;
+use crate::rules::matches_highlight_rule;
+use crate::seventv::{fetch_channel_emotes as fetch_seventv_emotes, ImageFile};
+use crate::state::RwLockExt;
+
+pub static MESSAGE_CSS: &str = "
+.message-box {
+    border: 1px solid alpha(#999, 0.3);
+    border-radius: 8px;
+    padding: 8px;
+    margin-bottom: 4px;
+    background-color: alpha(#fff, 0.02);
+    display: block;
+    overflow: hidden;
+    box-sizing: border-box;
+}
+.message-row {
+    background-color: transparent;
+}
+.message-header {
+    display: block;
+    margin-bottom: 4px;
+}
+.message-text {
+    font-size: 12pt;
+    line-height: 28px; /* Consistent line height matching emote size */
+    display: inline;
+}
+.message-text img {
+    display: inline-block;
+    vertical-align: middle;
+    height: 28px;
+    width: auto;
+    margin: 0 2px;
+    max-height: 28px;
+    max-width: none;
+    pointer-events: auto;
+    cursor: pointer;
+    backface-visibility: hidden;
+    transition: transform 0.1s ease;
+}
+
+.message-text img:hover {
+    transform: scale(1.1);
+}
+.dim-label {
+    color: alpha(#aaa, 0.8);
+    font-size: 0.8em;
+}
+.message-content {
+    padding-top: 4px;
+    display: block;
+    word-wrap: break-word;
+    overflow-wrap: break-word;
+}
+.sender {
+    font-weight: bold;
+}
+.timestamp {
+    color: alpha(#aaa, 0.8);
+    font-size: 0.8em;
+}
+.emote-popover-label {
+    font-family: monospace;
+    font-size: 11pt;
+    padding: 4px 8px;
+}
+.flowbox-child {
+    margin: 0;
+    padding: 0;
+}
+";
+
+// --- Global State for Emote Maps and Fetching ---
+static EMOTE_MAPS: Lazy<RwLock<HashMap<String, Arc<HashMap<String, (String, bool)>>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+// Original names of emotes a channel renamed in its 7TV set, mapped to the channel's name
+static EMOTE_ALIASES: Lazy<RwLock<HashMap<String, HashMap<String, String>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+// Maps with the extra keys loose matching needs, built from EMOTE_MAPS on first use
+static MATCHING_MAPS: Lazy<RwLock<HashMap<String, Arc<HashMap<String, (String, bool)>>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+static CASE_INSENSITIVE: AtomicBool = AtomicBool::new(false);
+static MATCH_ORIGINAL_NAMES: AtomicBool = AtomicBool::new(false);
+// When each channel's map was stored, for evicting old ones
+static LAST_FETCH_TIME: Lazy<RwLock<HashMap<String, Instant>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+// Image bytes for emotes shown in native widgets (the WebView has its own HTTP cache)
+static EMOTE_IMAGE_BYTES: Lazy<RwLock<HashMap<String, Arc<[u8]>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+const MAX_CACHED_IMAGES: usize = 200;
+// Channels whose map came from the disk copy while offline, fetched again once online
+static MAPS_FROM_DISK: Lazy<RwLock<HashSet<String>>> = Lazy::new(|| RwLock::new(HashSet::new()));
+// Opening many tabs at once queues their lookups here instead of hitting 7TV all together
+static EMOTE_FETCHES: Lazy<FetchScheduler> = Lazy::new(|| {
+    FetchScheduler::new("7TV", MAX_CONCURRENT_FETCHES, MAX_FETCH_ATTEMPTS, FAILED_FETCH_COOLDOWN, fetch_channel_emotes)
+});
+// Twitch user ids by login, since 7TV only looks channels up by id. Kept on disk so
+// starred channels can be prefetched before their first message arrives.
+static CHANNEL_IDS: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(|| {
+    let ids = std::fs::read_to_string(channel_ids_path())
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    RwLock::new(ids)
+});
+const MAX_CONCURRENT_FETCHES: usize = 3;
+const MAX_FETCH_ATTEMPTS: u32 = 4;
+const FAILED_FETCH_COOLDOWN: Duration = Duration::from_secs(60);
+// Maps older than this are dropped by the cache cleanup and fetched again on use
+const MAX_EMOTE_MAP_AGE: Duration = Duration::from_secs(3600);
+
+// Stored under [emotes] in favorites.toml. Off by default since Twitch itself only
+// matches emote names exactly.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmoteSettings {
+    pub case_insensitive: bool,  // "kekw" shows KEKW
+    pub match_original_names: bool, // A 7TV emote the channel renamed also shows under its original name
+}
+
+/// Applies changed settings; called at startup and from preferences
+pub fn configure_emote_matching(settings: &EmoteSettings) {
+    CASE_INSENSITIVE.store(settings.case_insensitive, Ordering::Relaxed);
+    MATCH_ORIGINAL_NAMES.store(settings.match_original_names, Ordering::Relaxed);
+    MATCHING_MAPS.write_locked().clear();
+}
+
+pub fn store_emote_map(channel_id: &str, emote_map: HashMap<String, (String, bool)>, aliases: HashMap<String, String>) {
+    MATCHING_MAPS.write_locked().remove(channel_id);
+    EMOTE_ALIASES.write_locked().insert(channel_id.to_string(), aliases);
+    EMOTE_MAPS.write_locked().insert(channel_id.to_string(), Arc::new(emote_map));
+    LAST_FETCH_TIME.write_locked().insert(channel_id.to_string(), Instant::now());
+}
+
+fn remove_emote_map(channel_id: &str) {
+    EMOTE_MAPS.write_locked().remove(channel_id);
+    EMOTE_ALIASES.write_locked().remove(channel_id);
+    MATCHING_MAPS.write_locked().remove(channel_id);
+}
+
+// The exact map plus lowercase and original-name keys, none of which shadow an exact name
+fn build_matching_map(channel_id: &str, exact: &HashMap<String, (String, bool)>) -> HashMap<String, (String, bool)> {
+    let mut map = exact.clone();
+    if MATCH_ORIGINAL_NAMES.load(Ordering::Relaxed) {
+        if let Some(aliases) = EMOTE_ALIASES.read_locked().get(channel_id) {
+            for (original, alias) in aliases {
+                if let Some(emote) = exact.get(alias) {
+                    map.entry(original.clone()).or_insert_with(|| emote.clone());
+                }
+            }
+        }
+    }
+    if CASE_INSENSITIVE.load(Ordering::Relaxed) {
+        let names: Vec<String> = map.keys().cloned().collect();
+        for name in names {
+            let lowercase = name.to_lowercase();
+            if !map.contains_key(&lowercase) {
+                let emote = map[&name].clone();
+                map.insert(lowercase, emote);
+            }
+        }
+    }
+    map
+}
+
+/// Looks `word` up the way the matching settings ask for
+pub fn find_emote<'a>(emote_map: &'a HashMap<String, (String, bool)>, word: &str) -> Option<&'a (String, bool)> {
+    emote_map.get(word).or_else(|| {
+        if CASE_INSENSITIVE.load(Ordering::Relaxed) {
+            emote_map.get(&word.to_lowercase())
+        } else {
+            None
+        }
+    })
+}
+
+pub fn cleanup_emote_cache() {
+    cleanup_emote_cache_at(Instant::now());
+}
+
+/// `cleanup_emote_cache` as if run at `now`, so expiry can be checked without waiting
+pub fn cleanup_emote_cache_at(now: Instant) {
+    let mut last_fetch = LAST_FETCH_TIME.write_locked();
+
+    // Collect channels to remove based on time
+    let mut channels_to_remove: Vec<String> = last_fetch
+        .iter()
+        .filter_map(|(channel_id, time)| {
+            if now.duration_since(*time) >= MAX_EMOTE_MAP_AGE {
+                Some(channel_id.clone())
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    // Also check total emote count and remove oldest entries if exceeding limit
+    const MAX_TOTAL_EMOTES: usize = 5000;
+    let maps_read = EMOTE_MAPS.read_locked();
+    let total_emotes: usize = maps_read.values().map(|map| map.len()).sum();
+    drop(maps_read);
+
+    if total_emotes > MAX_TOTAL_EMOTES {
+        println!(
+            "Total emote count {} exceeds limit {}, pruning oldest entries",
+            total_emotes, MAX_TOTAL_EMOTES
+        );
+
+        // Sort channels by last fetch time and remove oldest ones
+        let mut channels_by_time: Vec<(String, Instant)> = last_fetch
+            .iter()
+            .map(|(id, time)| (id.clone(), *time))
+            .collect();
+
+        channels_by_time.sort_by_key(|(_, time)| *time);
+
+        // Remove oldest channels until we're under the limit
+        let mut removed_count = 0;
+        for (channel_id, _) in channels_by_time {
+            if total_emotes - removed_count <= MAX_TOTAL_EMOTES * 3 / 4 {
+                // Stop when we're at 75% of limit to avoid frequent cleanup
+                break;
+            }
+            channels_to_remove.push(channel_id.clone());
+            removed_count += EMOTE_MAPS
+                .read_locked()
+                .get(&channel_id)
+                .map(|m| m.len())
+                .unwrap_or(0);
+        }
+    }
+
+    // Remove from all caches
+    for channel_id in channels_to_remove {
+        last_fetch.remove(&channel_id);
+        remove_emote_map(&channel_id);
+        println!("Removed emote data for channel: {}", channel_id);
+    }
+
+    // Log final statistics
+    let remaining_channels = last_fetch.len();
+    let remaining_emotes: usize = EMOTE_MAPS
+        .read_locked()
+        .values()
+        .map(|map| map.len())
+        .sum();
+    println!(
+        "Cleaned up cache, {} channels and {} emotes remaining.",
+        remaining_channels, remaining_emotes
+    );
+}
+
+pub fn cleanup_media_file_cache() {
+    // No local files to clean now.
+    println!("No local emote cache to clean.");
+}
+
+/// The channel a message was first sent in, when it's shared into this one from another
+/// channel during a shared chat session
+pub fn source_room_id(msg: &PrivmsgMessage) -> Option<&str> {
+    msg.source
+        .tags
+        .0
+        .get("source-room-id")
+        .and_then(|value| value.as_deref())
+        .filter(|room_id| !room_id.is_empty() && *room_id != msg.channel_id)
+}
+
+/// Emotes to render `msg` with: its source channel's for a shared message, fetched on
+/// first use, and `channel_map` otherwise or until they arrive. Twitch emotes need no
+/// map, they come with the message.
+pub fn emote_map_for(
+    msg: &PrivmsgMessage,
+    channel_map: &Arc<HashMap<String, (String, bool)>>,
+) -> Arc<HashMap<String, (String, bool)>> {
+    let Some(room_id) = source_room_id(msg) else {
+        return Arc::clone(channel_map);
+    };
+    let source_map = get_emote_map(room_id);
+    if source_map.is_empty() {
+        Arc::clone(channel_map)
+    } else {
+        source_map
+    }
+}
+
+// --- Emote Map Retrieval (Uses Remote URLs) ---
+pub fn get_emote_map(channel_id: &str) -> Arc<HashMap<String, (String, bool)>> {
+    let exact = EMOTE_MAPS.read_locked().get(channel_id).cloned();
+    let Some(exact) = exact else {
+        EMOTE_FETCHES.request(channel_id);
+        return Arc::new(HashMap::new());
+    };
+    if !CASE_INSENSITIVE.load(Ordering::Relaxed) && !MATCH_ORIGINAL_NAMES.load(Ordering::Relaxed) {
+        return exact;
+    }
+    if let Some(map) = MATCHING_MAPS.read_locked().get(channel_id) {
+        return Arc::clone(map);
+    }
+    let map = Arc::new(build_matching_map(channel_id, &exact));
+    MATCHING_MAPS.write_locked().insert(channel_id.to_string(), Arc::clone(&map));
+    map
+}
+
+/// Downloads an emote image for use outside the WebView. Blocking, call from a worker thread.
+pub fn load_emote_image_bytes(url: &str) -> Option<Arc<[u8]>> {
+    if let Some(bytes) = EMOTE_IMAGE_BYTES.read_locked().get(url) {
+        return Some(bytes.clone());
+    }
+    let response = http_client()
+        .get(url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .ok()?;
+    if !response.status().is_success() {
+        eprintln!("Failed to load emote image {}: status {}", url, response.status());
+        return None;
+    }
+    let bytes: Arc<[u8]> = Arc::from(&response.bytes().ok()?[..]);
+    let mut cache = EMOTE_IMAGE_BYTES.write_locked();
+    if cache.len() >= MAX_CACHED_IMAGES {
+        cache.clear();
+    }
+    cache.insert(url.to_string(), bytes.clone());
+    Some(bytes)
+}
+
+// --- Background Emote Fetching (Updates In-Memory Map) ---
+// Runs on the scheduler's worker threads
+fn fetch_channel_emotes(channel_id: &str) -> Result<(), FetchError> {
+    let result = if is_offline() {
+        Err(FetchError::fatal("offline"))
+    } else {
+        download_emote_urls(channel_id)
+    };
+    match result {
+        Ok((remote_emote_map, aliases)) => {
+            save_emote_map(channel_id, &remote_emote_map);
+            MAPS_FROM_DISK.write_locked().remove(channel_id);
+            // Store the fetched map in the global in-memory cache
+            store_emote_map(channel_id, remote_emote_map, aliases);
+            Ok(())
+        }
+        Err(e) => {
+            // The last map saved for the channel is better than plain text while retrying
+            if !EMOTE_MAPS.read_locked().contains_key(channel_id) {
+                if let Some(saved_map) = load_saved_emote_map(channel_id) {
+                    println!("Using {} saved emotes for channel {}", saved_map.len(), channel_id);
+                    MAPS_FROM_DISK.write_locked().insert(channel_id.to_string());
+                    store_emote_map(channel_id, saved_map, HashMap::new());
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+// --- Disk Copies of Emote Maps (Used While Offline) ---
+fn saved_emote_map_path(channel_id: &str) -> std::path::PathBuf {
+    cache_dir().join("emotes").join(format!("{}.json", channel_id))
+}
+
+fn save_emote_map(channel_id: &str, emote_map: &HashMap<String, (String, bool)>) {
+    let path = saved_emote_map_path(channel_id);
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            eprintln!("Failed to create emote cache directory: {}", e);
+            return;
+        }
+    }
+    match serde_json::to_string(emote_map) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                eprintln!("Failed to save emotes for channel {}: {}", channel_id, e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize emotes for channel {}: {}", channel_id, e),
+    }
+}
+
+fn load_saved_emote_map(channel_id: &str) -> Option<HashMap<String, (String, bool)>> {
+    let json = std::fs::read_to_string(saved_emote_map_path(channel_id)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn channel_ids_path() -> std::path::PathBuf {
+    cache_dir().join("emotes").join("channel_ids.json")
+}
+
+/// Records which user id a channel has, as seen on its messages
+pub fn remember_channel_id(login: &str, channel_id: &str) {
+    if CHANNEL_IDS.read_locked().get(login).map(String::as_str) == Some(channel_id) {
+        return;
+    }
+    let mut ids = CHANNEL_IDS.write_locked();
+    ids.insert(login.to_string(), channel_id.to_string());
+    let path = channel_ids_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match serde_json::to_string(&*ids) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                eprintln!("Failed to save channel ids: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize channel ids: {}", e),
+    }
+}
+
+/// Queues emote lookups for `logins` whose id is known and whose map isn't loaded yet.
+/// Returns the logins with no known id.
+pub fn prefetch_emotes(logins: &[String]) -> Vec<String> {
+    let mut unknown = Vec::new();
+    for login in logins {
+        let channel_id = CHANNEL_IDS.read_locked().get(login).cloned();
+        match channel_id {
+            Some(channel_id) => {
+                if !EMOTE_MAPS.read_locked().contains_key(&channel_id) {
+                    EMOTE_FETCHES.request(&channel_id);
+                }
+            }
+            None => unknown.push(login.clone()),
+        }
+    }
+    unknown
+}
+
+/// Last seen user id of the channel `login`
+pub fn known_channel_id(login: &str) -> Option<String> {
+    CHANNEL_IDS.read_locked().get(login).cloned()
+}
+
+/// Looks the channel's emotes up again even if that happened or failed recently.
+/// The loaded ones stay in use until the new ones arrive.
+pub fn refresh_emotes(channel_id: &str) -> bool {
+    EMOTE_FETCHES.request_now(channel_id)
+}
+
+// What's loaded for one channel, for "my emote doesn't show" reports
+pub struct EmoteCacheInfo {
+    pub seventv: usize,
+    pub zero_width: usize,
+    pub loaded_at: Option<Instant>,
+    pub from_disk: bool,
+    pub fetching: bool,
+    pub total_channels: usize,
+    pub total_emotes: usize,
+}
+
+pub fn emote_cache_info(channel_id: &str) -> EmoteCacheInfo {
+    let maps = EMOTE_MAPS.read_locked();
+    let map = maps.get(channel_id);
+    EmoteCacheInfo {
+        seventv: map.map(|map| map.len()).unwrap_or(0),
+        zero_width: map.map(|map| map.values().filter(|(_, zero_width)| *zero_width).count()).unwrap_or(0),
+        loaded_at: LAST_FETCH_TIME.read_locked().get(channel_id).copied(),
+        from_disk: MAPS_FROM_DISK.read_locked().contains(channel_id),
+        fetching: EMOTE_FETCHES.is_scheduled(channel_id),
+        total_channels: maps.len(),
+        total_emotes: maps.values().map(|map| map.len()).sum(),
+    }
+}
+
+/// Drops maps that were loaded from disk so the next message fetches them fresh;
+/// called when the network comes back
+pub fn forget_saved_emote_maps() {
+    EMOTE_FETCHES.clear_failures();
+    let channels: Vec<String> = MAPS_FROM_DISK.write_locked().drain().collect();
+    let mut last_fetch = LAST_FETCH_TIME.write_locked();
+    for channel_id in channels {
+        remove_emote_map(&channel_id);
+        last_fetch.remove(&channel_id);
+    }
+}
+
+// --- Download Logic (Fetches Remote URLs) ---
+// Also returns the original names of emotes the channel renamed, keyed to the new names
+pub fn download_emote_urls(
+    channel_id: &str,
+) -> Result<(HashMap<String, (String, bool)>, HashMap<String, String>), FetchError> {
+    let active_emotes = fetch_seventv_emotes(channel_id)?;
+
+    let mut remote_emote_map = HashMap::new();
+    let mut aliases = HashMap::new();
+
+    if active_emotes.is_empty() {
+        eprintln!(
+            "WARNING: Channel {} has no emote set configured",
+            channel_id
+        );
+    }
+    for active_emote in active_emotes {
+        if let Some(emote_data) = &active_emote.data {
+            if let Some(host_info) = &emote_data.host {
+                if host_info.url.trim().is_empty() {
+                    eprintln!(
+                        "WARNING: Emote '{}' has empty host URL, skipping",
+                        active_emote.name
+                    );
+                    continue;
+                }
+                let file_opt = find_best_image_file(&host_info.files);
+                if let Some(file_to_use) = file_opt {
+                    // Construct the full URL for the specific file
+                    let base_emote_url = host_info
+                        .url
+                        .trim_start_matches("https://")
+                        .trim_start_matches("http://")
+                        .trim_start_matches("//");
+                    let emote_remote_url =
+                        format!("https://{}/{}", base_emote_url, file_to_use.name);
+
+                    // Validate the constructed URL
+                    if let Err(e) = validate_emote_url(&emote_remote_url, &active_emote.name) {
+                        eprintln!("ERROR: Failed to validate emote URL: {}", e);
+                        continue;
+                    }
+
+                    let is_zero_width = emote_data.flags.unwrap_or(0) & 256 != 0;
+                    if let Some(original) = emote_data.name.as_ref().filter(|name| **name != active_emote.name) {
+                        aliases.insert(original.clone(), active_emote.name.clone());
+                    }
+                    remote_emote_map
+                        .insert(active_emote.name, (emote_remote_url, is_zero_width));
+                } else {
+                    eprintln!("WARNING: Emote '{}' has no suitable image file (available files: {:?}), skipping",
+                        active_emote.name, host_info.files.iter().map(|f| &f.name).collect::<Vec<_>>());
+                }
+            } else {
+                eprintln!(
+                    "WARNING: Emote '{}' has no host information, skipping",
+                    active_emote.name
+                );
+            }
+        } else {
+            eprintln!(
+                "WARNING: Emote '{}' has no data, skipping",
+                active_emote.name
+            );
+        }
+    }
+
+    println!(
+        "Successfully loaded {} emotes for channel {}",
+        remote_emote_map.len(),
+        channel_id
+    );
+    Ok((remote_emote_map, aliases))
+}
+
+// --- Helper Functions ---
+
+/// Validates a URL and returns an error description if invalid
+fn validate_emote_url(url_str: &str, emote_name: &str) -> Result<(), String> {
+    // Check if URL is empty
+    if url_str.trim().is_empty() {
+        return Err(format!("URL is empty for emote '{}'", emote_name));
+    }
+
+    // Check if it starts with https://
+    if !url_str.starts_with("https://") {
+        return Err(format!(
+            "URL for emote '{}' does not start with https://: {}",
+            emote_name, url_str
+        ));
+    }
+
+    // Try to parse as URL
+    let parsed_url = Url::parse(url_str).map_err(|e| {
+        format!(
+            "Failed to parse URL for emote '{}': {} - URL was: {}",
+            emote_name, e, url_str
+        )
+    })?;
+
+    // Check if scheme is https
+    if parsed_url.scheme() != "https" {
+        return Err(format!(
+            "URL for emote '{}' does not use HTTPS scheme: {}",
+            emote_name,
+            parsed_url.scheme()
+        ));
+    }
+
+    // Check if host exists
+    if parsed_url.host().is_none() {
+        return Err(format!(
+            "URL for emote '{}' is missing host: {}",
+            emote_name, url_str
+        ));
+    }
+
+    // Check if host is valid (not empty, has a dot)
+    let host = parsed_url.host_str().unwrap();
+    if !host.contains('.') {
+        return Err(format!(
+            "URL for emote '{}' has invalid host: {}",
+            emote_name, host
+        ));
+    }
+
+    // Check if path exists
+    if parsed_url.path().is_empty() {
+        return Err(format!(
+            "URL for emote '{}' is missing file path: {}",
+            emote_name, url_str
+        ));
+    }
+
+    Ok(())
+}
+
+fn find_best_image_file(files: &[ImageFile]) -> Option<&ImageFile> {
+    // Prioritize 1x versions, then prefer GIF for animation, then PNG for quality, then first available
+    if let Some(file) = files
+        .iter()
+        .find(|f| f.name.contains("1x") && f.format.eq_ignore_ascii_case("gif"))
+    {
+        return Some(file);
+    }
+    if let Some(file) = files
+        .iter()
+        .find(|f| f.name.contains("1x") && f.format.eq_ignore_ascii_case("png"))
+    {
+        return Some(file);
+    }
+    if let Some(file) = files.iter().find(|f| f.name.contains("1x")) {
+        return Some(file);
+    }
+    // If no 1x found, look for any GIF
+    if let Some(file) = files.iter().find(|f| f.format.eq_ignore_ascii_case("gif")) {
+        return Some(file);
+    }
+    // Otherwise, take the first one (could prioritize PNG over others)
+    files.first()
+}
+
+fn rgb_to_hex(color: &RGBColor) -> String {
+    let mut r = color.r as f32 / 255.0;
+    let mut g = color.g as f32 / 255.0;
+    let mut b = color.b as f32 / 255.0;
+    let luminance = 0.299 * r + 0.587 * g + 0.114 * b;
+    if luminance < 0.3 {
+        let boost = 0.3 / (luminance + 0.001);
+        r *= boost;
+        g *= boost;
+        b *= boost;
+    }
+    let avg = (r + g + b) / 3.0;
+    let vibrancy_limit = 0.7;
+    r = avg + (r - avg) * vibrancy_limit;
+    g = avg + (g - avg) * vibrancy_limit;
+    b = avg + (b - avg) * vibrancy_limit;
+    let r = (r.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let g = (g.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let b = (b.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+// Where an emote or badge comes from, named in its tooltip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmoteProvider {
+    Twitch,
+    SevenTv,
+}
+
+impl EmoteProvider {
+    pub fn label(self) -> &'static str {
+        match self {
+            EmoteProvider::Twitch => "Twitch",
+            EmoteProvider::SevenTv => "7TV",
+        }
+    }
+}
+
+// "KEKW · 7TV emote"
+fn emote_tooltip(name: &str, provider: EmoteProvider, zero_width: bool) -> String {
+    let kind = if zero_width { "zero-width emote" } else { "emote" };
+    format!("{} · {} {}", name, provider.label(), kind)
+}
+
+pub fn twitch_emote_url(id: &str) -> String {
+    format!("https://static-cdn.jtvnw.net/emoticons/v2/{}/default/dark/1.0", id)
+}
+
+// Per-message presentation decided by the caller before rendering
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    pub bot_display: BotDisplay,
+    pub enlarge_emote_only: bool,
+    pub emoji_images: bool,
+}
+
+// --- Parse Message to HTML (Updated to use remote URLs) ---
+pub fn parse_message_html(
+    msg: &PrivmsgMessage,
+    emote_map: &Arc<HashMap<String, (String, bool)>>,
+    options: &RenderOptions,
+) -> String {
+    let sender_name_escaped = escape_html(&msg.sender.name);
+    let sender_login_escaped = escape_html(&msg.sender.login);
+    let timestamp = msg
+        .server_timestamp
+        .with_timezone(&Local)
+        .format("%-I:%M:%S %p")
+        .to_string();
+    let timestamp_escaped = escape_html(&timestamp);
+    // Full date in the tooltip, since scrollback can span days
+    let full_date = msg
+        .server_timestamp
+        .with_timezone(&Local)
+        .format("%A, %B %-d, %Y %-I:%M:%S %p")
+        .to_string();
+    let full_date_escaped = escape_html(&full_date);
+
+    let badges = badges_html(msg);
+    let sender_color_html = if let Some(color) = &msg.name_color {
+        let color_hex = rgb_to_hex(color);
+        format!(
+            r#"{}<span class="sender" data-login="{}" style="color: {};">{}</span>"#,
+            badges, sender_login_escaped, color_hex, sender_name_escaped
+        )
+    } else {
+        format!(r#"{}<span class="sender" data-login="{}">{}</span>"#, badges, sender_login_escaped, sender_name_escaped)
+    };
+
+    fn emit_img(html: &mut String, name: &str, url: &str, provider: EmoteProvider, zero_width: bool) {
+        let emote_name_escaped = escape_html(name);
+        let remote_url_escaped = escape_html(url);
+        html.push_str(r#"<img width="28" height="28" src=""#);
+        html.push_str(&remote_url_escaped);
+        html.push_str(r#"" alt=":"#);
+        html.push_str(&emote_name_escaped);
+        html.push_str(r#":" title=""#);
+        html.push_str(&escape_html(&emote_tooltip(name, provider, zero_width)));
+        html.push_str(r#"" data-provider=""#);
+        html.push_str(provider.label());
+        html.push_str(r#"" crossorigin="anonymous"/>"#);
+    }
+
+    fn emit_emote_stack(
+        html: &mut String,
+        base_name: &str,
+        base_url: &str,
+        base_provider: EmoteProvider,
+        overlays: &[(&str, &str)],
+    ) {
+        html.push_str(r#"<span class="emote-stack">"#);
+        emit_img(html, base_name, base_url, base_provider, false);
+        for (name, url) in overlays {
+            let emote_name_escaped = escape_html(name);
+            let url_escaped = escape_html(url);
+            html.push_str(r#"<img class="emote-overlay" height="28" src=""#);
+            html.push_str(&url_escaped);
+            html.push_str(r#"" alt=":"#);
+            html.push_str(&emote_name_escaped);
+            html.push_str(r#":" title=""#);
+            html.push_str(&escape_html(&emote_tooltip(name, EmoteProvider::SevenTv, true)));
+            html.push_str(r#"" data-provider=""#);
+            html.push_str(EmoteProvider::SevenTv.label());
+            html.push_str(r#"" crossorigin="anonymous"/>"#);
+        }
+        html.push_str(r#"</span>"#);
+    }
+
+    // Twitch marks its own emotes in the message tags; those win over a 7TV emote of the same name
+    let twitch_emotes: HashMap<&str, &str> = msg
+        .emotes
+        .iter()
+        .map(|emote| (emote.code.as_str(), emote.id.as_str()))
+        .collect();
+    let lookup = |word: &str| -> Option<(String, bool, EmoteProvider)> {
+        if let Some(id) = twitch_emotes.get(word) {
+            return Some((twitch_emote_url(id), false, EmoteProvider::Twitch));
+        }
+        find_emote(emote_map, word).map(|(url, zero_width)| (url.clone(), *zero_width, EmoteProvider::SevenTv))
+    };
+
+    let mut html_content = String::with_capacity(msg.message_text.len() * 2);
+    let words: Vec<&str> = msg.message_text.split_whitespace().collect();
+    let mut i = 0;
+    let mut first = true;
+
+    while i < words.len() {
+        let word = words[i];
+
+        // Cheers only count in messages that actually carry bits
+        if let Some(cheer) = msg.bits.and_then(|_| cheer_html(&msg.channel_id, word)) {
+            if !first {
+                html_content.push(' ');
+            }
+            html_content.push_str(&cheer);
+            first = false;
+        } else if let Some((url, is_zw, provider)) = lookup(word) {
+            if is_zw {
+                if !first {
+                    html_content.push(' ');
+                }
+                emit_img(&mut html_content, word, &url, provider, true);
+                first = false;
+            } else {
+                if !first {
+                    html_content.push(' ');
+                }
+                let mut overlays: Vec<(&str, &str)> = Vec::new();
+                while i + 1 < words.len() {
+                    if let Some((overlay_url, true)) = find_emote(emote_map, words[i + 1]) {
+                        overlays.push((words[i + 1], overlay_url));
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                if overlays.is_empty() {
+                    emit_img(&mut html_content, word, &url, provider, false);
+                } else {
+                    emit_emote_stack(&mut html_content, word, &url, provider, &overlays);
+                }
+                first = false;
+            }
+        } else {
+            if !first {
+                html_content.push(' ');
+            }
+            if word.len() > 1 && word.starts_with('@') {
+                html_content.push_str(r#"<span class="mention">"#);
+                html_content.push_str(&escape_html(word));
+                html_content.push_str("</span>");
+            } else if options.emoji_images {
+                push_text_with_emoji(&mut html_content, word);
+            } else {
+                html_content.push_str(&escape_html(word));
+            }
+            first = false;
+        }
+
+        i += 1;
+    }
+
+    let mut box_classes = match options.bot_display.css_class() {
+        Some(bot_class) => format!("message-box bot-message {}", bot_class),
+        None => "message-box".to_string(),
+    };
+    let highlighted = msg
+        .source
+        .tags
+        .0
+        .get("msg-id")
+        .and_then(|value| value.as_deref())
+        == Some("highlighted-message")
+        || matches_highlight_rule(&msg.message_text);
+    if highlighted {
+        box_classes.push_str(" highlighted");
+    }
+    // Only whole-message emotes count, a single word of text keeps normal size
+    if options.enlarge_emote_only
+        && !words.is_empty()
+        && words.iter().all(|word| lookup(word).is_some())
+    {
+        box_classes.push_str(" emote-only");
+    }
+    // Paid messages get their tier's color band, and the page pins them until `data-paid-until`
+    let mut paid_attrs = String::new();
+    let mut paid_html = String::new();
+    if let Some(paid) = PaidMessage::from_message(msg) {
+        box_classes.push_str(&format!(" paid-message paid-level-{}", paid.level));
+        paid_attrs = format!(r#" data-paid-until="{}""#, paid.pinned_until().timestamp_millis());
+        paid_html = format!(r#"<span class="paid-amount">{}</span> "#, escape_html(&paid.formatted_amount()));
+    }
+
+    format!(
+        r#"<div class="{}" data-msg-id="{}" data-sent-at="{}"{}><div class="message-header">{} {}<span class="timestamp" title="{}">{}</span></div><div class="message-content"><span class="message-text">{}</span></div></div>"#,
+        box_classes, escape_html(&msg.message_id), msg.server_timestamp.timestamp_millis(), paid_attrs, sender_color_html, paid_html, full_date_escaped, timestamp_escaped, html_content
+    )
+}
@@ -0,0 +1,189 @@
+// fetch_scheduler.rs
+
+use once_cell::sync::Lazy;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::state::MutexExt;
+
+// Gap kept between two requests to the same host, across all schedulers
+const MIN_HOST_INTERVAL: Duration = Duration::from_millis(250);
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+static HOST_NEXT_SLOT: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn host_of(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Blocks until `url`'s host may be contacted again. Call right before each request.
+pub fn wait_for_host(url: &str) {
+    let host = host_of(url);
+    let wait = {
+        let mut slots = HOST_NEXT_SLOT.locked();
+        let now = Instant::now();
+        let slot = slots.get(&host).copied().unwrap_or(now).max(now);
+        slots.insert(host, slot + MIN_HOST_INTERVAL);
+        slot - now
+    };
+    if !wait.is_zero() {
+        thread::sleep(wait);
+    }
+}
+
+/// Holds back every request to `url`'s host for `delay`, e.g. after a 429
+pub fn back_off_host(url: &str, delay: Duration) {
+    let until = Instant::now() + delay;
+    let mut slots = HOST_NEXT_SLOT.locked();
+    let slot = slots.entry(host_of(url)).or_insert(until);
+    *slot = (*slot).max(until);
+}
+
+// Up to half the delay again, so retries from several channels don't line up
+fn jittered(delay: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    delay + delay.mul_f64((random % 1000) as f64 / 2000.0)
+}
+
+pub struct FetchError {
+    pub message: String,
+    pub retryable: bool, // Rate limits, server errors and dropped connections
+}
+
+impl FetchError {
+    pub fn retryable(message: impl Into<String>) -> Self {
+        Self { message: message.into(), retryable: true }
+    }
+
+    pub fn fatal(message: impl Into<String>) -> Self {
+        Self { message: message.into(), retryable: false }
+    }
+}
+
+impl<E: std::error::Error> From<E> for FetchError {
+    fn from(e: E) -> Self {
+        FetchError::retryable(e.to_string())
+    }
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    pending: VecDeque<String>,
+    scheduled: HashSet<String>, // Pending or running, so repeated requests share one fetch
+    running: usize,
+    failed_until: HashMap<String, Instant>,
+}
+
+/// Runs fetches keyed by e.g. channel id on a few worker threads. Requests for a key
+/// that's already queued or running are merged, and keys that failed rest for a while.
+pub struct FetchScheduler {
+    name: &'static str,
+    max_concurrent: usize,
+    max_attempts: u32,
+    failure_cooldown: Duration,
+    fetch: fn(&str) -> Result<(), FetchError>,
+    state: Mutex<SchedulerState>,
+}
+
+impl FetchScheduler {
+    pub fn new(
+        name: &'static str,
+        max_concurrent: usize,
+        max_attempts: u32,
+        failure_cooldown: Duration,
+        fetch: fn(&str) -> Result<(), FetchError>,
+    ) -> Self {
+        Self {
+            name,
+            max_concurrent,
+            max_attempts,
+            failure_cooldown,
+            fetch,
+            state: Mutex::new(SchedulerState::default()),
+        }
+    }
+
+    /// Queues a fetch of `key`; false when it's already scheduled or cooling down after failing
+    pub fn request(&'static self, key: &str) -> bool {
+        let mut state = self.state.locked();
+        if state.scheduled.contains(key) {
+            return false;
+        }
+        if let Some(until) = state.failed_until.get(key) {
+            if Instant::now() < *until {
+                return false;
+            }
+            state.failed_until.remove(key);
+        }
+        state.scheduled.insert(key.to_string());
+        state.pending.push_back(key.to_string());
+        if state.running < self.max_concurrent {
+            state.running += 1;
+            thread::spawn(move || self.work());
+        }
+        true
+    }
+
+    /// Same as `request`, ignoring an earlier failure of `key`
+    pub fn request_now(&'static self, key: &str) -> bool {
+        self.state.locked().failed_until.remove(key);
+        self.request(key)
+    }
+
+    /// Lets keys that failed be fetched again right away, e.g. once the network is back
+    pub fn clear_failures(&self) {
+        self.state.locked().failed_until.clear();
+    }
+
+    pub fn is_scheduled(&self, key: &str) -> bool {
+        self.state.locked().scheduled.contains(key)
+    }
+
+    fn work(&self) {
+        loop {
+            let key = {
+                let mut state = self.state.locked();
+                match state.pending.pop_front() {
+                    Some(key) => key,
+                    None => {
+                        state.running -= 1;
+                        return;
+                    }
+                }
+            };
+
+            let mut attempt = 1;
+            let result = loop {
+                match (self.fetch)(&key) {
+                    Err(e) if e.retryable && attempt < self.max_attempts => {
+                        let delay = jittered(RETRY_BASE_DELAY * 2u32.pow(attempt - 1));
+                        eprintln!(
+                            "{} fetch for {} failed ({}), retrying in {:.1}s",
+                            self.name,
+                            key,
+                            e.message,
+                            delay.as_secs_f64()
+                        );
+                        thread::sleep(delay);
+                        attempt += 1;
+                    }
+                    result => break result,
+                }
+            };
+
+            let mut state = self.state.locked();
+            state.scheduled.remove(&key);
+            if let Err(e) = result {
+                eprintln!("{} fetch for {} failed: {}", self.name, key, e.message);
+                state.failed_until.insert(key, Instant::now() + self.failure_cooldown);
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+// filters.rs
+
+use regex::Regex;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::bots::{BotDisplay, BotSettings};
+use crate::rules::matches_filter_rule;
+
+/// Whether a message stays out of the chat: sent by a hidden bot, or matching one of
+/// the tab's filter patterns or a saved filter rule
+pub fn is_hidden(msg: &PrivmsgMessage, bot_settings: &BotSettings, filters: &[Regex]) -> bool {
+    bot_settings.display_for(&msg.channel_login, &msg.sender.login) == BotDisplay::Hidden
+        || filters.iter().any(|filter| filter.is_match(&msg.message_text))
+        || matches_filter_rule(&msg.message_text)
+}
+
+/// Drops hidden messages, keeping the order of the rest
+pub fn remove_hidden_messages(messages: &mut Vec<PrivmsgMessage>, bot_settings: &BotSettings, filters: &[Regex]) {
+    messages.retain(|msg| !is_hidden(msg, bot_settings, filters));
+}
@@ -0,0 +1,61 @@
+// headless.rs
+
+use std::io::Write;
+use twitch_irc::login::StaticLoginCredentials;
+use twitch_irc::message::ServerMessage;
+
+use crate::bots::BotSettings;
+use crate::emotes::get_emote_map;
+use crate::filters::is_hidden;
+use crate::terminal::{format_chat_line, format_notice_line};
+use crate::transport::{ChatClient, ChatTransport};
+
+pub struct HeadlessOptions {
+    pub transport: ChatTransport,
+    pub bot_settings: BotSettings,
+    pub color: bool, // ANSI styling; off when stdout isn't a terminal
+}
+
+/// Prints `channel`'s chat to stdout until Ctrl+C, the connection closing or stdout
+/// going away. Blocking; 7TV emotes are looked up in the background and highlighted
+/// once they arrive.
+pub fn run_headless(channel: &str, options: &HeadlessOptions) -> Result<(), String> {
+    let channel = channel.trim().trim_start_matches('#').to_lowercase();
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to start the runtime: {}", e))?;
+    runtime.block_on(async {
+        let (mut incoming_messages, client) = ChatClient::connect(options.transport, StaticLoginCredentials::anonymous());
+        client
+            .join(channel.clone())
+            .map_err(|e| format!("Failed to join channel '{}': {}", channel, e))?;
+        eprintln!("Reading #{} over {}, Ctrl+C to stop", channel, options.transport.label());
+
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+        let mut stdout = std::io::stdout();
+        loop {
+            let message = tokio::select! {
+                message = incoming_messages.recv() => message,
+                _ = &mut ctrl_c => return Ok(()),
+            };
+            let Some(message) = message else {
+                return Err("Disconnected from chat".to_string());
+            };
+            let line = match &message {
+                ServerMessage::Privmsg(msg) => {
+                    if is_hidden(msg, &options.bot_settings, &[]) {
+                        continue;
+                    }
+                    let display = options.bot_settings.display_for(&msg.channel_login, &msg.sender.login);
+                    let emote_map = get_emote_map(&msg.channel_id);
+                    format_chat_line(msg, &emote_map, display, options.color)
+                }
+                ServerMessage::Notice(msg) => format_notice_line(&msg.message_text, options.color),
+                _ => continue,
+            };
+            // A closed pipe, as in `admiral --headless x | head`, ends the reader quietly
+            if writeln!(stdout, "{}", line).is_err() {
+                return Ok(());
+            }
+        }
+    })
+}
@@ -0,0 +1,247 @@
+// history.rs
+
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use twitch_irc::message::{AsRawIRC, IRCMessage, PrivmsgMessage};
+
+use crate::profile::data_dir;
+use crate::state::MutexExt;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS messages (
+    id INTEGER PRIMARY KEY,
+    message_id TEXT NOT NULL UNIQUE,
+    channel TEXT NOT NULL,
+    sender_login TEXT NOT NULL,
+    text TEXT NOT NULL,
+    sent_at INTEGER NOT NULL, -- Unix milliseconds
+    raw TEXT NOT NULL         -- Original IRC line, parsed again when loaded
+);
+CREATE INDEX IF NOT EXISTS messages_channel_time ON messages (channel, sent_at);
+CREATE INDEX IF NOT EXISTS messages_sender_time ON messages (channel, sender_login, sent_at);
+CREATE INDEX IF NOT EXISTS messages_time ON messages (sent_at);
+";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RETENTION_DAYS: AtomicU32 = AtomicU32::new(0);
+// Inserts go through one writer thread so the UI never waits on disk
+static WRITER: Lazy<Mutex<Option<Sender<StoredMessage>>>> = Lazy::new(|| Mutex::new(None));
+// Lookups share one connection, opened on first use
+static READER: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+
+// How often the writer thread looks for a changed retention setting, and how often it
+// prunes when that hasn't changed
+const PRUNE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+// Stored under [history] in favorites.toml
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HistorySettings {
+    pub enabled: bool,
+    pub retention_days: u32, // 0 keeps messages forever
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: 30,
+        }
+    }
+}
+
+struct StoredMessage {
+    message_id: String,
+    channel: String,
+    sender_login: String,
+    text: String,
+    sent_at: i64,
+    raw: String,
+}
+
+impl From<&PrivmsgMessage> for StoredMessage {
+    fn from(msg: &PrivmsgMessage) -> Self {
+        Self {
+            message_id: msg.message_id.clone(),
+            channel: msg.channel_login.clone(),
+            sender_login: msg.sender.login.clone(),
+            text: msg.message_text.clone(),
+            sent_at: msg.server_timestamp.timestamp_millis(),
+            raw: msg.source.as_raw_irc(),
+        }
+    }
+}
+
+fn history_path() -> PathBuf {
+    data_dir().join("history.sqlite3")
+}
+
+fn open_history() -> rusqlite::Result<Connection> {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            eprintln!("Failed to create history directory: {}", e);
+        }
+    }
+    let conn = Connection::open(path)?;
+    // Lets lookups from the UI run while the writer thread holds a transaction
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+/// Applies changed settings; called at startup and from preferences
+pub fn configure_history(settings: &HistorySettings) {
+    ENABLED.store(settings.enabled, Ordering::Relaxed);
+    RETENTION_DAYS.store(settings.retention_days, Ordering::Relaxed);
+}
+
+pub fn is_history_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// The first call comes from the UI thread, so the database is opened on the writer
+// thread; messages sent meanwhile wait in the channel
+fn writer() -> Option<Sender<StoredMessage>> {
+    let mut writer = WRITER.locked();
+    if writer.is_none() {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || match open_history() {
+            Ok(conn) => write_loop(conn, rx),
+            Err(e) => {
+                eprintln!("Failed to open chat history, not saving messages: {}", e);
+                ENABLED.store(false, Ordering::Relaxed);
+                WRITER.locked().take();
+            }
+        });
+        *writer = Some(tx);
+    }
+    writer.clone()
+}
+
+/// Queues messages for the on-disk history when it's enabled
+pub fn record_history(messages: &[PrivmsgMessage]) {
+    if messages.is_empty() || !is_history_enabled() {
+        return;
+    }
+    let Some(writer) = writer() else {
+        return;
+    };
+    for msg in messages {
+        let _ = writer.send(StoredMessage::from(msg));
+    }
+}
+
+fn write_loop(mut conn: Connection, rx: Receiver<StoredMessage>) {
+    let mut pruned_days = RETENTION_DAYS.load(Ordering::Relaxed);
+    prune_history(&conn, pruned_days);
+    let mut last_pruned = Instant::now();
+    loop {
+        let days = RETENTION_DAYS.load(Ordering::Relaxed);
+        if days != pruned_days || last_pruned.elapsed() >= PRUNE_INTERVAL {
+            prune_history(&conn, days);
+            pruned_days = days;
+            last_pruned = Instant::now();
+        }
+        let first = match rx.recv_timeout(PRUNE_CHECK_INTERVAL) {
+            Ok(first) => first,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        // Everything that piled up meanwhile goes into the same transaction
+        let mut batch = vec![first];
+        batch.extend(rx.try_iter());
+        if let Err(e) = insert_batch(&mut conn, &batch) {
+            eprintln!("Failed to write chat history: {}", e);
+        }
+    }
+}
+
+fn insert_batch(conn: &mut Connection, batch: &[StoredMessage]) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR IGNORE INTO messages (message_id, channel, sender_login, text, sent_at, raw)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for msg in batch {
+            stmt.execute(params![msg.message_id, msg.channel, msg.sender_login, msg.text, msg.sent_at, msg.raw])?;
+        }
+    }
+    tx.commit()
+}
+
+fn prune_history(conn: &Connection, days: u32) {
+    if days == 0 {
+        return;
+    }
+    let cutoff = chrono::Utc::now().timestamp_millis() - days as i64 * 86_400_000;
+    match conn.execute("DELETE FROM messages WHERE sent_at < ?1", params![cutoff]) {
+        Ok(0) => {}
+        Ok(removed) => println!("Pruned {} messages from chat history", removed),
+        Err(e) => eprintln!("Failed to prune chat history: {}", e),
+    }
+}
+
+fn parse_stored(raw: &str) -> Option<PrivmsgMessage> {
+    let irc = IRCMessage::parse(raw).ok()?;
+    PrivmsgMessage::try_from(irc).ok()
+}
+
+fn query_messages(sql: &str, params: impl rusqlite::Params) -> rusqlite::Result<Vec<PrivmsgMessage>> {
+    let mut reader = READER.locked();
+    if reader.is_none() {
+        *reader = Some(open_history()?);
+    }
+    let conn = reader.as_ref().unwrap();
+    let mut stmt = conn.prepare_cached(sql)?;
+    let rows = stmt.query_map(params, |row| row.get::<_, String>(0))?;
+    let mut messages = Vec::new();
+    for raw in rows {
+        if let Some(msg) = parse_stored(&raw?) {
+            messages.push(msg);
+        }
+    }
+    Ok(messages)
+}
+
+/// Up to `limit` saved messages in `channel` sent before `before_ms`, oldest first
+pub fn messages_before(channel: &str, before_ms: i64, limit: usize) -> Vec<PrivmsgMessage> {
+    if !is_history_enabled() {
+        return Vec::new();
+    }
+    let mut messages = query_messages(
+        "SELECT raw FROM messages WHERE channel = ?1 AND sent_at < ?2 ORDER BY sent_at DESC LIMIT ?3",
+        params![channel, before_ms, limit as i64],
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Failed to read chat history: {}", e);
+        Vec::new()
+    });
+    messages.reverse();
+    messages
+}
+
+/// A chatter's saved messages in `channel`, newest first
+pub fn user_history(channel: &str, login: &str, limit: usize) -> Vec<PrivmsgMessage> {
+    if !is_history_enabled() {
+        return Vec::new();
+    }
+    query_messages(
+        "SELECT raw FROM messages WHERE channel = ?1 AND sender_login = ?2 ORDER BY sent_at DESC LIMIT ?3",
+        params![channel, login, limit as i64],
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Failed to read chat history: {}", e);
+        Vec::new()
+    })
+}
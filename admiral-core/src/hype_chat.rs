@@ -0,0 +1,79 @@
+// hype_chat.rs
+
+use chrono::{DateTime, Duration, Utc};
+use twitch_irc::message::PrivmsgMessage;
+
+// Twitch's ten Hype Chat tiers, cheapest first, with how long each stays pinned
+const LEVELS: [(&str, i64); 10] = [
+    ("ONE", 30),
+    ("TWO", 150),
+    ("THREE", 300),
+    ("FOUR", 600),
+    ("FIVE", 1800),
+    ("SIX", 3600),
+    ("SEVEN", 7200),
+    ("EIGHT", 10800),
+    ("NINE", 14400),
+    ("TEN", 18000),
+];
+
+/// A paid message pinned to the top of chat (Hype Chat), read from the
+/// `pinned-chat-paid-*` tags Twitch puts on its PRIVMSG
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaidMessage {
+    pub amount: u64, // In the currency's minor unit, see `exponent`
+    pub currency: String,
+    pub exponent: u32,
+    pub level: usize, // 1 to 10
+    pub sent_at: DateTime<Utc>,
+}
+
+impl PaidMessage {
+    pub fn from_message(msg: &PrivmsgMessage) -> Option<Self> {
+        let tag = |name: &str| msg.source.tags.0.get(name).and_then(|value| value.as_deref());
+        let amount = tag("pinned-chat-paid-amount")?.parse().ok()?;
+        let currency = tag("pinned-chat-paid-currency")?.to_string();
+        let exponent = tag("pinned-chat-paid-exponent")
+            .and_then(|value| value.parse().ok())
+            .filter(|exponent| *exponent <= 8)
+            .unwrap_or(2);
+        // An unknown level still renders, just at the lowest tier
+        let level = tag("pinned-chat-paid-level")
+            .and_then(|name| LEVELS.iter().position(|(level, _)| *level == name))
+            .map_or(1, |index| index + 1);
+        Some(Self {
+            amount,
+            currency,
+            exponent,
+            level,
+            sent_at: msg.server_timestamp,
+        })
+    }
+
+    /// The amount as shown to viewers, like "5.00 USD"
+    pub fn formatted_amount(&self) -> String {
+        let divisor = 10u64.pow(self.exponent);
+        if self.exponent == 0 {
+            return format!("{} {}", self.amount, self.currency);
+        }
+        format!(
+            "{}.{:0width$} {}",
+            self.amount / divisor,
+            self.amount % divisor,
+            self.currency,
+            width = self.exponent as usize
+        )
+    }
+
+    pub fn pin_duration(&self) -> Duration {
+        Duration::seconds(LEVELS[self.level.clamp(1, LEVELS.len()) - 1].1)
+    }
+
+    pub fn pinned_until(&self) -> DateTime<Utc> {
+        self.sent_at + self.pin_duration()
+    }
+
+    pub fn is_pinned_at(&self, now: DateTime<Utc>) -> bool {
+        now < self.pinned_until()
+    }
+}
@@ -0,0 +1,27 @@
+// lib.rs
+
+//! The parts of Admiral that don't need a window: the chat connection, 7TV emotes,
+//! message rendering to HTML, saved history and the settings behind them. The GTK app
+//! is a frontend over this crate, and headless tools can use it directly.
+
+pub mod badges;
+pub mod bots;
+pub mod cheermotes;
+pub mod emoji;
+pub mod emotes;
+pub mod fetch_scheduler;
+pub mod filters;
+pub mod headless;
+pub mod history;
+pub mod hype_chat;
+pub mod markup;
+pub mod moderation;
+pub mod network;
+pub mod profile;
+pub mod room_state;
+pub mod rules;
+pub mod seventv;
+pub mod state;
+pub mod terminal;
+pub mod transport;
+pub mod tunnel;
@@ -0,0 +1,58 @@
+// moderation.rs
+
+use serde::{Deserialize, Serialize};
+
+// Stored under [moderation] in favorites.toml
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ModerationSettings {
+    pub show_account_age: bool, // Tag first-seen chatters with their account age
+    pub timeout_durations: Vec<u32>, // Seconds, one quick button each in the user card
+    pub clear_on_chat_clear: bool, // Empty the view when a moderator clears chat, rather than only marking it
+    pub show_deleted_messages: bool, // Keep messages moderators delete, struck through, instead of removing them
+}
+
+impl Default for ModerationSettings {
+    fn default() -> Self {
+        Self {
+            show_account_age: false,
+            timeout_durations: vec![60, 600, 3600, 86400],
+            clear_on_chat_clear: false,
+            show_deleted_messages: false,
+        }
+    }
+}
+
+// "30s", "10m", "1h", "7d"
+pub fn format_timeout(secs: u32) -> String {
+    match secs {
+        s if s % 86400 == 0 => format!("{}d", s / 86400),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// Parses "1m, 10m, 1h, 24h" style lists. Twitch allows timeouts of up to two weeks.
+pub fn parse_timeout_list(text: &str) -> Vec<u32> {
+    let mut durations: Vec<u32> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|part| {
+            let part = part.trim().to_lowercase();
+            let split = part.find(|c: char| !c.is_ascii_digit()).unwrap_or(part.len());
+            let amount: u32 = part[..split].parse().ok()?;
+            let multiplier = match &part[split..] {
+                "" | "s" => 1,
+                "m" => 60,
+                "h" => 3600,
+                "d" => 86400,
+                "w" => 604800,
+                _ => return None,
+            };
+            Some(amount.checked_mul(multiplier)?.clamp(1, 1_209_600))
+        })
+        .collect();
+    durations.sort_unstable();
+    durations.dedup();
+    durations
+}
@@ -0,0 +1,58 @@
+// profile.rs
+
+use once_cell::sync::OnceCell;
+use std::path::PathBuf;
+
+// Set once at launch from `--profile <name>`, before anything reads a path
+static PROFILE: OnceCell<String> = OnceCell::new();
+
+/// Profile names end up in paths and the application id, so they're kept to letters,
+/// digits, '-' and '_'
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Selects a profile: its own settings, saved history, caches and login, kept apart from
+/// the default ones. Call once, before anything else runs.
+pub fn set_profile(name: &str) -> Result<(), String> {
+    if !is_valid_profile_name(name) {
+        return Err(format!(
+            "Invalid profile name {:?}: use letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    PROFILE
+        .set(name.to_string())
+        .map_err(|_| "A profile is already selected".to_string())
+}
+
+/// The selected profile, None for the default one
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+// The default profile keeps the paths Admiral always used
+fn app_dir(base: PathBuf) -> PathBuf {
+    let dir = base.join("admiral");
+    match profile() {
+        Some(name) => dir.join("profiles").join(name),
+        None => dir,
+    }
+}
+
+/// Where favorites.toml lives
+pub fn config_dir() -> PathBuf {
+    app_dir(PathBuf::from(shellexpand::tilde("~/.config").into_owned()))
+}
+
+/// Saved history, notes, activity, crash reports and the WebView's cookies
+pub fn data_dir() -> PathBuf {
+    app_dir(dirs::data_dir().unwrap_or_else(|| PathBuf::from(shellexpand::tilde("~/.local/share").into_owned())))
+}
+
+/// Emote maps, avatars and the WebView's HTTP cache
+pub fn cache_dir() -> PathBuf {
+    app_dir(dirs::cache_dir().unwrap_or_else(|| PathBuf::from(shellexpand::tilde("~/.cache").into_owned())))
+}
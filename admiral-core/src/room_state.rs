@@ -0,0 +1,211 @@
+// room_state.rs
+
+use regex::Regex;
+use once_cell::sync::Lazy;
+use std::time::{Duration, Instant};
+use twitch_irc::message::{FollowersOnlyMode, NoticeMessage, RoomStateMessage, UserStateMessage};
+
+// Matches the wait time in NOTICE texts such as
+// "You will be able to talk again in 5 seconds."
+static WAIT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\bin (\d+) (second|minute|hour)s?\b").unwrap());
+
+// Chat restrictions for a channel, kept up to date from ROOMSTATE, USERSTATE and NOTICE
+#[derive(Debug, Clone, Default)]
+pub struct RoomState {
+    pub emote_only: bool,
+    pub followers_only: Option<Duration>, // None when off, Some(0) for any follower
+    pub slow_mode: Duration,
+    pub subscribers_only: bool,
+    pub r9k: bool,
+    pub is_privileged: bool, // Broadcaster or moderator, exempt from slow mode
+    pub own_name: Option<String>, // Display name, once USERSTATE has told us
+    pub own_color: Option<String>, // "#RRGGBB"; None until set, or if never chosen
+    pub last_sent: Option<Instant>,
+    pub blocked: Option<BlockedNotice>,
+}
+
+// A NOTICE that rejected our last message, with an optional end time
+#[derive(Debug, Clone)]
+pub struct BlockedNotice {
+    pub reason: String,
+    pub until: Option<Instant>,
+}
+
+// What the send input should show while sending is restricted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendHint {
+    pub placeholder: String,
+    pub can_send: bool,
+}
+
+impl RoomState {
+    /// ROOMSTATE only carries the fields that changed, so missing ones are kept
+    pub fn apply_roomstate(&mut self, msg: &RoomStateMessage) {
+        if let Some(emote_only) = msg.emote_only {
+            self.emote_only = emote_only;
+        }
+        if let Some(mode) = &msg.follwers_only {
+            self.followers_only = match mode {
+                FollowersOnlyMode::Disabled => None,
+                FollowersOnlyMode::Enabled(duration) => Some(*duration),
+            };
+        }
+        if let Some(slow_mode) = msg.slow_mode {
+            self.slow_mode = slow_mode;
+        }
+        if let Some(subscribers_only) = msg.subscribers_only {
+            self.subscribers_only = subscribers_only;
+        }
+        if let Some(r9k) = msg.r9k {
+            self.r9k = r9k;
+        }
+    }
+
+    pub fn apply_userstate(&mut self, msg: &UserStateMessage) {
+        self.is_privileged = msg
+            .badges
+            .iter()
+            .any(|badge| badge.name == "broadcaster" || badge.name == "moderator");
+        self.own_name = Some(msg.user_name.clone());
+        self.own_color = msg
+            .name_color
+            .as_ref()
+            .map(|color| format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b));
+    }
+
+    pub fn apply_notice(&mut self, msg: &NoticeMessage) {
+        let Some(message_id) = msg.message_id.as_deref() else {
+            return;
+        };
+        let reason = match message_id {
+            "msg_slowmode" => "Slow mode",
+            "msg_followersonly" | "msg_followersonly_followed" | "msg_followersonly_zero" => {
+                "Followers-only"
+            }
+            "msg_subsonly" => "Subscribers-only",
+            "msg_emoteonly" => "Emote-only",
+            "msg_timedout" => "Timed out",
+            "msg_banned" => "Banned from this channel",
+            "msg_duplicate" | "msg_r9k" => "Message must be unique",
+            "msg_requires_verified_phone_number" | "msg_verified_email" => "Verification required",
+            _ => return,
+        };
+        // Only these count down. The followers-only notices quote the channel's required
+        // follow age ("in 10 minutes followers-only mode"), which is no wait at all.
+        let until = match message_id {
+            "msg_slowmode" | "msg_timedout" => {
+                parse_wait_duration(&msg.message_text).map(|wait| Instant::now() + wait)
+            }
+            _ => None,
+        };
+        self.blocked = Some(BlockedNotice {
+            reason: reason.to_string(),
+            until,
+        });
+    }
+
+    pub fn record_sent(&mut self) {
+        self.last_sent = Some(Instant::now());
+        self.blocked = None;
+    }
+
+    /// Remaining slow mode cooldown after our last message
+    pub fn slow_mode_remaining(&self) -> Option<Duration> {
+        if self.is_privileged || self.slow_mode.is_zero() {
+            return None;
+        }
+        let elapsed = self.last_sent?.elapsed();
+        self.slow_mode.checked_sub(elapsed).filter(|d| !d.is_zero())
+    }
+
+    /// Placeholder text for the send input, or None when nothing restricts sending
+    pub fn send_hint(&self) -> Option<SendHint> {
+        if let Some(blocked) = &self.blocked {
+            match blocked.until.map(|until| until.saturating_duration_since(Instant::now())) {
+                Some(remaining) if !remaining.is_zero() => {
+                    return Some(SendHint {
+                        placeholder: format!("{} — wait {}", blocked.reason, format_wait(remaining)),
+                        can_send: false,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    return Some(SendHint {
+                        placeholder: blocked.reason.clone(),
+                        can_send: true,
+                    });
+                }
+            }
+        }
+
+        if let Some(remaining) = self.slow_mode_remaining() {
+            return Some(SendHint {
+                placeholder: format!("Slow mode — wait {}", format_wait(remaining)),
+                can_send: false,
+            });
+        }
+
+        if self.is_privileged {
+            return None;
+        }
+        let modes = self.active_modes();
+        if modes.is_empty() {
+            return None;
+        }
+        Some(SendHint {
+            placeholder: modes.join(", "),
+            can_send: true,
+        })
+    }
+
+    /// The chat modes the channel has on, like "Slow mode (30s)", whoever is reading
+    pub fn active_modes(&self) -> Vec<String> {
+        let mut modes = Vec::new();
+        if self.subscribers_only {
+            modes.push("Subscribers-only".to_string());
+        }
+        if let Some(duration) = self.followers_only {
+            if duration.is_zero() {
+                modes.push("Followers-only".to_string());
+            } else {
+                modes.push(format!("Followers-only ({})", format_wait(duration)));
+            }
+        }
+        if self.emote_only {
+            modes.push("Emote-only".to_string());
+        }
+        if !self.slow_mode.is_zero() {
+            modes.push(format!("Slow mode ({})", format_wait(self.slow_mode)));
+        }
+        if self.r9k {
+            modes.push("Unique messages".to_string());
+        }
+        modes
+    }
+}
+
+fn parse_wait_duration(text: &str) -> Option<Duration> {
+    let captures = WAIT_REGEX.captures(text)?;
+    let amount: u64 = captures[1].parse().ok()?;
+    let seconds = match captures[2].to_ascii_lowercase().as_str() {
+        "minute" => amount * 60,
+        "hour" => amount * 3600,
+        _ => amount,
+    };
+    Some(Duration::from_secs(seconds))
+}
+
+// "45s", "10m", "2h 5m"
+pub fn format_wait(duration: Duration) -> String {
+    let secs = duration.as_secs().max(1);
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs.div_ceil(60))
+    } else if secs % 3600 == 0 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
@@ -0,0 +1,105 @@
+// rules.rs
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+use crate::state::RwLockExt;
+
+// Compiled from the saved patterns, read for every incoming message
+static COMPILED: Lazy<RwLock<CompiledRules>> = Lazy::new(|| RwLock::new(CompiledRules::default()));
+
+#[derive(Default)]
+struct CompiledRules {
+    highlights: Vec<Regex>,
+    filters: Vec<Regex>,
+}
+
+// Stored under [rules] in favorites.toml
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RuleSettings {
+    pub highlights: Vec<String>, // Messages matching any of these are highlighted and notify
+    pub filters: Vec<String>, // Messages matching any of these are hidden in every tab
+}
+
+/// A set of rules saved to its own TOML file, so a mod team can hand around one
+/// configuration
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RulePreset {
+    pub name: String,
+    pub description: String,
+    pub highlights: Vec<String>,
+    pub filters: Vec<String>,
+}
+
+impl RulePreset {
+    pub fn from_settings(name: &str, settings: &RuleSettings) -> Self {
+        Self {
+            name: name.to_string(),
+            description: String::new(),
+            highlights: settings.highlights.clone(),
+            filters: settings.filters.clone(),
+        }
+    }
+
+    /// Patterns in the preset that don't compile, which would otherwise be skipped quietly
+    pub fn invalid_patterns(&self) -> Vec<String> {
+        self.highlights
+            .iter()
+            .chain(&self.filters)
+            .filter(|pattern| !is_valid_pattern(pattern))
+            .cloned()
+            .collect()
+    }
+}
+
+impl RuleSettings {
+    /// Adds the preset's rules to these, skipping ones already present
+    pub fn merge(&mut self, preset: &RulePreset) {
+        for pattern in &preset.highlights {
+            if !self.highlights.contains(pattern) {
+                self.highlights.push(pattern.clone());
+            }
+        }
+        for pattern in &preset.filters {
+            if !self.filters.contains(pattern) {
+                self.filters.push(pattern.clone());
+            }
+        }
+    }
+}
+
+pub fn is_valid_pattern(pattern: &str) -> bool {
+    !pattern.is_empty() && Regex::new(pattern).is_ok()
+}
+
+fn compile(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|pattern| match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                eprintln!("Skipping invalid rule pattern {:?}: {}", pattern, e);
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn configure_rules(settings: &RuleSettings) {
+    *COMPILED.write_locked() = CompiledRules {
+        highlights: compile(&settings.highlights),
+        filters: compile(&settings.filters),
+    };
+}
+
+pub fn matches_highlight_rule(text: &str) -> bool {
+    COMPILED.read_locked().highlights.iter().any(|rule| rule.is_match(text))
+}
+
+pub fn matches_filter_rule(text: &str) -> bool {
+    COMPILED.read_locked().filters.iter().any(|rule| rule.is_match(text))
+}
@@ -0,0 +1,205 @@
+// seventv.rs
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::fetch_scheduler::{back_off_host, wait_for_host, FetchError};
+use crate::network::http_client;
+use crate::state::RwLockExt;
+
+const DEFAULT_API_BASE: &str = "https://7tv.io/v3";
+
+static API_BASE: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(DEFAULT_API_BASE.to_string()));
+// Skipped emotes logged per fetch; the rest are only counted
+const MAX_LOGGED_MISMATCHES: usize = 3;
+
+const GQL_USER_QUERY: &str = "query($id: String!) {
+  userByConnection(platform: TWITCH, id: $id) { connections { platform emote_set_id } }
+}";
+const GQL_EMOTE_SET_QUERY: &str = "query($id: ObjectID!) {
+  emoteSet(id: $id) { emotes { id name data { name flags host { url files { name format } } } } }
+}";
+
+#[derive(Debug, Deserialize)]
+pub struct ApiActiveEmote {
+    pub id: String,
+    pub name: String,
+    pub data: Option<ApiEmoteData>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiEmoteData {
+    pub name: Option<String>, // The emote's own name, which the channel's set may override
+    pub host: Option<ImageHost>,
+    pub flags: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ImageHost {
+    pub url: String, // Base URL for the host (e.g., cdn.7tv.app)
+    pub files: Vec<ImageFile>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ImageFile {
+    pub name: String,   // Filename (e.g., 1x.webp)
+    pub format: String, // Format (e.g., "WEBP", "PNG", "GIF")
+}
+
+// The ways of asking 7TV for a channel's emotes, tried in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SevenTvEndpoint {
+    RestV3,
+    Gql,
+}
+
+impl SevenTvEndpoint {
+    pub const ALL: [SevenTvEndpoint; 2] = [SevenTvEndpoint::RestV3, SevenTvEndpoint::Gql];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SevenTvEndpoint::RestV3 => "REST v3",
+            SevenTvEndpoint::Gql => "GraphQL",
+        }
+    }
+}
+
+/// Points the lookups at another server; the tests use it for a mock of the API
+pub fn configure_seventv_api(base: &str) {
+    *API_BASE.write_locked() = base.trim_end_matches('/').to_string();
+}
+
+fn api_base() -> String {
+    API_BASE.read_locked().clone()
+}
+
+enum ApiError {
+    Fetch(FetchError),
+    Schema(String), // The response didn't have the shape this code expects
+}
+
+impl From<FetchError> for ApiError {
+    fn from(e: FetchError) -> Self {
+        ApiError::Fetch(e)
+    }
+}
+
+/// The emotes in the channel's active set, from whichever endpoint still answers in a
+/// known shape. Entries that don't parse are skipped and logged. Blocking.
+pub fn fetch_channel_emotes(channel_id: &str) -> Result<Vec<ApiActiveEmote>, FetchError> {
+    let mut last_mismatch = String::new();
+    for endpoint in SevenTvEndpoint::ALL {
+        let result = match endpoint {
+            SevenTvEndpoint::RestV3 => fetch_rest(channel_id),
+            SevenTvEndpoint::Gql => fetch_gql(channel_id),
+        };
+        match result {
+            Ok(emotes) => return Ok(emotes),
+            Err(ApiError::Fetch(e)) => return Err(e),
+            Err(ApiError::Schema(message)) => {
+                eprintln!("7TV {} response changed shape: {}", endpoint.label(), message);
+                last_mismatch = message;
+            }
+        }
+    }
+    Err(FetchError::fatal(format!("No 7TV endpoint answered in a known shape: {}", last_mismatch)))
+}
+
+fn check_status(url: &str, response: reqwest::blocking::Response) -> Result<Value, FetchError> {
+    let status = response.status();
+    if status.as_u16() == 429 {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+            .unwrap_or(2);
+        back_off_host(url, Duration::from_secs(retry_after));
+        return Err(FetchError::retryable("rate limited by 7TV"));
+    }
+    if !status.is_success() {
+        let message = format!(
+            "7TV API request failed with status {}: {}",
+            status,
+            response.text().unwrap_or_else(|_| "No error body".to_string())
+        );
+        return Err(if status.is_server_error() {
+            FetchError::retryable(message)
+        } else {
+            FetchError::fatal(message)
+        });
+    }
+    Ok(response.json()?)
+}
+
+fn fetch_rest(channel_id: &str) -> Result<Vec<ApiActiveEmote>, ApiError> {
+    let url = format!("{}/users/twitch/{}", api_base(), channel_id);
+    wait_for_host(&url);
+    let response = http_client().get(&url).send().map_err(FetchError::from)?;
+    let body = check_status(&url, response)?;
+    match body.get("emote_set") {
+        Some(Value::Null) => Ok(Vec::new()), // Has a 7TV account but no set picked
+        Some(emote_set) => parse_emote_list(emote_set.get("emotes")),
+        None => Err(ApiError::Schema("no emote_set in user response".to_string())),
+    }
+}
+
+fn gql_request(query: &str, id: &str) -> Result<Value, ApiError> {
+    let url = format!("{}/gql", api_base());
+    wait_for_host(&url);
+    let response = http_client()
+        .post(&url)
+        .json(&json!({ "query": query, "variables": { "id": id } }))
+        .send()
+        .map_err(FetchError::from)?;
+    let mut body = check_status(&url, response)?;
+    if let Some(errors) = body.get("errors").filter(|errors| !errors.is_null()) {
+        return Err(ApiError::Schema(format!("GraphQL errors: {}", errors)));
+    }
+    Ok(body.get_mut("data").map(Value::take).unwrap_or(Value::Null))
+}
+
+fn fetch_gql(channel_id: &str) -> Result<Vec<ApiActiveEmote>, ApiError> {
+    let user = gql_request(GQL_USER_QUERY, channel_id)?;
+    let Some(connections) = user.pointer("/userByConnection/connections").and_then(Value::as_array) else {
+        return Err(ApiError::Fetch(FetchError::fatal("No 7TV account for this channel")));
+    };
+    let emote_set_id = connections
+        .iter()
+        .find(|connection| connection["platform"] == "TWITCH")
+        .and_then(|connection| connection["emote_set_id"].as_str());
+    let Some(emote_set_id) = emote_set_id else {
+        return Ok(Vec::new());
+    };
+    let emote_set = gql_request(GQL_EMOTE_SET_QUERY, emote_set_id)?;
+    parse_emote_list(emote_set.pointer("/emoteSet/emotes"))
+}
+
+// Keeps every emote that parses, so one odd entry doesn't cost the whole set
+fn parse_emote_list(emotes: Option<&Value>) -> Result<Vec<ApiActiveEmote>, ApiError> {
+    let Some(entries) = emotes.and_then(Value::as_array) else {
+        return Err(ApiError::Schema("emote list is missing or not an array".to_string()));
+    };
+    let mut parsed = Vec::with_capacity(entries.len());
+    let mut mismatches = 0;
+    for entry in entries {
+        match ApiActiveEmote::deserialize(entry) {
+            Ok(emote) => parsed.push(emote),
+            Err(e) => {
+                mismatches += 1;
+                if mismatches <= MAX_LOGGED_MISMATCHES {
+                    eprintln!("Skipping 7TV emote {}: {}", entry.get("name").unwrap_or(&Value::Null), e);
+                }
+            }
+        }
+    }
+    if mismatches > 0 {
+        eprintln!("Skipped {} of {} 7TV emotes that didn't parse", mismatches, entries.len());
+    }
+    if parsed.is_empty() && mismatches > 0 {
+        return Err(ApiError::Schema(format!("none of {} emotes parsed", mismatches)));
+    }
+    Ok(parsed)
+}
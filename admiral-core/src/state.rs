@@ -0,0 +1,47 @@
+// state.rs
+
+use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+// A panic while a lock is held poisons it, and unwrapping every later lock() would
+// spread that one failure to every tab and timer touching the same state. The data
+// behind these locks stays usable after a panic (at worst a batch is half applied),
+// so the guard is taken back and the poison cleared.
+
+/// Locking that recovers from poisoning instead of panicking
+pub trait MutexExt<T> {
+    fn locked(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> MutexExt<T> for Mutex<T> {
+    fn locked(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(|e| {
+            eprintln!("Recovered state left locked by a panic");
+            self.clear_poison();
+            e.into_inner()
+        })
+    }
+}
+
+/// Read and write locking that recovers from poisoning instead of panicking
+pub trait RwLockExt<T> {
+    fn read_locked(&self) -> RwLockReadGuard<'_, T>;
+    fn write_locked(&self) -> RwLockWriteGuard<'_, T>;
+}
+
+impl<T> RwLockExt<T> for RwLock<T> {
+    fn read_locked(&self) -> RwLockReadGuard<'_, T> {
+        self.read().unwrap_or_else(|e| {
+            eprintln!("Recovered state left locked by a panic");
+            self.clear_poison();
+            e.into_inner()
+        })
+    }
+
+    fn write_locked(&self) -> RwLockWriteGuard<'_, T> {
+        self.write().unwrap_or_else(|e| {
+            eprintln!("Recovered state left locked by a panic");
+            self.clear_poison();
+            e.into_inner()
+        })
+    }
+}
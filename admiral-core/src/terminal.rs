@@ -0,0 +1,77 @@
+// terminal.rs
+
+use chrono::Local;
+use std::collections::{HashMap, HashSet};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::bots::BotDisplay;
+use crate::emotes::find_emote;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const ITALIC: &str = "\x1b[3m";
+const EMOTE: &str = "\x1b[36m"; // Cyan, so emote names stand out from the words around them
+
+// Chat text comes from strangers, so nothing in it gets to steer the terminal
+fn printable(text: &str) -> String {
+    text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect()
+}
+
+/// One chat message as a terminal line, "[12:34] Name: text". With `color` the name
+/// takes the sender's color, emote names are highlighted, actions are italic and
+/// dimmed or collapsed bots are dim.
+pub fn format_chat_line(
+    msg: &PrivmsgMessage,
+    emote_map: &HashMap<String, (String, bool)>,
+    display: BotDisplay,
+    color: bool,
+) -> String {
+    let timestamp = msg.server_timestamp.with_timezone(&Local).format("%H:%M");
+    let name = printable(&msg.sender.name);
+    let separator = if msg.is_action { " " } else { ": " };
+    let twitch_emotes: HashSet<&str> = msg.emotes.iter().map(|emote| emote.code.as_str()).collect();
+
+    if !color {
+        let text = printable(&msg.message_text);
+        return format!("[{}] {}{}{}", timestamp, name, separator, text);
+    }
+
+    let name_color = match &msg.name_color {
+        Some(rgb) => format!("\x1b[38;2;{};{};{}m", rgb.r, rgb.g, rgb.b),
+        None => String::new(),
+    };
+    let (line_style, text_style) = match (display, msg.is_action) {
+        (BotDisplay::Dimmed | BotDisplay::Collapsed, _) => (DIM, DIM.to_string()),
+        (_, true) => ("", format!("{}{}", ITALIC, name_color)),
+        (_, false) => ("", String::new()),
+    };
+
+    let mut text = String::new();
+    for (index, word) in msg.message_text.split(' ').enumerate() {
+        if index > 0 {
+            text.push(' ');
+        }
+        let word = printable(word);
+        if twitch_emotes.contains(word.as_str()) || find_emote(emote_map, &word).is_some() {
+            text.push_str(&format!("{}{}{}{}", EMOTE, word, RESET, text_style));
+        } else {
+            text.push_str(&word);
+        }
+    }
+
+    format!(
+        "{line}{DIM}[{timestamp}]{RESET} {line}{BOLD}{name_color}{name}{RESET}{line}{separator}{text_style}{text}{RESET}",
+        line = line_style
+    )
+}
+
+/// A server notice, like "This room is now in slow mode", as a dim line
+pub fn format_notice_line(text: &str, color: bool) -> String {
+    let text = printable(text);
+    if color {
+        format!("{}-- {}{}", DIM, text, RESET)
+    } else {
+        format!("-- {}", text)
+    }
+}
@@ -0,0 +1,263 @@
+// transport.rs
+
+use async_trait::async_trait;
+use async_tungstenite::tungstenite::Message;
+use either::Either;
+use futures_util::stream::{self, FusedStream};
+use futures_util::{future, sink, Sink, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::UnboundedReceiver;
+use twitch_irc::login::StaticLoginCredentials;
+use twitch_irc::message::{AsRawIRC, IRCMessage, IRCParseError, ServerMessage};
+use twitch_irc::transport::Transport;
+use twitch_irc::{ClientConfig, PlainTCPTransport, PlainWSTransport, SecureTCPTransport, SecureWSTransport, TwitchIRCClient};
+
+use crate::network::{network_settings, NetworkSettings};
+use crate::tunnel::{self, chat_proxy, io_error, tls_connect, ChatStream};
+
+// twitch-irc's own transports connect straight to Twitch's fixed hosts: irc.chat.twitch.tv
+// on 6697 or 6667, irc-ws.chat.twitch.tv on 443 or 80. ConfiguredTransport covers proxies
+// and other chat servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum ChatTransport {
+    #[default]
+    SecureTcp,
+    PlainTcp,
+    SecureWebSocket, // Goes through networks that only allow HTTPS
+    PlainWebSocket,
+}
+
+impl ChatTransport {
+    pub const ALL: [ChatTransport; 4] = [
+        ChatTransport::SecureTcp,
+        ChatTransport::PlainTcp,
+        ChatTransport::SecureWebSocket,
+        ChatTransport::PlainWebSocket,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChatTransport::SecureTcp => "IRC over TLS (port 6697)",
+            ChatTransport::PlainTcp => "Plain IRC (port 6667)",
+            ChatTransport::SecureWebSocket => "Secure WebSocket (port 443)",
+            ChatTransport::PlainWebSocket => "WebSocket (port 80)",
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        Self::ALL.iter().position(|t| *t == self).unwrap_or(0) as u32
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+
+    pub fn default_host(self) -> &'static str {
+        match self {
+            ChatTransport::SecureTcp | ChatTransport::PlainTcp => "irc.chat.twitch.tv",
+            ChatTransport::SecureWebSocket | ChatTransport::PlainWebSocket => "irc-ws.chat.twitch.tv",
+        }
+    }
+
+    pub fn default_port(self) -> u16 {
+        match self {
+            ChatTransport::SecureTcp => 6697,
+            ChatTransport::PlainTcp => 6667,
+            ChatTransport::SecureWebSocket => 443,
+            ChatTransport::PlainWebSocket => 80,
+        }
+    }
+
+    pub fn is_tls(self) -> bool {
+        matches!(self, ChatTransport::SecureTcp | ChatTransport::SecureWebSocket)
+    }
+
+    pub fn is_websocket(self) -> bool {
+        matches!(self, ChatTransport::SecureWebSocket | ChatTransport::PlainWebSocket)
+    }
+}
+
+/// Whether chat needs ConfiguredTransport under `settings` rather than twitch-irc's own
+pub fn needs_configured_transport(settings: &NetworkSettings) -> bool {
+    chat_proxy(settings).is_some() || !settings.chat_host.is_empty() || settings.chat_port != 0
+}
+
+/// The chat server to connect to, falling back to Twitch's for the chosen transport
+pub fn chat_endpoint(settings: &NetworkSettings) -> (String, u16) {
+    let transport = settings.chat_transport;
+    let host = if settings.chat_host.is_empty() { transport.default_host().to_string() } else { settings.chat_host.clone() };
+    let port = if settings.chat_port == 0 { transport.default_port() } else { settings.chat_port };
+    (host, port)
+}
+
+type IncomingMessages = Pin<Box<dyn FusedStream<Item = Result<IRCMessage, Either<io::Error, IRCParseError>>> + Send + Sync>>;
+type OutgoingMessages = Pin<Box<dyn Sink<IRCMessage, Error = io::Error> + Send + Sync>>;
+
+/// A chat connection to the configured server and through the configured proxy, speaking
+/// whichever transport the settings chose. The settings are read on every (re)connect.
+pub struct ConfiguredTransport {
+    incoming: IncomingMessages,
+    outgoing: OutgoingMessages,
+}
+
+impl fmt::Debug for ConfiguredTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfiguredTransport").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Transport for ConfiguredTransport {
+    type ConnectError = io::Error;
+    type IncomingError = io::Error;
+    type OutgoingError = io::Error;
+    type Incoming = IncomingMessages;
+    type Outgoing = OutgoingMessages;
+
+    async fn new() -> Result<Self, io::Error> {
+        let settings = network_settings();
+        let transport = settings.chat_transport;
+        let (host, port) = chat_endpoint(&settings);
+        let mut stream = tunnel::connect(&host, port, chat_proxy(&settings).as_ref()).await?;
+        if transport.is_tls() {
+            stream = tls_connect(&host, stream).await?;
+        }
+        let (incoming, outgoing) = if transport.is_websocket() {
+            let scheme = if transport.is_tls() { "wss" } else { "ws" };
+            websocket_messages(stream, &format!("{}://{}:{}", scheme, host, port)).await?
+        } else {
+            irc_messages(stream)
+        };
+        Ok(Self { incoming, outgoing })
+    }
+
+    fn split(self) -> (Self::Incoming, Self::Outgoing) {
+        (self.incoming, self.outgoing)
+    }
+}
+
+// Plain IRC: one message per CRLF-terminated line
+fn irc_messages(stream: Box<dyn ChatStream>) -> (IncomingMessages, OutgoingMessages) {
+    let (read_half, write_half) = tokio::io::split(stream);
+    let incoming = stream::unfold(BufReader::new(read_half).lines(), |mut lines| async move {
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) if line.is_empty() => {}
+                Ok(Some(line)) => return Some((IRCMessage::parse(&line).map_err(Either::Right), lines)),
+                Ok(None) => return None,
+                Err(e) => return Some((Err(Either::Left(e)), lines)),
+            }
+        }
+    });
+    let outgoing = sink::unfold(write_half, |mut write_half, message: IRCMessage| async move {
+        let mut line = message.as_raw_irc();
+        line.push_str("\r\n");
+        write_half.write_all(line.as_bytes()).await?;
+        Ok::<_, io::Error>(write_half)
+    });
+    (Box::pin(incoming.fuse()), Box::pin(outgoing))
+}
+
+// Twitch's WebSocket chat: IRC lines in text frames, possibly several to a frame
+async fn websocket_messages(stream: Box<dyn ChatStream>, url: &str) -> io::Result<(IncomingMessages, OutgoingMessages)> {
+    let (websocket, _) = async_tungstenite::tokio::client_async(url, stream).await.map_err(io_error)?;
+    let (frames_out, frames_in) = websocket.split();
+    let incoming = frames_in.flat_map(|frame| {
+        let messages: Vec<_> = match frame {
+            Ok(Message::Text(text)) => text
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| IRCMessage::parse(line).map_err(Either::Right))
+                .collect(),
+            // Pings are answered by the WebSocket library, and nothing else carries chat
+            Ok(_) => Vec::new(),
+            Err(e) => vec![Err(Either::Left(io_error(e)))],
+        };
+        stream::iter(messages)
+    });
+    let outgoing = frames_out
+        .sink_map_err(io_error)
+        .with(|message: IRCMessage| future::ready(Ok::<_, io::Error>(Message::Text(message.as_raw_irc()))));
+    Ok((Box::pin(incoming.fuse()), Box::pin(outgoing)))
+}
+
+// A connected client over whichever transport the settings chose
+#[derive(Clone)]
+pub enum ChatClient {
+    SecureTcp(TwitchIRCClient<SecureTCPTransport, StaticLoginCredentials>),
+    PlainTcp(TwitchIRCClient<PlainTCPTransport, StaticLoginCredentials>),
+    SecureWebSocket(TwitchIRCClient<SecureWSTransport, StaticLoginCredentials>),
+    PlainWebSocket(TwitchIRCClient<PlainWSTransport, StaticLoginCredentials>),
+    Configured(TwitchIRCClient<ConfiguredTransport, StaticLoginCredentials>),
+}
+
+impl ChatClient {
+    /// Starts a client, anonymous unless given a login and token; must run inside a
+    /// tokio runtime
+    pub fn connect(transport: ChatTransport, credentials: StaticLoginCredentials) -> (UnboundedReceiver<ServerMessage>, Self) {
+        let config = ClientConfig::new_simple(credentials);
+        if needs_configured_transport(&network_settings()) {
+            let (incoming, client) = TwitchIRCClient::new(config);
+            return (incoming, ChatClient::Configured(client));
+        }
+        match transport {
+            ChatTransport::SecureTcp => {
+                let (incoming, client) = TwitchIRCClient::new(config);
+                (incoming, ChatClient::SecureTcp(client))
+            }
+            ChatTransport::PlainTcp => {
+                let (incoming, client) = TwitchIRCClient::new(config);
+                (incoming, ChatClient::PlainTcp(client))
+            }
+            ChatTransport::SecureWebSocket => {
+                let (incoming, client) = TwitchIRCClient::new(config);
+                (incoming, ChatClient::SecureWebSocket(client))
+            }
+            ChatTransport::PlainWebSocket => {
+                let (incoming, client) = TwitchIRCClient::new(config);
+                (incoming, ChatClient::PlainWebSocket(client))
+            }
+        }
+    }
+
+    pub fn join(&self, channel: String) -> Result<(), String> {
+        let result = match self {
+            ChatClient::SecureTcp(client) => client.join(channel),
+            ChatClient::PlainTcp(client) => client.join(channel),
+            ChatClient::SecureWebSocket(client) => client.join(channel),
+            ChatClient::PlainWebSocket(client) => client.join(channel),
+            ChatClient::Configured(client) => client.join(channel),
+        };
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Needs a client connected with a login; anonymous ones can only read
+    pub async fn say(&self, channel: &str, message: String) -> Result<(), String> {
+        let channel = channel.to_string();
+        let result = match self {
+            ChatClient::SecureTcp(client) => client.say(channel, message).await,
+            ChatClient::PlainTcp(client) => client.say(channel, message).await,
+            ChatClient::SecureWebSocket(client) => client.say(channel, message).await,
+            ChatClient::PlainWebSocket(client) => client.say(channel, message).await,
+            ChatClient::Configured(client) => client.say(channel, message).await,
+        };
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Sends `message` as a reply to the message with id `parent_id`
+    pub async fn say_in_reply_to(&self, channel: &str, parent_id: &str, message: String) -> Result<(), String> {
+        let parent = (channel, parent_id);
+        let result = match self {
+            ChatClient::SecureTcp(client) => client.say_in_reply_to(&parent, message).await,
+            ChatClient::PlainTcp(client) => client.say_in_reply_to(&parent, message).await,
+            ChatClient::SecureWebSocket(client) => client.say_in_reply_to(&parent, message).await,
+            ChatClient::PlainWebSocket(client) => client.say_in_reply_to(&parent, message).await,
+            ChatClient::Configured(client) => client.say_in_reply_to(&parent, message).await,
+        };
+        result.map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,211 @@
+// tunnel.rs
+
+use base64::Engine;
+use percent_encoding::percent_decode_str;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use url::Url;
+
+use crate::network::{NetworkSettings, ProxyMode};
+
+// Largest CONNECT response we read before giving up on the proxy
+const MAX_CONNECT_RESPONSE: usize = 8192;
+const DEFAULT_SOCKS_PORT: u16 = 1080;
+
+/// A byte stream to a chat server, direct or through a proxy
+pub trait ChatStream: AsyncRead + AsyncWrite + Send + Sync + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin> ChatStream for T {}
+
+pub fn io_error(e: impl ToString) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// The proxy chat connections go through: the custom one, or for the system settings
+/// ALL_PROXY or HTTPS_PROXY from the environment, since IRC isn't plain HTTP
+pub fn chat_proxy(settings: &NetworkSettings) -> Option<Url> {
+    let url = match settings.proxy_mode {
+        ProxyMode::Direct => return None,
+        ProxyMode::Custom => settings.proxy_url.clone(),
+        ProxyMode::System => ["ALL_PROXY", "all_proxy", "HTTPS_PROXY", "https_proxy"]
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))?,
+    };
+    match Url::parse(&url) {
+        Ok(url) => Some(url),
+        Err(e) => {
+            eprintln!("Ignoring invalid proxy {}: {}", url, e);
+            None
+        }
+    }
+}
+
+/// Opens a connection to `host`:`port`, tunneled through `proxy` when there is one.
+/// HTTP(S) and socks5h proxies resolve `host` themselves, so chat works where only the
+/// proxy can look names up; with socks5 it is resolved here, as the scheme promises.
+pub async fn connect(host: &str, port: u16, proxy: Option<&Url>) -> io::Result<Box<dyn ChatStream>> {
+    let Some(proxy) = proxy else {
+        return Ok(Box::new(TcpStream::connect((host, port)).await?));
+    };
+    let proxy_host = proxy.host_str().ok_or_else(|| io_error("The proxy URL has no host"))?;
+    let username = percent_decode_str(proxy.username()).decode_utf8_lossy().to_string();
+    let password = percent_decode_str(proxy.password().unwrap_or_default()).decode_utf8_lossy().to_string();
+    let stream: Box<dyn ChatStream> = match proxy.scheme() {
+        "http" => {
+            let stream = TcpStream::connect((proxy_host, proxy.port_or_known_default().unwrap_or(80))).await?;
+            Box::new(http_connect(stream, host, port, &username, &password).await?)
+        }
+        // CONNECT sent inside TLS to the proxy, then the chat's own TLS (if any) inside that
+        "https" => {
+            let stream = TcpStream::connect((proxy_host, proxy.port_or_known_default().unwrap_or(443))).await?;
+            let stream = tls_connect(proxy_host, Box::new(stream)).await?;
+            Box::new(http_connect(stream, host, port, &username, &password).await?)
+        }
+        "socks5" | "socks5h" => {
+            let target = if proxy.scheme() == "socks5" {
+                let address = tokio::net::lookup_host((host, port))
+                    .await?
+                    .next()
+                    .ok_or_else(|| io_error(format!("No addresses found for {}", host)))?;
+                SocksTarget::Address(address)
+            } else {
+                SocksTarget::Name(host, port)
+            };
+            let stream = TcpStream::connect((proxy_host, proxy.port().unwrap_or(DEFAULT_SOCKS_PORT))).await?;
+            Box::new(socks5_connect(stream, target, &username, &password).await?)
+        }
+        other => return Err(io_error(format!("Chat can't go through {} proxies", other))),
+    };
+    Ok(stream)
+}
+
+/// Wraps `stream` in TLS for `host`, checked against the system's certificates
+pub async fn tls_connect(host: &str, stream: Box<dyn ChatStream>) -> io::Result<Box<dyn ChatStream>> {
+    let connector = tokio_native_tls::native_tls::TlsConnector::new().map_err(io_error)?;
+    let stream = tokio_native_tls::TlsConnector::from(connector)
+        .connect(host, stream)
+        .await
+        .map_err(io_error)?;
+    Ok(Box::new(stream))
+}
+
+// Asks an HTTP proxy for a tunnel with CONNECT
+async fn http_connect<S: ChatStream>(mut stream: S, host: &str, port: u16, username: &str, password: &str) -> io::Result<S> {
+    let mut request = format!("CONNECT {}:{} HTTP/1.1\r\nHost: {}:{}\r\n", host, port, host, port);
+    if !username.is_empty() {
+        let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // A byte at a time, so nothing past the headers (the tunnel's first bytes) is consumed
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_CONNECT_RESPONSE {
+            return Err(io_error("The proxy sent an oversized response"));
+        }
+        response.push(stream.read_u8().await?);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    // "HTTP/1.1 200 Connection established"
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(io_error(format!("The proxy refused the chat connection: {}", status_line)));
+    }
+    Ok(stream)
+}
+
+// Where a SOCKS5 proxy is asked to connect: a name it resolves, or an address we did
+enum SocksTarget<'a> {
+    Name(&'a str, u16),
+    Address(SocketAddr),
+}
+
+// The SOCKS5 handshake (RFC 1928), with username and password auth (RFC 1929) when the
+// proxy URL has them
+async fn socks5_connect(mut stream: TcpStream, target: SocksTarget<'_>, username: &str, password: &str) -> io::Result<TcpStream> {
+    let with_password = !username.is_empty();
+    let greeting: &[u8] = if with_password { &[5, 2, 0, 2] } else { &[5, 1, 0] };
+    stream.write_all(greeting).await?;
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await?;
+    match choice {
+        [5, 0] => {}
+        [5, 2] if with_password => {
+            if username.len() > 255 || password.len() > 255 {
+                return Err(io_error("The proxy username or password is too long"));
+            }
+            let mut auth = vec![1, username.len() as u8];
+            auth.extend_from_slice(username.as_bytes());
+            auth.push(password.len() as u8);
+            auth.extend_from_slice(password.as_bytes());
+            stream.write_all(&auth).await?;
+            let mut status = [0u8; 2];
+            stream.read_exact(&mut status).await?;
+            if status[1] != 0 {
+                return Err(io_error("The proxy rejected the username or password"));
+            }
+        }
+        _ => return Err(io_error("The proxy accepts none of our login methods")),
+    }
+
+    let mut request = vec![5, 1, 0];
+    let port = match target {
+        SocksTarget::Name(host, port) => {
+            if host.len() > 255 {
+                return Err(io_error("The chat server name is too long for SOCKS"));
+            }
+            request.extend_from_slice(&[3, host.len() as u8]);
+            request.extend_from_slice(host.as_bytes());
+            port
+        }
+        SocksTarget::Address(address) => {
+            match address.ip() {
+                IpAddr::V4(ip) => {
+                    request.push(1);
+                    request.extend_from_slice(&ip.octets());
+                }
+                IpAddr::V6(ip) => {
+                    request.push(4);
+                    request.extend_from_slice(&ip.octets());
+                }
+            }
+            address.port()
+        }
+    };
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0 {
+        return Err(io_error(format!("The proxy couldn't reach the chat server ({})", socks5_error(reply[1]))));
+    }
+    // The address the proxy connected from, which we don't need
+    let address_len = match reply[3] {
+        1 => 4,
+        3 => stream.read_u8().await? as usize,
+        4 => 16,
+        _ => return Err(io_error("The proxy sent an unknown address type")),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(stream)
+}
+
+fn socks5_error(code: u8) -> &'static str {
+    match code {
+        1 => "general failure",
+        2 => "not allowed by its rules",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        7 => "command not supported",
+        8 => "address type not supported",
+        _ => "unknown error",
+    }
+}
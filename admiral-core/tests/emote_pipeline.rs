@@ -0,0 +1,302 @@
+// Emote pipeline against a mock 7TV API: cargo test -p admiral-core --test emote_pipeline
+
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use admiral_core::emotes::{cleanup_emote_cache_at, download_emote_urls, emote_cache_info, store_emote_map};
+use admiral_core::fetch_scheduler::{FetchError, FetchScheduler};
+use admiral_core::network::{configure_network, NetworkSettings, ProxyMode};
+use admiral_core::seventv::configure_seventv_api;
+
+type EmoteMap = HashMap<String, (String, bool)>;
+
+// The API base and the emote cache are global, so tests take turns
+static SERIAL: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+static FETCHES: Lazy<FetchScheduler> =
+    Lazy::new(|| FetchScheduler::new("test", 1, 3, Duration::from_secs(60), fetch_into_cache));
+
+fn fetch_into_cache(channel_id: &str) -> Result<(), FetchError> {
+    let (emote_map, aliases) = download_emote_urls(channel_id)?;
+    store_emote_map(channel_id, emote_map, aliases);
+    Ok(())
+}
+
+async fn mock_7tv() -> MockServer {
+    let server = MockServer::start().await;
+    configure_seventv_api(&format!("{}/v3", server.uri()));
+    configure_network(&NetworkSettings {
+        proxy_mode: ProxyMode::Direct,
+        ..NetworkSettings::default()
+    });
+    server
+}
+
+async fn download(channel_id: &str) -> Result<(EmoteMap, HashMap<String, String>), FetchError> {
+    let channel_id = channel_id.to_string();
+    tokio::task::spawn_blocking(move || download_emote_urls(&channel_id))
+        .await
+        .expect("download panicked")
+}
+
+// FetchError has no Debug, so Result::unwrap and friends don't apply
+fn expect_ok<T>(result: Result<T, FetchError>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => panic!("expected success, got: {}", e.message),
+    }
+}
+
+fn expect_err<T>(result: Result<T, FetchError>) -> FetchError {
+    match result {
+        Ok(_) => panic!("expected an error"),
+        Err(e) => e,
+    }
+}
+
+async fn wait_until_fetched(channel_id: &str) {
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while FETCHES.is_scheduled(channel_id) {
+        assert!(Instant::now() < deadline, "fetch for {} never finished", channel_id);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+fn emote(id: &str, name: &str, original: &str, flags: i32, files: &[(&str, &str)]) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "data": {
+            "name": original,
+            "flags": flags,
+            "host": {
+                "url": format!("//cdn.7tv.app/emote/{}", id),
+                "files": files.iter().map(|(name, format)| json!({ "name": name, "format": format })).collect::<Vec<_>>(),
+            },
+        },
+    })
+}
+
+fn sample_emotes() -> Vec<Value> {
+    vec![
+        emote("a1", "Clap", "Clap", 0, &[("2x.webp", "WEBP"), ("1x.webp", "WEBP"), ("1x.gif", "GIF")]),
+        emote("b2", "RainTime", "RainTime", 256, &[("1x.png", "PNG")]),
+        emote("c3", "catJAM", "catJAM2", 0, &[("1x.webp", "WEBP")]),
+        json!({ "id": "d4", "name": "NoData" }),
+        json!({ "id": "e5", "name": "NoHost", "data": { "name": "NoHost", "flags": 0 } }),
+        json!({ "id": "f6", "name": "EmptyHost", "data": { "flags": 0, "host": { "url": " ", "files": [] } } }),
+    ]
+}
+
+fn rest_user(emotes: Vec<Value>) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "id": "user", "emote_set": { "id": "set", "emotes": emotes } }))
+}
+
+#[tokio::test]
+async fn rest_response_becomes_emote_urls() {
+    let _serial = SERIAL.lock().await;
+    let server = mock_7tv().await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1001"))
+        .respond_with(rest_user(sample_emotes()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (emote_map, aliases) = expect_ok(download("1001").await);
+
+    assert_eq!(emote_map.len(), 3, "entries without data or host are skipped");
+    // 1x is preferred, and GIF over other formats
+    assert_eq!(emote_map["Clap"], ("https://cdn.7tv.app/emote/a1/1x.gif".to_string(), false));
+    assert_eq!(emote_map["RainTime"], ("https://cdn.7tv.app/emote/b2/1x.png".to_string(), true));
+    assert_eq!(emote_map["catJAM"].0, "https://cdn.7tv.app/emote/c3/1x.webp");
+    assert_eq!(aliases.get("catJAM2").map(String::as_str), Some("catJAM"));
+    assert_eq!(aliases.len(), 1);
+}
+
+#[tokio::test]
+async fn channel_without_emote_set_is_empty() {
+    let _serial = SERIAL.lock().await;
+    let server = mock_7tv().await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1002"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "user", "emote_set": null })))
+        .mount(&server)
+        .await;
+
+    let (emote_map, _) = expect_ok(download("1002").await);
+    assert!(emote_map.is_empty());
+}
+
+#[tokio::test]
+async fn changed_rest_shape_falls_back_to_graphql() {
+    let _serial = SERIAL.lock().await;
+    let server = mock_7tv().await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1003"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "user", "emoteSetV4": {} })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v3/gql"))
+        .and(body_string_contains("userByConnection"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "userByConnection": { "connections": [
+                { "platform": "YOUTUBE", "emote_set_id": "wrong" },
+                { "platform": "TWITCH", "emote_set_id": "set-1003" },
+            ] } }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v3/gql"))
+        .and(body_string_contains("emoteSet"))
+        .and(body_string_contains("set-1003"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "emoteSet": { "emotes": sample_emotes() } }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (emote_map, aliases) = expect_ok(download("1003").await);
+    assert_eq!(emote_map.len(), 3);
+    assert!(emote_map["RainTime"].1);
+    assert_eq!(aliases.get("catJAM2").map(String::as_str), Some("catJAM"));
+}
+
+#[tokio::test]
+async fn unknown_shape_on_every_endpoint_is_fatal() {
+    let _serial = SERIAL.lock().await;
+    let server = mock_7tv().await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1004"))
+        .respond_with(rest_user(vec![json!({ "id": 1 }), json!("not an emote")]))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v3/gql"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "errors": [{ "message": "unknown field" }] })))
+        .mount(&server)
+        .await;
+
+    let error = expect_err(download("1004").await);
+    assert!(!error.retryable);
+    assert!(error.message.contains("known shape"), "{}", error.message);
+}
+
+#[tokio::test]
+async fn rate_limits_and_server_errors_are_retryable() {
+    let _serial = SERIAL.lock().await;
+    let server = mock_7tv().await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1005"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1006"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1007"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+
+    assert!(expect_err(download("1005").await).retryable);
+    // Retry-After holds back the next request to the host
+    let started = Instant::now();
+    assert!(expect_err(download("1006").await).retryable);
+    assert!(started.elapsed() >= Duration::from_millis(900), "the 429 backoff was not honoured");
+    assert!(!expect_err(download("1007").await).retryable);
+}
+
+#[tokio::test]
+async fn scheduler_retries_server_errors_with_backoff() {
+    let _serial = SERIAL.lock().await;
+    let server = mock_7tv().await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1008"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1008"))
+        .respond_with(rest_user(sample_emotes()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let started = Instant::now();
+    assert!(FETCHES.request("1008"));
+    assert!(!FETCHES.request("1008"), "a second request joins the running fetch");
+    wait_until_fetched("1008").await;
+
+    // Waits of at least 1s and 2s before the second and third attempts
+    assert!(started.elapsed() >= Duration::from_secs(3));
+    assert_eq!(emote_cache_info("1008").seventv, 3);
+}
+
+#[tokio::test]
+async fn scheduler_gives_up_on_client_errors() {
+    let _serial = SERIAL.lock().await;
+    let server = mock_7tv().await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users/twitch/1009"))
+        .respond_with(ResponseTemplate::new(403))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    assert!(FETCHES.request("1009"));
+    wait_until_fetched("1009").await;
+
+    assert!(emote_cache_info("1009").loaded_at.is_none());
+    assert!(!FETCHES.request("1009"), "a failed key cools down before the next try");
+}
+
+fn sized_map(prefix: &str, size: usize) -> EmoteMap {
+    (0..size)
+        .map(|i| (format!("{}{}", prefix, i), (format!("https://cdn.7tv.app/emote/{}{}/1x.webp", prefix, i), false)))
+        .collect()
+}
+
+#[tokio::test]
+async fn cleanup_expires_maps_after_an_hour() {
+    let _serial = SERIAL.lock().await;
+    store_emote_map("2001", sized_map("Old", 10), HashMap::new());
+    let stored = Instant::now();
+
+    cleanup_emote_cache_at(stored + Duration::from_secs(30 * 60));
+    assert_eq!(emote_cache_info("2001").seventv, 10, "a half hour old map stays");
+
+    cleanup_emote_cache_at(stored + Duration::from_secs(61 * 60));
+    let info = emote_cache_info("2001");
+    assert_eq!(info.seventv, 0);
+    assert!(info.loaded_at.is_none());
+}
+
+#[tokio::test]
+async fn cleanup_drops_oldest_maps_over_the_total_limit() {
+    let _serial = SERIAL.lock().await;
+    store_emote_map("2002", sized_map("Older", 3000), HashMap::new());
+    std::thread::sleep(Duration::from_millis(5));
+    store_emote_map("2003", sized_map("Newer", 3000), HashMap::new());
+
+    cleanup_emote_cache_at(Instant::now());
+    assert_eq!(emote_cache_info("2002").seventv, 0, "the oldest map goes first");
+    assert_eq!(emote_cache_info("2003").seventv, 3000);
+
+    cleanup_emote_cache_at(Instant::now() + Duration::from_secs(2 * 60 * 60));
+    assert_eq!(emote_cache_info("2003").total_emotes, 0);
+}
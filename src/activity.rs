@@ -0,0 +1,278 @@
+// activity.rs
+
+use adw::gio;
+use adw::prelude::*;
+use chrono::{DateTime, Local};
+use gtk::{Align, Box as GtkBox, Button, Label, ListBox, Orientation, ScrolledWindow};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::profile::data_dir;
+use crate::quiet_hours::is_quiet;
+use crate::state::MutexExt;
+
+const MAX_EVENTS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ActivityKind {
+    Mention,
+    Highlight,
+    GoLive,
+    ConnectionError,
+}
+
+impl ActivityKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ActivityKind::Mention => "Mention",
+            ActivityKind::Highlight => "Highlighted message",
+            ActivityKind::GoLive => "Went live",
+            ActivityKind::ConnectionError => "Connection error",
+        }
+    }
+
+    pub fn icon_name(self) -> &'static str {
+        match self {
+            ActivityKind::Mention => "user-available-symbolic",
+            ActivityKind::Highlight => "starred-symbolic",
+            ActivityKind::GoLive => "media-record-symbolic",
+            ActivityKind::ConnectionError => "network-error-symbolic",
+        }
+    }
+}
+
+// One entry in the notification center, kept across restarts
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ActivityEvent {
+    pub kind: ActivityKind,
+    pub channel: String,
+    pub text: String,
+    pub sender: Option<String>,
+    pub message_id: Option<String>, // For jumping back to the message
+    pub time: String, // RFC 3339
+    #[serde(default)]
+    pub unread: bool, // Cleared once the channel has been seen by an active user
+}
+
+impl ActivityEvent {
+    pub fn new(kind: ActivityKind, channel: &str, text: &str) -> Self {
+        Self {
+            kind,
+            channel: channel.to_string(),
+            text: text.to_string(),
+            sender: None,
+            message_id: None,
+            time: Local::now().to_rfc3339(),
+            unread: false,
+        }
+    }
+
+    pub fn local_time(&self) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&self.time)
+            .ok()
+            .map(|time| time.with_timezone(&Local))
+    }
+}
+
+// Oldest first; loaded from disk on first use
+static EVENTS: Lazy<Mutex<Vec<ActivityEvent>>> = Lazy::new(|| Mutex::new(load_events()));
+
+fn activity_path() -> PathBuf {
+    data_dir().join("activity.json")
+}
+
+fn load_events() -> Vec<ActivityEvent> {
+    let Ok(contents) = fs::read_to_string(activity_path()) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        eprintln!("Failed to parse activity history: {}", e);
+        Vec::new()
+    })
+}
+
+fn save_events(events: &[ActivityEvent]) {
+    let path = activity_path();
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            eprintln!("Failed to create data directory: {}", e);
+            return;
+        }
+    }
+    match serde_json::to_string(events) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                eprintln!("Failed to write activity history: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize activity history: {}", e),
+    }
+}
+
+pub fn record_activity(event: ActivityEvent) {
+    notify_if_hidden(&event);
+    let mut events = EVENTS.locked();
+    events.push(event);
+    if events.len() > MAX_EVENTS {
+        let excess = events.len() - MAX_EVENTS;
+        events.drain(..excess);
+    }
+    save_events(&events);
+}
+
+// While the window is hidden in the background, events also go to the desktop,
+// except during quiet hours
+fn notify_if_hidden(event: &ActivityEvent) {
+    if is_quiet() {
+        return;
+    }
+    let title = match event.kind {
+        ActivityKind::ConnectionError => return,
+        ActivityKind::GoLive => format!("{} went live", event.channel),
+        kind => format!("{} in #{}", kind.label(), event.channel),
+    };
+    let body = match &event.sender {
+        Some(sender) => format!("{}: {}", sender, event.text),
+        None => event.text.clone(),
+    };
+    // Later events from the same channel replace the earlier notification
+    let id = format!("{:?}-{}", event.kind, event.channel);
+    // Go-live checks record from a worker thread
+    glib::MainContext::default().invoke(move || {
+        let Some(app) = gio::Application::default().and_downcast::<gtk::Application>() else {
+            return;
+        };
+        if app.windows().iter().any(|window| window.is_visible()) {
+            return;
+        }
+        let notification = gio::Notification::new(&title);
+        notification.set_body(Some(&body));
+        app.send_notification(Some(&id), &notification);
+    });
+}
+
+/// All events, newest first
+pub fn activity_events() -> Vec<ActivityEvent> {
+    EVENTS.locked().iter().rev().cloned().collect()
+}
+
+pub fn unread_activity_count() -> usize {
+    EVENTS.locked().iter().filter(|event| event.unread).count()
+}
+
+/// Marks every event from `channel` as read
+pub fn mark_channel_read(channel: &str) {
+    let mut events = EVENTS.locked();
+    let mut changed = false;
+    for event in events.iter_mut().filter(|event| event.unread && event.channel == channel) {
+        event.unread = false;
+        changed = true;
+    }
+    if changed {
+        save_events(&events);
+    }
+}
+
+pub fn clear_activity() {
+    let mut events = EVENTS.locked();
+    events.clear();
+    save_events(&events);
+}
+
+// --- Notification Center Panel ---
+
+pub fn build_activity_panel() -> (GtkBox, ListBox) {
+    let panel = GtkBox::new(Orientation::Vertical, 6);
+    panel.set_margin_top(6);
+    panel.set_margin_bottom(6);
+    panel.set_margin_start(6);
+    panel.set_margin_end(6);
+
+    let header = GtkBox::new(Orientation::Horizontal, 6);
+    let title = Label::new(Some("Activity"));
+    title.add_css_class("heading");
+    title.set_hexpand(true);
+    title.set_halign(Align::Start);
+    let clear_button = Button::builder()
+        .icon_name("edit-clear-all-symbolic")
+        .tooltip_text("Clear history")
+        .build();
+    clear_button.add_css_class("flat");
+    header.append(&title);
+    header.append(&clear_button);
+
+    let list = ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .build();
+    list.add_css_class("boxed-list");
+    let scrolled = ScrolledWindow::builder()
+        .vexpand(true)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .child(&list)
+        .build();
+
+    panel.append(&header);
+    panel.append(&scrolled);
+
+    let list_for_clear = list.clone();
+    clear_button.connect_clicked(move |_| {
+        clear_activity();
+        refresh_activity_list(&list_for_clear);
+    });
+
+    refresh_activity_list(&list);
+    (panel, list)
+}
+
+pub fn refresh_activity_list(list: &ListBox) {
+    list.remove_all();
+    let events = activity_events();
+    if events.is_empty() {
+        let empty_label = Label::new(Some("No activity yet"));
+        empty_label.add_css_class("dim-label");
+        empty_label.set_margin_top(12);
+        empty_label.set_margin_bottom(12);
+        let row = gtk::ListBoxRow::builder()
+            .child(&empty_label)
+            .activatable(false)
+            .selectable(false)
+            .build();
+        list.append(&row);
+        return;
+    }
+
+    for event in events {
+        let time = event
+            .local_time()
+            .map(|time| time.format("%b %-d, %-I:%M %p").to_string())
+            .unwrap_or_default();
+        let subtitle = match &event.sender {
+            Some(sender) => format!("{} · {} · {}", event.channel, sender, time),
+            None => format!("{} · {}", event.channel, time),
+        };
+        let row = adw::ActionRow::builder()
+            .title(glib::markup_escape_text(&event.text).as_str())
+            .subtitle(glib::markup_escape_text(&subtitle).as_str())
+            .title_lines(3)
+            .tooltip_text(event.kind.label())
+            .build();
+        row.add_prefix(&gtk::Image::from_icon_name(event.kind.icon_name()));
+        if event.unread {
+            let unread_icon = gtk::Image::from_icon_name("mail-unread-symbolic");
+            unread_icon.add_css_class("accent");
+            unread_icon.set_tooltip_text(Some("Unread"));
+            row.add_suffix(&unread_icon);
+        }
+        if let Some(message_id) = event.message_id {
+            row.set_activatable(true);
+            let target = (event.channel, message_id).to_variant();
+            row.connect_activated(move |row| {
+                let _ = row.activate_action("win.jump-to-message", Some(&target));
+            });
+        }
+        list.append(&row);
+    }
+}
@@ -0,0 +1,235 @@
+// appearance.rs
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// Defines the chat page's `applySettings`, which takes everything the app controls about
+/// the page's look as `{ vars: { name: value or null }, classes: { name: on }, frameCap }`.
+/// Only what's given changes. Exported sessions carry it too, so it stands on its own.
+pub const APPLY_SETTINGS_JS: &str = r#"window.applySettings = function(settings) {
+  const style = document.documentElement.style;
+  for (const [name, value] of Object.entries(settings.vars || {})) {
+    if (value === null) { style.removeProperty(name); } else { style.setProperty(name, value); }
+  }
+  for (const [name, on] of Object.entries(settings.classes || {})) {
+    document.body.classList.toggle(name, on);
+  }
+  if (settings.frameCap !== undefined && window.setFrameCap) { setFrameCap(settings.frameCap); }
+};"#;
+
+/// JS handing `settings` to a loaded chat page's applySettings
+pub fn apply_settings_js(settings: &Value) -> String {
+    format!("if (window.applySettings) {{ applySettings({}); }}", settings)
+}
+
+/// The `vars` of page settings as a `:root` rule for a WebKit user style sheet, null ones
+/// left out so the page's defaults apply. User-origin `!important` beats the page's own
+/// declarations of the same variables.
+pub fn vars_css(vars: &Map<String, Value>) -> String {
+    let mut css = String::from(":root {\n");
+    for (name, value) in vars {
+        let Some(value) = value.as_str() else {
+            continue;
+        };
+        // Values are colors and sizes; anything that could end the rule is dropped
+        if value.contains(['{', '}', ';', '<']) {
+            eprintln!("Ignoring unsafe value for {}: {}", name, value);
+            continue;
+        }
+        css.push_str(&format!("  {}: {} !important;\n", name, value));
+    }
+    css.push('}');
+    css
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum Density {
+    Cozy,
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+impl Density {
+    pub const ALL: [Density; 3] = [Density::Cozy, Density::Comfortable, Density::Compact];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Density::Cozy => "Cozy",
+            Density::Comfortable => "Comfortable",
+            Density::Compact => "Compact",
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        Self::ALL.iter().position(|d| *d == self).unwrap_or(0) as u32
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+
+    // (padding, spacing between messages, border width, corner radius)
+    fn metrics(self) -> (&'static str, &'static str, &'static str, &'static str) {
+        match self {
+            Density::Cozy => ("12px", "8px", "1px", "10px"),
+            Density::Comfortable => ("8px", "4px", "1px", "8px"),
+            Density::Compact => ("2px 6px", "1px", "0px", "4px"),
+        }
+    }
+
+    /// The message box CSS variables used by the chat template
+    fn css_variables(self) -> [(&'static str, &'static str); 4] {
+        let (padding, spacing, border, radius) = self.metrics();
+        [
+            ("--message-padding", padding),
+            ("--message-spacing", spacing),
+            ("--message-border-width", border),
+            ("--message-radius", radius),
+        ]
+    }
+}
+
+// How often animated emotes may redraw; lower rates spare older hardware in emote-heavy chats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum AnimationLimit {
+    #[default]
+    Unlimited,
+    Fps30,
+    Fps15,
+    Fps5,
+    Still, // First frame only
+}
+
+impl AnimationLimit {
+    pub const ALL: [AnimationLimit; 5] = [
+        AnimationLimit::Unlimited,
+        AnimationLimit::Fps30,
+        AnimationLimit::Fps15,
+        AnimationLimit::Fps5,
+        AnimationLimit::Still,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AnimationLimit::Unlimited => "No Limit",
+            AnimationLimit::Fps30 => "30 fps",
+            AnimationLimit::Fps15 => "15 fps",
+            AnimationLimit::Fps5 => "5 fps",
+            AnimationLimit::Still => "Don't Animate",
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        Self::ALL.iter().position(|l| *l == self).unwrap_or(0) as u32
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+
+    // Milliseconds between redraws as the chat page's setFrameCap takes it: 0 for no cap, -1 for still
+    fn frame_interval_ms(self) -> i32 {
+        match self {
+            AnimationLimit::Unlimited => 0,
+            AnimationLimit::Fps30 => 1000 / 30,
+            AnimationLimit::Fps15 => 1000 / 15,
+            AnimationLimit::Fps5 => 1000 / 5,
+            AnimationLimit::Still => -1,
+        }
+    }
+}
+
+// Where sender names take their color from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum NameColors {
+    #[default]
+    Twitch, // The color each chatter picked
+    Accent, // The system accent color
+    Plain, // The theme's text color
+}
+
+impl NameColors {
+    pub const ALL: [NameColors; 3] = [NameColors::Twitch, NameColors::Accent, NameColors::Plain];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NameColors::Twitch => "Chatter's Color",
+            NameColors::Accent => "Accent Color",
+            NameColors::Plain => "Text Color",
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        Self::ALL.iter().position(|c| *c == self).unwrap_or(0) as u32
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+}
+
+// Stored under [appearance] in favorites.toml
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppearanceSettings {
+    pub enlarge_emote_only: bool, // Render messages made only of emotes at 2x size
+    pub density: Density,
+    pub timestamps_on_hover: bool,
+    pub emoji_images: bool, // Twemoji pictures instead of WebKit's font fallback
+    pub animation_limit: AnimationLimit,
+    pub accent_colors: bool, // Highlights, mentions and links in the system accent color
+    pub name_colors: NameColors,
+    pub font_size: u32, // Pixels
+    pub emote_size: u32, // Pixels of height; emote-only messages get twice that
+}
+
+impl Default for AppearanceSettings {
+    fn default() -> Self {
+        Self {
+            enlarge_emote_only: true,
+            density: Density::Comfortable,
+            timestamps_on_hover: false,
+            emoji_images: false,
+            animation_limit: AnimationLimit::Unlimited,
+            accent_colors: false,
+            name_colors: NameColors::Twitch,
+            font_size: 16,
+            emote_size: 28,
+        }
+    }
+}
+
+impl AppearanceSettings {
+    /// These settings in the form the chat page's applySettings takes
+    pub fn page_settings(&self) -> Value {
+        let mut vars: Map<String, Value> = self
+            .density
+            .css_variables()
+            .iter()
+            .map(|(name, value)| (name.to_string(), json!(value)))
+            .collect();
+        vars.insert("--chat-font-size".to_string(), json!(format!("{}px", self.font_size)));
+        vars.insert("--emote-size".to_string(), json!(format!("{}px", self.emote_size)));
+        json!({
+            "vars": vars,
+            "classes": {
+                "timestamps-on-hover": self.timestamps_on_hover,
+                "accent-colors": self.accent_colors,
+                "names-accent": self.name_colors == NameColors::Accent,
+                "names-plain": self.name_colors == NameColors::Plain,
+            },
+            "frameCap": self.animation_limit.frame_interval_ms(),
+        })
+    }
+
+    /// JS that applies the classes and frame cap to an already loaded chat page. The
+    /// variables reach live pages through their user style sheet instead.
+    pub fn apply_js(&self) -> String {
+        let mut settings = self.page_settings();
+        if let Some(settings) = settings.as_object_mut() {
+            settings.remove("vars");
+        }
+        apply_settings_js(&settings)
+    }
+}
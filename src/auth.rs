@@ -5,19 +5,44 @@ use keyring::Entry as KeyringEntry;
 use reqwest::Client;
 use std::sync::Arc;
 use open;
+use crate::helix::{forget_own_user, refresh_token_info, revoke_token};
+use crate::network::{async_http_client, http_client};
+use crate::profile::profile;
+use crate::scopes::{authorize_url, set_token_info, Feature};
+use crate::token_store::{delete_token_file, has_token_file, save_token_file, unlock_token_file, unlocked_token};
 use glib::MainContext;
 
-const CLIENT_ID: &str = "your_client_id";
-const REDIRECT_URI: &str = "http://localhost:8080";
+pub const CLIENT_ID: &str = "your_client_id";
+pub const REDIRECT_URI: &str = "http://localhost:8080";
+const KEYRING_SERVICE: &str = "your_app_name";
+const KEYRING_USER: &str = "twitch_token";
+
+// Each profile signs in separately
+fn keyring_user() -> String {
+    match profile() {
+        Some(name) => format!("{}:{}", KEYRING_USER, name),
+        None => KEYRING_USER.to_string(),
+    }
+}
+
+fn keyring_entry() -> Option<KeyringEntry> {
+    KeyringEntry::new(KEYRING_SERVICE, &keyring_user()).ok()
+}
+
+// A missing entry still means there's a Secret Service to store one in
+fn keyring_available(keyring: &KeyringEntry) -> bool {
+    matches!(keyring.get_password(), Ok(_) | Err(keyring::Error::NoEntry))
+}
 
 pub struct AuthWindow {
     window: ApplicationWindow,
     client: Arc<Client>,
-    keyring: Arc<KeyringEntry>,
+    keyring: Option<Arc<KeyringEntry>>, // None without a Secret Service, then the token goes to an encrypted file
+    features: Vec<Feature>, // Asked for on top of what the saved token already has
 }
 
 impl AuthWindow {
-    pub fn new(app: &Application) -> Self {
+    pub fn new(app: &Application, features: Vec<Feature>) -> Self {
         let window = ApplicationWindow::builder()
             .application(app)
             .title("Twitch Login")
@@ -25,13 +50,14 @@ impl AuthWindow {
             .default_height(200)
             .build();
 
-        let keyring = Arc::new(KeyringEntry::new("your_app_name", "twitch_token").unwrap());
-        let client = Arc::new(Client::new());
+        let keyring = keyring_entry().filter(keyring_available).map(Arc::new);
+        let client = Arc::new(async_http_client());
 
         Self {
             window,
             client,
             keyring,
+            features,
         }
     }
 
@@ -46,6 +72,21 @@ impl AuthWindow {
         // Main content with padding
         let content_box = GtkBox::new(Orientation::Vertical, 20);
 
+        if !self.features.is_empty() {
+            let labels: Vec<&str> = self.features.iter().map(|feature| feature.label()).collect();
+            let reason = Label::builder()
+                .label(format!(
+                    "{} needs permissions your saved token doesn't have. Log in again and save the new token.",
+                    labels.join(", ")
+                ))
+                .wrap(true)
+                .margin_top(10)
+                .margin_start(20)
+                .margin_end(20)
+                .build();
+            content_box.append(&reason);
+        }
+
         let login_button = Button::with_label("Login");
         login_button.set_margin_top(0);
         login_button.set_margin_bottom(10);
@@ -79,14 +120,12 @@ impl AuthWindow {
 
         // Clone necessary references for async callbacks
         let keyring = self.keyring.clone();
+        let window = self.window.clone();
+        let features = self.features.clone();
 
         // Open Twitch login URL
         login_button.connect_clicked(move |_| {
-            let auth_url = format!(
-                "https://id.twitch.tv/oauth2/authorize?client_id={}&redirect_uri={}&response_type=code&scope=chat:read+chat:edit",
-                CLIENT_ID, REDIRECT_URI
-            );
-            if open::that(auth_url).is_err() {
+            if open::that(authorize_url(&features)).is_err() {
                 eprintln!("Failed to open browser");
             }
         });
@@ -94,16 +133,21 @@ impl AuthWindow {
         // Save access token
         save_button.connect_clicked(move |_| {
             let token = token_entry.text().to_string();
-            if !token.is_empty() {
-                let keyring = keyring.clone();
-                MainContext::default().spawn_local(async move {
-                    if keyring.set_password(&token).is_ok() {
-                        println!("Token saved!");
-                    } else {
-                        eprintln!("Failed to save token");
-                    }
-                });
+            if token.is_empty() {
+                return;
             }
+            let Some(keyring) = keyring.clone() else {
+                save_token_with_passphrase(&window, token);
+                return;
+            };
+            MainContext::default().spawn_local(async move {
+                if keyring.set_password(&token).is_ok() {
+                    println!("Token saved!");
+                    token_changed();
+                } else {
+                    eprintln!("Failed to save token");
+                }
+            });
         });
     }
 
@@ -114,7 +158,169 @@ impl AuthWindow {
 
 pub fn create_auth_window(app: &Application) {
     println!("Creating Auth Window...");
-    let auth_window = AuthWindow::new(app);
+    let auth_window = AuthWindow::new(app, Vec::new());
     auth_window.build_ui();
     auth_window.show();
 }
+
+/// Opens the login window asking for `features` on top of the token's current scopes
+pub fn reauthorize(window: &ApplicationWindow, features: &[Feature]) {
+    let Some(app) = window.application().and_downcast::<Application>() else {
+        return;
+    };
+    let auth_window = AuthWindow::new(&app, features.to_vec());
+    auth_window.build_ui();
+    auth_window.show();
+}
+
+// A different token may belong to a different user with different scopes
+fn token_changed() {
+    forget_own_user();
+    refresh_token_info();
+}
+
+/// Removes the saved token from the keyring and the encrypted file. Open chats stay
+/// signed in until they reconnect.
+pub fn forget_token() -> Result<(), String> {
+    if let Some(keyring) = keyring_entry().filter(keyring_available) {
+        match keyring.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(format!("Failed to remove the token from the keyring: {}", e)),
+        }
+    }
+    delete_token_file()?;
+    forget_own_user();
+    set_token_info(None);
+    println!("Saved login removed");
+    Ok(())
+}
+
+/// Revokes the saved token at Twitch, then forgets it. Blocking.
+pub fn revoke_and_forget_token() -> Result<(), String> {
+    if let Some(token) = load_token() {
+        revoke_token(&http_client(), &token).map_err(|e| e.to_string())?;
+    }
+    forget_token()
+}
+
+/// Returns the saved access token, if the user has logged in. Without a keyring that's
+/// the token from the encrypted file, once unlocked.
+pub fn load_token() -> Option<String> {
+    keyring_entry()
+        .and_then(|keyring| keyring.get_password().ok())
+        .or_else(unlocked_token)
+        .map(|token| token.trim().trim_start_matches("oauth:").to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// Asks for the passphrase of the encrypted token file at launch, when there's no keyring
+/// to hold the token instead. Skipping it leaves the app signed out for this run.
+pub fn unlock_saved_token(window: &ApplicationWindow) {
+    if !has_token_file() || unlocked_token().is_some() {
+        return;
+    }
+    if keyring_entry().is_some_and(|keyring| keyring_available(&keyring)) {
+        return;
+    }
+    ask_unlock_passphrase(window, None);
+}
+
+fn ask_unlock_passphrase(window: &ApplicationWindow, error: Option<String>) {
+    let body = error.unwrap_or_else(|| {
+        "No keyring is available, so your Twitch token is stored encrypted. Enter its passphrase to sign in.".to_string()
+    });
+    let window_retry = window.clone();
+    ask_passphrase(window, "Unlock Twitch Login", &body, false, move |passphrase| {
+        let window_retry = window_retry.clone();
+        // scrypt takes about a second on purpose, too long to hold up the window
+        MainContext::default().spawn_local(async move {
+            let result = adw::gio::spawn_blocking(move || unlock_token_file(&passphrase))
+                .await
+                .unwrap_or_else(|_| Err("Unlocking the token panicked".to_string()));
+            match result {
+                Ok(()) => token_changed(),
+                Err(e) => {
+                    eprintln!("Failed to unlock token: {}", e);
+                    ask_unlock_passphrase(&window_retry, Some(e));
+                }
+            }
+        });
+    });
+}
+
+fn save_token_with_passphrase(window: &ApplicationWindow, token: String) {
+    let body = "No keyring is available, so the token is stored in a file encrypted with a passphrase. You'll be asked for it when Admiral starts.";
+    ask_passphrase(window, "Choose a Passphrase", body, true, move |passphrase| {
+        let token = token.clone();
+        // Off the main loop like unlocking, since encrypting runs scrypt too
+        MainContext::default().spawn_local(async move {
+            let result = adw::gio::spawn_blocking(move || save_token_file(&token, &passphrase))
+                .await
+                .unwrap_or_else(|_| Err("Saving the token panicked".to_string()));
+            match result {
+                Ok(()) => {
+                    println!("Token saved to the encrypted file!");
+                    token_changed();
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+        });
+    });
+}
+
+// With `confirm`, the passphrase has to be typed twice and can't be empty
+fn ask_passphrase(
+    window: &ApplicationWindow,
+    heading: &str,
+    body: &str,
+    confirm: bool,
+    on_entered: impl Fn(String) + 'static,
+) {
+    let passphrase_entry = gtk::PasswordEntry::builder()
+        .placeholder_text("Passphrase")
+        .show_peek_icon(true)
+        .activates_default(true)
+        .build();
+    let confirm_entry = gtk::PasswordEntry::builder()
+        .placeholder_text("Repeat passphrase")
+        .show_peek_icon(true)
+        .activates_default(true)
+        .visible(confirm)
+        .build();
+    let entries = GtkBox::new(Orientation::Vertical, 6);
+    entries.append(&passphrase_entry);
+    entries.append(&confirm_entry);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(heading)
+        .body(body)
+        .extra_child(&entries)
+        .build();
+    dialog.add_responses(&[("cancel", "Cancel"), ("ok", if confirm { "Save" } else { "Unlock" })]);
+    dialog.set_response_appearance("ok", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("ok"));
+    dialog.set_close_response("cancel");
+
+    let update_enabled = {
+        let dialog = dialog.downgrade();
+        let passphrase_entry = passphrase_entry.clone();
+        let confirm_entry = confirm_entry.clone();
+        move || {
+            let Some(dialog) = dialog.upgrade() else {
+                return;
+            };
+            let passphrase = passphrase_entry.text();
+            let enabled = !confirm || (!passphrase.is_empty() && passphrase == confirm_entry.text());
+            dialog.set_response_enabled("ok", enabled);
+        }
+    };
+    update_enabled();
+    let update_passphrase = update_enabled.clone();
+    passphrase_entry.connect_changed(move |_| update_passphrase());
+    confirm_entry.connect_changed(move |_| update_enabled());
+
+    dialog.connect_response(Some("ok"), move |_, _| {
+        on_entered(passphrase_entry.text().to_string());
+    });
+    dialog.present(Some(window));
+}
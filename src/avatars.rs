@@ -0,0 +1,129 @@
+// avatars.rs
+
+use adw::prelude::*;
+use gtk::gdk;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::helix::lookup_profile_images;
+use crate::network::http_client;
+use crate::offline::is_offline;
+use crate::profile::cache_dir;
+
+// Profile pictures change rarely; older copies are still shown while a new one loads
+const AVATAR_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+thread_local! {
+    // Avatars shown without a picture yet, by channel login
+    static WAITING: RefCell<HashMap<String, Vec<glib::WeakRef<adw::Avatar>>>> = RefCell::new(HashMap::new());
+    // Logins already looked up this session, so a channel without a picture isn't asked for again
+    static REQUESTED: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+    static FETCH_SCHEDULED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn avatar_path(login: &str) -> PathBuf {
+    cache_dir().join("avatars").join(login)
+}
+
+fn is_stale(path: &PathBuf) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_none_or(|age| age > AVATAR_MAX_AGE)
+}
+
+/// An avatar for `login` labelled `name`: the channel's profile picture when it's cached,
+/// initials until then. Missing pictures are fetched in the background and filled in.
+pub fn channel_avatar(login: &str, name: &str, size: i32) -> adw::Avatar {
+    let avatar = adw::Avatar::new(size, Some(name), true);
+    let path = avatar_path(login);
+    match gdk::Texture::from_filename(&path) {
+        Ok(texture) => {
+            avatar.set_custom_image(Some(&texture));
+            if is_stale(&path) {
+                wait_for_picture(login, &avatar);
+            }
+        }
+        Err(_) => wait_for_picture(login, &avatar),
+    }
+    avatar
+}
+
+fn wait_for_picture(login: &str, avatar: &adw::Avatar) {
+    WAITING.with(|waiting| {
+        waiting
+            .borrow_mut()
+            .entry(login.to_string())
+            .or_default()
+            .push(avatar.downgrade());
+    });
+    // Rows are built in a burst; one Helix request covers all of them
+    if !REQUESTED.with(|requested| requested.borrow().contains(login)) && !FETCH_SCHEDULED.replace(true) {
+        glib::idle_add_local_once(fetch_waiting);
+    }
+}
+
+fn fetch_waiting() {
+    FETCH_SCHEDULED.set(false);
+    if is_offline() {
+        return;
+    }
+    let logins: Vec<String> = REQUESTED.with(|requested| {
+        let mut requested = requested.borrow_mut();
+        let waiting = WAITING.with(|waiting| waiting.borrow().keys().cloned().collect::<Vec<_>>());
+        waiting.into_iter().filter(|login| requested.insert(login.clone())).collect()
+    });
+    if logins.is_empty() {
+        return;
+    }
+    glib::MainContext::default().spawn_local(async move {
+        let to_fetch = logins.clone();
+        let fetched = adw::gio::spawn_blocking(move || download_pictures(&to_fetch))
+            .await
+            .unwrap_or_default();
+        for login in &logins {
+            let avatars = WAITING.with(|waiting| waiting.borrow_mut().remove(login)).unwrap_or_default();
+            // Channels without a picture keep their initials
+            if !fetched.contains(login) {
+                continue;
+            }
+            let Ok(texture) = gdk::Texture::from_filename(avatar_path(login)) else {
+                continue;
+            };
+            for avatar in avatars.iter().filter_map(|avatar| avatar.upgrade()) {
+                avatar.set_custom_image(Some(&texture));
+            }
+        }
+    });
+}
+
+// Saves each channel's picture to the cache, returning the logins that got one. Blocking.
+fn download_pictures(logins: &[String]) -> Vec<String> {
+    let images = lookup_profile_images(logins);
+    let client = http_client();
+    let mut fetched = Vec::new();
+    for (login, url) in images {
+        let bytes = match client.get(&url).send().and_then(|response| response.error_for_status()?.bytes()) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("Failed to download avatar for {}: {}", login, e);
+                continue;
+            }
+        };
+        let path = avatar_path(&login);
+        if let Some(parent) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                eprintln!("Failed to create avatar cache directory: {}", e);
+                return fetched;
+            }
+        }
+        match std::fs::write(&path, &bytes) {
+            Ok(()) => fetched.push(login),
+            Err(e) => eprintln!("Failed to save avatar for {}: {}", login, e),
+        }
+    }
+    fetched
+}
@@ -0,0 +1,144 @@
+// benchmark.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::demo::{DEFAULT_DEMO_RATE, MAX_DEMO_RATE};
+use crate::state::MutexExt;
+
+const DEFAULT_SECONDS: u64 = 30;
+
+// Hidden `--benchmark [rate] [--benchmark-seconds N]` mode
+#[derive(Debug, Clone, Copy)]
+pub struct BenchmarkConfig {
+    pub rate: u32,
+    pub duration: Duration,
+}
+
+struct BenchmarkRun {
+    started: Instant,
+    latencies_ms: Vec<f64>,
+}
+
+static CONFIG: Lazy<Mutex<Option<BenchmarkConfig>>> = Lazy::new(|| Mutex::new(None));
+static RUN: Lazy<Mutex<Option<BenchmarkRun>>> = Lazy::new(|| Mutex::new(None));
+
+// Frame counters reported by the chat page's frame monitor
+#[derive(Debug, Default, Deserialize)]
+pub struct FrameStats {
+    pub frames: u64,
+    pub dropped: u64,
+}
+
+/// Takes the benchmark flags out of `args`, leaving the rest for GTK
+pub fn take_benchmark_args(args: Vec<String>) -> Vec<String> {
+    let mut remaining = Vec::with_capacity(args.len());
+    let mut config: Option<BenchmarkConfig> = None;
+    let mut iter = args.into_iter().peekable();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--benchmark" => {
+                let rate = iter
+                    .next_if(|next| next.parse::<u32>().is_ok())
+                    .and_then(|next| next.parse::<u32>().ok())
+                    .unwrap_or(DEFAULT_DEMO_RATE)
+                    .clamp(1, MAX_DEMO_RATE);
+                let duration = config.map(|c| c.duration).unwrap_or(Duration::from_secs(DEFAULT_SECONDS));
+                config = Some(BenchmarkConfig { rate, duration });
+            }
+            "--benchmark-seconds" => {
+                let seconds = iter
+                    .next()
+                    .and_then(|next| next.parse::<u64>().ok())
+                    .unwrap_or(DEFAULT_SECONDS)
+                    .max(1);
+                let rate = config.map(|c| c.rate).unwrap_or(DEFAULT_DEMO_RATE);
+                config = Some(BenchmarkConfig {
+                    rate,
+                    duration: Duration::from_secs(seconds),
+                });
+            }
+            _ => remaining.push(arg),
+        }
+    }
+    *CONFIG.locked() = config;
+    remaining
+}
+
+pub fn benchmark_config() -> Option<BenchmarkConfig> {
+    *CONFIG.locked()
+}
+
+pub fn start_benchmark() {
+    *RUN.locked() = Some(BenchmarkRun {
+        started: Instant::now(),
+        latencies_ms: Vec::new(),
+    });
+}
+
+/// Records how long `timestamps` took from generation until the WebView finished appending them
+pub fn record_rendered(timestamps: &[chrono::DateTime<Utc>]) {
+    let mut run = RUN.locked();
+    let Some(run) = run.as_mut() else {
+        return;
+    };
+    let now = Utc::now();
+    run.latencies_ms.extend(
+        timestamps
+            .iter()
+            .map(|sent| (now - *sent).num_microseconds().unwrap_or(0) as f64 / 1000.0),
+    );
+}
+
+pub fn is_benchmarking() -> bool {
+    RUN.locked().is_some()
+}
+
+pub fn message_timestamps(messages: &[PrivmsgMessage]) -> Vec<chrono::DateTime<Utc>> {
+    messages.iter().map(|msg| msg.server_timestamp).collect()
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index]
+}
+
+/// Ends the run and prints its report to stdout
+pub fn finish_benchmark(config: &BenchmarkConfig, frames: &FrameStats) {
+    let Some(run) = RUN.locked().take() else {
+        return;
+    };
+    let elapsed = run.started.elapsed().as_secs_f64();
+    let mut latencies = run.latencies_ms;
+    latencies.sort_by(|a, b| a.total_cmp(b));
+    let expected = (config.rate as f64 * elapsed) as u64;
+    let rendered = latencies.len() as u64;
+
+    println!("Benchmark: {} msg/s for {:.1}s", config.rate, elapsed);
+    println!("  rendered:       {} of ~{} messages ({:.1} msg/s)", rendered, expected, rendered as f64 / elapsed);
+    println!("  not rendered:   {}", expected.saturating_sub(rendered));
+    println!(
+        "  latency (ms):   p50 {:.1}  p95 {:.1}  p99 {:.1}  max {:.1}",
+        percentile(&latencies, 0.50),
+        percentile(&latencies, 0.95),
+        percentile(&latencies, 0.99),
+        latencies.last().copied().unwrap_or(0.0),
+    );
+    println!(
+        "  frames:         {} ({} dropped, {:.1}%)",
+        frames.frames,
+        frames.dropped,
+        if frames.frames + frames.dropped == 0 {
+            0.0
+        } else {
+            frames.dropped as f64 * 100.0 / (frames.frames + frames.dropped) as f64
+        }
+    );
+}
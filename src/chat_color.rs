@@ -0,0 +1,165 @@
+// chat_color.rs
+
+use adw::prelude::*;
+use gtk::{gdk, Align, Box as GtkBox, Button, DrawingArea, Grid, Label, MenuButton, Orientation, Popover};
+use std::cell::RefCell;
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use crate::markup::escape_html;
+
+/// Twitch's named chat colors, which anyone can pick: (Helix name, label, hex)
+pub const PRESET_COLORS: [(&str, &str, &str); 15] = [
+    ("blue", "Blue", "#0000FF"),
+    ("blue_violet", "Blue Violet", "#8A2BE2"),
+    ("cadet_blue", "Cadet Blue", "#5F9EA0"),
+    ("chocolate", "Chocolate", "#D2691E"),
+    ("coral", "Coral", "#FF7F50"),
+    ("dodger_blue", "Dodger Blue", "#1E90FF"),
+    ("firebrick", "Firebrick", "#B22222"),
+    ("golden_rod", "Goldenrod", "#DAA520"),
+    ("green", "Green", "#008000"),
+    ("hot_pink", "Hot Pink", "#FF69B4"),
+    ("orange_red", "Orange Red", "#FF4500"),
+    ("red", "Red", "#FF0000"),
+    ("sea_green", "Sea Green", "#2E8B57"),
+    ("spring_green", "Spring Green", "#00FF7F"),
+    ("yellow_green", "Yellow Green", "#9ACD32"),
+];
+
+const SWATCH_SIZE: i32 = 20;
+const PRESET_COLUMNS: i32 = 5;
+
+/// The hex value of a color as Helix takes it, a preset's name or "#RRGGBB"
+pub fn color_hex(color: &str) -> Option<String> {
+    if color.starts_with('#') {
+        return Some(color.to_uppercase());
+    }
+    PRESET_COLORS
+        .iter()
+        .find(|(name, _, _)| *name == color)
+        .map(|(_, _, hex)| hex.to_string())
+}
+
+fn rgba_hex(rgba: &gdk::RGBA) -> String {
+    format!(
+        "#{:02X}{:02X}{:02X}",
+        (rgba.red() * 255.0).round() as u8,
+        (rgba.green() * 255.0).round() as u8,
+        (rgba.blue() * 255.0).round() as u8
+    )
+}
+
+fn color_swatch(hex: &str) -> DrawingArea {
+    let rgba = gdk::RGBA::parse(hex).unwrap_or(gdk::RGBA::BLACK);
+    let swatch = DrawingArea::builder()
+        .content_width(SWATCH_SIZE)
+        .content_height(SWATCH_SIZE)
+        .build();
+    swatch.set_draw_func(move |_, cr, width, height| {
+        let radius = width.min(height) as f64 / 2.0;
+        cr.arc(width as f64 / 2.0, height as f64 / 2.0, radius, 0.0, 2.0 * PI);
+        cr.set_source_rgb(rgba.red() as f64, rgba.green() as f64, rgba.blue() as f64);
+        let _ = cr.fill();
+    });
+    swatch
+}
+
+/// The user's name at the start of the message input, in their chat color
+pub struct IdentityButton {
+    pub button: MenuButton,
+    label: Label,
+    on_pick: Rc<RefCell<Option<Rc<dyn Fn(String)>>>>,
+}
+
+/// Clicking the name offers the preset colors and, for Prime and Turbo users, any other
+pub fn build_identity_button() -> IdentityButton {
+    let label = Label::new(None);
+    let button = MenuButton::builder()
+        .child(&label)
+        .tooltip_text("Your name in this chat. Click to change its color.")
+        .css_classes(["flat"])
+        .visible(false)
+        .build();
+    let on_pick: Rc<RefCell<Option<Rc<dyn Fn(String)>>>> = Rc::new(RefCell::new(None));
+
+    let popover = Popover::new();
+    let content = GtkBox::new(Orientation::Vertical, 8);
+    content.set_margin_top(8);
+    content.set_margin_bottom(8);
+    content.set_margin_start(8);
+    content.set_margin_end(8);
+    let heading = Label::builder().label("Chat Color").xalign(0.0).css_classes(["heading"]).build();
+    content.append(&heading);
+
+    let presets = Grid::builder().row_spacing(4).column_spacing(4).build();
+    for (index, (name, title, hex)) in PRESET_COLORS.iter().enumerate() {
+        let preset_button = Button::builder()
+            .child(&color_swatch(hex))
+            .tooltip_text(*title)
+            .css_classes(["flat"])
+            .build();
+        let popover = popover.clone();
+        let on_pick = on_pick.clone();
+        preset_button.connect_clicked(move |_| {
+            popover.popdown();
+            if let Some(on_pick) = on_pick.borrow().clone() {
+                on_pick(name.to_string());
+            }
+        });
+        let index = index as i32;
+        presets.attach(&preset_button, index % PRESET_COLUMNS, index / PRESET_COLUMNS, 1, 1);
+    }
+    content.append(&presets);
+
+    let custom_button = Button::builder().halign(Align::Fill).build();
+    let custom_box = GtkBox::new(Orientation::Vertical, 0);
+    custom_box.append(&Label::new(Some("Custom Color…")));
+    custom_box.append(&Label::builder().label("Prime and Turbo only").css_classes(["caption", "dim-label"]).build());
+    custom_button.set_child(Some(&custom_box));
+    let popover_custom = popover.clone();
+    let on_pick_custom = on_pick.clone();
+    custom_button.connect_clicked(move |button| {
+        popover_custom.popdown();
+        let window = button.root().and_downcast::<gtk::Window>();
+        let dialog = gtk::ColorDialog::builder().title("Chat Color").with_alpha(false).build();
+        let on_pick = on_pick_custom.clone();
+        dialog.choose_rgba(window.as_ref(), None, None::<&adw::gio::Cancellable>, move |result| {
+            // Dismissing the dialog counts as an error too
+            let Ok(rgba) = result else {
+                return;
+            };
+            if let Some(on_pick) = on_pick.borrow().clone() {
+                on_pick(rgba_hex(&rgba));
+            }
+        });
+    });
+    content.append(&custom_button);
+
+    popover.set_child(Some(&content));
+    button.set_popover(Some(&popover));
+    IdentityButton { button, label, on_pick }
+}
+
+impl IdentityButton {
+    /// Runs `on_pick` with the color chosen, as Helix takes it
+    pub fn connect_color_picked(&self, on_pick: impl Fn(String) + 'static) {
+        *self.on_pick.borrow_mut() = Some(Rc::new(on_pick));
+    }
+
+    /// Shows `name` in `color`, or in the theme's text color without one; hidden for None
+    pub fn show(&self, name: Option<&str>, color: Option<&str>) {
+        let Some(name) = name else {
+            self.button.set_visible(false);
+            return;
+        };
+        let markup = match color {
+            Some(color) => format!(r#"<span foreground="{}" weight="bold">{}</span>"#, escape_html(color), escape_html(name)),
+            None => format!("<b>{}</b>", escape_html(name)),
+        };
+        if self.label.label() != markup {
+            self.label.set_markup(&markup);
+        }
+        self.button.set_visible(true);
+    }
+}
@@ -0,0 +1,204 @@
+// chat_events.rs
+
+use std::time::Duration;
+use twitch_irc::message::{ClearChatAction, ServerMessage, UserNoticeEvent, UserNoticeMessage};
+
+use crate::markup::escape_html;
+use crate::moderation::format_timeout;
+
+pub const CHAT_CLEARED_TEXT: &str = "Chat cleared by a moderator";
+
+/// Moderator actions and channel events the tab has to reflect, passed from the
+/// connection thread alongside the messages
+#[derive(Debug, Clone)]
+pub enum ChatEvent {
+    ChatCleared,
+    MessageDeleted { message_id: String },
+    UserModerated { login: String, timeout: Option<Duration> }, // Timed out, or banned when None
+    GiftBomb { gift_id: String, gifter: String, count: u64 }, // Shown as one card for all its subs
+    GiftRecipient { gift_id: String, recipient: String }, // One sub of a gift bomb, added to its card
+    Celebration { kind: CelebrationKind, text: String, message: Option<String> }, // Twitch's system text, and what the user added
+}
+
+/// Channel events shown as a styled row between messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CelebrationKind {
+    Sub,
+    Resub,
+    Gift, // A single gifted sub; gift bombs get a card
+    Raid,
+    WatchStreak,
+}
+
+impl CelebrationKind {
+    pub fn css_class(self) -> &'static str {
+        match self {
+            CelebrationKind::Sub => "celebration-sub",
+            CelebrationKind::Resub => "celebration-resub",
+            CelebrationKind::Gift => "celebration-gift",
+            CelebrationKind::Raid => "celebration-raid",
+            CelebrationKind::WatchStreak => "celebration-watch-streak",
+        }
+    }
+}
+
+impl ChatEvent {
+    pub fn from_server_message(message: &ServerMessage) -> Option<Self> {
+        match message {
+            ServerMessage::ClearChat(msg) => match &msg.action {
+                // Without a target user, CLEARCHAT empties the whole room
+                ClearChatAction::ChatCleared => Some(ChatEvent::ChatCleared),
+                ClearChatAction::UserBanned { user_login, .. } => Some(ChatEvent::UserModerated {
+                    login: user_login.clone(),
+                    timeout: None,
+                }),
+                ClearChatAction::UserTimedOut {
+                    user_login,
+                    timeout_length,
+                    ..
+                } => Some(ChatEvent::UserModerated {
+                    login: user_login.clone(),
+                    timeout: Some(*timeout_length),
+                }),
+            },
+            ServerMessage::ClearMsg(msg) => Some(ChatEvent::MessageDeleted {
+                message_id: msg.message_id.clone(),
+            }),
+            ServerMessage::UserNotice(msg) => user_notice_event(msg),
+            _ => None,
+        }
+    }
+}
+
+// A gift bomb arrives as one notice with the count, then one per recipient, all carrying
+// the same community gift id
+fn user_notice_event(msg: &UserNoticeMessage) -> Option<ChatEvent> {
+    let tag = |name: &str| msg.source.tags.0.get(name).and_then(|value| value.as_deref());
+    let celebration = |kind| {
+        Some(ChatEvent::Celebration {
+            kind,
+            text: msg.system_message.clone(),
+            message: msg.message_text.clone().filter(|text| !text.trim().is_empty()),
+        })
+    };
+    let gift_id = tag("msg-param-community-gift-id").map(str::to_string);
+    match (&msg.event, gift_id) {
+        (UserNoticeEvent::SubMysteryGift { mass_gift_count, .. }, Some(gift_id)) => Some(ChatEvent::GiftBomb {
+            gift_id,
+            gifter: msg.sender.name.clone(),
+            count: *mass_gift_count,
+        }),
+        (UserNoticeEvent::AnonSubMysteryGift { mass_gift_count, .. }, Some(gift_id)) => Some(ChatEvent::GiftBomb {
+            gift_id,
+            gifter: "An anonymous gifter".to_string(),
+            count: *mass_gift_count,
+        }),
+        (UserNoticeEvent::SubGift { recipient, .. }, Some(gift_id)) => Some(ChatEvent::GiftRecipient {
+            gift_id,
+            recipient: recipient.name.clone(),
+        }),
+        (UserNoticeEvent::SubGift { .. }, None) => celebration(CelebrationKind::Gift),
+        (UserNoticeEvent::SubOrResub { is_resub: true, .. }, _) => celebration(CelebrationKind::Resub),
+        (UserNoticeEvent::SubOrResub { .. }, _)
+        | (UserNoticeEvent::GiftPaidUpgrade { .. }, _)
+        | (UserNoticeEvent::AnonGiftPaidUpgrade { .. }, _) => celebration(CelebrationKind::Sub),
+        (UserNoticeEvent::Raid { .. }, _) => celebration(CelebrationKind::Raid),
+        _ if tag("msg-id") == Some("viewermilestone") && tag("msg-param-category") == Some("watch-streak") => {
+            celebration(CelebrationKind::WatchStreak)
+        }
+        _ => None,
+    }
+}
+
+pub fn chat_cleared_notice_html() -> String {
+    format!(
+        r#"<div class="skipped-notice chat-cleared-notice" role="separator">{}</div>"#,
+        CHAT_CLEARED_TEXT
+    )
+}
+
+pub fn moderation_label(timeout: Option<Duration>) -> String {
+    match timeout {
+        Some(timeout) => format!("timed out for {}", format_timeout(timeout.as_secs().min(u32::MAX as u64) as u32)),
+        None => "banned".to_string(),
+    }
+}
+
+/// Tags a rendered message of a timed out or banned user, the way the chat page's
+/// markUserModerated does. A later timeout or ban replaces the earlier tag.
+pub fn mark_moderated_html(message_html: &str, label: &str) -> Option<String> {
+    const TAG_START: &str = r#"<span class="moderation-tag">"#;
+    let tag = format!("{}{}</span>", TAG_START, escape_html(label));
+    if let Some(start) = message_html.find(TAG_START) {
+        let end = start + message_html[start..].find("</span>")? + "</span>".len();
+        return Some(format!("{}{}{}", &message_html[..start], tag, &message_html[end..]));
+    }
+    let timestamp = message_html.find(r#"<span class="timestamp""#)?;
+    let html = format!("{}{} {}", &message_html[..timestamp], tag, &message_html[timestamp..]);
+    Some(html.replacen(r#"class="message-box"#, r#"class="message-box moderated"#, 1))
+}
+
+/// Strikes a rendered message through and tags it as deleted, the way the chat page's
+/// deleteMessage does. None if it already is.
+pub fn mark_deleted_html(message_html: &str) -> Option<String> {
+    if message_html.contains("deleted-tag") {
+        return None;
+    }
+    let timestamp = message_html.find(r#"<span class="timestamp""#)?;
+    let mut html = String::with_capacity(message_html.len() + 64);
+    html.push_str(&message_html[..timestamp]);
+    html.push_str(r#"<span class="deleted-tag">deleted</span> "#);
+    html.push_str(&message_html[timestamp..]);
+    Some(html.replacen(r#"class="message-box"#, r#"class="message-box deleted"#, 1))
+}
+
+pub fn gift_bomb_text(gifter: &str, count: u64) -> String {
+    format!("{} gifted {} subs", gifter, count)
+}
+
+// What the recipients of a gift bomb are found by, in the buffer and on the page
+pub fn gift_card_marker(gift_id: &str) -> String {
+    format!(r#"data-gift-id="{}""#, escape_html(gift_id))
+}
+
+/// A collapsed card standing in for a whole gift bomb, filled in as the recipients arrive
+pub fn gift_card_html(gift_id: &str, gifter: &str, count: u64) -> String {
+    format!(
+        r#"<details class="gift-card" {}><summary>{} — click to expand recipients</summary><ul class="gift-recipients"></ul></details>"#,
+        gift_card_marker(gift_id),
+        escape_html(&gift_bomb_text(gifter, count))
+    )
+}
+
+/// Adds one recipient to a stored gift card, the way the chat page's addGiftRecipient does
+pub fn add_gift_recipient_html(card_html: &str, recipient: &str) -> Option<String> {
+    let end = card_html.rfind("</ul>")?;
+    Some(format!(
+        "{}<li>{}</li>{}",
+        &card_html[..end],
+        escape_html(recipient),
+        &card_html[end..]
+    ))
+}
+
+/// A system row for a sub, resub, gift, raid or watch streak, with the message the user
+/// sent along below it
+pub fn celebration_notice_html(kind: CelebrationKind, text: &str, message: Option<&str>) -> String {
+    let message_html = message
+        .map(|message| format!(r#"<div class="celebration-message">{}</div>"#, escape_html(message)))
+        .unwrap_or_default();
+    format!(
+        r#"<div class="celebration-notice {}" role="note"><span class="celebration-text">{}</span>{}</div>"#,
+        kind.css_class(),
+        escape_html(text),
+        message_html
+    )
+}
+
+/// The same row as a line of text, for text-only mode
+pub fn celebration_notice_text(text: &str, message: Option<&str>) -> String {
+    match message {
+        Some(message) => format!("{} \u{2014} {}", text, message),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,73 @@
+// command_bar.rs
+
+use crate::demo::{DEFAULT_DEMO_RATE, MAX_DEMO_RATE};
+use crate::vod::parse_vod_id;
+
+// A parsed `:` command from the command bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Join(String),
+    Close,
+    Disconnect,
+    Split, // Moves the current tab beside the others
+    Only, // Puts the split tabs back
+    FilterAdd(String),
+    FilterRemove(String),
+    FilterClear,
+    FilterList,
+    Vod(String),
+    Demo(u32), // Messages per second
+    Help,
+}
+
+pub const HELP_TEXT: &str =
+    ":join <channel>, :close, :disconnect, :split, :only, :filter add|remove <regex>, :filter clear, :filter list, :vod <id|url>, :demo [rate]";
+
+pub fn parse_command(input: &str) -> Result<Command, String> {
+    let input = input.trim().trim_start_matches(':').trim();
+    let (name, rest) = match input.split_once(char::is_whitespace) {
+        Some((name, rest)) => (name, rest.trim()),
+        None => (input, ""),
+    };
+
+    match name {
+        "join" | "j" => {
+            let channel = rest.trim_start_matches('#').to_lowercase();
+            if channel.is_empty() || channel.contains(char::is_whitespace) {
+                return Err("Usage: :join <channel>".to_string());
+            }
+            Ok(Command::Join(channel))
+        }
+        "close" | "q" => Ok(Command::Close),
+        "disconnect" | "part" => Ok(Command::Disconnect),
+        "split" | "sp" => Ok(Command::Split),
+        "only" | "on" => Ok(Command::Only),
+        "filter" => {
+            let (action, pattern) = match rest.split_once(char::is_whitespace) {
+                Some((action, pattern)) => (action, pattern.trim()),
+                None => (rest, ""),
+            };
+            match (action, pattern.is_empty()) {
+                ("add", false) => Ok(Command::FilterAdd(pattern.to_string())),
+                ("remove" | "rm", false) => Ok(Command::FilterRemove(pattern.to_string())),
+                ("clear", _) => Ok(Command::FilterClear),
+                ("list" | "", _) => Ok(Command::FilterList),
+                _ => Err("Usage: :filter add|remove <regex>, :filter clear, :filter list".to_string()),
+            }
+        }
+        "vod" => parse_vod_id(rest, true)
+            .map(Command::Vod)
+            .ok_or_else(|| "Usage: :vod <id|url>".to_string()),
+        "demo" | "preview" => {
+            if rest.is_empty() {
+                return Ok(Command::Demo(DEFAULT_DEMO_RATE));
+            }
+            match rest.parse::<u32>() {
+                Ok(rate) if (1..=MAX_DEMO_RATE).contains(&rate) => Ok(Command::Demo(rate)),
+                _ => Err(format!("Usage: :demo [messages per second, 1-{}]", MAX_DEMO_RATE)),
+            }
+        }
+        "help" | "h" | "" => Ok(Command::Help),
+        other => Err(format!("Unknown command: {}", other)),
+    }
+}
@@ -0,0 +1,28 @@
+// connections.rs
+
+use once_cell::sync::Lazy;
+use std::future::Future;
+use tokio::runtime::{Builder, Runtime};
+use tokio::task::JoinHandle;
+
+// Chat connections spend nearly all their time waiting on sockets, so every tab shares
+// one small runtime instead of each bringing its own runtime and threads
+const WORKER_THREADS: usize = 2;
+
+static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+    Builder::new_multi_thread()
+        .worker_threads(WORKER_THREADS)
+        .thread_name("admiral-chat")
+        .enable_all()
+        .build()
+        .expect("Failed to start the chat runtime")
+});
+
+/// Runs a tab's connection on the shared runtime. Aborting the handle drops the
+/// connection with it.
+pub fn spawn_connection<F>(connection: F) -> JoinHandle<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    RUNTIME.spawn(connection)
+}
@@ -0,0 +1,148 @@
+// crash.rs
+
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::profile::data_dir;
+
+// Written next to the logs when Admiral panics and removed once offered on the next launch
+const PENDING_REPORT_FILE: &str = "pending.json";
+const MAX_KEPT_LOGS: usize = 10;
+
+// Channels open in tab order, kept current from the UI so the panic hook never has to
+// reach into GTK
+static OPEN_CHANNELS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+// This run left the pending report, as opposed to an earlier one not yet offered
+static REPORTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CrashReport {
+    pub time: String, // RFC 3339
+    pub channels: Vec<String>,
+    pub log_path: PathBuf,
+}
+
+impl CrashReport {
+    pub fn local_time(&self) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&self.time)
+            .ok()
+            .map(|time| time.with_timezone(&Local))
+    }
+}
+
+fn crash_dir() -> PathBuf {
+    data_dir().join("crashes")
+}
+
+pub fn remember_open_channels(channels: Vec<String>) {
+    *OPEN_CHANNELS.lock().unwrap_or_else(|e| e.into_inner()) = channels;
+}
+
+/// Makes panics leave a log and the open channels on disk, on top of the usual message
+pub fn install_crash_handler() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        write_crash_report(info);
+    }));
+}
+
+fn write_crash_report(info: &PanicHookInfo) {
+    let crashed_at = Local::now();
+    // try_lock, since the panic may have happened while this thread held the lock
+    let channels = match OPEN_CHANNELS.try_lock() {
+        Ok(channels) => channels.clone(),
+        Err(std::sync::TryLockError::Poisoned(e)) => e.into_inner().clone(),
+        Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+    };
+
+    let mut log = String::new();
+    let _ = writeln!(log, "Admiral {} crashed at {}", env!("CARGO_PKG_VERSION"), crashed_at.format("%Y-%m-%d %H:%M:%S"));
+    let _ = writeln!(log, "Thread: {}", std::thread::current().name().unwrap_or("unnamed"));
+    let _ = writeln!(log, "{}", info);
+    let _ = writeln!(log, "\nOpen channels: {}", if channels.is_empty() { "none".to_string() } else { channels.join(", ") });
+    let _ = writeln!(log, "\nBacktrace:\n{}", Backtrace::force_capture());
+
+    let dir = crash_dir();
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("Failed to create crash log directory: {}", e);
+        return;
+    }
+    let log_path = dir.join(format!("crash-{}.log", crashed_at.format("%Y%m%d-%H%M%S")));
+    if let Err(e) = fs::write(&log_path, log) {
+        eprintln!("Failed to write crash log: {}", e);
+        return;
+    }
+    eprintln!("Crash log written to {}", log_path.display());
+
+    // Only the first panic of a run is offered for recovery; later ones are often fallout
+    let pending = dir.join(PENDING_REPORT_FILE);
+    if pending.exists() {
+        return;
+    }
+    let report = CrashReport {
+        time: crashed_at.to_rfc3339(),
+        channels,
+        log_path,
+    };
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => {
+            match fs::write(&pending, json) {
+                Ok(()) => REPORTED.store(true, Ordering::Relaxed),
+                Err(e) => eprintln!("Failed to save crash report: {}", e),
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize crash report: {}", e),
+    }
+}
+
+/// Withdraws this run's pending report after a panic was caught and Admiral carried on.
+/// The log stays.
+pub fn discard_crash_report() {
+    if REPORTED.swap(false, Ordering::Relaxed) {
+        if let Err(e) = fs::remove_file(crash_dir().join(PENDING_REPORT_FILE)) {
+            eprintln!("Failed to remove crash report: {}", e);
+        }
+    }
+}
+
+/// The report left by a crash in an earlier run, if it hasn't been offered yet.
+/// Also trims old logs.
+pub fn take_crash_report() -> Option<CrashReport> {
+    let dir = crash_dir();
+    prune_logs(&dir);
+    let pending = dir.join(PENDING_REPORT_FILE);
+    let contents = fs::read_to_string(&pending).ok()?;
+    if let Err(e) = fs::remove_file(&pending) {
+        eprintln!("Failed to remove crash report: {}", e);
+    }
+    serde_json::from_str(&contents)
+        .map_err(|e| eprintln!("Failed to parse crash report: {}", e))
+        .ok()
+}
+
+fn prune_logs(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut logs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "log"))
+        .collect();
+    // Names carry the time, so they sort oldest first
+    logs.sort();
+    let excess = logs.len().saturating_sub(MAX_KEPT_LOGS);
+    for path in &logs[..excess] {
+        if let Err(e) = fs::remove_file(path) {
+            eprintln!("Failed to remove old crash log: {}", e);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+// demo.rs
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use twitch_irc::message::{IRCMessage, PrivmsgMessage};
+
+use crate::emotes::get_emote_map;
+use crate::helix::cached_own_login;
+use crate::message_queue::MessageQueue;
+use crate::vod::escape_tag;
+
+pub const DEMO_CHANNEL: &str = "admiral_preview";
+// Room id whose 7TV emote set the preview borrows, so emotes render like a real channel
+pub const DEMO_EMOTE_CHANNEL_ID: &str = "71092938";
+pub const DEFAULT_DEMO_RATE: u32 = 5;
+pub const MAX_DEMO_RATE: u32 = 1000;
+const TICK: Duration = Duration::from_millis(10);
+
+const NAMES: &[&str] = &[
+    "PixelPirate", "nightowl_42", "CozyGamer", "xXSniperXx", "tea_enjoyer", "LurkMaster",
+    "froggo", "SpeedrunSam", "moonlight_m", "Chatty_Cathy", "bitwizard", "ok_boomer_99",
+];
+const COLORS: &[&str] = &["#FF4500", "#1E90FF", "#9ACD32", "#DAA520", "#FF69B4", "#8A2BE2", "#00FF7F", ""];
+const BADGES: &[&str] = &["", "", "", "subscriber/12", "subscriber/3", "vip/1", "moderator/1", "premium/1"];
+const PHRASES: &[&str] = &[
+    "hello chat",
+    "that was insane",
+    "no way",
+    "can we get a W in the chat",
+    "first time here, love the vibes",
+    "what game is this?",
+    "the music is so good today",
+    "LMAO",
+    "clip it",
+    "gg",
+    "this chat moves way too fast",
+    "how long has the stream been going?",
+];
+
+// Small xorshift generator, good enough for picking sample content
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x2545_F491_4F6C_DD1D);
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+/// Produces synthetic chat with emotes, badges, mentions and sub events
+pub struct DemoGenerator {
+    rng: Rng,
+    counter: u64,
+}
+
+impl Default for DemoGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DemoGenerator {
+    pub fn new() -> Self {
+        Self {
+            rng: Rng::new(),
+            counter: 0,
+        }
+    }
+
+    pub fn next_message(&mut self) -> Option<PrivmsgMessage> {
+        self.counter += 1;
+        let name = self.rng.pick(NAMES);
+        let roll = self.rng.below(100);
+        let (text, highlighted) = if roll < 4 {
+            (
+                format!("{} just subscribed for {} months!", name, 1 + self.rng.below(48)),
+                true,
+            )
+        } else if roll < 10 {
+            let target = cached_own_login().unwrap_or_else(|| self.rng.pick(NAMES).to_string());
+            (format!("@{} {}", target, self.rng.pick(PHRASES)), false)
+        } else {
+            (self.text_with_emotes(), false)
+        };
+        let raw = format!(
+            "@badge-info=;badges={};color={};display-name={};emotes=;id=demo-{}{};room-id={};tmi-sent-ts={};user-id={} :{login}!{login}@{login}.tmi.twitch.tv PRIVMSG #{} :{}",
+            self.rng.pick(BADGES),
+            self.rng.pick(COLORS),
+            escape_tag(name),
+            self.counter,
+            if highlighted { ";msg-id=highlighted-message" } else { "" },
+            DEMO_EMOTE_CHANNEL_ID,
+            chrono::Utc::now().timestamp_millis(),
+            1000 + self.rng.below(NAMES.len()),
+            DEMO_CHANNEL,
+            text,
+            login = name.to_lowercase(),
+        );
+        let irc = IRCMessage::parse(&raw).ok()?;
+        PrivmsgMessage::try_from(irc).ok()
+    }
+
+    // A phrase, an emote-only message or a phrase followed by emotes
+    fn text_with_emotes(&mut self) -> String {
+        let emote_map = get_emote_map(DEMO_EMOTE_CHANNEL_ID);
+        let mut emotes: Vec<&str> = emote_map
+            .iter()
+            .filter(|(_, (_, zero_width))| !zero_width)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        emotes.sort_unstable(); // HashMap order isn't stable between calls
+        if emotes.is_empty() {
+            return self.rng.pick(PHRASES).to_string();
+        }
+        let emote_count = 1 + self.rng.below(3);
+        let picked: Vec<&str> = (0..emote_count).map(|_| self.rng.pick(&emotes)).collect();
+        match self.rng.below(3) {
+            0 => picked.join(" "),
+            1 => self.rng.pick(PHRASES).to_string(),
+            _ => format!("{} {}", self.rng.pick(PHRASES), picked.join(" ")),
+        }
+    }
+}
+
+/// Feeds `rate` synthetic messages per second into `queue` until `stop` is set
+pub fn start_demo(
+    queue: Arc<MessageQueue>,
+    rate: u32,
+    stop: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut generator = DemoGenerator::new();
+        let started = Instant::now();
+        let mut sent: u64 = 0;
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(TICK);
+            let due = (started.elapsed().as_secs_f64() * rate as f64) as u64;
+            while sent < due {
+                sent += 1;
+                if let Some(msg) = generator.next_message() {
+                    queue.push(msg);
+                }
+            }
+        }
+    })
+}
@@ -1,616 +0,0 @@
-// emotes.rs
-
-use chrono::Local;
-use gtk::prelude::*; // For glib::markup_escape_text
-use once_cell::sync::Lazy;
-use reqwest::blocking::Client; // Blocking client for background threads
-use serde::Deserialize;
-use std::error::Error as StdError;
-use std::sync::{mpsc, Mutex, RwLock};
-use std::{
-    collections::HashMap,
-    sync::Arc,
-    time::{Duration, Instant},
-};
-use std::{collections::HashSet, thread};
-use twitch_irc::message::PrivmsgMessage; // Import the message struct
-use twitch_irc::message::RGBColor;
-use url::Url;
-
-pub static MESSAGE_CSS: &str = "
-.message-box {
-    border: 1px solid alpha(#999, 0.3);
-    border-radius: 8px;
-    padding: 8px;
-    margin-bottom: 4px;
-    background-color: alpha(#fff, 0.02);
-    display: block;
-    overflow: hidden;
-    box-sizing: border-box;
-}
-.message-row {
-    background-color: transparent;
-}
-.message-header {
-    display: block;
-    margin-bottom: 4px;
-}
-.message-text {
-    font-size: 12pt;
-    line-height: 28px; /* Consistent line height matching emote size */
-    display: inline;
-}
-.message-text img {
-    display: inline-block;
-    vertical-align: middle;
-    height: 28px;
-    width: auto;
-    margin: 0 2px;
-    max-height: 28px;
-    max-width: none;
-    pointer-events: auto;
-    cursor: pointer;
-    backface-visibility: hidden;
-    transition: transform 0.1s ease;
-}
-
-.message-text img:hover {
-    transform: scale(1.1);
-}
-.dim-label {
-    color: alpha(#aaa, 0.8);
-    font-size: 0.8em;
-}
-.message-content {
-    padding-top: 4px;
-    display: block;
-    word-wrap: break-word;
-    overflow-wrap: break-word;
-}
-.sender {
-    font-weight: bold;
-}
-.timestamp {
-    color: alpha(#aaa, 0.8);
-    font-size: 0.8em;
-}
-.emote-popover-label {
-    font-family: monospace;
-    font-size: 11pt;
-    padding: 4px 8px;
-}
-.flowbox-child {
-    margin: 0;
-    padding: 0;
-}
-";
-
-// --- Global State for Emote Maps and Fetching ---
-static EMOTE_MAPS: Lazy<RwLock<HashMap<String, Arc<HashMap<String, (String, bool)>>>>> =
-    Lazy::new(|| RwLock::new(HashMap::new()));
-static DOWNLOADING_CHANNELS: Lazy<RwLock<HashMap<String, bool>>> =
-    Lazy::new(|| RwLock::new(HashMap::new()));
-static LAST_FETCH_TIME: Lazy<RwLock<HashMap<String, Instant>>> =
-    Lazy::new(|| RwLock::new(HashMap::new()));
-
-#[derive(Debug, Deserialize)]
-struct SevenTVUserResponse {
-    emote_set: Option<ApiEmoteSet>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ApiEmoteSet {
-    id: String,
-    name: String,
-    emotes: Vec<ApiActiveEmote>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ApiActiveEmote {
-    id: String,
-    name: String,
-    data: Option<ApiEmoteData>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ApiEmoteData {
-    host: Option<ImageHost>,
-    flags: Option<i32>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct ImageHost {
-    url: String, // Base URL for the host (e.g., cdn.7tv.app)
-    files: Vec<ImageFile>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct ImageFile {
-    name: String,   // Filename (e.g., 1x.webp)
-    format: String, // Format (e.g., "WEBP", "PNG", "GIF")
-}
-
-pub fn cleanup_emote_cache() {
-    let mut last_fetch = LAST_FETCH_TIME.write().unwrap();
-    let now = Instant::now();
-
-    // Collect channels to remove based on time
-    let mut channels_to_remove: Vec<String> = last_fetch
-        .iter()
-        .filter_map(|(channel_id, time)| {
-            if now.duration_since(*time) >= Duration::from_secs(3600) {
-                Some(channel_id.clone())
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    // Also check total emote count and remove oldest entries if exceeding limit
-    const MAX_TOTAL_EMOTES: usize = 5000;
-    let maps_read = EMOTE_MAPS.read().unwrap();
-    let total_emotes: usize = maps_read.values().map(|map| map.len()).sum();
-    drop(maps_read);
-
-    if total_emotes > MAX_TOTAL_EMOTES {
-        println!(
-            "Total emote count {} exceeds limit {}, pruning oldest entries",
-            total_emotes, MAX_TOTAL_EMOTES
-        );
-
-        // Sort channels by last fetch time and remove oldest ones
-        let mut channels_by_time: Vec<(String, Instant)> = last_fetch
-            .iter()
-            .map(|(id, time)| (id.clone(), *time))
-            .collect();
-
-        channels_by_time.sort_by_key(|(_, time)| *time);
-
-        // Remove oldest channels until we're under the limit
-        let mut removed_count = 0;
-        for (channel_id, _) in channels_by_time {
-            if total_emotes - removed_count <= MAX_TOTAL_EMOTES * 3 / 4 {
-                // Stop when we're at 75% of limit to avoid frequent cleanup
-                break;
-            }
-            channels_to_remove.push(channel_id.clone());
-            removed_count += EMOTE_MAPS
-                .read()
-                .unwrap()
-                .get(&channel_id)
-                .map(|m| m.len())
-                .unwrap_or(0);
-        }
-    }
-
-    // Remove from all caches
-    for channel_id in channels_to_remove {
-        last_fetch.remove(&channel_id);
-        EMOTE_MAPS.write().unwrap().remove(&channel_id);
-        DOWNLOADING_CHANNELS.write().unwrap().remove(&channel_id);
-        println!("Removed emote data for channel: {}", channel_id);
-    }
-
-    // Log final statistics
-    let remaining_channels = last_fetch.len();
-    let remaining_emotes: usize = EMOTE_MAPS
-        .read()
-        .unwrap()
-        .values()
-        .map(|map| map.len())
-        .sum();
-    println!(
-        "Cleaned up cache, {} channels and {} emotes remaining.",
-        remaining_channels, remaining_emotes
-    );
-}
-
-pub fn cleanup_media_file_cache() {
-    // No local files to clean now.
-    glib::idle_add_local_once(|| {
-        println!("No local emote cache to clean.");
-    });
-}
-
-// --- Emote Map Retrieval (Uses Remote URLs) ---
-pub fn get_emote_map(channel_id: &str) -> Arc<HashMap<String, (String, bool)>> {
-    {
-        let maps_read = EMOTE_MAPS.read().unwrap();
-        if let Some(map) = maps_read.get(channel_id) {
-            return Arc::clone(map);
-        }
-    }
-
-    fetch_missing_emotes(channel_id);
-
-    Arc::new(HashMap::new())
-}
-
-const FETCH_COOLDOWN: Duration = Duration::from_secs(60 * 1); // 1 minute
-
-// --- Background Emote Fetching (Updates In-Memory Map) ---
-fn fetch_missing_emotes(channel_id: &str) -> Option<thread::JoinHandle<()>> {
-    let channel_id = channel_id.to_string(); // Clone for thread
-    let now = Instant::now();
-
-    // Check if download is already in progress
-    {
-        let downloading = DOWNLOADING_CHANNELS.read().unwrap();
-        if downloading.get(&channel_id).copied().unwrap_or(false) {
-            return None;
-        }
-    }
-
-    // Check if cooldown period has passed
-    {
-        let last_fetch_read = LAST_FETCH_TIME.read().unwrap();
-        if let Some(&last_fetch) = last_fetch_read.get(&channel_id) {
-            if now.duration_since(last_fetch) < FETCH_COOLDOWN {
-                return None;
-            }
-        }
-    }
-
-    // Check in-memory map again just before starting fetch (double-check)
-    {
-        let maps_read = EMOTE_MAPS.read().unwrap();
-        if maps_read.contains_key(&channel_id) {
-            // Update fetch time anyway
-            let mut last_fetch_write = LAST_FETCH_TIME.write().unwrap();
-            last_fetch_write.insert(channel_id.clone(), now);
-            return None;
-        }
-    }
-
-    // Clone channel_id for the thread
-    let channel_id_clone = channel_id.clone();
-    let handle = thread::spawn(move || {
-        match download_emote_urls(&channel_id_clone) {
-            // Fetch remote URLs
-            Ok(remote_emote_map) => {
-                // Store the fetched map in the global in-memory cache
-                let mut maps_write = EMOTE_MAPS.write().unwrap();
-                maps_write.insert(channel_id_clone.clone(), Arc::new(remote_emote_map));
-            }
-            Err(e) => {
-                eprintln!(
-                    "Failed to fetch emote URLs for channel_id {}: {:?}",
-                    channel_id_clone, e
-                );
-            }
-        }
-        // Mark download as finished
-        let mut downloading = DOWNLOADING_CHANNELS.write().unwrap();
-        downloading.insert(channel_id_clone.clone(), false);
-        // Update fetch time
-        let mut last_fetch_write = LAST_FETCH_TIME.write().unwrap();
-        last_fetch_write.insert(channel_id_clone.clone(), now);
-    });
-
-    // Mark download as in progress
-    {
-        let mut downloading = DOWNLOADING_CHANNELS.write().unwrap();
-        downloading.insert(channel_id.clone(), true);
-    }
-
-    Some(handle)
-}
-
-// --- Download Logic (Fetches Remote URLs) ---
-fn download_emote_urls(
-    channel_id: &str,
-) -> Result<HashMap<String, (String, bool)>, Box<dyn StdError + Send + Sync>> {
-    let client = Client::new();
-    let twitch_lookup_url = format!("https://7tv.io/v3/users/twitch/{}", channel_id);
-    const MAX_RETRIES: usize = 3;
-
-    let mut success = false;
-    let mut response_text = String::new();
-    for retry in 1..=MAX_RETRIES {
-        let response = client.get(&twitch_lookup_url).send()?;
-        if response.status().is_success() {
-            response_text = response.text()?;
-            success = true;
-            break;
-        } else if response.status().as_u16() == 429 {
-            thread::sleep(Duration::from_secs(2 * retry as u64)); // Exponential backoff
-        } else {
-            return Err(format!(
-                "7TV API request failed with status {}: {}",
-                response.status(),
-                response
-                    .text()
-                    .unwrap_or_else(|_| "No error body".to_string())
-            )
-            .into());
-        }
-    }
-
-    if !success {
-        return Err(format!(
-            "Failed to fetch 7TV API response for channel_id {} after {} retries.",
-            channel_id, MAX_RETRIES
-        )
-        .into());
-    }
-
-    let user_response: SevenTVUserResponse = serde_json::from_str(&response_text)?;
-
-    let mut remote_emote_map = HashMap::new();
-
-    if let Some(api_emote_set) = user_response.emote_set {
-        for active_emote in api_emote_set.emotes {
-            if let Some(emote_data) = &active_emote.data {
-                if let Some(host_info) = &emote_data.host {
-                    if host_info.url.trim().is_empty() {
-                        eprintln!(
-                            "WARNING: Emote '{}' has empty host URL, skipping",
-                            active_emote.name
-                        );
-                        continue;
-                    }
-                    let file_opt = find_best_image_file(&host_info.files);
-                    if let Some(file_to_use) = file_opt {
-                        // Construct the full URL for the specific file
-                        let base_emote_url = host_info
-                            .url
-                            .trim_start_matches("https://")
-                            .trim_start_matches("http://")
-                            .trim_start_matches("//");
-                        let emote_remote_url =
-                            format!("https://{}/{}", base_emote_url, file_to_use.name);
-
-                        // Validate the constructed URL
-                        if let Err(e) = validate_emote_url(&emote_remote_url, &active_emote.name) {
-                            eprintln!("ERROR: Failed to validate emote URL: {}", e);
-                            continue;
-                        }
-
-                        let is_zero_width = emote_data.flags.unwrap_or(0) & 256 != 0;
-                        remote_emote_map
-                            .insert(active_emote.name, (emote_remote_url, is_zero_width));
-                    } else {
-                        eprintln!("WARNING: Emote '{}' has no suitable image file (available files: {:?}), skipping",
-                            active_emote.name, host_info.files.iter().map(|f| &f.name).collect::<Vec<_>>());
-                    }
-                } else {
-                    eprintln!(
-                        "WARNING: Emote '{}' has no host information, skipping",
-                        active_emote.name
-                    );
-                }
-            } else {
-                eprintln!(
-                    "WARNING: Emote '{}' has no data, skipping",
-                    active_emote.name
-                );
-            }
-        }
-    } else {
-        eprintln!(
-            "WARNING: Channel {} has no emote set configured",
-            channel_id
-        );
-    }
-
-    println!(
-        "Successfully loaded {} emotes for channel {}",
-        remote_emote_map.len(),
-        channel_id
-    );
-    Ok(remote_emote_map)
-}
-
-// --- Helper Functions ---
-
-/// Validates a URL and returns an error description if invalid
-fn validate_emote_url(url_str: &str, emote_name: &str) -> Result<(), String> {
-    // Check if URL is empty
-    if url_str.trim().is_empty() {
-        return Err(format!("URL is empty for emote '{}'", emote_name));
-    }
-
-    // Check if it starts with https://
-    if !url_str.starts_with("https://") {
-        return Err(format!(
-            "URL for emote '{}' does not start with https://: {}",
-            emote_name, url_str
-        ));
-    }
-
-    // Try to parse as URL
-    let parsed_url = Url::parse(url_str).map_err(|e| {
-        format!(
-            "Failed to parse URL for emote '{}': {} - URL was: {}",
-            emote_name, e, url_str
-        )
-    })?;
-
-    // Check if scheme is https
-    if parsed_url.scheme() != "https" {
-        return Err(format!(
-            "URL for emote '{}' does not use HTTPS scheme: {}",
-            emote_name,
-            parsed_url.scheme()
-        ));
-    }
-
-    // Check if host exists
-    if parsed_url.host().is_none() {
-        return Err(format!(
-            "URL for emote '{}' is missing host: {}",
-            emote_name, url_str
-        ));
-    }
-
-    // Check if host is valid (not empty, has a dot)
-    let host = parsed_url.host_str().unwrap();
-    if !host.contains('.') {
-        return Err(format!(
-            "URL for emote '{}' has invalid host: {}",
-            emote_name, host
-        ));
-    }
-
-    // Check if path exists
-    if parsed_url.path().is_empty() {
-        return Err(format!(
-            "URL for emote '{}' is missing file path: {}",
-            emote_name, url_str
-        ));
-    }
-
-    Ok(())
-}
-
-fn find_best_image_file(files: &[ImageFile]) -> Option<&ImageFile> {
-    // Prioritize 1x versions, then prefer GIF for animation, then PNG for quality, then first available
-    if let Some(file) = files
-        .iter()
-        .find(|f| f.name.contains("1x") && f.format.eq_ignore_ascii_case("gif"))
-    {
-        return Some(file);
-    }
-    if let Some(file) = files
-        .iter()
-        .find(|f| f.name.contains("1x") && f.format.eq_ignore_ascii_case("png"))
-    {
-        return Some(file);
-    }
-    if let Some(file) = files.iter().find(|f| f.name.contains("1x")) {
-        return Some(file);
-    }
-    // If no 1x found, look for any GIF
-    if let Some(file) = files.iter().find(|f| f.format.eq_ignore_ascii_case("gif")) {
-        return Some(file);
-    }
-    // Otherwise, take the first one (could prioritize PNG over others)
-    files.first()
-}
-
-fn rgb_to_hex(color: &RGBColor) -> String {
-    let mut r = color.r as f32 / 255.0;
-    let mut g = color.g as f32 / 255.0;
-    let mut b = color.b as f32 / 255.0;
-    let luminance = 0.299 * r + 0.587 * g + 0.114 * b;
-    if luminance < 0.3 {
-        let boost = 0.3 / (luminance + 0.001);
-        r *= boost;
-        g *= boost;
-        b *= boost;
-    }
-    let avg = (r + g + b) / 3.0;
-    let vibrancy_limit = 0.7;
-    r = avg + (r - avg) * vibrancy_limit;
-    g = avg + (g - avg) * vibrancy_limit;
-    b = avg + (b - avg) * vibrancy_limit;
-    let r = (r.clamp(0.0, 1.0) * 255.0).round() as u8;
-    let g = (g.clamp(0.0, 1.0) * 255.0).round() as u8;
-    let b = (b.clamp(0.0, 1.0) * 255.0).round() as u8;
-    format!("#{:02X}{:02X}{:02X}", r, g, b)
-}
-
-// --- Parse Message to HTML (Updated to use remote URLs) ---
-pub fn parse_message_html(
-    msg: &PrivmsgMessage,
-    emote_map: &Arc<HashMap<String, (String, bool)>>,
-) -> String {
-    let sender_name_escaped = glib::markup_escape_text(&msg.sender.name);
-    let timestamp = msg
-        .server_timestamp
-        .with_timezone(&Local)
-        .format("%-I:%M:%S %p")
-        .to_string();
-    let timestamp_escaped = glib::markup_escape_text(&timestamp);
-
-    let sender_color_html = if let Some(color) = &msg.name_color {
-        let color_hex = rgb_to_hex(color);
-        format!(
-            r#"<span class="sender" style="color: {};">{}</span>"#,
-            color_hex, sender_name_escaped
-        )
-    } else {
-        format!(r#"<span class="sender">{}</span>"#, sender_name_escaped)
-    };
-
-    fn emit_img(html: &mut String, name: &str, url: &str) {
-        let emote_name_escaped = glib::markup_escape_text(name);
-        let remote_url_escaped = glib::markup_escape_text(url);
-        html.push_str(r#"<img width="28" height="28" src=""#);
-        html.push_str(&remote_url_escaped);
-        html.push_str(r#"" alt=":"#);
-        html.push_str(&emote_name_escaped);
-        html.push_str(r#":" title="Click to view emote details" crossorigin="anonymous"/>"#);
-    }
-
-    fn emit_emote_stack(
-        html: &mut String,
-        base_name: &str,
-        base_url: &str,
-        overlays: &[(&str, &str)],
-    ) {
-        html.push_str(r#"<span class="emote-stack">"#);
-        emit_img(html, base_name, base_url);
-        for (name, url) in overlays {
-            let emote_name_escaped = glib::markup_escape_text(name);
-            let url_escaped = glib::markup_escape_text(url);
-            html.push_str(r#"<img class="emote-overlay" height="28" src=""#);
-            html.push_str(&url_escaped);
-            html.push_str(r#"" alt=":"#);
-            html.push_str(&emote_name_escaped);
-            html.push_str(r#":" title="Click to view emote details" crossorigin="anonymous"/>"#);
-        }
-        html.push_str(r#"</span>"#);
-    }
-
-    let mut html_content = String::with_capacity(msg.message_text.len() * 2);
-    let words: Vec<&str> = msg.message_text.split_whitespace().collect();
-    let mut i = 0;
-    let mut first = true;
-
-    while i < words.len() {
-        let word = words[i];
-
-        if let Some((url, is_zw)) = emote_map.get(word) {
-            if *is_zw {
-                if !first {
-                    html_content.push(' ');
-                }
-                emit_img(&mut html_content, word, url);
-                first = false;
-            } else {
-                if !first {
-                    html_content.push(' ');
-                }
-                let mut overlays: Vec<(&str, &str)> = Vec::new();
-                while i + 1 < words.len() {
-                    if let Some((overlay_url, true)) = emote_map.get(words[i + 1]) {
-                        overlays.push((words[i + 1], overlay_url));
-                        i += 1;
-                    } else {
-                        break;
-                    }
-                }
-                if overlays.is_empty() {
-                    emit_img(&mut html_content, word, url);
-                } else {
-                    emit_emote_stack(&mut html_content, word, url, &overlays);
-                }
-                first = false;
-            }
-        } else {
-            if !first {
-                html_content.push(' ');
-            }
-            html_content.push_str(&glib::markup_escape_text(word));
-            first = false;
-        }
-
-        i += 1;
-    }
-
-    format!(
-        r#"<div class="message-box"><div class="message-header">{} <span class="timestamp">{}</span></div><div class="message-content"><span class="message-text">{}</span></div></div>"#,
-        sender_color_html, timestamp_escaped, html_content
-    )
-}
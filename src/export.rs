@@ -0,0 +1,105 @@
+// export.rs
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::emotes::{find_emote, twitch_emote_url};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    // Picked from the file name chosen in the save dialog, HTML unless it ends in .json
+    pub fn from_path(path: &std::path::Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => ExportFormat::Json,
+            _ => ExportFormat::Html,
+        }
+    }
+}
+
+/// Standalone page with the chat view's own styles, viewable without Admiral.
+/// `style` is the template's CSS and `setup_js` applies the appearance settings.
+pub fn session_html(channel: &str, style: &str, setup_js: &str, messages: &[String]) -> String {
+    let title = glib::markup_escape_text(&format!("#{} chat", channel));
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>{}</title>
+  <style>{}</style>
+</head>
+<body>
+  <div id="chat-container">
+    <div id="chat-body">
+{}
+    </div>
+  </div>
+  <script>{}</script>
+</body>
+</html>
+"#,
+        title,
+        style,
+        messages.join("\n"),
+        setup_js,
+    )
+}
+
+/// The session as JSON. Third-party emotes are listed with their image URLs since
+/// nothing in the message text marks them.
+pub fn session_json(
+    channel: &str,
+    messages: &[PrivmsgMessage],
+    emote_map: &HashMap<String, (String, bool)>,
+) -> String {
+    let messages: Vec<Value> = messages
+        .iter()
+        .map(|msg| {
+            let mut emotes: Vec<Value> = msg
+                .emotes
+                .iter()
+                .map(|emote| {
+                    json!({
+                        "name": emote.code,
+                        "url": twitch_emote_url(&emote.id),
+                    })
+                })
+                .collect();
+            let mut seen: Vec<&str> = Vec::new();
+            for word in msg.message_text.split_whitespace() {
+                if let Some((url, _)) = find_emote(emote_map, word) {
+                    if !seen.contains(&word) {
+                        seen.push(word);
+                        emotes.push(json!({ "name": word, "url": url }));
+                    }
+                }
+            }
+            json!({
+                "id": msg.message_id,
+                "sent_at": msg.server_timestamp.to_rfc3339(),
+                "sender": {
+                    "id": msg.sender.id,
+                    "login": msg.sender.login,
+                    "display_name": msg.sender.name,
+                    "color": msg.name_color.as_ref().map(|c| format!("#{:02X}{:02X}{:02X}", c.r, c.g, c.b)),
+                },
+                "badges": msg.badges.iter().map(|b| format!("{}/{}", b.name, b.version)).collect::<Vec<_>>(),
+                "action": msg.is_action,
+                "text": msg.message_text,
+                "emotes": emotes,
+            })
+        })
+        .collect();
+    let export = json!({
+        "channel": channel,
+        "exported_at": chrono::Utc::now().to_rfc3339(),
+        "messages": messages,
+    });
+    serde_json::to_string_pretty(&export).unwrap_or_default()
+}
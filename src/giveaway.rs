@@ -0,0 +1,243 @@
+// giveaway.rs
+
+use adw::prelude::*;
+use gtk::{Align, Box as GtkBox, Button, Entry, Label, Orientation, Popover, SpinButton};
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::state::MutexExt;
+
+const DEFAULT_WINDOW_MINUTES: f64 = 2.0;
+const MAX_WINDOW_MINUTES: f64 = 60.0;
+
+/// Keyword giveaway for one tab: collects each user who types the keyword while
+/// entries are open, then draws winners from them
+#[derive(Default)]
+pub struct Giveaway {
+    keyword: String, // Lowercase; empty until armed
+    closes_at: Option<Instant>,
+    entrants: Vec<String>, // Display names in entry order; drawn winners are removed
+    logins: HashSet<String>, // Everyone who entered, so a drawn winner can't re-enter
+    winner: Option<String>,
+}
+
+impl Giveaway {
+    /// Starts a fresh round, forgetting earlier entrants and winners
+    pub fn arm(&mut self, keyword: &str, window: Duration) {
+        *self = Self {
+            keyword: keyword.trim().to_lowercase(),
+            closes_at: Some(Instant::now() + window),
+            ..Self::default()
+        };
+    }
+
+    pub fn close(&mut self) {
+        self.closes_at = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.closes_at.is_some_and(|closes_at| Instant::now() < closes_at)
+    }
+
+    pub fn time_left(&self) -> Option<Duration> {
+        self.closes_at
+            .map(|closes_at| closes_at.saturating_duration_since(Instant::now()))
+            .filter(|left| !left.is_zero())
+    }
+
+    /// Enters the sender if the message has the keyword as a whole word
+    pub fn record(&mut self, msg: &PrivmsgMessage) {
+        if !self.is_open() || self.logins.contains(&msg.sender.login) {
+            return;
+        }
+        let entered = msg
+            .message_text
+            .split_whitespace()
+            .any(|word| word.to_lowercase() == self.keyword);
+        if entered {
+            self.logins.insert(msg.sender.login.clone());
+            self.entrants.push(msg.sender.name.clone());
+        }
+    }
+
+    /// Closes entries and picks a random entrant, who is left out of later draws
+    pub fn draw(&mut self) -> Option<String> {
+        self.close();
+        if self.entrants.is_empty() {
+            return None;
+        }
+        let index = RandomState::new().build_hasher().finish() as usize % self.entrants.len();
+        let winner = self.entrants.remove(index);
+        self.winner = Some(winner.clone());
+        Some(winner)
+    }
+
+    pub fn announcement(&self) -> Option<String> {
+        let winner = self.winner.as_ref()?;
+        Some(format!("@{} won the giveaway! Congratulations!", winner))
+    }
+}
+
+// Line in the chat view recording a draw, styled like the other tool notices
+pub fn winner_notice_html(winner: &str, entrants: usize) -> String {
+    format!(
+        r#"<div class="tool-notice" role="status">Giveaway winner: <b>{}</b> (out of {} entrant{})</div>"#,
+        glib::markup_escape_text(winner),
+        entrants,
+        if entrants == 1 { "" } else { "s" }
+    )
+}
+
+/// Popover for running a giveaway; `announce` gets the notice HTML for each draw
+pub fn build_giveaway_popover(giveaway: &Arc<Mutex<Giveaway>>, announce: impl Fn(String) + 'static) -> Popover {
+    let popover = Popover::builder().autohide(true).build();
+
+    let content = GtkBox::new(Orientation::Vertical, 6);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.set_width_request(260);
+
+    let title = Label::new(Some("Giveaway"));
+    title.add_css_class("heading");
+    title.set_halign(Align::Start);
+
+    let keyword_entry = Entry::builder().placeholder_text("Keyword, e.g. !join").build();
+
+    let window_box = GtkBox::new(Orientation::Horizontal, 6);
+    let window_label = Label::new(Some("Entries open for (minutes)"));
+    window_label.set_hexpand(true);
+    window_label.set_halign(Align::Start);
+    let window_spin = SpinButton::with_range(1.0, MAX_WINDOW_MINUTES, 1.0);
+    window_spin.set_value(DEFAULT_WINDOW_MINUTES);
+    window_box.append(&window_label);
+    window_box.append(&window_spin);
+
+    let status_label = Label::new(None);
+    status_label.set_halign(Align::Start);
+    let winner_label = Label::new(None);
+    winner_label.add_css_class("title-4");
+    winner_label.set_selectable(true);
+
+    let button_box = GtkBox::new(Orientation::Horizontal, 6);
+    button_box.set_halign(Align::End);
+    button_box.set_margin_top(6);
+    let start_button = Button::with_label("Start");
+    let draw_button = Button::with_label("Draw Winner");
+    draw_button.add_css_class("suggested-action");
+    let copy_button = Button::with_label("Copy Announcement");
+    copy_button.set_tooltip_text(Some("Copy a message announcing the winner, to paste into chat"));
+    button_box.append(&start_button);
+    button_box.append(&draw_button);
+
+    content.append(&title);
+    content.append(&keyword_entry);
+    content.append(&window_box);
+    content.append(&status_label);
+    content.append(&winner_label);
+    content.append(&button_box);
+    content.append(&copy_button);
+    popover.set_child(Some(&content));
+
+    let refresh = {
+        let giveaway = giveaway.clone();
+        let status_label = status_label.clone();
+        let winner_label = winner_label.clone();
+        let start_button = start_button.clone();
+        let draw_button = draw_button.clone();
+        let copy_button = copy_button.clone();
+        move || {
+            let giveaway = giveaway.locked();
+            let entrants = giveaway.entrants.len();
+            let status = match giveaway.time_left() {
+                Some(left) => format!(
+                    "Collecting \"{}\": {} entrant{}, {}:{:02} left",
+                    giveaway.keyword,
+                    entrants,
+                    if entrants == 1 { "" } else { "s" },
+                    left.as_secs() / 60,
+                    left.as_secs() % 60
+                ),
+                None if giveaway.keyword.is_empty() => "Not running".to_string(),
+                None => format!("Entries closed: {} entrant{} left to draw", entrants, if entrants == 1 { "" } else { "s" }),
+            };
+            status_label.set_text(&status);
+            winner_label.set_text(giveaway.winner.as_deref().unwrap_or(""));
+            winner_label.set_visible(giveaway.winner.is_some());
+            start_button.set_label(if giveaway.is_open() { "Close Entries" } else { "Start" });
+            draw_button.set_sensitive(entrants > 0);
+            draw_button.set_label(if giveaway.winner.is_some() { "Redraw" } else { "Draw Winner" });
+            copy_button.set_sensitive(giveaway.winner.is_some());
+        }
+    };
+
+    // Refresh while visible; the timer stops itself once the popover closes
+    let refresh_on_show = refresh.clone();
+    popover.connect_show(move |popover| {
+        refresh_on_show();
+        let refresh = refresh_on_show.clone();
+        let popover_weak = popover.downgrade();
+        glib::timeout_add_local(Duration::from_secs(1), move || {
+            match popover_weak.upgrade() {
+                Some(popover) if popover.is_visible() => {
+                    refresh();
+                    glib::ControlFlow::Continue
+                }
+                _ => glib::ControlFlow::Break,
+            }
+        });
+    });
+
+    let giveaway_for_start = giveaway.clone();
+    let refresh_on_start = refresh.clone();
+    let keyword_entry_for_start = keyword_entry.clone();
+    start_button.connect_clicked(move |_| {
+        let mut giveaway = giveaway_for_start.locked();
+        if giveaway.is_open() {
+            giveaway.close();
+        } else {
+            let keyword = keyword_entry_for_start.text();
+            if keyword.trim().is_empty() || keyword.trim().contains(char::is_whitespace) {
+                keyword_entry_for_start.add_css_class("error");
+                return;
+            }
+            keyword_entry_for_start.remove_css_class("error");
+            let minutes = window_spin.value_as_int().max(1) as u64;
+            giveaway.arm(&keyword, Duration::from_secs(minutes * 60));
+        }
+        drop(giveaway);
+        refresh_on_start();
+    });
+    keyword_entry.connect_activate(glib::clone!(
+        #[weak]
+        start_button,
+        move |_| start_button.emit_clicked()
+    ));
+
+    let giveaway_for_draw = giveaway.clone();
+    let refresh_on_draw = refresh.clone();
+    draw_button.connect_clicked(move |_| {
+        let mut giveaway = giveaway_for_draw.locked();
+        let entrants = giveaway.entrants.len();
+        let winner = giveaway.draw();
+        drop(giveaway);
+        if let Some(winner) = winner {
+            announce(winner_notice_html(&winner, entrants));
+        }
+        refresh_on_draw();
+    });
+
+    let giveaway_for_copy = giveaway.clone();
+    copy_button.connect_clicked(move |button| {
+        if let Some(announcement) = giveaway_for_copy.locked().announcement() {
+            button.clipboard().set_text(&announcement);
+        }
+    });
+
+    popover
+}
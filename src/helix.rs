@@ -0,0 +1,723 @@
+// helix.rs
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use reqwest::blocking::{Client, RequestBuilder, Response}; // Blocking client for worker threads
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::error::Error as StdError;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::activity::{record_activity, ActivityEvent, ActivityKind};
+use crate::auth::{load_token, CLIENT_ID};
+use crate::badges::{badge_key, store_badges, BadgeImage, GLOBAL_BADGES};
+use crate::cheermotes::{store_cheermotes, CheerTier, Cheermote};
+use crate::network::{http_client, http_client_builder};
+use crate::pump::PumpSender;
+use crate::scopes::{is_granted, request_consent, set_token_info, Feature, TokenInfo};
+use crate::state::{MutexExt, RwLockExt};
+
+// Delivered back to the owning tab once Helix has answered
+#[derive(Debug, Clone)]
+pub struct AccountAge {
+    pub message_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+struct AccountAgeJob {
+    user_id: String,
+    message_id: String,
+    reply: PumpSender<AccountAge>,
+}
+
+const MAX_QUEUED_JOBS: usize = 500;
+const MAX_USERS_PER_REQUEST: usize = 100; // Helix limit for /users
+const MAX_CACHED_ACCOUNTS: usize = 10000;
+
+// --- Global State for the Helix Worker ---
+static ACCOUNT_CREATED: Lazy<RwLock<HashMap<String, DateTime<Utc>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+static OWN_USER: Lazy<RwLock<Option<(String, String)>>> = Lazy::new(|| RwLock::new(None)); // (id, login)
+static FOLLOWED_CHANNELS: Lazy<RwLock<Vec<String>>> = Lazy::new(|| RwLock::new(Vec::new()));
+// Viewer counts by login; None until the first poll, so channels already live at startup aren't reported
+static LIVE_CHANNELS: Lazy<Mutex<Option<HashMap<String, u32>>>> = Lazy::new(|| Mutex::new(None));
+static LIVE_GENERATION: AtomicU64 = AtomicU64::new(0); // Bumped after every completed poll
+// Badge scopes fetched or being fetched, see request_badges
+static BADGES_REQUESTED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+// Channels whose cheermotes were fetched or are being fetched, see request_cheermotes
+static CHEERMOTES_REQUESTED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static JOB_SENDER: Lazy<Mutex<mpsc::SyncSender<AccountAgeJob>>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::sync_channel::<AccountAgeJob>(MAX_QUEUED_JOBS);
+    thread::spawn(move || run_worker(rx));
+    Mutex::new(tx)
+});
+
+#[derive(Debug, Deserialize)]
+struct HelixUsersResponse {
+    data: Vec<HelixUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixUser {
+    id: String,
+    login: String,
+    created_at: String, // RFC 3339
+    #[serde(default)]
+    profile_image_url: String,
+}
+
+/// Queues an account age lookup for `user_id`. Cached accounts answer immediately;
+/// without a saved token nothing is sent.
+pub fn request_account_age(user_id: &str, message_id: &str, reply: &PumpSender<AccountAge>) {
+    if let Some(created_at) = ACCOUNT_CREATED.read_locked().get(user_id) {
+        let _ = reply.send(AccountAge {
+            message_id: message_id.to_string(),
+            created_at: *created_at,
+        });
+        return;
+    }
+
+    let job = AccountAgeJob {
+        user_id: user_id.to_string(),
+        message_id: message_id.to_string(),
+        reply: reply.clone(),
+    };
+    if let Err(mpsc::TrySendError::Full(_)) = JOB_SENDER.locked().try_send(job) {
+        eprintln!("Account age queue full, skipping user {}", user_id);
+    }
+}
+
+// --- Worker Thread ---
+fn run_worker(rx: mpsc::Receiver<AccountAgeJob>) {
+    let client = http_client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_else(|_| http_client());
+
+    while let Ok(first) = rx.recv() {
+        // Batch whatever else is already waiting into the same request
+        let mut jobs = vec![first];
+        while jobs.len() < MAX_USERS_PER_REQUEST {
+            match rx.try_recv() {
+                Ok(job) => jobs.push(job),
+                Err(_) => break,
+            }
+        }
+
+        let missing: Vec<String> = {
+            let cache = ACCOUNT_CREATED.read_locked();
+            let unique: HashSet<&str> = jobs
+                .iter()
+                .map(|job| job.user_id.as_str())
+                .filter(|id| !cache.contains_key(*id))
+                .collect();
+            unique.into_iter().map(str::to_string).collect()
+        };
+
+        if !missing.is_empty() {
+            match fetch_created_at(&client, &missing) {
+                Ok(created) => {
+                    let mut cache = ACCOUNT_CREATED.write_locked();
+                    if cache.len() + created.len() > MAX_CACHED_ACCOUNTS {
+                        cache.clear();
+                    }
+                    cache.extend(created);
+                }
+                Err(e) => {
+                    eprintln!("Failed to fetch account ages: {}", e);
+                    continue;
+                }
+            }
+        }
+
+        let cache = ACCOUNT_CREATED.read_locked();
+        for job in jobs {
+            if let Some(created_at) = cache.get(&job.user_id) {
+                // The tab may have been closed in the meantime
+                let _ = job.reply.send(AccountAge {
+                    message_id: job.message_id,
+                    created_at: *created_at,
+                });
+            }
+        }
+    }
+}
+
+// --- Helix Requests ---
+
+// Adds the Client-Id and bearer token every Helix endpoint needs
+fn authorized(request: RequestBuilder) -> Result<RequestBuilder, Box<dyn StdError + Send + Sync>> {
+    let token = load_token().ok_or("Not logged in, this needs a Twitch token")?;
+    Ok(request
+        .header("Client-Id", CLIENT_ID)
+        .header("Authorization", format!("Bearer {}", token)))
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidateResponse {
+    #[serde(default)]
+    login: String,
+    #[serde(default)]
+    scopes: Vec<String>,
+    #[serde(default)]
+    expires_in: i64, // Seconds; 0 for tokens that don't expire
+}
+
+/// Asks Twitch who the saved token belongs to, which scopes it has and when it expires,
+/// in the background
+pub fn refresh_token_info() {
+    let Some(token) = load_token() else {
+        set_token_info(None);
+        return;
+    };
+    thread::spawn(move || {
+        let response = http_client()
+            .get("https://id.twitch.tv/oauth2/validate")
+            .header("Authorization", format!("OAuth {}", token))
+            .send();
+        match response {
+            Ok(response) if response.status().is_success() => match response.json::<ValidateResponse>() {
+                Ok(parsed) => set_token_info(Some(TokenInfo {
+                    valid: true,
+                    login: parsed.login,
+                    scopes: parsed.scopes.into_iter().collect(),
+                    expires_at: (parsed.expires_in > 0).then(|| Utc::now() + chrono::Duration::seconds(parsed.expires_in)),
+                })),
+                Err(e) => eprintln!("Failed to read token info: {}", e),
+            },
+            // Expired or revoked: nothing is granted until the user logs in again
+            Ok(response) if response.status() == StatusCode::UNAUTHORIZED => {
+                eprintln!("The saved Twitch token is no longer valid");
+                set_token_info(Some(TokenInfo::invalid()));
+            }
+            Ok(response) => eprintln!("Token validation failed with status {}", response.status()),
+            Err(e) => eprintln!("Failed to validate token: {}", e),
+        }
+    });
+}
+
+/// Invalidates `token` at Twitch, so a copy left anywhere stops working. Blocking.
+pub fn revoke_token(client: &Client, token: &str) -> Result<(), Box<dyn StdError + Send + Sync>> {
+    let response = client
+        .post("https://id.twitch.tv/oauth2/revoke")
+        .form(&[("client_id", CLIENT_ID), ("token", token)])
+        .send()?;
+    if !response.status().is_success() {
+        return Err(format!("Token revocation failed with status {}", response.status()).into());
+    }
+    Ok(())
+}
+
+/// Drops the looked up user after logging out, so the next login is looked up afresh
+pub fn forget_own_user() {
+    *OWN_USER.write_locked() = None;
+}
+
+// Fails early when the token is known to lack `feature`, offering to grant it
+fn require_scopes(feature: Feature) -> Result<(), Box<dyn StdError + Send + Sync>> {
+    if is_granted(feature) == Some(false) {
+        request_consent(feature);
+        return Err(format!("Your Twitch login doesn't allow {} yet", feature.label().to_lowercase()).into());
+    }
+    Ok(())
+}
+
+// Twitch answers 401 or 403 when the token lacks the scope an endpoint needs
+fn check_scoped_response(response: &Response, feature: Feature, what: &str) -> Result<(), Box<dyn StdError + Send + Sync>> {
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        request_consent(feature);
+        return Err(format!("Your Twitch login doesn't allow {} yet", feature.label().to_lowercase()).into());
+    }
+    if !status.is_success() {
+        return Err(format!("Helix {} request failed with status {}", what, status).into());
+    }
+    Ok(())
+}
+
+/// The user id belonging to the saved token. Blocking.
+pub fn own_user_id(client: &Client) -> Result<String, Box<dyn StdError + Send + Sync>> {
+    own_user(client).map(|(id, _)| id)
+}
+
+/// Login belonging to the saved token. Blocking.
+pub fn own_login(client: &Client) -> Result<String, Box<dyn StdError + Send + Sync>> {
+    own_user(client).map(|(_, login)| login)
+}
+
+/// Id and login of the logged-in user if they have been looked up already. Never blocks.
+pub fn cached_own_user() -> Option<(String, String)> {
+    OWN_USER.read_locked().clone()
+}
+
+/// Login of the logged-in user if it has been looked up already. Never blocks.
+pub fn cached_own_login() -> Option<String> {
+    OWN_USER.read_locked().as_ref().map(|(_, login)| login.clone())
+}
+
+/// Looks up the logged-in user in the background so `cached_own_login` can answer
+pub fn refresh_own_user() {
+    if load_token().is_none() {
+        return;
+    }
+    thread::spawn(|| {
+        if let Err(e) = own_user(&http_client()) {
+            eprintln!("Failed to look up logged-in user: {}", e);
+        }
+    });
+}
+
+fn own_user(client: &Client) -> Result<(String, String), Box<dyn StdError + Send + Sync>> {
+    if let Some(user) = OWN_USER.read_locked().clone() {
+        return Ok(user);
+    }
+    let response = authorized(client.get("https://api.twitch.tv/helix/users"))?.send()?;
+    if !response.status().is_success() {
+        return Err(format!("Helix users request failed with status {}", response.status()).into());
+    }
+    let parsed: HelixUsersResponse = response.json()?;
+    let user = parsed
+        .data
+        .into_iter()
+        .next()
+        .map(|user| (user.id, user.login))
+        .ok_or("Helix returned no user for the saved token")?;
+    *OWN_USER.write_locked() = Some(user.clone());
+    Ok(user)
+}
+
+/// User ids of `logins`, keyed by login. Blocking; empty without a saved token.
+pub fn lookup_user_ids(logins: &[String]) -> HashMap<String, String> {
+    if load_token().is_none() {
+        return HashMap::new();
+    }
+    let client = http_client();
+    let mut ids = HashMap::new();
+    for chunk in logins.chunks(MAX_USERS_PER_REQUEST) {
+        match fetch_users_by_login(&client, chunk) {
+            Ok(users) => ids.extend(users.into_iter().map(|user| (user.login, user.id))),
+            Err(e) => eprintln!("Failed to look up user ids: {}", e),
+        }
+    }
+    ids
+}
+
+/// Profile image URLs of `logins`, keyed by login. Blocking; empty without a saved token.
+pub fn lookup_profile_images(logins: &[String]) -> HashMap<String, String> {
+    if load_token().is_none() {
+        return HashMap::new();
+    }
+    let client = http_client();
+    let mut images = HashMap::new();
+    for chunk in logins.chunks(MAX_USERS_PER_REQUEST) {
+        match fetch_users_by_login(&client, chunk) {
+            Ok(users) => images.extend(
+                users
+                    .into_iter()
+                    .filter(|user| !user.profile_image_url.is_empty())
+                    .map(|user| (user.login, user.profile_image_url)),
+            ),
+            Err(e) => eprintln!("Failed to look up profile images: {}", e),
+        }
+    }
+    images
+}
+
+fn fetch_users_by_login(client: &Client, logins: &[String]) -> Result<Vec<HelixUser>, Box<dyn StdError + Send + Sync>> {
+    let query: Vec<(&str, &str)> = logins.iter().map(|login| ("login", login.as_str())).collect();
+    let response = authorized(client.get("https://api.twitch.tv/helix/users").query(&query))?.send()?;
+    if !response.status().is_success() {
+        return Err(format!("Helix users request failed with status {}", response.status()).into());
+    }
+    let parsed: HelixUsersResponse = response.json()?;
+    Ok(parsed.data)
+}
+
+/// Bans `user_id`, or times them out when `duration_secs` is set. Blocking.
+pub fn ban_user(
+    client: &Client,
+    broadcaster_id: &str,
+    user_id: &str,
+    duration_secs: Option<u32>,
+    reason: &str,
+) -> Result<(), Box<dyn StdError + Send + Sync>> {
+    require_scopes(Feature::Moderation)?;
+    let moderator_id = own_user_id(client)?;
+    let mut data = serde_json::json!({
+        "user_id": user_id,
+        "reason": reason,
+    });
+    if let Some(duration) = duration_secs {
+        data["duration"] = serde_json::Value::from(duration);
+    }
+
+    let response = authorized(
+        client
+            .post("https://api.twitch.tv/helix/moderation/bans")
+            .query(&[("broadcaster_id", broadcaster_id), ("moderator_id", moderator_id.as_str())]),
+    )?
+    .json(&serde_json::json!({ "data": data }))
+    .send()?;
+    check_scoped_response(&response, Feature::Moderation, "ban")
+}
+
+/// Sets the logged-in user's chat color: one of Twitch's named colors for anyone, or
+/// "#RRGGBB" for Prime and Turbo users. Blocking.
+pub fn update_chat_color(client: &Client, color: &str) -> Result<(), Box<dyn StdError + Send + Sync>> {
+    require_scopes(Feature::ChatColor)?;
+    let user_id = own_user_id(client)?;
+    let response = authorized(
+        client
+            .put("https://api.twitch.tv/helix/chat/color")
+            .query(&[("user_id", user_id.as_str()), ("color", color)]),
+    )?
+    .send()?;
+    if response.status() == StatusCode::BAD_REQUEST && color.starts_with('#') {
+        return Err("Custom colors need Prime or Turbo".into());
+    }
+    check_scoped_response(&response, Feature::ChatColor, "chat color")
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixFollowedResponse {
+    data: Vec<HelixFollowedChannel>,
+    pagination: Option<HelixPagination>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixFollowedChannel {
+    broadcaster_login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixPagination {
+    cursor: Option<String>,
+}
+
+/// Logins of channels the logged-in user follows, as of the last refresh
+pub fn cached_followed_channels() -> Vec<String> {
+    FOLLOWED_CHANNELS.read_locked().clone()
+}
+
+/// Refreshes the followed channel cache in the background. Needs the user:read:follows
+/// scope; without it the cache stays empty until granted from Preferences.
+pub fn refresh_followed_channels() {
+    if load_token().is_none() || is_granted(Feature::Follows) == Some(false) {
+        return;
+    }
+    thread::spawn(|| {
+        let client = http_client();
+        match fetch_followed_channels(&client) {
+            Ok(channels) => *FOLLOWED_CHANNELS.write_locked() = channels,
+            Err(e) => eprintln!("Failed to fetch followed channels: {}", e),
+        }
+    });
+}
+
+fn fetch_followed_channels(client: &Client) -> Result<Vec<String>, Box<dyn StdError + Send + Sync>> {
+    let user_id = own_user_id(client)?;
+    let mut channels = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut query = vec![("user_id", user_id.clone()), ("first", "100".to_string())];
+        if let Some(after) = cursor.take() {
+            query.push(("after", after));
+        }
+        let response = authorized(
+            client
+                .get("https://api.twitch.tv/helix/channels/followed")
+                .query(&query),
+        )?
+        .send()?;
+        if !response.status().is_success() {
+            return Err(format!("Helix followed channels request failed with status {}", response.status()).into());
+        }
+        let parsed: HelixFollowedResponse = response.json()?;
+        channels.extend(parsed.data.into_iter().map(|c| c.broadcaster_login));
+        cursor = parsed.pagination.and_then(|p| p.cursor);
+        if cursor.is_none() {
+            break;
+        }
+    }
+    channels.sort();
+    Ok(channels)
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixStreamsResponse {
+    data: Vec<HelixStream>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixStream {
+    user_login: String,
+    title: String,
+    #[serde(default)]
+    viewer_count: u32,
+}
+
+/// Polls which of `logins` are live in the background and records newly live ones as activity
+pub fn check_live_channels(logins: Vec<String>) {
+    if logins.is_empty() || load_token().is_none() {
+        return;
+    }
+    thread::spawn(move || {
+        let client = http_client();
+        let mut streams = Vec::new();
+        for chunk in logins.chunks(MAX_USERS_PER_REQUEST) {
+            match fetch_streams(&client, chunk) {
+                Ok(found) => streams.extend(found),
+                Err(e) => {
+                    eprintln!("Failed to fetch live channels: {}", e);
+                    return;
+                }
+            }
+        }
+        let mut live = LIVE_CHANNELS.locked();
+        if let Some(previous) = live.as_ref() {
+            for stream in streams.iter().filter(|s| !previous.contains_key(&s.user_login)) {
+                record_activity(ActivityEvent::new(ActivityKind::GoLive, &stream.user_login, &stream.title));
+            }
+        }
+        *live = Some(streams.into_iter().map(|s| (s.user_login, s.viewer_count)).collect());
+        LIVE_GENERATION.fetch_add(1, Ordering::Relaxed);
+    });
+}
+
+/// Viewer counts of the polled channels that were live at the last poll, by login
+pub fn live_viewer_counts() -> HashMap<String, u32> {
+    LIVE_CHANNELS.locked().clone().unwrap_or_default()
+}
+
+/// Changes whenever a live poll completes, so the UI knows to pick up new counts
+pub fn live_status_generation() -> u64 {
+    LIVE_GENERATION.load(Ordering::Relaxed)
+}
+
+fn fetch_streams(client: &Client, logins: &[String]) -> Result<Vec<HelixStream>, Box<dyn StdError + Send + Sync>> {
+    let mut query: Vec<(&str, &str)> = logins.iter().map(|login| ("user_login", login.as_str())).collect();
+    query.push(("first", "100"));
+    let response = authorized(client.get("https://api.twitch.tv/helix/streams").query(&query))?.send()?;
+    if !response.status().is_success() {
+        return Err(format!("Helix streams request failed with status {}", response.status()).into());
+    }
+    let parsed: HelixStreamsResponse = response.json()?;
+    Ok(parsed.data)
+}
+
+fn fetch_created_at(
+    client: &Client,
+    user_ids: &[String],
+) -> Result<HashMap<String, DateTime<Utc>>, Box<dyn StdError + Send + Sync>> {
+    let query: Vec<(&str, &str)> = user_ids.iter().map(|id| ("id", id.as_str())).collect();
+
+    let response = authorized(client.get("https://api.twitch.tv/helix/users").query(&query))?.send()?;
+    if !response.status().is_success() {
+        return Err(format!("Helix users request failed with status {}", response.status()).into());
+    }
+    let parsed: HelixUsersResponse = response.json()?;
+    Ok(parsed
+        .data
+        .into_iter()
+        .filter_map(|user| {
+            let created_at = DateTime::parse_from_rfc3339(&user.created_at).ok()?;
+            Some((user.id, created_at.with_timezone(&Utc)))
+        })
+        .collect())
+}
+
+// --- Helper Functions ---
+
+// "42m", "5h", "12d", "8mo", "3y"
+pub fn format_account_age(created_at: DateTime<Utc>) -> String {
+    let minutes = Utc::now().signed_duration_since(created_at).num_minutes().max(0);
+    match minutes {
+        m if m < 60 => format!("{}m", m),
+        m if m < 60 * 24 => format!("{}h", m / 60),
+        m if m < 60 * 24 * 60 => format!("{}d", m / (60 * 24)),
+        m if m < 60 * 24 * 365 => format!("{}mo", m / (60 * 24 * 30)),
+        m => format!("{}y", m / (60 * 24 * 365)),
+    }
+}
+
+pub fn account_age_html(created_at: DateTime<Utc>) -> String {
+    let is_new = Utc::now().signed_duration_since(created_at).num_days() < 7;
+    format!(
+        r#"<span class="account-age{}" title="Account created {}">{}</span>"#,
+        if is_new { " account-new" } else { "" },
+        glib::markup_escape_text(&created_at.format("%B %-d, %Y %H:%M UTC").to_string()),
+        format_account_age(created_at)
+    )
+}
+
+/// Places the account age tag after the sender name of a rendered message
+pub fn insert_account_age_html(message_html: &str, created_at: DateTime<Utc>) -> Option<String> {
+    if message_html.contains("account-age") {
+        return None;
+    }
+    let sender = message_html.find(r#"class="sender""#)?;
+    let close = sender + message_html[sender..].find("</span>")? + "</span>".len();
+    let mut html = String::with_capacity(message_html.len() + 128);
+    html.push_str(&message_html[..close]);
+    html.push(' ');
+    html.push_str(&account_age_html(created_at));
+    html.push_str(&message_html[close..]);
+    Some(html)
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixBadgesResponse {
+    data: Vec<HelixBadgeSet>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixBadgeSet {
+    set_id: String,
+    versions: Vec<HelixBadgeVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixBadgeVersion {
+    id: String,
+    image_url_2x: String, // Sharp at the 18px they're shown at on HiDPI screens
+    #[serde(default)]
+    title: String,
+}
+
+/// Fetches the global badges and `channel_id`'s own in the background, once per app
+/// run each. Needs a saved token; without one messages show no badges.
+pub fn request_badges(channel_id: &str) {
+    let scopes: Vec<String> = {
+        let requested = BADGES_REQUESTED.locked();
+        [GLOBAL_BADGES, channel_id]
+            .into_iter()
+            .filter(|scope| !requested.contains(*scope))
+            .map(str::to_string)
+            .collect()
+    };
+    if scopes.is_empty() || load_token().is_none() {
+        return;
+    }
+    BADGES_REQUESTED.locked().extend(scopes.iter().cloned());
+    thread::spawn(move || {
+        let client = http_client();
+        for scope in scopes {
+            match fetch_badges(&client, &scope) {
+                Ok(badges) => store_badges(&scope, badges),
+                Err(e) => {
+                    eprintln!("Failed to fetch {} badges: {}", scope, e);
+                    // Tried again with the next message from the channel
+                    BADGES_REQUESTED.locked().remove(&scope);
+                }
+            }
+        }
+    });
+}
+
+fn fetch_badges(client: &Client, scope: &str) -> Result<HashMap<String, BadgeImage>, Box<dyn StdError + Send + Sync>> {
+    let request = if scope == GLOBAL_BADGES {
+        client.get("https://api.twitch.tv/helix/chat/badges/global")
+    } else {
+        client.get("https://api.twitch.tv/helix/chat/badges").query(&[("broadcaster_id", scope)])
+    };
+    let response = authorized(request)?.send()?;
+    if !response.status().is_success() {
+        return Err(format!("Helix badges request failed with status {}", response.status()).into());
+    }
+    let parsed: HelixBadgesResponse = response.json()?;
+    let mut badges = HashMap::new();
+    for set in parsed.data {
+        for version in set.versions {
+            badges.insert(
+                badge_key(&set.set_id, &version.id),
+                BadgeImage {
+                    url: version.image_url_2x,
+                    title: version.title,
+                },
+            );
+        }
+    }
+    Ok(badges)
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixCheermotesResponse {
+    data: Vec<HelixCheermote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixCheermote {
+    prefix: String,
+    tiers: Vec<HelixCheermoteTier>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixCheermoteTier {
+    min_bits: u64,
+    color: String,
+    images: HelixCheermoteImages,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixCheermoteImages {
+    dark: HelixCheermoteThemeImages,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixCheermoteThemeImages {
+    animated: HashMap<String, String>, // By scale: "1", "1.5", "2", "3", "4"
+}
+
+/// Fetches the cheermotes usable in `channel_id` in the background, once per app run.
+/// Needs a saved token; without one cheers stay plain text.
+pub fn request_cheermotes(channel_id: &str) {
+    if CHEERMOTES_REQUESTED.locked().contains(channel_id) || load_token().is_none() {
+        return;
+    }
+    CHEERMOTES_REQUESTED.locked().insert(channel_id.to_string());
+    let channel_id = channel_id.to_string();
+    thread::spawn(move || match fetch_cheermotes(&http_client(), &channel_id) {
+        Ok(cheermotes) => store_cheermotes(&channel_id, cheermotes),
+        Err(e) => {
+            eprintln!("Failed to fetch cheermotes for {}: {}", channel_id, e);
+            CHEERMOTES_REQUESTED.locked().remove(&channel_id);
+        }
+    });
+}
+
+fn fetch_cheermotes(client: &Client, channel_id: &str) -> Result<Vec<Cheermote>, Box<dyn StdError + Send + Sync>> {
+    let response = authorized(
+        client
+            .get("https://api.twitch.tv/helix/bits/cheermotes")
+            .query(&[("broadcaster_id", channel_id)]),
+    )?
+    .send()?;
+    if !response.status().is_success() {
+        return Err(format!("Helix cheermotes request failed with status {}", response.status()).into());
+    }
+    let parsed: HelixCheermotesResponse = response.json()?;
+    Ok(parsed
+        .data
+        .into_iter()
+        .map(|cheermote| Cheermote {
+            prefix: cheermote.prefix,
+            tiers: cheermote
+                .tiers
+                .into_iter()
+                .filter_map(|tier| {
+                    let mut animated = tier.images.dark.animated;
+                    let url = animated.remove("2").or_else(|| animated.remove("1"))?;
+                    Some(CheerTier {
+                        min_bits: tier.min_bits,
+                        color: tier.color,
+                        url,
+                    })
+                })
+                .collect(),
+        })
+        .collect())
+}
@@ -0,0 +1,82 @@
+// idle.rs
+
+use adw::gio;
+use adw::prelude::*;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Session idle state as reported by logind or the GNOME session manager
+static SESSION_IDLE: AtomicBool = AtomicBool::new(false);
+
+const GNOME_PRESENCE_IDLE: u32 = 3;
+
+thread_local! {
+    // Proxies stop emitting property changes once dropped
+    static PROXIES: RefCell<Vec<gio::DBusProxy>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn is_session_idle() -> bool {
+    SESSION_IDLE.load(Ordering::Relaxed)
+}
+
+/// Starts following the session idle state. Missing services are ignored, in which
+/// case the session is treated as always active.
+pub fn watch_session_idle() {
+    watch_property(
+        gio::BusType::System,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1/session/auto",
+        "org.freedesktop.login1.Session",
+        "IdleHint",
+        |value| value.get::<bool>(),
+    );
+    watch_property(
+        gio::BusType::Session,
+        "org.gnome.SessionManager",
+        "/org/gnome/SessionManager/Presence",
+        "org.gnome.SessionManager.Presence",
+        "status",
+        |value| value.get::<u32>().map(|status| status == GNOME_PRESENCE_IDLE),
+    );
+}
+
+fn watch_property(
+    bus_type: gio::BusType,
+    name: &'static str,
+    object_path: &str,
+    interface_name: &str,
+    property: &'static str,
+    is_idle: fn(&glib::Variant) -> Option<bool>,
+) {
+    gio::DBusProxy::for_bus(
+        bus_type,
+        gio::DBusProxyFlags::DO_NOT_AUTO_START,
+        None,
+        name,
+        object_path,
+        interface_name,
+        None::<&gio::Cancellable>,
+        move |result| {
+            let proxy = match result {
+                Ok(proxy) => proxy,
+                Err(e) => {
+                    eprintln!("Idle detection via {} unavailable: {}", name, e);
+                    return;
+                }
+            };
+            let update = move |proxy: &gio::DBusProxy| {
+                if let Some(idle) = proxy.cached_property(property).as_ref().and_then(is_idle) {
+                    SESSION_IDLE.store(idle, Ordering::Relaxed);
+                }
+            };
+            update(&proxy);
+            proxy.connect_local("g-properties-changed", false, move |values| {
+                if let Some(proxy) = values.first().and_then(|v| v.get::<gio::DBusProxy>().ok()) {
+                    update(&proxy);
+                }
+                None
+            });
+            PROXIES.with(|proxies| proxies.borrow_mut().push(proxy));
+        },
+    );
+}
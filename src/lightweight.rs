@@ -0,0 +1,163 @@
+// lightweight.rs
+
+use chrono::Local;
+use gtk::prelude::*;
+use gtk::{ScrolledWindow, Stack, TextBuffer, TextTag, TextView};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::hype_chat::PaidMessage;
+use crate::message_budget::max_retained_messages;
+use crate::rules::matches_highlight_rule;
+
+/// The chat area of a tab, which shows either the WebView or, in lightweight mode, the
+/// chat as plain text: no emote images and no WebKit process, for machines too old to
+/// keep a WebView per channel running
+#[derive(Clone)]
+pub struct LightweightView {
+    pub views: Stack, // "web" or "text"
+    text_view: TextView,
+}
+
+pub fn build_lightweight_view(web: &impl IsA<gtk::Widget>) -> LightweightView {
+    let buffer = TextBuffer::new(None);
+    for tag in [
+        TextTag::builder().name("timestamp").foreground("#888888").scale(0.85).build(),
+        TextTag::builder().name("name").weight(700).build(),
+        TextTag::builder().name("notice").foreground("#888888").style(gtk::pango::Style::Italic).build(),
+        TextTag::builder().name("highlight").paragraph_background("rgba(255, 196, 0, 0.2)").build(),
+        TextTag::builder().name("paid").weight(700).foreground("#2ec27e").build(),
+    ] {
+        buffer.tag_table().add(&tag);
+    }
+    // Right gravity, so it stays at the end as lines go in and the view can follow it
+    buffer.create_mark(Some("end"), &buffer.end_iter(), false);
+
+    let text_view = TextView::builder()
+        .buffer(&buffer)
+        .editable(false)
+        .cursor_visible(false)
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .left_margin(8)
+        .right_margin(8)
+        .top_margin(4)
+        .bottom_margin(4)
+        .build();
+    let scrolled_window = ScrolledWindow::builder()
+        .vexpand(true)
+        .hexpand(true)
+        .child(&text_view)
+        .build();
+
+    let views = Stack::builder()
+        .vexpand(true)
+        .hexpand(true)
+        .build();
+    views.add_named(web, Some("web"));
+    views.add_named(&scrolled_window, Some("text"));
+    views.set_visible_child_name("web");
+    LightweightView { views, text_view }
+}
+
+impl LightweightView {
+    pub fn show_text(&self, text: bool) {
+        self.views.set_visible_child_name(if text { "text" } else { "web" });
+    }
+
+    pub fn clear(&self) {
+        self.text_view.buffer().set_text("");
+    }
+
+    pub fn append_messages(&self, messages: &[PrivmsgMessage]) {
+        let following = self.is_at_bottom();
+        let buffer = self.text_view.buffer();
+        for msg in messages {
+            self.start_line(&buffer);
+            let line_start = buffer.end_iter().offset();
+            let timestamp = msg.server_timestamp.with_timezone(&Local).format("%H:%M ").to_string();
+            buffer.insert_with_tags_by_name(&mut buffer.end_iter(), &timestamp, &["timestamp"]);
+            // No pinned area here; the amount in front marks a Hype Chat
+            if let Some(paid) = PaidMessage::from_message(msg) {
+                let amount = format!("[{}] ", paid.formatted_amount());
+                buffer.insert_with_tags_by_name(&mut buffer.end_iter(), &amount, &["paid"]);
+            }
+            let name_tag = name_tag(&buffer, msg);
+            buffer.insert_with_tags(&mut buffer.end_iter(), &msg.sender.name, &[&name_tag]);
+            let separator = if msg.is_action { " " } else { ": " };
+            buffer.insert(&mut buffer.end_iter(), &format!("{}{}", separator, msg.message_text));
+            if is_highlighted(msg) {
+                let start = buffer.iter_at_offset(line_start);
+                buffer.apply_tag_by_name("highlight", &start, &buffer.end_iter());
+            }
+        }
+        self.trim(&buffer);
+        if following {
+            self.scroll_to_end(&buffer);
+        }
+    }
+
+    /// A line from Admiral itself, like the skipped messages divider
+    pub fn append_notice(&self, text: &str) {
+        let following = self.is_at_bottom();
+        let buffer = self.text_view.buffer();
+        self.start_line(&buffer);
+        buffer.insert_with_tags_by_name(&mut buffer.end_iter(), &format!("-- {}", text), &["notice"]);
+        self.trim(&buffer);
+        if following {
+            self.scroll_to_end(&buffer);
+        }
+    }
+
+    fn start_line(&self, buffer: &TextBuffer) {
+        if buffer.char_count() > 0 {
+            buffer.insert(&mut buffer.end_iter(), "\n");
+        }
+    }
+
+    // Same limit as the WebView's buffer, oldest lines first
+    fn trim(&self, buffer: &TextBuffer) {
+        let excess = buffer.line_count() - max_retained_messages() as i32;
+        if excess <= 0 {
+            return;
+        }
+        if let Some(mut end) = buffer.iter_at_line(excess) {
+            buffer.delete(&mut buffer.start_iter(), &mut end);
+        }
+    }
+
+    // Scrolled up to read back, the view stays put as lines arrive
+    fn is_at_bottom(&self) -> bool {
+        let Some(adjustment) = self.text_view.vadjustment() else {
+            return true;
+        };
+        adjustment.value() + adjustment.page_size() >= adjustment.upper() - 1.0
+    }
+
+    fn scroll_to_end(&self, buffer: &TextBuffer) {
+        if let Some(mark) = buffer.mark("end") {
+            self.text_view.scroll_mark_onscreen(&mark);
+        }
+    }
+}
+
+fn is_highlighted(msg: &PrivmsgMessage) -> bool {
+    msg.source.tags.0.get("msg-id").and_then(|value| value.as_deref()) == Some("highlighted-message")
+        || matches_highlight_rule(&msg.message_text)
+}
+
+// One tag per name color, made on first use
+fn name_tag(buffer: &TextBuffer, msg: &PrivmsgMessage) -> TextTag {
+    let Some(rgb) = &msg.name_color else {
+        return buffer.tag_table().lookup("name").expect("name tag is created with the buffer");
+    };
+    let tag_name = format!("name-{:02x}{:02x}{:02x}", rgb.r, rgb.g, rgb.b);
+    if let Some(tag) = buffer.tag_table().lookup(&tag_name) {
+        return tag;
+    }
+    let tag = TextTag::builder()
+        .name(tag_name.as_str())
+        .weight(700)
+        .foreground(format!("#{:02x}{:02x}{:02x}", rgb.r, rgb.g, rgb.b))
+        .build();
+    buffer.tag_table().add(&tag);
+    tag
+}
@@ -3,32 +3,125 @@
 // Import the correct gio for webkit6
 use adw::prelude::*;
 use adw::{Application, ApplicationWindow, HeaderBar, TabBar, TabView, TabPage, TabOverview};
+use regex::Regex;
 use gtk::{gdk, ScrolledWindow, Button, Entry, Button as GtkButton, Orientation, Box, Align, Stack, ListBoxRow, Popover};
 use webkit6::WebView;
 use webkit6::prelude::WebViewExt;
-use std::sync::{Arc, Mutex, atomic::{AtomicU64, AtomicBool, Ordering}};
-use twitch_irc::{ClientConfig, SecureTCPTransport, TwitchIRCClient};
 use twitch_irc::login::StaticLoginCredentials;
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, AtomicU64, Ordering}};
 use glib::clone;
 use adw::gio::SimpleAction;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::collections::VecDeque;
-use std::sync::mpsc;
 use std::thread;
-use tokio::runtime::Runtime;
 use serde::Deserialize;
 use serde::Serialize;
 use shellexpand;
 use std::fs;
 use std::path::Path;
-use std::io::Read;
+use std::io::{IsTerminal, Read};
 use toml;
 use rlimit;
 use std::time::{Instant, Duration};
 
+// The window-free parts live in admiral-core; imported here so they're reached through
+// crate:: like the app's own modules
+use admiral_core::{badges, bots, cheermotes, emotes, filters, headless, history, hype_chat, markup, moderation, network, profile, room_state, rules, state, transport};
+mod activity;
+mod appearance;
+mod avatars;
 mod auth;
-mod emotes;
-use crate::emotes::{MESSAGE_CSS, get_emote_map, parse_message_html, cleanup_emote_cache, cleanup_media_file_cache};
+mod chat_color;
+mod chat_events;
+mod benchmark;
+mod command_bar;
+mod connections;
+mod crash;
+mod demo;
+mod export;
+mod giveaway;
+mod helix;
+mod idle;
+mod lightweight;
+mod message_budget;
+mod message_queue;
+mod mod_tools;
+mod offline;
+mod preferences;
+mod pump;
+mod notes;
+mod pacing;
+mod page_bridge;
+mod palette;
+mod poll;
+mod quiet_hours;
+mod render;
+mod schedule;
+mod send_history;
+mod scopes;
+mod script_messages;
+mod startup;
+mod stats;
+mod status_icon;
+mod token_store;
+mod translate;
+mod updates;
+mod upload;
+mod user_card;
+mod vod;
+mod watchdog;
+use crate::appearance::{APPLY_SETTINGS_JS, AppearanceSettings, apply_settings_js, vars_css};
+use crate::avatars::channel_avatar;
+use crate::bots::BotSettings;
+use crate::chat_color::{IdentityButton, build_identity_button, color_hex};
+use crate::chat_events::{CHAT_CLEARED_TEXT, ChatEvent, add_gift_recipient_html, celebration_notice_html, celebration_notice_text, chat_cleared_notice_html, gift_bomb_text, gift_card_html, gift_card_marker, mark_deleted_html, mark_moderated_html, moderation_label};
+use crate::command_bar::{Command, HELP_TEXT, parse_command};
+use crate::crash::{discard_crash_report, install_crash_handler, remember_open_channels, take_crash_report};
+use crate::activity::{ActivityEvent, ActivityKind, build_activity_panel, mark_channel_read, record_activity, refresh_activity_list, unread_activity_count};
+use crate::vod::{ReplayBar, ReplayControl, build_replay_bar, escape_tag, fetch_vod_info, parse_vod_id, start_replay};
+use crate::benchmark::{FrameStats, benchmark_config, finish_benchmark, is_benchmarking, message_timestamps, record_rendered, start_benchmark, take_benchmark_args};
+use crate::demo::{DEFAULT_DEMO_RATE, DEMO_CHANNEL, start_demo};
+use crate::idle::{is_session_idle, watch_session_idle};
+use crate::lightweight::{LightweightView, build_lightweight_view};
+use crate::export::{ExportFormat, session_html, session_json};
+use crate::filters::{is_hidden, remove_hidden_messages};
+use crate::headless::{HeadlessOptions, run_headless};
+use crate::giveaway::{Giveaway, build_giveaway_popover};
+use crate::history::{HistorySettings, configure_history, messages_before, record_history};
+use crate::watchdog::{WATCHDOG_INTERVAL_SECS, WatchdogAction, WatchdogSettings, claim_web_process, release_web_process, resident_mb};
+use crate::helix::{AccountAge, account_age_html, cached_followed_channels, cached_own_login, cached_own_user, check_live_channels, insert_account_age_html, live_status_generation, live_viewer_counts, lookup_user_ids, own_login, refresh_followed_channels, refresh_own_user, refresh_token_info, request_account_age, request_badges, request_cheermotes, update_chat_color};
+use crate::message_budget::{MessageBudgetSettings, MessageBuffer, configure_message_budget, max_retained_messages};
+use crate::message_queue::{DEFAULT_QUEUE_CAPACITY, MessageQueue, skipped_notice_html};
+use crate::moderation::ModerationSettings;
+use crate::network::{NetworkSettings, ProxyMode, configure_network, http_client};
+use crate::notes::{NotesPane, build_notes_pane};
+use crate::pacing::RenderPacing;
+use crate::offline::{is_offline, watch_network};
+use crate::page_bridge::{PageCalls, call_page};
+use crate::palette::{PaletteItem, show_palette};
+use crate::poll::{Poll, build_poll_popover};
+use crate::profile::{cache_dir, config_dir, data_dir, profile, set_profile};
+use crate::pump::{PumpSender, TabWaker, pump_channel, set_pump_handler};
+use crate::render::{RenderJob, RenderedBatch, submit_render};
+use crate::room_state::RoomState;
+use crate::rules::{RuleSettings, configure_rules, matches_highlight_rule};
+use crate::quiet_hours::{QuietHoursSettings, configure_quiet_hours};
+use crate::schedule::{ChannelSchedule, SCHEDULE_CHECK_INTERVAL_SECS, show_schedule_dialog};
+use crate::send_history::{SendHistory, attach_send_history};
+use crate::script_messages::{ScriptMessage, parse_script_message};
+use crate::user_card::{UserCardContext, show_user_card};
+use crate::startup::{StartupBehavior, StartupSettings};
+use crate::state::MutexExt;
+use crate::stats::{ChannelStats, build_activity_sparkline, build_stats_popover};
+use crate::transport::ChatClient;
+use crate::scopes::watch_consent_requests;
+use crate::updates::check_for_updates;
+use crate::upload::{UploadSettings, attach_paste_upload};
+use crate::status_icon::set_status_icon_visible;
+use crate::emotes::{EmoteSettings, MESSAGE_CSS, RenderOptions, configure_emote_matching, emote_map_for, find_emote, get_emote_map, parse_message_html, source_room_id, cleanup_emote_cache, cleanup_media_file_cache, emote_cache_info, forget_saved_emote_maps, known_channel_id, prefetch_emotes, refresh_emotes, remember_channel_id};
+use crate::translate::{TranslationConfig, TranslatedMessage, request_translation, is_translatable, translation_html, insert_translation_html};
 
 // Connection state management
 #[derive(Debug, Clone)]
@@ -62,8 +155,9 @@ fn get_chat_html_template() -> &'static str {
             display: flex;
             flex-direction: column;
             font-family: sans-serif;
-            background-color: transparent;
-            color: inherit;
+            font-size: var(--chat-font-size, 16px);
+            background-color: var(--chat-background, var(--theme-bg, transparent));
+            color: var(--theme-fg);
             will-change: transform;
             transform: translateZ(0);
             -webkit-transform: translateZ(0);
@@ -86,29 +180,43 @@ fn get_chat_html_template() -> &'static str {
             contain: layout style paint; /* Optimize repaints */
         }
         .message-box {
-            border: 1px solid rgba(153, 153, 153, 0.3);
-            border-radius: 8px;
-            padding: 8px;
-            margin-bottom: 4px;
-            background-color: rgba(255, 255, 255, 0.02);
+            border: var(--message-border-width, 1px) solid rgba(153, 153, 153, 0.3);
+            border-radius: var(--message-radius, 8px);
+            padding: var(--message-padding, 8px);
+            margin-bottom: var(--message-spacing, 4px);
+            background-color: var(--theme-message-bg);
             contain: layout style paint; /* Isolate repaints */
         }
-        .message-header { display: flex; justify-content: space-between; }
-        .sender { font-weight: bold; }
+        .message-header { display: flex; align-items: center; }
+        .message-header .timestamp { margin-left: auto; }
+        .badge {
+            margin-right: 3px;
+            vertical-align: middle;
+        }
+        .cheer {
+            white-space: nowrap;
+        }
+        .cheer-amount {
+            font-weight: bold;
+        }
+        .sender { font-weight: bold; cursor: pointer; }
+        .sender:hover { text-decoration: underline; }
         .timestamp { color: rgba(170, 170, 170, 0.8); font-size: 0.8em; }
+        .timestamps-on-hover .timestamp { visibility: hidden; }
+        .timestamps-on-hover .message-box:hover .timestamp { visibility: visible; }
         .message-content {
             margin-top: 4px;
             word-wrap: break-word;
-            line-height: 28px;
+            line-height: var(--emote-size, 28px);
             font-weight: light;
         }
         .message-content img {
-            height: 28px;
+            height: var(--emote-size, 28px);
             width: auto;
             vertical-align: middle;
             display: inline-block;
             margin: 0 2px;
-            max-height: 28px;
+            max-height: var(--emote-size, 28px);
             max-width: none;
             pointer-events: auto;
             cursor: pointer;
@@ -119,6 +227,12 @@ fn get_chat_html_template() -> &'static str {
         .message-content img:hover {
             transform: scale(1.1);
         }
+        .message-content img.emoji {
+            cursor: auto;
+        }
+        .message-content img.emoji:hover {
+            transform: none;
+        }
         .emote-stack {
             display: inline-grid;
             vertical-align: middle;
@@ -130,17 +244,285 @@ fn get_chat_html_template() -> &'static str {
             align-self: center;
         }
         .emote-stack > img {
-            height: 28px;
+            height: var(--emote-size, 28px);
             width: auto;
             max-width: none;
         }
         .emote-overlay {
             pointer-events: none;
         }
+        /* Frame-capped emotes: the canvas shows the frame, the transparent image above takes clicks */
+        .emote-stack > img.frame-held {
+            opacity: 0;
+            z-index: 1;
+        }
+        .emote-stack > img.frame-held:hover + canvas.held-frame {
+            transform: scale(1.1);
+        }
+        canvas.held-frame {
+            height: var(--emote-size, 28px);
+            width: auto;
+            pointer-events: none;
+        }
+        .emote-only canvas.held-frame {
+            height: calc(var(--emote-size, 28px) * 2);
+        }
+        .emote-only .message-content img,
+        .emote-only .emote-stack > img {
+            height: calc(var(--emote-size, 28px) * 2);
+            max-height: calc(var(--emote-size, 28px) * 2);
+        }
+        .emote-only .message-content {
+            line-height: calc(var(--emote-size, 28px) * 2);
+        }
+        .message-translation {
+            margin-top: 4px;
+            padding-left: 6px;
+            border-left: 2px solid rgba(153, 153, 153, 0.5);
+            font-size: 0.9em;
+            font-style: italic;
+            opacity: 0.8;
+            word-wrap: break-word;
+        }
+        .skipped-notice {
+            display: flex;
+            align-items: center;
+            gap: 8px;
+            font-size: 0.85em;
+            opacity: 0.6;
+            margin: 4px 0;
+        }
+        .skipped-notice::before,
+        .skipped-notice::after {
+            content: "";
+            flex: 1;
+            border-top: 1px solid currentColor;
+        }
+        .tool-notice {
+            margin: 6px 0;
+            padding: 6px 8px;
+            border-left: 4px solid var(--accent-bg);
+            border-radius: 4px;
+            background-color: var(--accent-soft);
+        }
+        .message-box.highlighted {
+            border-left: 4px solid rgba(145, 70, 255, 0.9);
+            background-color: rgba(145, 70, 255, 0.12);
+        }
+        /* Deleted by a moderator, kept when showing deleted messages is turned on */
+        .message-box.deleted .message-content {
+            text-decoration: line-through;
+            opacity: 0.6;
+        }
+        .message-box.moderated .message-content {
+            opacity: 0.5;
+        }
+        .moderation-tag,
+        .deleted-tag {
+            margin-left: 6px;
+            padding: 0 4px;
+            border-radius: 3px;
+            font-size: 0.75em;
+            text-transform: uppercase;
+            background-color: rgba(224, 27, 36, 0.25);
+        }
+        .message-box.keyboard-selected {
+            outline: 2px solid rgba(53, 132, 228, 0.8);
+            outline-offset: -1px;
+        }
+        /* Shown while reading back, until the user returns to the newest messages */
+        #jump-to-live {
+            position: fixed;
+            bottom: 12px;
+            left: 50%;
+            transform: translateX(-50%);
+            padding: 6px 14px;
+            border: none;
+            border-radius: 16px;
+            font: inherit;
+            font-size: 0.9em;
+            color: #ffffff;
+            background-color: rgba(53, 132, 228, 0.9);
+            box-shadow: 0 2px 6px rgba(0, 0, 0, 0.3);
+            cursor: pointer;
+            z-index: 10;
+        }
+        #jump-to-live[hidden] {
+            display: none;
+        }
+        /* Copies of the selected messages, on top of the chat while it's captured as an image */
+        #snapshot-view {
+            position: fixed;
+            top: 0;
+            left: 0;
+            right: 0;
+            padding: 8px;
+            z-index: 20;
+            background-color: var(--theme-bg, Canvas);
+            background-image: linear-gradient(var(--chat-background, transparent), var(--chat-background, transparent));
+        }
+        .gift-card,
+        .celebration-notice {
+            margin-bottom: var(--message-spacing, 4px);
+            padding: 6px 8px;
+            border-radius: var(--message-radius, 8px);
+            font-size: 0.9em;
+            background-color: rgba(145, 70, 255, 0.12);
+        }
+        .gift-card summary {
+            cursor: pointer;
+            font-weight: bold;
+        }
+        .gift-recipients {
+            margin: 4px 0 0 0;
+            padding-left: 20px;
+            columns: 3 8em;
+        }
+        .celebration-notice {
+            border-left: 4px solid var(--celebration-color, rgba(145, 70, 255, 0.9));
+        }
+        .celebration-text {
+            font-weight: bold;
+        }
+        .celebration-message {
+            margin-top: 4px;
+            font-size: 1.1em;
+        }
+        .celebration-sub,
+        .celebration-resub { --celebration-color: #9146ff; }
+        .celebration-gift { --celebration-color: #2ec27e; }
+        .celebration-raid {
+            --celebration-color: #e01b24;
+            background-color: rgba(224, 27, 36, 0.12);
+        }
+        .celebration-watch-streak { --celebration-color: #e5a50a; }
+        /* Pinned strip above the chat. Hype Chat messages carry their tier's color band
+           and stay pinned for as long as the tier pays for. */
+        #pinned-messages {
+            flex: none;
+            max-height: 40%;
+            overflow-y: auto;
+            padding: 8px 8px 0 8px;
+            border-bottom: 1px solid rgba(153, 153, 153, 0.3);
+        }
+        #pinned-messages[hidden] {
+            display: none;
+        }
+        .pinned-copy {
+            position: relative;
+            cursor: pointer;
+        }
+        .pinned-copy:not(.paid-message) {
+            border-left: 4px solid rgba(53, 132, 228, 0.9);
+        }
+        .pin-dismiss {
+            float: right;
+            margin-left: 6px;
+            padding: 0 6px;
+            border: none;
+            border-radius: 50%;
+            font: inherit;
+            color: inherit;
+            background: none;
+            cursor: pointer;
+        }
+        .pin-dismiss:hover {
+            background-color: rgba(128, 128, 128, 0.2);
+        }
+        .message-box.paid-message {
+            border-left: 6px solid var(--paid-color);
+            background-color: color-mix(in srgb, var(--paid-color) 15%, transparent);
+        }
+        .paid-amount {
+            margin-left: 6px;
+            padding: 0 6px;
+            border-radius: 3px;
+            font-size: 0.85em;
+            font-weight: bold;
+            color: #ffffff;
+            background-color: var(--paid-color);
+        }
+        .paid-level-1 { --paid-color: #3584e4; }
+        .paid-level-2 { --paid-color: #1c9ec4; }
+        .paid-level-3 { --paid-color: #2ec27e; }
+        .paid-level-4 { --paid-color: #8fb82a; }
+        .paid-level-5 { --paid-color: #e5a50a; }
+        .paid-level-6 { --paid-color: #ff7800; }
+        .paid-level-7 { --paid-color: #e01b24; }
+        .paid-level-8 { --paid-color: #c01c6b; }
+        .paid-level-9 { --paid-color: #9141ac; }
+        .paid-level-10 { --paid-color: #613583; }
+        .mention {
+            font-weight: bold;
+        }
+        /* System accent color, when turned on in preferences */
+        .accent-colors .mention,
+        .accent-colors a {
+            color: var(--accent);
+        }
+        .accent-colors .message-box.highlighted {
+            border-left-color: var(--accent-bg);
+            background-color: var(--accent-soft);
+        }
+        .accent-colors .message-box.keyboard-selected {
+            outline-color: var(--accent-bg);
+        }
+        .accent-colors .emote-popover-provider {
+            color: var(--accent);
+        }
+        /* Names carry the chatter's color inline, so these need to win over it */
+        .names-accent .sender {
+            color: var(--accent) !important;
+        }
+        .names-plain .sender {
+            color: inherit !important;
+        }
+        .bot-dimmed {
+            opacity: 0.45;
+        }
+        .bot-collapsed {
+            opacity: 0.6;
+            padding-top: 2px;
+            padding-bottom: 2px;
+            cursor: pointer;
+        }
+        .bot-collapsed .message-content {
+            display: none;
+        }
+        .bot-collapsed.expanded .message-content {
+            display: block;
+        }
+        .account-age {
+            font-size: 0.75em;
+            padding: 0 4px;
+            border-radius: 4px;
+            background-color: rgba(153, 153, 153, 0.2);
+            color: rgba(170, 170, 170, 0.9);
+        }
+        .account-age.account-new {
+            background-color: rgba(230, 97, 0, 0.3);
+            color: inherit;
+        }
+        .translation-lang {
+            font-style: normal;
+            font-size: 0.8em;
+            font-weight: bold;
+            color: rgba(170, 170, 170, 0.8);
+        }
+        /* Defaults until the window's theme colors are applied */
         :root {
+            --theme-fg: #ffffff;
+            --theme-message-bg: rgba(255, 255, 255, 0.02);
+            --accent: #78aeed;
+            --accent-bg: rgba(53, 132, 228, 0.9);
+            --accent-soft: rgba(53, 132, 228, 0.12);
             --popover-bg: rgba(30, 30, 30, 0.95);
             --popover-border: rgba(255, 255, 255, 0.2);
             --popover-text: rgba(255, 255, 255, 0.6);
+            --message-padding: 8px;
+            --message-spacing: 4px;
+            --message-border-width: 1px;
+            --message-radius: 8px;
         }
         /* Emote popover styles */
         .emote-popover {
@@ -171,6 +553,12 @@ fn get_chat_html_template() -> &'static str {
             margin-bottom: 4px;
             font-size: 14px;
         }
+        .emote-popover-provider {
+            font-size: 11px;
+            text-align: center;
+            opacity: 0.7;
+            margin-bottom: 4px;
+        }
         .emote-popover-url {
             font-size: 10px;
             color: var(--popover-text);
@@ -198,21 +586,30 @@ fn get_chat_html_template() -> &'static str {
             background-color: rgba(128, 128, 128, 0.2);
             color: inherit;
         }
+        .emote-popover-insert {
+            display: block;
+            margin: 8px auto 0;
+            padding: 4px 10px;
+            border: none;
+            border-radius: 6px;
+            background-color: rgba(128, 128, 128, 0.2);
+            color: var(--popover-text);
+            cursor: pointer;
+        }
+        .emote-popover-insert:hover {
+            background-color: rgba(128, 128, 128, 0.35);
+        }
         /* Buffer element for maintaining scroll position */
         .scroll-buffer {
             height: 1px;
             width: 100%;
             flex-shrink: 0;
         }
-        @media (prefers-color-scheme: dark) {
-            body { color: #ffffff; }
-        }
         @media (prefers-color-scheme: light) {
-            body {
-                color: #000000;
-                background-color: transparent;
+            :root {
+                --theme-fg: #000000;
+                --theme-message-bg: rgba(0, 0, 0, 0.02);
             }
-            .message-box { background-color: rgba(0, 0, 0, 0.02); }
         }
         #chat-container {
             position: relative;
@@ -223,40 +620,100 @@ fn get_chat_html_template() -> &'static str {
       </style>
     </head>
     <body>
+    <div id="pinned-messages" hidden></div>
     <div id="chat-container">
       <div id="chat-body">
         <div class="scroll-buffer"></div> <!-- Initial buffer element -->
       </div>
     </div>
+    <button id="jump-to-live" type="button" hidden>Jump to live</button>
     <script>
       let isUserScrolling = false;
       let scrollTimeout = null;
       const chatContainer = document.getElementById('chat-container');
       const chatBody = document.getElementById('chat-body');
-      const MAX_MESSAGES = 200; // Increased buffer size
-      const CLEANUP_THRESHOLD = 300; // Cleanup only when significantly over limit
-      let messageCount = 0;
-      let messageQueue = [];
+      // Every retained message node lives in `entries` (oldest first), but only the window
+      // entries[windowStart..windowEnd) is attached to the DOM. The rest stay detached, so
+      // annotations added while they're offscreen survive until they're scrolled back in.
+      const MAX_STORED = 2000;
+      const WINDOW_SIZE = 150;
+      const PAGE_SIZE = 50;
+      const EDGE_DISTANCE = 300; // px from the top or bottom edge that pages in more messages
+      let entries = [];
+      let windowStart = 0;
+      let windowEnd = 0;
+      let pendingHtml = [];
+      let frameScheduled = false;
       let lastScrollHeight = 0;
       let lastScrollTop = 0;
       const emoteCache = new Map();
 
+      const jumpToLiveButton = document.getElementById('jump-to-live');
+      const pinnedMessages = document.getElementById('pinned-messages');
+
       let scrollEventHandler = function() {
         const isAtBottom = chatContainer.scrollHeight - chatContainer.scrollTop <= chatContainer.clientHeight + 50;
-        isUserScrolling = !isAtBottom;
+        isUserScrolling = !isAtBottom || windowEnd < entries.length;
+        jumpToLiveButton.hidden = !isUserScrolling;
 
         // Store scroll position for anchoring
         lastScrollTop = chatContainer.scrollTop;
         lastScrollHeight = chatContainer.scrollHeight;
 
-        clearTimeout(scrollTimeout);
-        scrollTimeout = setTimeout(() => {
-          isUserScrolling = false;
-          flushMessageQueue();
-        }, 3000);
+        if (chatContainer.scrollTop < EDGE_DISTANCE && windowStart > 0) {
+          scheduleFrame(loadOlder);
+        } else if (chatContainer.scrollTop < EDGE_DISTANCE) {
+          requestOlderHistory();
+        } else if (chatContainer.scrollHeight - chatContainer.scrollTop - chatContainer.clientHeight < EDGE_DISTANCE
+            && windowEnd < entries.length) {
+          scheduleFrame(loadNewer);
+        }
+
+        reportScrollAnchor();
       };
       chatContainer.addEventListener('scroll', scrollEventHandler);
 
+      jumpToLiveButton.addEventListener('click', () => {
+        isUserScrolling = false;
+        jumpToLiveButton.hidden = true;
+        jumpToLatest();
+        reportScrollAnchor();
+      });
+
+      // Tells the app which message is at the top while reading back (null while following
+      // the chat), so the position survives the page being refilled on a tab switch
+      function reportScrollAnchor() {
+        clearTimeout(scrollTimeout);
+        scrollTimeout = setTimeout(() => {
+          postToApp({ type: 'scroll-anchor', message_id: isUserScrolling ? firstVisibleMessageId() : null });
+        }, 250);
+      }
+
+      function firstVisibleMessageId() {
+        const top = chatContainer.getBoundingClientRect().top;
+        for (let i = windowStart; i < windowEnd; i++) {
+          const node = entries[i];
+          if (node.dataset && node.dataset.msgId && node.getBoundingClientRect().bottom > top) {
+            return node.dataset.msgId;
+          }
+        }
+        return null;
+      }
+
+      // Puts a message reported by reportScrollAnchor back at the top. One that has since
+      // been evicted leaves the view following the chat.
+      function restoreScrollAnchor(messageId) {
+        const index = findMessageIndex(messageId);
+        if (index === -1) {
+          reportScrollAnchor();
+          return;
+        }
+        isUserScrolling = true;
+        jumpToLiveButton.hidden = false;
+        showWindowAround(index);
+        entries[index].scrollIntoView({ block: 'start' });
+      }
+
       function maintainScrollPosition() {
         const currentScrollHeight = chatContainer.scrollHeight;
         const heightDiff = currentScrollHeight - lastScrollHeight;
@@ -265,185 +722,673 @@ fn get_chat_html_template() -> &'static str {
           // Auto-scroll to bottom
           chatContainer.scrollTop = chatContainer.scrollHeight;
         }
+        lastScrollHeight = chatContainer.scrollHeight;
       }
 
-      function cleanupOldMessages() {
-        const messages = chatBody.getElementsByClassName('message-box');
-        messageCount = messages.length;
+      // All DOM work happens in animation frames; the same callback is only queued once per frame
+      let frameCallbacks = new Set();
 
-        if (messageCount > CLEANUP_THRESHOLD) {
-          const toRemove = messageCount - MAX_MESSAGES;
-          const currentScrollTop = chatContainer.scrollTop;
-          const currentScrollHeight = chatContainer.scrollHeight;
-
-          // Batch remove with requestAnimationFrame for smooth performance
+      function scheduleFrame(callback) {
+        frameCallbacks.add(callback);
+        if (!frameScheduled) {
+          frameScheduled = true;
           requestAnimationFrame(() => {
-            for (let i = 0; i < toRemove; i++) {
-              if (messages.length > 0 && messages[0].className !== 'scroll-buffer') {
-                chatBody.removeChild(messages[0]);
-              }
-            }
-
-            // Maintain relative scroll position
-            const newScrollHeight = chatContainer.scrollHeight;
-            const scrollRatio = currentScrollTop / currentScrollHeight;
-            chatContainer.scrollTop = newScrollHeight * scrollRatio;
+            frameScheduled = false;
+            const callbacks = frameCallbacks;
+            frameCallbacks = new Set();
+            callbacks.forEach(cb => cb());
           });
         }
       }
 
-      function flushMessageQueue() {
-        if (messageQueue.length > 0) {
-          const batchSize = Math.min(messageQueue.length, 20); // Process in smaller batches
+      function parseNodes(htmlString) {
+        const tempDiv = document.createElement('div');
+        tempDiv.innerHTML = htmlString;
+        return Array.from(tempDiv.children);
+      }
 
-          for (let i = 0; i < batchSize; i++) {
-            const emoteUrls = extractEmoteUrls(messageQueue[i]);
-            emoteUrls.forEach(url => preloadEmote(url));
-          }
+      function attachRange(start, end, beforeNode) {
+        const fragment = document.createDocumentFragment();
+        for (let i = start; i < end; i++) {
+          fragment.appendChild(entries[i]);
+        }
+        chatBody.insertBefore(fragment, beforeNode || null);
+      }
 
-          const fragment = document.createDocumentFragment();
+      function detachAll() {
+        for (let i = windowStart; i < windowEnd; i++) {
+          entries[i].remove();
+        }
+        windowStart = windowEnd = 0;
+      }
 
-          for (let i = 0; i < batchSize; i++) {
-            const tempDiv = document.createElement('div');
-            tempDiv.innerHTML = messageQueue[i];
-            while (tempDiv.firstChild) {
-              fragment.appendChild(tempDiv.firstChild);
-            }
+      function renderPending() {
+        if (pendingHtml.length === 0) {
+          return;
+        }
+        const htmlString = pendingHtml.join('\n');
+        pendingHtml = [];
+        extractEmoteUrls(htmlString).forEach(url => preloadEmote(url));
+
+        const tailing = windowEnd === entries.length;
+        const start = entries.length;
+        const nodes = parseNodes(htmlString);
+        entries.push(...nodes);
+        pinPaidMessages(nodes);
+        if (tailing && !isUserScrolling) {
+          attachRange(start, entries.length);
+          windowEnd = entries.length;
+          while (windowEnd - windowStart > WINDOW_SIZE) {
+            entries[windowStart++].remove();
           }
-
-          chatBody.appendChild(fragment);
-          messageQueue.splice(0, batchSize);
-
           maintainScrollPosition();
-
-          // Schedule cleanup if needed
-          if (chatBody.getElementsByClassName('message-box').length > CLEANUP_THRESHOLD) {
-            setTimeout(cleanupOldMessages, 100);
-          }
-
-          // Process more if queue still has items
-          if (messageQueue.length > 0) {
-            requestAnimationFrame(flushMessageQueue);
-          }
         }
+        dropOldest();
       }
 
-      function appendMessages(htmlString) {
-        if (isUserScrolling) {
-          messageQueue.push(htmlString);
-          if (messageQueue.length === 1) {
-            requestAnimationFrame(flushMessageQueue);
-          }
+      // Forgets messages past MAX_STORED, but never ones currently attached
+      function dropOldest() {
+        const excess = Math.min(entries.length - MAX_STORED, windowStart);
+        if (excess <= 0) {
           return;
         }
-
-        const emoteUrls = extractEmoteUrls(htmlString);
-        emoteUrls.forEach(url => preloadEmote(url));
-
-        const tempDiv = document.createElement('div');
-        tempDiv.innerHTML = htmlString;
-        const fragment = document.createDocumentFragment();
-        while (tempDiv.firstChild) {
-          fragment.appendChild(tempDiv.firstChild);
+        for (let i = 0; i < excess; i++) {
+          entries[i].remove();
         }
+        entries.splice(0, excess);
+        windowStart = Math.max(0, windowStart - excess);
+        windowEnd = Math.max(windowStart, windowEnd - excess);
+      }
 
-        chatBody.appendChild(fragment);
-        maintainScrollPosition();
-
-        // Schedule cleanup asynchronously
-        const messages = chatBody.getElementsByClassName('message-box');
-        if (messages.length > CLEANUP_THRESHOLD) {
-          requestAnimationFrame(cleanupOldMessages);
+      function loadOlder() {
+        if (windowStart === 0) {
+          return;
+        }
+        const count = Math.min(PAGE_SIZE, windowStart);
+        const previousHeight = chatContainer.scrollHeight;
+        attachRange(windowStart - count, windowStart, windowEnd > windowStart ? entries[windowStart] : null);
+        windowStart -= count;
+        chatContainer.scrollTop += chatContainer.scrollHeight - previousHeight;
+        while (windowEnd - windowStart > WINDOW_SIZE + PAGE_SIZE) {
+          entries[--windowEnd].remove();
         }
+        lastScrollHeight = chatContainer.scrollHeight;
       }
 
-      function replaceAllMessages(htmlString) {
-        messageQueue.length = 0;
-        const scrollBuffer = chatBody.querySelector('.scroll-buffer');
-        chatBody.innerHTML = '';
-        if (scrollBuffer) chatBody.appendChild(scrollBuffer);
-        const tempDiv = document.createElement('div');
-        tempDiv.innerHTML = htmlString;
-        const fragment = document.createDocumentFragment();
-        while (tempDiv.firstChild) {
-          fragment.appendChild(tempDiv.firstChild);
+      function loadNewer() {
+        if (windowEnd === entries.length) {
+          return;
         }
-        chatBody.appendChild(fragment);
-        messageCount = chatBody.getElementsByClassName('message-box').length;
-        isUserScrolling = false;
-        chatContainer.scrollTop = chatContainer.scrollHeight;
+        const count = Math.min(PAGE_SIZE, entries.length - windowEnd);
+        attachRange(windowEnd, windowEnd + count);
+        windowEnd += count;
+        const previousHeight = chatContainer.scrollHeight;
+        while (windowEnd - windowStart > WINDOW_SIZE + PAGE_SIZE) {
+          entries[windowStart++].remove();
+        }
+        chatContainer.scrollTop -= previousHeight - chatContainer.scrollHeight;
         lastScrollHeight = chatContainer.scrollHeight;
       }
 
-      window.onload = function() {
+      // Re-renders the newest window and resumes following the chat
+      function jumpToLatest() {
+        if (windowEnd < entries.length || windowEnd - windowStart > WINDOW_SIZE) {
+          detachAll();
+          windowEnd = entries.length;
+          windowStart = Math.max(0, windowEnd - WINDOW_SIZE);
+          attachRange(windowStart, windowEnd);
+        }
         chatContainer.scrollTop = chatContainer.scrollHeight;
         lastScrollHeight = chatContainer.scrollHeight;
-        setupEmotePopovers();
-      };
-
-      // Emote popover functionality
-      let currentPopover = null;
-      let clickEventHandler = null;
-      let keydownEventHandler = null;
+      }
 
-      function preloadEmote(url) {
-        if (!emoteCache.has(url)) {
-          emoteCache.set(url, true);
-          const img = new Image();
-          img.src = url;
+      // Attaches a window centred on entries[index]
+      function showWindowAround(index) {
+        if (index >= windowStart && index < windowEnd) {
+          return;
         }
+        detachAll();
+        windowStart = Math.max(0, index - Math.floor(WINDOW_SIZE / 2));
+        windowEnd = Math.min(entries.length, windowStart + WINDOW_SIZE);
+        attachRange(windowStart, windowEnd);
+        lastScrollHeight = chatContainer.scrollHeight;
       }
 
-      function extractEmoteUrls(htmlString) {
-        const urls = [];
-        const regex = /src="([^"]+)"/g;
-        let match;
-        while ((match = regex.exec(htmlString)) !== null) {
-          urls.push(match[1]);
-        }
-        return urls;
+      function appendMessages(htmlString) {
+        pendingHtml.push(htmlString);
+        scheduleFrame(renderPending);
       }
 
-      function cleanupEventListeners() {
-        // Remove event listeners to prevent memory leaks
-        if (clickEventHandler) {
-          document.removeEventListener('click', clickEventHandler);
-          clickEventHandler = null;
+      // Scrollback past the retained messages comes from the app's saved history
+      let olderRequested = false;
+      let historyExhausted = false;
+
+      function requestOlderHistory() {
+        if (olderRequested || historyExhausted) {
+          return;
         }
-        if (keydownEventHandler) {
-          document.removeEventListener('keydown', keydownEventHandler);
-          keydownEventHandler = null;
+        const oldest = entries.find(node => node.dataset && node.dataset.sentAt);
+        olderRequested = true;
+        postToApp({ type: 'load-older', before: oldest ? Number(oldest.dataset.sentAt) : Date.now() });
+      }
+
+      function prependMessages(htmlString, hasMore) {
+        olderRequested = false;
+        historyExhausted = !hasMore;
+        const nodes = parseNodes(htmlString);
+        pinPaidMessages(nodes);
+        if (nodes.length > 0) {
+          entries.unshift(...nodes);
+          windowStart += nodes.length;
+          windowEnd += nodes.length;
+          loadOlder();
         }
-        // Force garbage collection if available
-        if (window.gc) {
-          window.gc();
+        if (pendingJumpTime !== null) {
+          scrollToTime(pendingJumpTime);
         }
       }
 
-      function setupEmotePopovers() {
-        console.log('Setting up emote popovers');
+      // Used by the activity sparkline: shows the first message sent at or after `time`
+      // (ms since epoch), pulling in saved history when the retained messages start later
+      let pendingJumpTime = null;
 
-        // Clean up any existing event listeners first
-        cleanupEventListeners();
+      function scrollToTime(time) {
+        pendingJumpTime = null;
+        const index = entries.findIndex(node => node.dataset && Number(node.dataset.sentAt) >= time);
+        if (index === -1) {
+          isUserScrolling = false;
+          jumpToLatest();
+          return;
+        }
+        const firstTimed = entries.findIndex(node => node.dataset && node.dataset.sentAt);
+        if (index === firstTimed && Number(entries[index].dataset.sentAt) - time > 60000 && !historyExhausted) {
+          pendingJumpTime = time;
+          requestOlderHistory();
+          return;
+        }
+        const box = entries[index];
+        isUserScrolling = true;
+        showWindowAround(index);
+        box.scrollIntoView({ block: 'start' });
+      }
 
-        clickEventHandler = function(event) {
-          const target = event.target;
+      function replaceAllMessages(htmlString) {
+        pendingHtml = [];
+        detachAll();
+        entries = parseNodes(htmlString).slice(-MAX_STORED);
+        // A tab restore brings back the same messages, so their pins stay; a clear drops them
+        const kept = new Set(entries.map(node => node.dataset && node.dataset.msgId));
+        Array.from(pinnedMessages.children)
+          .filter(pinned => !kept.has(pinned.dataset.msgId))
+          .forEach(unpinMessage);
+        pinPaidMessages(entries);
+        olderRequested = false;
+        historyExhausted = false;
+        pendingJumpTime = null;
+        isUserScrolling = false;
+        jumpToLiveButton.hidden = true;
+        windowEnd = entries.length;
+        windowStart = Math.max(0, windowEnd - WINDOW_SIZE);
+        attachRange(windowStart, windowEnd);
+        chatContainer.scrollTop = chatContainer.scrollHeight;
+        lastScrollHeight = chatContainer.scrollHeight;
+      }
 
-          // If clicking on an emote, show popover
-          if (target.tagName === 'IMG' &&
-              ((target.alt && target.alt.startsWith(':') && target.alt.endsWith(':')) ||
-               (target.src && (target.src.includes('7tv.app') || target.src.includes('emote'))))) {
-            event.preventDefault();
-            event.stopPropagation();
-            showEmotePopover(target);
+      // A moderator timed out or banned the user: each of their messages is dimmed and
+      // tagged with what happened. Matches mark_moderated_html on the app side.
+      function markUserModerated(login, label) {
+        entries.forEach(box => {
+          const sender = box.querySelector ? box.querySelector('.sender') : null;
+          const header = box.querySelector ? box.querySelector('.message-header') : null;
+          if (!sender || !header || sender.dataset.login !== login) {
             return;
           }
+          box.classList.add('moderated');
+          let tag = header.querySelector('.moderation-tag');
+          if (!tag) {
+            tag = document.createElement('span');
+            tag.className = 'moderation-tag';
+            const timestamp = header.querySelector('.timestamp');
+            header.insertBefore(tag, timestamp);
+            header.insertBefore(document.createTextNode(' '), timestamp);
+          }
+          tag.textContent = label;
+        });
+      }
 
-          // If clicking on close button, hide popover
-          if (currentPopover && (target.classList.contains('emote-popover-close') || target.closest('.emote-popover-close'))) {
-            event.preventDefault();
-            event.stopPropagation();
-            hideEmotePopover();
+      // One recipient of a gift bomb, added to its card. The card may still be waiting
+      // for the next frame, so pending messages go in first.
+      function addGiftRecipient(giftId, name) {
+        renderPending();
+        const card = entries.find(node => node.dataset && node.dataset.giftId === giftId);
+        const list = card ? card.querySelector('.gift-recipients') : null;
+        if (!list) {
+          return;
+        }
+        const item = document.createElement('li');
+        item.textContent = name;
+        list.appendChild(item);
+      }
+
+      // The strip above the chat holds copies of pinned messages, moderator pins first and
+      // then paid ones by tier. Clicking a copy jumps to the original; dismissed ones stay
+      // away until the page reloads.
+      const MODERATOR_PIN_LEVEL = 100;
+      const dismissedPins = new Set();
+
+      function pinMessage(node, level, remaining) {
+        const messageId = node.dataset.msgId;
+        if (!messageId || findPinned(messageId) || dismissedPins.has(messageId)) {
+          return;
+        }
+        const pinned = node.cloneNode(true);
+        pinned.classList.remove('keyboard-selected');
+        pinned.classList.add('pinned-copy');
+        pinned.dataset.level = String(level);
+        pinned.title = 'Click to jump to the message';
+        const dismiss = document.createElement('button');
+        dismiss.className = 'pin-dismiss';
+        dismiss.type = 'button';
+        dismiss.title = 'Dismiss';
+        dismiss.textContent = '\u00d7';
+        pinned.prepend(dismiss);
+        const before = Array.from(pinnedMessages.children)
+          .find(other => Number(other.dataset.level) < level);
+        pinnedMessages.insertBefore(pinned, before || null);
+        pinnedMessages.hidden = false;
+        if (remaining !== null) {
+          setTimeout(() => unpinMessage(pinned), remaining);
+        }
+      }
+
+      // Paid messages whose pin time hasn't run out, each taken out again when its time is up
+      function pinPaidMessages(nodes) {
+        nodes.forEach(node => {
+          if (!node.classList || !node.classList.contains('paid-message')) {
+            return;
+          }
+          const remaining = Number(node.dataset.paidUntil) - Date.now();
+          if (remaining > 0) {
+            pinMessage(node, Number(node.className.match(/paid-level-(\d+)/)?.[1] || 1), remaining);
+          }
+        });
+      }
+
+      // Moderator pins stay until unpinned or dismissed
+      function togglePin(box) {
+        const pinned = findPinned(box.dataset.msgId);
+        if (pinned) {
+          unpinMessage(pinned);
+          return;
+        }
+        dismissedPins.delete(box.dataset.msgId);
+        pinMessage(box, MODERATOR_PIN_LEVEL, null);
+      }
+
+      function findPinned(messageId) {
+        return Array.from(pinnedMessages.children).find(node => node.dataset.msgId === messageId);
+      }
+
+      function unpinMessage(pinned) {
+        pinned.remove();
+        pinnedMessages.hidden = pinnedMessages.childElementCount === 0;
+      }
+
+      function dismissPin(pinned) {
+        dismissedPins.add(pinned.dataset.msgId);
+        unpinMessage(pinned);
+      }
+
+      // A moderator deleted the message: struck through and tagged when deleted messages
+      // are kept, otherwise dropped. Matches mark_deleted_html on the app side.
+      function deleteMessage(messageId, keep) {
+        const pinned = findPinned(messageId);
+        if (pinned) {
+          unpinMessage(pinned);
+        }
+        const index = findMessageIndex(messageId);
+        if (index === -1) {
+          return;
+        }
+        const box = entries[index];
+        if (keep) {
+          const header = box.querySelector('.message-header');
+          if (header && !box.classList.contains('deleted')) {
+            box.classList.add('deleted');
+            const tag = document.createElement('span');
+            tag.className = 'deleted-tag';
+            tag.textContent = 'deleted';
+            header.insertBefore(tag, header.querySelector('.timestamp'));
+            header.insertBefore(document.createTextNode(' '), header.querySelector('.timestamp'));
+          }
+          return;
+        }
+        box.remove();
+        entries.splice(index, 1);
+        if (index < windowStart) {
+          windowStart--;
+          windowEnd--;
+        } else if (index < windowEnd) {
+          windowEnd--;
+        }
+      }
+
+      // Looks up a message whether or not it's currently attached
+      function findMessageIndex(messageId) {
+        for (let i = entries.length - 1; i >= 0; i--) {
+          if (entries[i].dataset && entries[i].dataset.msgId === messageId) {
+            return i;
+          }
+        }
+        return -1;
+      }
+
+      function findMessageBox(messageId) {
+        const index = findMessageIndex(messageId);
+        return index === -1 ? null : entries[index];
+      }
+
+      function appendTranslation(messageId, htmlString) {
+        const box = findMessageBox(messageId);
+        if (!box || box.querySelector('.message-translation')) {
+          return;
+        }
+        const tempDiv = document.createElement('div');
+        tempDiv.innerHTML = htmlString;
+        while (tempDiv.firstChild) {
+          box.appendChild(tempDiv.firstChild);
+        }
+        maintainScrollPosition();
+      }
+
+      function appendAccountAge(messageId, htmlString) {
+        const box = findMessageBox(messageId);
+        const sender = box ? box.querySelector('.sender') : null;
+        if (!sender || box.querySelector('.account-age')) {
+          return;
+        }
+        sender.insertAdjacentHTML('afterend', ' ' + htmlString);
+      }
+
+      // Messages touched by the current selection, or the ones in view when nothing is selected
+      function messagesForExport() {
+        const boxes = Array.from(chatBody.getElementsByClassName('message-box'));
+        const selection = window.getSelection();
+        if (selection && !selection.isCollapsed) {
+          const selected = boxes.filter(box => selection.containsNode(box, true));
+          if (selected.length > 0) {
+            return selected;
+          }
+        }
+        const view = chatContainer.getBoundingClientRect();
+        return boxes.filter(box => {
+          const rect = box.getBoundingClientRect();
+          return rect.bottom > view.top && rect.top < view.bottom;
+        });
+      }
+
+      function messageToText(box) {
+        const sender = box.querySelector('.sender');
+        const timestamp = box.querySelector('.timestamp');
+        const text = box.querySelector('.message-text');
+        let body = '';
+        if (text) {
+          text.childNodes.forEach(node => {
+            body += exportNodeText(node);
+          });
+        }
+        const time = timestamp ? '[' + timestamp.textContent + '] ' : '';
+        const name = sender ? sender.textContent + ': ' : '';
+        return time + name + body.trim();
+      }
+
+      function exportNodeText(node) {
+        if (node.nodeType === Node.TEXT_NODE) {
+          return node.textContent;
+        }
+        if (node.tagName === 'IMG') {
+          if (node.classList.contains('emote-overlay')) {
+            return ' ' + node.alt.replace(/^:|:$/g, '');
+          }
+          return node.alt.replace(/^:|:$/g, '');
+        }
+        let text = '';
+        node.childNodes.forEach(child => {
+          text += exportNodeText(child);
+        });
+        return text;
+      }
+
+      function collectChatExport(format) {
+        const boxes = messagesForExport();
+        if (format === 'html') {
+          return boxes.map(box => withoutHeldFrames(box).outerHTML).join('\n');
+        }
+        return boxes.map(messageToText).join('\n');
+      }
+
+      // Lays copies of the selected messages over the top of the view for a snapshot, and
+      // resolves to their height once drawn; 0 without a selection. The chat underneath,
+      // scroll position included, is left alone.
+      function showSelectionSnapshot() {
+        const selection = window.getSelection();
+        if (!selection || selection.isCollapsed) {
+          return Promise.resolve(0);
+        }
+        const boxes = Array.from(chatBody.getElementsByClassName('message-box'))
+          .filter(box => selection.containsNode(box, true));
+        if (boxes.length === 0) {
+          return Promise.resolve(0);
+        }
+        hideSelectionSnapshot();
+        const view = document.createElement('div');
+        view.id = 'snapshot-view';
+        boxes.forEach(box => view.appendChild(withoutHeldFrames(box)));
+        document.body.appendChild(view);
+        return new Promise(resolve => {
+          requestAnimationFrame(() => requestAnimationFrame(() => resolve(view.offsetHeight)));
+        });
+      }
+
+      function hideSelectionSnapshot() {
+        const view = document.getElementById('snapshot-view');
+        if (view) {
+          view.remove();
+        }
+      }
+
+      window.onload = function() {
+        chatContainer.scrollTop = chatContainer.scrollHeight;
+        lastScrollHeight = chatContainer.scrollHeight;
+        setupEmotePopovers();
+      };
+
+      // Emote animation cap: each emote is drawn into a canvas every `frameCapInterval` ms
+      // (once for -1, never for 0), so the page repaints at that rate instead of the GIF's
+      let frameCapInterval = 0;
+      let frameCapTimer = null;
+      const visibleHeldImages = new Set();
+      const heldImageObserver = new IntersectionObserver(observed => {
+        observed.forEach(entry => {
+          if (entry.isIntersecting) {
+            visibleHeldImages.add(entry.target);
+            drawHeldFrame(entry.target);
+          } else {
+            visibleHeldImages.delete(entry.target);
+          }
+        });
+      });
+      const newMessageObserver = new MutationObserver(mutations => {
+        mutations.forEach(mutation => mutation.addedNodes.forEach(holdImagesIn));
+      });
+
+      function drawHeldFrame(img) {
+        const canvas = img.nextElementSibling;
+        if (!canvas || !canvas.classList.contains('held-frame') || !img.complete || !img.naturalWidth) {
+          return;
+        }
+        if (canvas.width !== img.naturalWidth || canvas.height !== img.naturalHeight) {
+          canvas.width = img.naturalWidth;
+          canvas.height = img.naturalHeight;
+        }
+        const context = canvas.getContext('2d');
+        context.clearRect(0, 0, canvas.width, canvas.height);
+        context.drawImage(img, 0, 0);
+      }
+
+      function holdImage(img) {
+        if (img.classList.contains('frame-held')) {
+          return;
+        }
+        // A lone emote gets the same stacking wrapper zero-width emotes use
+        if (!img.parentElement.classList.contains('emote-stack')) {
+          const stack = document.createElement('span');
+          stack.className = 'emote-stack held-wrap';
+          img.replaceWith(stack);
+          stack.appendChild(img);
+        }
+        const canvas = document.createElement('canvas');
+        canvas.className = 'held-frame';
+        img.after(canvas);
+        img.classList.add('frame-held');
+        img.addEventListener('load', () => drawHeldFrame(img));
+        drawHeldFrame(img);
+        heldImageObserver.observe(img);
+      }
+
+      function releaseImage(img) {
+        heldImageObserver.unobserve(img);
+        visibleHeldImages.delete(img);
+        img.classList.remove('frame-held');
+        const canvas = img.nextElementSibling;
+        if (canvas && canvas.classList.contains('held-frame')) {
+          canvas.remove();
+        }
+        const stack = img.parentElement;
+        if (stack.classList.contains('held-wrap')) {
+          stack.replaceWith(img);
+        }
+      }
+
+      function holdImagesIn(node) {
+        if (node.nodeType === Node.ELEMENT_NODE) {
+          node.querySelectorAll('.message-content img:not(.emoji)').forEach(holdImage);
+        }
+      }
+
+      function forEachEntryImage(selector, callback) {
+        entries.forEach(node => {
+          if (node.nodeType === Node.ELEMENT_NODE) {
+            node.querySelectorAll(selector).forEach(callback);
+          }
+        });
+      }
+
+      function setFrameCap(interval) {
+        if (interval === frameCapInterval) {
+          return;
+        }
+        frameCapInterval = interval;
+        clearInterval(frameCapTimer);
+        frameCapTimer = null;
+        if (interval === 0) {
+          newMessageObserver.disconnect();
+          forEachEntryImage('img.frame-held', releaseImage);
+          return;
+        }
+        newMessageObserver.observe(chatBody, { childList: true });
+        forEachEntryImage('.message-content img:not(.emoji)', holdImage);
+        if (interval > 0) {
+          frameCapTimer = setInterval(() => visibleHeldImages.forEach(drawHeldFrame), interval);
+        }
+      }
+
+      function withoutHeldFrames(box) {
+        const copy = box.cloneNode(true);
+        copy.querySelectorAll('canvas.held-frame').forEach(canvas => canvas.remove());
+        copy.querySelectorAll('img.frame-held').forEach(img => img.classList.remove('frame-held'));
+        return copy;
+      }
+
+      // Emote popover functionality
+      let currentPopover = null;
+      let clickEventHandler = null;
+      let keydownEventHandler = null;
+
+      function postToApp(message) {
+        if (window.webkit && window.webkit.messageHandlers && window.webkit.messageHandlers.admiral) {
+          window.webkit.messageHandlers.admiral.postMessage(JSON.stringify(message));
+        }
+      }
+
+      function preloadEmote(url) {
+        if (!emoteCache.has(url)) {
+          emoteCache.set(url, true);
+          const img = new Image();
+          img.src = url;
+        }
+      }
+
+      function extractEmoteUrls(htmlString) {
+        const urls = [];
+        const regex = /src="([^"]+)"/g;
+        let match;
+        while ((match = regex.exec(htmlString)) !== null) {
+          urls.push(match[1]);
+        }
+        return urls;
+      }
+
+      function cleanupEventListeners() {
+        // Remove event listeners to prevent memory leaks
+        if (clickEventHandler) {
+          document.removeEventListener('click', clickEventHandler);
+          clickEventHandler = null;
+        }
+        if (keydownEventHandler) {
+          document.removeEventListener('keydown', keydownEventHandler);
+          keydownEventHandler = null;
+        }
+        // Force garbage collection if available
+        if (window.gc) {
+          window.gc();
+        }
+      }
+
+      function setupEmotePopovers() {
+        console.log('Setting up emote popovers');
+
+        // Clean up any existing event listeners first
+        cleanupEventListeners();
+
+        clickEventHandler = function(event) {
+          const target = event.target;
+
+          // If clicking on an emote, show popover
+          if (target.tagName === 'IMG' &&
+              ((target.alt && target.alt.startsWith(':') && target.alt.endsWith(':')) ||
+               (target.src && (target.src.includes('7tv.app') || target.src.includes('emote'))))) {
+            event.preventDefault();
+            event.stopPropagation();
+            showEmotePopover(target);
+            return;
+          }
+
+          // Hand the emote's name to the message entry
+          const insertEmote = target.closest('.emote-popover-insert');
+          if (insertEmote) {
+            event.preventDefault();
+            event.stopPropagation();
+            postToApp({ type: 'insert-emote', name: insertEmote.dataset.name });
+            hideEmotePopover();
+            return;
+          }
+
+          // If clicking on close button, hide popover
+          if (currentPopover && (target.classList.contains('emote-popover-close') || target.closest('.emote-popover-close'))) {
+            event.preventDefault();
+            event.stopPropagation();
+            hideEmotePopover();
             return;
           }
 
@@ -452,12 +1397,43 @@ fn get_chat_html_template() -> &'static str {
             hideEmotePopover();
             return;
           }
+
+          // Pinned strip: dismiss a pin, or jump to the original message
+          const pinDismiss = target.closest('.pin-dismiss');
+          if (pinDismiss) {
+            event.preventDefault();
+            dismissPin(pinDismiss.closest('.pinned-copy'));
+            return;
+          }
+          const pinnedCopy = target.closest('.pinned-copy');
+          if (pinnedCopy && !target.closest('.sender')) {
+            event.preventDefault();
+            scrollToMessage(pinnedCopy.dataset.msgId);
+            return;
+          }
+
+          // Open the user card when clicking a sender name
+          const sender = target.closest('.sender');
+          if (sender && sender.dataset.login) {
+            event.preventDefault();
+            postToApp({ type: 'user-card', login: sender.dataset.login });
+            return;
+          }
+
+          // Expand or collapse a collapsed bot message
+          const collapsedBot = target.closest('.bot-collapsed');
+          if (collapsedBot) {
+            collapsedBot.classList.toggle('expanded');
+            return;
+          }
         };
 
         keydownEventHandler = function(event) {
           if (event.key === 'Escape' && currentPopover) {
             hideEmotePopover();
+            return;
           }
+          handleNavigationKey(event);
         };
 
         // Add event listeners with references
@@ -465,6 +1441,126 @@ fn get_chat_html_template() -> &'static str {
         document.addEventListener('keydown', keydownEventHandler);
       }
 
+      // Keyboard selection cursor for navigating messages without a mouse
+      let selectedMessage = null;
+
+      function selectMessage(box) {
+        if (selectedMessage) {
+          selectedMessage.classList.remove('keyboard-selected');
+        }
+        selectedMessage = box;
+        if (box) {
+          box.classList.add('keyboard-selected');
+          box.scrollIntoView({ block: 'nearest' });
+        }
+      }
+
+      function moveSelection(step) {
+        const boxes = Array.from(chatBody.getElementsByClassName('message-box'));
+        if (boxes.length === 0) {
+          return;
+        }
+        let index = selectedMessage ? boxes.indexOf(selectedMessage) : -1;
+        if (index === -1) {
+          // Start from the newest message in view
+          const visible = messagesForExport();
+          const start = visible.length > 0 ? visible[visible.length - 1] : boxes[boxes.length - 1];
+          selectMessage(start);
+          return;
+        }
+        // Page in neighbouring messages before stepping off either end of the window
+        if (index + step < 0 && windowStart > 0) {
+          loadOlder();
+        } else if (index + step >= boxes.length && windowEnd < entries.length) {
+          loadNewer();
+        }
+        const attached = Array.from(chatBody.getElementsByClassName('message-box'));
+        index = Math.max(0, Math.min(attached.length - 1, attached.indexOf(selectedMessage) + step));
+        selectMessage(attached[index]);
+      }
+
+      // Frame pacing monitor for the --benchmark mode
+      let frameStats = { frames: 0, dropped: 0 };
+
+      function startFrameMonitor() {
+        const frameBudget = 1000 / 60;
+        let last = 0;
+        frameStats = { frames: 0, dropped: 0 };
+        function tick(now) {
+          if (last) {
+            frameStats.frames++;
+            const missed = Math.round((now - last) / frameBudget) - 1;
+            if (missed > 0) {
+              frameStats.dropped += missed;
+            }
+          }
+          last = now;
+          requestAnimationFrame(tick);
+        }
+        requestAnimationFrame(tick);
+      }
+
+      // Used by the notification center to jump back to a message
+      function scrollToMessage(messageId) {
+        const index = findMessageIndex(messageId);
+        if (index === -1) {
+          return;
+        }
+        const box = entries[index];
+        isUserScrolling = true;
+        showWindowAround(index);
+        selectMessage(box);
+        box.scrollIntoView({ block: 'center' });
+      }
+
+      function handleNavigationKey(event) {
+        if (event.ctrlKey || event.altKey || event.metaKey) {
+          return;
+        }
+        switch (event.key) {
+          case 'ArrowDown':
+          case 'j':
+            moveSelection(1);
+            break;
+          case 'ArrowUp':
+          case 'k':
+            moveSelection(-1);
+            break;
+          case 'Escape':
+            selectMessage(null);
+            break;
+          case 'End':
+            jumpToLiveButton.click();
+            break;
+          case 'Enter': {
+            const sender = selectedMessage ? selectedMessage.querySelector('.sender') : null;
+            if (!sender) return;
+            postToApp({ type: 'user-card', login: sender.dataset.login });
+            break;
+          }
+          case 'r': {
+            const sender = selectedMessage ? selectedMessage.querySelector('.sender') : null;
+            if (!sender) return;
+            postToApp({ type: 'reply', login: sender.dataset.login, message_id: selectedMessage.dataset.msgId });
+            break;
+          }
+          case ':':
+            postToApp({ type: 'command-bar' });
+            break;
+          case 'c':
+            if (!selectedMessage) return;
+            postToApp({ type: 'copy', text: messageToText(selectedMessage) });
+            break;
+          case 'p':
+            if (!selectedMessage) return;
+            togglePin(selectedMessage);
+            break;
+          default:
+            return;
+        }
+        event.preventDefault();
+      }
+
       function showEmotePopover(emoteImg) {
         // Hide existing popover if any
         hideEmotePopover();
@@ -473,22 +1569,43 @@ fn get_chat_html_template() -> &'static str {
           ? emoteImg.alt.substring(1, emoteImg.alt.length - 1)
           : 'Emote';
         const emoteUrl = emoteImg.src;
+        const provider = emoteImg.dataset.provider ? `${emoteImg.dataset.provider} emote` : '';
 
         // Create popover element
         const popover = document.createElement('div');
         popover.className = 'emote-popover';
         popover.style.display = 'block';
-        popover.innerHTML = `
-          <button class="emote-popover-close" title="Close">&times;</button>
-          <img src="${emoteUrl}" alt="${emoteName}" />
-          <div class="emote-popover-name">${emoteName}</div>
-          <div class="emote-popover-url">${emoteUrl}</div>
-        `;
+        // Built from nodes, not markup: emote names come from third-party emote sets
+        const closeButton = document.createElement('button');
+        closeButton.className = 'emote-popover-close';
+        closeButton.title = 'Close';
+        closeButton.textContent = '\u00d7';
+        const image = document.createElement('img');
+        image.src = emoteUrl;
+        image.alt = emoteName;
+        popover.append(closeButton, image);
+        for (const [className, text] of [
+          ['emote-popover-name', emoteName],
+          ['emote-popover-provider', provider],
+          ['emote-popover-url', emoteUrl],
+        ]) {
+          const line = document.createElement('div');
+          line.className = className;
+          line.textContent = text;
+          popover.appendChild(line);
+        }
+        if (emoteName !== 'Emote') {
+          const insertButton = document.createElement('button');
+          insertButton.className = 'emote-popover-insert';
+          insertButton.textContent = 'Add to Message';
+          insertButton.dataset.name = emoteName;
+          popover.appendChild(insertButton);
+        }
 
         // Position popover near the clicked emote
         const rect = emoteImg.getBoundingClientRect();
         const popoverWidth = 250;
-        const popoverHeight = 150;
+        const popoverHeight = 180;
 
         let left = rect.left + (rect.width / 2) - (popoverWidth / 2);
         let top = rect.bottom + 10;
@@ -531,73 +1648,23 @@ fn get_chat_html_template() -> &'static str {
     "#
 }
 
-fn get_chat_html_template_with_color(background_color: Option<&str>) -> String {
-    let base_template = get_chat_html_template();
-
-    if let Some(color) = background_color {
-        let css_replacement = format!("            background-color: {}; /* Solid background prevents overdraw */", color);
-        let light_css_replacement = format!("            body {{ background-color: {}; }}", color);
-        let light_msg_css_replacement = format!("            .message-box {{ background-color: {}; }}", color);
-
-        base_template
-            .replace("            background-color: rgba(0, 0, 0, 0.95); /* Solid background prevents overdraw */", &css_replacement)
-            .replace("            body { color: #000000; background-color: rgba(255, 255, 255, 0.95); }", &light_css_replacement)
-            .replace("            .message-box { background-color: rgba(0, 0, 0, 0.02); }", &light_msg_css_replacement)
-    } else {
-        base_template.to_string()
-    }
-}
-
-fn escape_js_string(s: &str) -> String {
-    let mut out = String::with_capacity(s.len());
-    for ch in s.chars() {
-        match ch {
-            '\\' => out.push_str("\\\\"),
-            '\'' => out.push_str("\\'"),
-            '\n' => out.push_str("\\n"),
-            '\r' => out.push_str("\\r"),
-            _ => out.push(ch),
-        }
-    }
-    out
-}
-
 struct ClientState {
-    client: Option<TwitchIRCClient<SecureTCPTransport, StaticLoginCredentials>>,
-    runtime: Option<Runtime>,
-    join_handle: Option<thread::JoinHandle<()>>,
-    shutdown_flag: Arc<AtomicBool>, // Flag to signal graceful shutdown
+    client: Option<ChatClient>,
+    connection: Option<tokio::task::JoinHandle<()>>, // The tab's task on the shared chat runtime
 }
 
 impl ClientState {
     fn new() -> Self {
         Self {
             client: None,
-            runtime: Some(Runtime::new().unwrap()),
-            join_handle: None,
-            shutdown_flag: Arc::new(AtomicBool::new(false)),
+            connection: None,
         }
     }
     fn disconnect(&mut self) {
         self.client = None;
-        self.shutdown_flag.store(true, Ordering::SeqCst);
-        if let Some(handle) = self.join_handle.take() {
-            let timeout = Duration::from_millis(500);
-            if !handle.is_finished() {
-                let thread = thread::current();
-                thread.unpark();
-                // Wait with timeout to prevent blocking indefinitely
-                let start = Instant::now();
-                while start.elapsed() < timeout && !handle.is_finished() {
-                    thread::sleep(Duration::from_millis(10));
-                }
-            }
-            // Thread may have finished or timed out, drop the handle
-            drop(handle);
-        }
-        self.shutdown_flag.store(false, Ordering::SeqCst);
-        if self.runtime.is_none() {
-            self.runtime = Some(Runtime::new().unwrap());
+        // Aborting drops the task's client and receiver, which closes the connection
+        if let Some(connection) = self.connection.take() {
+            connection.abort();
         }
     }
 }
@@ -608,6 +1675,47 @@ struct Favorites {
     channels: Vec<String>,
     starred: Vec<String>, // List of starred channels
     background_color: Option<String>, // Custom background color hex code
+    #[serde(default)]
+    translation: TranslationConfig,
+    #[serde(default)]
+    bots: BotSettings,
+    #[serde(default)]
+    appearance: AppearanceSettings,
+    #[serde(default)]
+    moderation: ModerationSettings,
+    #[serde(default)]
+    watchdog: WatchdogSettings,
+    #[serde(default)]
+    message_budget: MessageBudgetSettings,
+    #[serde(default)]
+    history: HistorySettings,
+    #[serde(default)]
+    schedules: HashMap<String, ChannelSchedule>, // Only followed while the channel is starred
+    #[serde(default)]
+    aliases: HashMap<String, String>, // Display names for favorites, keyed by login
+    #[serde(default)]
+    auto_sort: bool, // Keep channels alphabetical instead of in the order they were dragged to
+    #[serde(default)]
+    startup: StartupSettings,
+    #[serde(default)]
+    network: NetworkSettings,
+    #[serde(default)]
+    emotes: EmoteSettings,
+    #[serde(default)]
+    quiet_hours: QuietHoursSettings,
+    #[serde(default)]
+    upload: UploadSettings,
+    #[serde(default)]
+    rules: RuleSettings,
+    #[serde(default)]
+    lightweight: Vec<String>, // Channels shown as plain text instead of in a WebView
+}
+
+// Message picked for a reply, used by the send input
+#[derive(Debug, Clone)]
+struct ReplyTarget {
+    login: String,
+    message_id: String,
 }
 
 struct TabData {
@@ -618,21 +1726,79 @@ struct TabData {
     channel_name: Arc<Mutex<Option<String>>>,
     client_state: Arc<Mutex<ClientState>>,
     connection_state: Arc<Mutex<ConnectionState>>,
-    tx: std::sync::mpsc::SyncSender<twitch_irc::message::PrivmsgMessage>,
-    rx: Arc<Mutex<std::sync::mpsc::Receiver<twitch_irc::message::PrivmsgMessage>>>,
-    error_tx: std::sync::mpsc::Sender<()>,
+    queue: Arc<MessageQueue>,
+    waker: TabWaker, // Schedules the tab's next pass of the message pump
+    error_tx: PumpSender<()>,
     error_rx: Arc<Mutex<std::sync::mpsc::Receiver<()>>>,
     last_js_execution: Arc<Mutex<Instant>>,
-    shutdown_flag: Arc<AtomicBool>,
-    message_buffer: Arc<Mutex<VecDeque<String>>>,
+    pacing: Arc<Mutex<RenderPacing>>, // Batch size and interval for injections while shown
+    message_buffer: Arc<Mutex<MessageBuffer>>,
     pending_messages: Arc<Mutex<VecDeque<twitch_irc::message::PrivmsgMessage>>>,
+    translate_enabled: Arc<AtomicBool>,
+    translation_tx: PumpSender<TranslatedMessage>,
+    translation_rx: Arc<Mutex<std::sync::mpsc::Receiver<TranslatedMessage>>>,
+    room_state: Arc<Mutex<RoomState>>,
+    stats: Arc<Mutex<ChannelStats>>,
+    giveaway: Arc<Mutex<Giveaway>>,
+    poll: Arc<Mutex<Poll>>,
+    seen_chatters: Arc<Mutex<HashSet<String>>>,
+    recent_messages: Arc<Mutex<VecDeque<twitch_irc::message::PrivmsgMessage>>>,
+    reply_target: Arc<Mutex<Option<ReplyTarget>>>,
+    send_history: Arc<Mutex<SendHistory>>, // For the send input, via attach_send_history
+    send_bar: Box, // Shown while connected to a live channel
+    identity: IdentityButton, // The user's name and chat color, ahead of the input
+    room_modes: gtk::Label, // Strip above the chat listing slow mode, emote-only and the like
+    message_entry: Entry,
+    send_button: Button,
+    signed_in: Arc<AtomicBool>, // Connected with the user's login, so messages can be sent
+    notes_pane: NotesPane,
+    filters: Arc<Mutex<Vec<Regex>>>, // Session-only :filter patterns
+    unread_mentions: Arc<Mutex<u32>>,
+    muted: Arc<AtomicBool>, // Mentions neither count as unread nor reach the notification center
+    replay: Arc<Mutex<Option<Arc<Mutex<ReplayControl>>>>>, // Set while the tab replays a VOD
+    replay_bar: ReplayBar,
+    demo_stop: Arc<Mutex<Option<Arc<AtomicBool>>>>, // Set while the tab shows the preview channel
+    web_process: Arc<Mutex<Option<i32>>>, // Pid of the WebKit process rendering this tab, if known
+    memory_warned: Arc<AtomicBool>, // Watchdog already acted on the current web process
+    hibernated: Arc<AtomicBool>, // Web process dropped by the watchdog until the tab is selected
+    scroll_anchor: Arc<Mutex<Option<String>>>, // Top message while reading back, put back when the page is refilled
+    chat_page_loaded: Arc<AtomicBool>, // The chat template is in the WebView, so content can change without a reload
+    render_tx: PumpSender<RenderedBatch>,
+    render_rx: Arc<Mutex<std::sync::mpsc::Receiver<RenderedBatch>>>,
+    render_in_flight: Arc<AtomicBool>, // A batch is with the render thread
+    chat_generation: Arc<AtomicU64>, // Bumped when the view is cleared, so batches rendered before are dropped
+    stalled: Arc<AtomicBool>, // Left out of the message pump after panicking there, until reconnected
+    crash_banner: adw::Banner,
+    lightweight: Arc<AtomicBool>, // Chat shown as plain text, with the WebView's process stopped
+    lightweight_view: LightweightView,
+    account_age_tx: PumpSender<AccountAge>,
+    account_age_rx: Arc<Mutex<std::sync::mpsc::Receiver<AccountAge>>>,
+    chat_event_tx: PumpSender<ChatEvent>,
+    chat_event_rx: Arc<Mutex<std::sync::mpsc::Receiver<ChatEvent>>>,
 }
 
 
 // In your main function, replace the rlimit code with:
 fn main() {
+    // First, since the crash handler and everything after it read the profile's paths
+    let args = take_profile_args(std::env::args().collect());
+    install_crash_handler();
+    // Before the window setup, which prints to stdout
+    let args = take_benchmark_args(args);
+    if let Some(index) = args.iter().position(|arg| arg == "--headless") {
+        let Some(channel) = args.get(index + 1) else {
+            eprintln!("Usage: admiral --headless <channel>");
+            std::process::exit(2);
+        };
+        std::process::exit(run_headless_reader(channel));
+    }
+    // Each profile is its own instance, so launching one doesn't raise another's window
+    let application_id = match profile() {
+        Some(name) => format!("com.toasterrepair.Admiral.profile_{}", name.replace('-', "_")),
+        None => "com.toasterrepair.Admiral".to_string(),
+    };
     let app = Application::builder()
-        .application_id("com.toasterrepair.Admiral")
+        .application_id(application_id)
         .build();
 
     // Add this code right after setting up the app, before app.connect_activate
@@ -651,21 +1817,60 @@ fn main() {
         eprintln!("Failed to get current file descriptor limits using rlimit crate.");
     }
 
-    // Set environment variables to optimize WebKit for chat rendering
-    std::env::set_var("WEBKIT_FORCE_MONOSPACE_FONT", "1");
-    std::env::set_var("WEBKIT_FORCE_SANDBOX", "0");
-    std::env::set_var("WEBKIT_DISABLE_COMPOSITING_MODE", "0");
-    std::env::set_var("WEBKIT_NO_TIMEOUT", "1");
-    std::env::set_var("WEBKIT_USE_SYSTEM_MALLOC", "0");
-    std::env::set_var("WEBKIT_DISABLE_PAGE_CACHE", "1");
     app.connect_activate(build_ui);
-    app.run();
+    app.run_with_args(&args);
+}
+
+// `admiral --profile <name>`: separate settings, history, caches and login for e.g. a
+// moderating and a lurking setup. Exits on a missing or invalid name.
+fn take_profile_args(args: Vec<String>) -> Vec<String> {
+    let mut remaining = Vec::with_capacity(args.len());
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let name = match arg.strip_prefix("--profile=") {
+            Some(name) => Some(name.to_string()),
+            None if arg == "--profile" => iter.next(),
+            None => {
+                remaining.push(arg);
+                continue;
+            }
+        };
+        let Some(name) = name else {
+            eprintln!("Usage: admiral --profile <name>");
+            std::process::exit(2);
+        };
+        if let Err(e) = set_profile(&name) {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+    remaining
+}
+
+// `admiral --headless <channel>`: the channel's chat in the terminal, with the saved
+// network, emote, bot and rule settings, and no window
+fn run_headless_reader(channel: &str) -> i32 {
+    configure_network(&get_network_settings());
+    configure_rules(&get_rule_settings());
+    configure_emote_matching(&get_emote_settings());
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let options = HeadlessOptions {
+        transport: get_network_settings().chat_transport,
+        bot_settings: get_bot_settings(),
+        color,
+    };
+    match run_headless(channel, &options) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
 }
 
 // Favorites management functions (remain largely the same)
 fn get_favorites_path() -> std::path::PathBuf {
-    let config_dir = shellexpand::tilde("~/.config/admiral").into_owned();
-    std::path::PathBuf::from(config_dir).join("favorites.toml")
+    config_dir().join("favorites.toml")
 }
 
 fn load_favorites() -> Favorites {
@@ -698,16 +1903,77 @@ fn add_favorite(channel: &str) {
     let channel_lower = channel.to_lowercase();
     if !favorites.channels.contains(&channel_lower) {
         favorites.channels.push(channel_lower);
-        favorites.channels.sort();
+        if favorites.auto_sort {
+            favorites.channels.sort();
+        }
         save_favorites(&favorites);
     }
 }
 
+// Moves `channel` next to `target` in the stored order
+fn move_favorite(channel: &str, target: &str, after: bool) {
+    let mut favorites = load_favorites();
+    let Some(from) = favorites.channels.iter().position(|c| c == channel) else {
+        return;
+    };
+    let moved = favorites.channels.remove(from);
+    let Some(to) = favorites.channels.iter().position(|c| c == target) else {
+        favorites.channels.insert(from, moved);
+        return;
+    };
+    favorites.channels.insert(if after { to + 1 } else { to }, moved);
+    save_favorites(&favorites);
+}
+
+fn set_favorites_auto_sort(auto_sort: bool) {
+    let mut favorites = load_favorites();
+    favorites.auto_sort = auto_sort;
+    if auto_sort {
+        favorites.channels.sort();
+    }
+    save_favorites(&favorites);
+}
+
 fn remove_favorite(channel: &str) {
     let mut favorites = load_favorites();
     let channel_lower = channel.to_lowercase();
     favorites.channels.retain(|c| c != &channel_lower);
     favorites.starred.retain(|c| c != &channel_lower);
+    favorites.schedules.remove(&channel_lower);
+    favorites.aliases.remove(&channel_lower);
+    save_favorites(&favorites);
+}
+
+fn get_favorite_alias(channel: &str) -> Option<String> {
+    load_favorites().aliases.remove(&channel.to_lowercase())
+}
+
+fn set_favorite_alias(channel: &str, alias: Option<&str>) {
+    let mut favorites = load_favorites();
+    let channel_lower = channel.to_lowercase();
+    match alias.map(str::trim).filter(|alias| !alias.is_empty()) {
+        Some(alias) => favorites.aliases.insert(channel_lower, alias.to_string()),
+        None => favorites.aliases.remove(&channel_lower),
+    };
+    save_favorites(&favorites);
+}
+
+// What the UI calls a channel: its alias if it's a favorite with one, otherwise the login
+fn channel_display_name(channel: &str) -> String {
+    get_favorite_alias(channel).unwrap_or_else(|| channel.to_string())
+}
+
+fn get_channel_schedule(channel: &str) -> Option<ChannelSchedule> {
+    load_favorites().schedules.remove(&channel.to_lowercase())
+}
+
+fn set_channel_schedule(channel: &str, schedule: Option<ChannelSchedule>) {
+    let mut favorites = load_favorites();
+    let channel_lower = channel.to_lowercase();
+    match schedule {
+        Some(schedule) => favorites.schedules.insert(channel_lower, schedule),
+        None => favorites.schedules.remove(&channel_lower),
+    };
     save_favorites(&favorites);
 }
 
@@ -725,11 +1991,40 @@ fn toggle_star(channel: &str) {
     save_favorites(&favorites);
 }
 
+// Stars or unstars several favorites with a single write
+fn set_starred(channels: &[String], starred: bool) {
+    let mut favorites = load_favorites();
+    for channel in channels {
+        let channel_lower = channel.to_lowercase();
+        favorites.starred.retain(|c| c != &channel_lower);
+        if starred && favorites.channels.contains(&channel_lower) {
+            favorites.starred.push(channel_lower);
+        }
+    }
+    favorites.starred.sort();
+    save_favorites(&favorites);
+}
+
 fn is_starred(channel: &str) -> bool {
     let favorites = load_favorites();
     favorites.starred.contains(&channel.to_lowercase())
 }
 
+fn is_lightweight_channel(channel: &str) -> bool {
+    load_favorites().lightweight.contains(&channel.to_lowercase())
+}
+
+fn set_lightweight_channel(channel: &str, lightweight: bool) {
+    let mut favorites = load_favorites();
+    let channel_lower = channel.to_lowercase();
+    favorites.lightweight.retain(|c| c != &channel_lower);
+    if lightweight {
+        favorites.lightweight.push(channel_lower);
+        favorites.lightweight.sort();
+    }
+    save_favorites(&favorites);
+}
+
 fn get_background_color() -> Option<String> {
     let favorites = load_favorites();
     favorites.background_color
@@ -741,6 +2036,230 @@ fn set_background_color(color: Option<&str>) {
     save_favorites(&favorites);
 }
 
+fn get_translation_config() -> TranslationConfig {
+    load_favorites().translation
+}
+
+fn set_translation_config(config: &TranslationConfig) {
+    let mut favorites = load_favorites();
+    favorites.translation = config.clone();
+    save_favorites(&favorites);
+}
+
+fn get_bot_settings() -> BotSettings {
+    load_favorites().bots
+}
+
+fn set_bot_settings(settings: &BotSettings) {
+    let mut favorites = load_favorites();
+    favorites.bots = settings.clone();
+    save_favorites(&favorites);
+}
+
+fn get_appearance_settings() -> AppearanceSettings {
+    load_favorites().appearance
+}
+
+fn set_appearance_settings(settings: &AppearanceSettings) {
+    let mut favorites = load_favorites();
+    favorites.appearance = settings.clone();
+    save_favorites(&favorites);
+}
+
+fn get_moderation_settings() -> ModerationSettings {
+    load_favorites().moderation
+}
+
+fn set_moderation_settings(settings: &ModerationSettings) {
+    let mut favorites = load_favorites();
+    favorites.moderation = settings.clone();
+    save_favorites(&favorites);
+}
+
+fn get_watchdog_settings() -> WatchdogSettings {
+    load_favorites().watchdog
+}
+
+fn set_watchdog_settings(settings: &WatchdogSettings) {
+    let mut favorites = load_favorites();
+    favorites.watchdog = settings.clone();
+    save_favorites(&favorites);
+}
+
+fn get_message_budget_settings() -> MessageBudgetSettings {
+    load_favorites().message_budget
+}
+
+fn set_message_budget_settings(settings: &MessageBudgetSettings) {
+    let mut favorites = load_favorites();
+    favorites.message_budget = settings.clone();
+    save_favorites(&favorites);
+    configure_message_budget(settings);
+}
+
+fn get_history_settings() -> HistorySettings {
+    load_favorites().history
+}
+
+fn set_history_settings(settings: &HistorySettings) {
+    let mut favorites = load_favorites();
+    favorites.history = settings.clone();
+    save_favorites(&favorites);
+    configure_history(settings);
+}
+
+fn get_emote_settings() -> EmoteSettings {
+    load_favorites().emotes
+}
+
+fn set_emote_settings(settings: &EmoteSettings) {
+    let mut favorites = load_favorites();
+    favorites.emotes = settings.clone();
+    save_favorites(&favorites);
+    configure_emote_matching(settings);
+}
+
+fn get_rule_settings() -> RuleSettings {
+    load_favorites().rules
+}
+
+fn set_rule_settings(settings: &RuleSettings) {
+    let mut favorites = load_favorites();
+    favorites.rules = settings.clone();
+    save_favorites(&favorites);
+    configure_rules(settings);
+}
+
+fn get_network_settings() -> NetworkSettings {
+    load_favorites().network
+}
+
+fn set_network_settings(settings: &NetworkSettings) {
+    let mut favorites = load_favorites();
+    favorites.network = settings.clone();
+    save_favorites(&favorites);
+    configure_network(settings);
+    NETWORK_SESSION.with(|network_session| apply_proxy_settings(network_session, settings));
+}
+
+fn get_startup_settings() -> StartupSettings {
+    load_favorites().startup
+}
+
+fn set_startup_settings(settings: &StartupSettings) {
+    let mut favorites = load_favorites();
+    favorites.startup = settings.clone();
+    save_favorites(&favorites);
+}
+
+fn get_quiet_hours_settings() -> QuietHoursSettings {
+    load_favorites().quiet_hours
+}
+
+fn set_quiet_hours_settings(settings: &QuietHoursSettings) {
+    let mut favorites = load_favorites();
+    favorites.quiet_hours = settings.clone();
+    save_favorites(&favorites);
+    configure_quiet_hours(settings);
+}
+
+fn get_upload_settings() -> UploadSettings {
+    load_favorites().upload
+}
+
+fn set_upload_settings(settings: &UploadSettings) {
+    let mut favorites = load_favorites();
+    favorites.upload = settings.clone();
+    save_favorites(&favorites);
+}
+
+// Starred channels seen before are queued right away; the others need their ids from Helix first
+fn prefetch_starred_emotes() {
+    let unknown = prefetch_emotes(&load_favorites().starred);
+    if unknown.is_empty() {
+        return;
+    }
+    thread::spawn(move || {
+        for (login, channel_id) in lookup_user_ids(&unknown) {
+            remember_channel_id(&login, &channel_id);
+        }
+        prefetch_emotes(&unknown);
+    });
+}
+
+// Open live channels in tab order
+fn session_channels(tab_view: &TabView, tabs: &HashMap<String, Arc<TabData>>) -> Vec<String> {
+    (0..tab_view.n_pages())
+        .filter_map(|index| {
+            let page = tab_view.nth_page(index);
+            let tab_data = tabs.values().find(|tab_data| tab_data.page == page)?;
+            let channel = tab_data.channel_name.locked().clone()?;
+            (channel != DEMO_CHANNEL && tab_data.replay.locked().is_none()).then_some(channel)
+        })
+        .collect()
+}
+
+// Remembers the open channels in tab order for "Restore Last Session"
+fn save_session(tab_view: &TabView, tabs: &HashMap<String, Arc<TabData>>) {
+    let mut favorites = load_favorites();
+    favorites.startup.last_session = session_channels(tab_view, tabs);
+    save_favorites(&favorites);
+}
+
+// Offers what a crash in the previous run left behind: its channels and the log
+fn offer_crash_recovery(
+    window: &ApplicationWindow,
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+    web_context: &webkit6::WebContext,
+) {
+    let Some(report) = take_crash_report() else {
+        return;
+    };
+    let crashed_at = report
+        .local_time()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "an earlier run".to_string());
+    let body = if report.channels.is_empty() {
+        format!("Admiral crashed on {}. The crash log has the details.", crashed_at)
+    } else {
+        format!(
+            "Admiral crashed on {} with {} open. Restore the tabs, or open the crash log for the details.",
+            crashed_at,
+            report.channels.join(", ")
+        )
+    };
+    let dialog = adw::AlertDialog::builder()
+        .heading("Admiral Quit Unexpectedly")
+        .body(body)
+        .build();
+    dialog.add_responses(&[("close", "Close"), ("log", "Open Log")]);
+    if !report.channels.is_empty() {
+        dialog.add_response("restore", "Restore Tabs");
+        dialog.set_response_appearance("restore", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("restore"));
+    }
+    dialog.set_close_response("close");
+
+    let window_weak = window.downgrade();
+    let tab_view = tab_view.clone();
+    let tabs = tabs.clone();
+    let web_context = web_context.clone();
+    dialog.connect_response(None, move |_, response| match response {
+        "restore" => open_channel_tabs(&report.channels, &tab_view, &tabs, &web_context),
+        "log" => {
+            let launcher = gtk::FileLauncher::new(Some(&adw::gio::File::for_path(&report.log_path)));
+            launcher.launch(window_weak.upgrade().as_ref(), None::<&adw::gio::Cancellable>, |result| {
+                if let Err(e) = result {
+                    eprintln!("Failed to open crash log: {}", e);
+                }
+            });
+        }
+        _ => {}
+    });
+    dialog.present(Some(window));
+}
+
 fn validate_hex_color(color: &str) -> bool {
     if color.len() != 7 || !color.starts_with('#') {
         return false;
@@ -748,82 +2267,29 @@ fn validate_hex_color(color: &str) -> bool {
     color[1..].chars().all(|c| c.is_ascii_hexdigit())
 }
 
-fn apply_background_color_to_tabs(
-    _tab_view: &TabView,
-    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
-    color: Option<&str>,
-) {
-    let tabs_map = tabs.lock().unwrap();
-    for (_, tab_data) in tabs_map.iter() {
-        // Update WebKit background color
-        if let Some(color_hex) = color {
-            // Parse hex color to RGBA
-            if let Ok(rgb) = u32::from_str_radix(&color_hex[1..], 16) {
-                let r = ((rgb >> 16) & 0xFF) as f32 / 255.0;
-                let g = ((rgb >> 8) & 0xFF) as f32 / 255.0;
-                let b = (rgb & 0xFF) as f32 / 255.0;
-                let bg_color = gdk::RGBA::new(r, g, b, 0.95);
-                tab_data.webview.set_background_color(&bg_color);
-
-                // Update the CSS in the WebView
-                let js_code = format!(
-                    r#"
-                    if (typeof updateBackgroundColor === 'function') {{
-                        updateBackgroundColor('{}');
-                    }} else {{
-                        // Create the function if it doesn't exist
-                        const style = document.createElement('style');
-                        style.textContent = `
-                            body {{ background-color: {} !important; }}
-                            @media (prefers-color-scheme: light) {{
-                                body {{ background-color: {} !important; }}
-                            }}
-                        `;
-                        document.head.appendChild(style);
-
-                        window.updateBackgroundColor = function(color) {{
-                            document.body.style.backgroundColor = color + 'e6'; // Add 90% opacity
-                        }};
-                    }}
-                    "#,
-                    color_hex, color_hex, color_hex
-                );
-
-                tab_data.webview.evaluate_javascript(
-                    &js_code,
-                    None,
-                    None,
-                    None::<&adw::gio::Cancellable>,
-                    move |result| {
-                        if let Err(e) = result {
-                            eprintln!("Error updating background color: {}", e);
-                        }
-                    },
-                );
-            }
-        } else {
-            // Reset to default background
-            let bg_color = gdk::RGBA::new(0.0, 0.0, 0.0, 0.95);
-            tab_data.webview.set_background_color(&bg_color);
+// The WebView's own background, drawn behind the page before it loads
+fn webview_background(color: Option<&str>) -> gdk::RGBA {
+    color
+        .filter(|color| validate_hex_color(color))
+        .and_then(|color| gdk::RGBA::parse(color).ok())
+        .map(|rgba| gdk::RGBA::new(rgba.red(), rgba.green(), rgba.blue(), 0.95))
+        .unwrap_or(gdk::RGBA::TRANSPARENT) // Lets the GTK background show
+}
 
-            let js_code = r#"
-            if (typeof updateBackgroundColor === 'function') {
-                updateBackgroundColor('rgba(0, 0, 0, 0.95)');
-            }
-            "#;
+// The custom background for the page, or none to fall back to the theme's
+fn background_vars(color: Option<&str>) -> serde_json::Map<String, serde_json::Value> {
+    let value = color
+        .filter(|color| validate_hex_color(color))
+        .map(|color| format!("{}e6", color)); // 90% opacity
+    serde_json::Map::from_iter([("--chat-background".to_string(), serde_json::json!(value))])
+}
 
-            tab_data.webview.evaluate_javascript(
-                js_code,
-                None,
-                None,
-                None::<&adw::gio::Cancellable>,
-                move |result| {
-                    if let Err(e) = result {
-                        eprintln!("Error resetting background color: {}", e);
-                    }
-                },
-            );
-        }
+fn apply_background_color_to_tabs(tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>, color: Option<&str>) {
+    let bg_color = webview_background(color);
+    let tabs_map = tabs.locked();
+    for tab_data in tabs_map.values() {
+        tab_data.webview.set_background_color(&bg_color);
+        apply_page_styles(&tab_data.webview, &tab_data.webview);
     }
 }
 
@@ -857,121 +2323,414 @@ fn get_theme_popover_colors(widget: &impl gtk::prelude::WidgetExt) -> (String, S
     (popover_bg, popover_border, popover_text)
 }
 
-fn apply_theme_to_popovers(
+fn css_rgba(color: &gdk::RGBA, alpha: f32) -> String {
+    format!(
+        "rgba({}, {}, {}, {})",
+        (color.red() * 255.0) as u8,
+        (color.green() * 255.0) as u8,
+        (color.blue() * 255.0) as u8,
+        alpha
+    )
+}
+
+// The page's colors from the GTK theme, so chat matches the window around it
+fn theme_vars(widget: &impl gtk::prelude::WidgetExt) -> serde_json::Map<String, serde_json::Value> {
+    let (popover_bg, popover_border, popover_text) = get_theme_popover_colors(widget);
+    let fg = widget.color();
+    let bg = widget.style_context().lookup_color("window_bg_color")
+        .unwrap_or_else(|| gdk::RGBA::new(0.118, 0.118, 0.118, 1.0));
+    // accent_color is the variant readable as text on the window background
+    let accent_bg = adw::StyleManager::default().accent_color_rgba();
+    let accent = widget.style_context().lookup_color("accent_color").unwrap_or(accent_bg);
+    let properties = [
+        ("--accent", css_rgba(&accent, 1.0)),
+        ("--accent-bg", css_rgba(&accent_bg, 0.9)),
+        ("--accent-soft", css_rgba(&accent_bg, 0.12)),
+        ("--theme-bg", css_rgba(&bg, 1.0)),
+        ("--theme-fg", css_rgba(&fg, 1.0)),
+        ("--theme-message-bg", css_rgba(&fg, 0.03)),
+        ("--popover-bg", popover_bg),
+        ("--popover-border", popover_border),
+        ("--popover-text", popover_text),
+    ];
+    properties
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), serde_json::Value::String(value)))
+        .collect()
+}
+
+// Everything applySettings takes: appearance, theme colors and the custom background
+fn page_settings(widget: &impl gtk::prelude::WidgetExt) -> serde_json::Value {
+    let mut settings = get_appearance_settings().page_settings();
+    if let Some(vars) = settings["vars"].as_object_mut() {
+        vars.extend(theme_vars(widget));
+        vars.extend(background_vars(get_background_color().as_deref()));
+    }
+    settings
+}
+
+// Defines applySettings and applies everything at once, for exports that have to stand
+// on their own
+fn page_settings_js(widget: &impl gtk::prelude::WidgetExt) -> String {
+    format!("{}{}", APPLY_SETTINGS_JS, apply_settings_js(&page_settings(widget)))
+}
+
+// The page's CSS variables go in a user style sheet on the tab's content manager, so
+// they hold from the first paint, survive reloads and crash recovery, and change
+// without touching the page
+fn apply_page_styles(webview: &WebView, widget: &impl gtk::prelude::WidgetExt) {
+    let settings = page_settings(widget);
+    let (Some(vars), Some(manager)) = (settings["vars"].as_object(), webview.user_content_manager()) else {
+        return;
+    };
+    let style_sheet = webkit6::UserStyleSheet::new(
+        &vars_css(vars),
+        webkit6::UserContentInjectedFrames::TopFrame,
+        webkit6::UserStyleLevel::User,
+        &[],
+        &[],
+    );
+    manager.remove_all_style_sheets();
+    manager.add_style_sheet(&style_sheet);
+}
+
+fn apply_theme_to_tabs(
     tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
     widget: &impl gtk::prelude::WidgetExt,
 ) {
-    let (popover_bg, popover_border, popover_text) = get_theme_popover_colors(widget);
-    let tabs_map = tabs.lock().unwrap();
+    let tabs_map = tabs.locked();
     for (_, tab_data) in tabs_map.iter() {
-        let js = format!(
-            "document.documentElement.style.setProperty('--popover-bg', '{}');\
-             document.documentElement.style.setProperty('--popover-border', '{}');\
-             document.documentElement.style.setProperty('--popover-text', '{}');",
-            popover_bg, popover_border, popover_text,
-        );
+        apply_page_styles(&tab_data.webview, widget);
+    }
+}
+
+fn update_unread_indicator(page: &TabPage, unread: u32) {
+    let tooltip = match unread {
+        0 => None,
+        1 => Some("1 unread mention".to_string()),
+        n => Some(format!("{} unread mentions", n)),
+    };
+    if page.indicator_tooltip().as_str() == tooltip.as_deref().unwrap_or("") {
+        return;
+    }
+    match &tooltip {
+        Some(tooltip) => {
+            page.set_indicator_icon(Some(&adw::gio::ThemedIcon::new("user-available-symbolic")));
+            page.set_indicator_tooltip(tooltip);
+        }
+        None => {
+            page.set_indicator_icon(None::<&adw::gio::Icon>);
+            page.set_indicator_tooltip("");
+        }
+    }
+    page.set_needs_attention(unread > 0);
+}
+
+fn apply_appearance_to_tabs(tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>) {
+    let js = get_appearance_settings().apply_js();
+    let tabs_map = tabs.locked();
+    for (_, tab_data) in tabs_map.iter() {
+        apply_page_styles(&tab_data.webview, &tab_data.webview);
         tab_data.webview.evaluate_javascript(
             &js,
             None,
             None,
             None::<&adw::gio::Cancellable>,
-            |_| {},
+            |result| {
+                if let Err(e) = result {
+                    eprintln!("Error applying appearance settings: {}", e);
+                }
+            },
         );
     }
 }
 
 fn load_and_display_favorites(
     list: &gtk::ListBox, // Use fully qualified name to avoid ambiguity
-    favorites_entry: &Entry,
-    favorites_list: &gtk::ListBox, // Use fully qualified name
     tab_view: &TabView,
     tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
     web_context: &webkit6::WebContext,
 ) {
     list.remove_all();
     let favorites = load_favorites();
-    let mut starred_channels = Vec::new();
-    let mut regular_channels = Vec::new();
-    for channel in &favorites.channels {
-        if favorites.starred.contains(channel) {
-            starred_channels.push(channel.clone());
-        } else {
-            regular_channels.push(channel.clone());
-        }
-    }
-    if !starred_channels.is_empty() {
-        for channel in &starred_channels {
-            create_favorite_row(
-                list,
-                channel,
-                true, // is_starred
-                &tab_view,
-                &tabs,
-                &favorites_entry,
-                &favorites_list,
-                web_context,
-            );
-        }
-    }
-    if !regular_channels.is_empty() {
-        for channel in &regular_channels {
-            create_favorite_row(
-                list,
-                channel,
-                false, // is_starred
-                &tab_view,
-                &tabs,
-                &favorites_entry,
-                &favorites_list,
-                web_context,
-            );
-        }
+    for channel in ordered_favorites(&favorites) {
+        let row = create_favorite_row(list, channel, favorites.starred.contains(channel), tab_view, tabs, web_context);
+        list.append(&row);
     }
     if favorites.channels.is_empty() {
-        // Create a status page style empty state
-        let empty_row = ListBoxRow::new();
-        empty_row.set_selectable(false);
-        empty_row.set_activatable(false);
-        let empty_box = Box::new(Orientation::Vertical, 12);
-        empty_box.set_margin_top(24);
-        empty_box.set_margin_bottom(24);
-        empty_box.set_halign(Align::Center);
-        let empty_label = gtk::Label::new(Some("No favorites yet"));
-        empty_label.add_css_class("title-4");
-        let subtitle_label = gtk::Label::new(Some("Add channels to get started"));
-        subtitle_label.add_css_class("dim-label");
-        empty_box.append(&empty_label);
-        empty_box.append(&subtitle_label);
-        empty_row.set_child(Some(&empty_box));
-        list.append(&empty_row);
+        list.append(&favorites_empty_row());
     }
 }
 
-fn create_favorite_row(
-    list: &gtk::ListBox, // Use fully qualified name
-    channel: &str,
-    is_starred: bool,
+// Starred channels first; within each group live channels lead by viewer count,
+// the rest keep the stored order
+fn ordered_favorites(favorites: &Favorites) -> Vec<&String> {
+    let live = live_viewer_counts();
+    let (mut starred, mut regular): (Vec<&String>, Vec<&String>) = favorites
+        .channels
+        .iter()
+        .partition(|channel| favorites.starred.contains(channel));
+    starred.sort_by_key(|channel| std::cmp::Reverse(live.get(*channel).copied()));
+    regular.sort_by_key(|channel| std::cmp::Reverse(live.get(*channel).copied()));
+    starred.extend(regular);
+    starred
+}
+
+thread_local! {
+    // Live badge of each favorite row, by login, so polls can update rows without rebuilding them
+    static FAVORITE_LIVE_LABELS: RefCell<HashMap<String, glib::WeakRef<gtk::Label>>> = RefCell::new(HashMap::new());
+    // Selection check box of each favorite row, by login, shown in selection mode
+    static FAVORITE_CHECKS: RefCell<HashMap<String, glib::WeakRef<gtk::CheckButton>>> = RefCell::new(HashMap::new());
+    static FAVORITES_SELECTING: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+// Shows or hides the rows' check boxes; leaving selection mode clears the selection
+fn set_favorites_selection_mode(selecting: bool) {
+    FAVORITES_SELECTING.set(selecting);
+    FAVORITE_CHECKS.with(|checks| {
+        checks.borrow_mut().retain(|_, check| match check.upgrade() {
+            Some(check) => {
+                check.set_visible(selecting);
+                check.set_active(false);
+                true
+            }
+            None => false,
+        });
+    });
+}
+
+fn selected_favorites() -> Vec<String> {
+    FAVORITE_CHECKS.with(|checks| {
+        checks
+            .borrow()
+            .iter()
+            .filter(|(_, check)| check.upgrade().is_some_and(|check| check.is_active()))
+            .map(|(channel, _)| channel.clone())
+            .collect()
+    })
+}
+
+// Opens a tab for each channel that doesn't have one yet
+fn open_channel_tabs(
+    channels: &[String],
     tab_view: &TabView,
     tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
-    favorites_entry: &Entry,
-    favorites_list: &gtk::ListBox, // Use fully qualified name
     web_context: &webkit6::WebContext,
 ) {
-    // Create ActionRow for a modern Libadwaita look
-    let action_row = adw::ActionRow::builder()
-        .title(channel)
-        .activatable(true)
-        .build();
+    for channel in channels {
+        if tab_for_channel(tabs, channel).is_none() {
+            open_channel_tab(channel, tab_view, tabs, web_context);
+        }
+    }
+}
 
-    // Create suffix button box
-    let suffix_box = Box::new(Orientation::Horizontal, 6);
+fn format_viewer_count(viewers: u32) -> String {
+    match viewers {
+        0..=999 => viewers.to_string(),
+        1000..=999_999 => format!("{:.1}K", viewers as f64 / 1000.0),
+        _ => format!("{:.1}M", viewers as f64 / 1_000_000.0),
+    }
+}
 
-    // Star button
-    let star_icon = if is_starred { "starred-symbolic" } else { "non-starred-symbolic" };
-    let star_tooltip = if is_starred { "Unstar channel" } else { "Star channel" };
-    let star_button = Button::builder()
-        .icon_name(star_icon)
-        .tooltip_text(star_tooltip)
+fn set_live_label(label: &gtk::Label, viewers: Option<u32>) {
+    match viewers {
+        Some(viewers) => {
+            label.set_label(&format!("Live · {}", format_viewer_count(viewers)));
+            label.set_tooltip_text(Some(&format!("{} viewers", viewers)));
+            label.set_visible(true);
+        }
+        None => label.set_visible(false),
+    }
+}
+
+// Applies the latest live poll to the favorites list: badges are updated and rows moved,
+// keeping the rows themselves
+fn refresh_favorites_live_status(list: &gtk::ListBox) {
+    let live = live_viewer_counts();
+    FAVORITE_LIVE_LABELS.with(|labels| {
+        labels.borrow_mut().retain(|channel, label| match label.upgrade() {
+            Some(label) => {
+                set_live_label(&label, live.get(channel).copied());
+                true
+            }
+            None => false,
+        });
+    });
+
+    let mut rows: HashMap<String, adw::ActionRow> = HashMap::new();
+    let mut child = list.first_child();
+    while let Some(widget) = child {
+        child = widget.next_sibling();
+        if let Ok(row) = widget.downcast::<adw::ActionRow>() {
+            rows.insert(row.widget_name().to_string(), row);
+        }
+    }
+    let favorites = load_favorites();
+    for (position, channel) in ordered_favorites(&favorites).into_iter().enumerate() {
+        let Some(row) = rows.get(channel.as_str()) else {
+            continue;
+        };
+        if row.index() != position as i32 {
+            list.remove(row);
+            list.insert(row, position as i32);
+        }
+    }
+}
+
+fn favorites_empty_row() -> ListBoxRow {
+    // Create a status page style empty state
+    let empty_row = ListBoxRow::new();
+    empty_row.set_selectable(false);
+    empty_row.set_activatable(false);
+    let empty_box = Box::new(Orientation::Vertical, 12);
+    empty_box.set_margin_top(24);
+    empty_box.set_margin_bottom(24);
+    empty_box.set_halign(Align::Center);
+    let empty_label = gtk::Label::new(Some("No favorites yet"));
+    empty_label.add_css_class("title-4");
+    let subtitle_label = gtk::Label::new(Some("Add channels to get started"));
+    subtitle_label.add_css_class("dim-label");
+    empty_box.append(&empty_label);
+    empty_box.append(&subtitle_label);
+    empty_row.set_child(Some(&empty_box));
+    empty_row
+}
+
+// Swaps a single favorite's row for a fresh one at its sorted place, e.g. after starring
+fn replace_favorite_row(
+    list: &gtk::ListBox,
+    old_row: &adw::ActionRow,
+    channel: &str,
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+    web_context: &webkit6::WebContext,
+) {
+    let favorites = load_favorites();
+    let position = ordered_favorites(&favorites)
+        .iter()
+        .position(|favorite| favorite.as_str() == channel)
+        .map_or(-1, |index| index as i32);
+    let row = create_favorite_row(list, channel, favorites.starred.iter().any(|c| c == channel), tab_view, tabs, web_context);
+    list.remove(old_row);
+    list.insert(&row, position);
+}
+
+// Builds the row for one favorite; its buttons update the row in place rather than the whole list
+fn create_favorite_row(
+    list: &gtk::ListBox, // Use fully qualified name
+    channel: &str,
+    is_starred: bool,
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+    web_context: &webkit6::WebContext,
+) -> adw::ActionRow {
+    let alias = get_favorite_alias(channel);
+    // Create ActionRow for a modern Libadwaita look
+    let action_row = adw::ActionRow::builder()
+        .title(alias.as_deref().unwrap_or(channel))
+        .activatable(true)
+        .build();
+    // Lets live polls find and move this row
+    action_row.set_widget_name(channel);
+
+    let select_check = gtk::CheckButton::builder()
+        .valign(gtk::Align::Center)
+        .visible(FAVORITES_SELECTING.get())
+        .build();
+    action_row.add_prefix(&select_check);
+    FAVORITE_CHECKS.with(|checks| {
+        checks.borrow_mut().insert(channel.to_string(), select_check.downgrade());
+    });
+    // The login stays visible under an alias
+    let mut subtitle: Vec<String> = alias.iter().map(|_| channel.to_string()).collect();
+
+    // Drag handle, hidden while the list keeps itself sorted
+    if !load_favorites().auto_sort {
+        let handle = gtk::Image::from_icon_name("list-drag-handle-symbolic");
+        handle.set_tooltip_text(Some("Drag to reorder"));
+        handle.set_cursor_from_name(Some("grab"));
+        action_row.add_prefix(&handle);
+
+        let drag_source = gtk::DragSource::builder()
+            .actions(gdk::DragAction::MOVE)
+            .content(&gdk::ContentProvider::for_value(&channel.to_value()))
+            .build();
+        let row_weak = action_row.downgrade();
+        drag_source.connect_drag_begin(move |source, _| {
+            if let Some(row) = row_weak.upgrade() {
+                source.set_icon(Some(&gtk::WidgetPaintable::new(Some(&row))), 0, 0);
+            }
+        });
+        handle.add_controller(drag_source);
+
+        // Dropping on the upper half of a row puts the channel above it, the lower half below
+        let drop_target = gtk::DropTarget::new(String::static_type(), gdk::DragAction::MOVE);
+        let channel_clone = channel.to_string();
+        let list_weak = list.downgrade();
+        let tab_view_clone = tab_view.clone();
+        let tabs_clone = tabs.clone();
+        let web_context_clone = web_context.clone();
+        drop_target.connect_drop(move |target, value, _, y| {
+            let Ok(dragged) = value.get::<String>() else {
+                return false;
+            };
+            if dragged == channel_clone {
+                return false;
+            }
+            let after = target.widget().is_some_and(|row| y > row.height() as f64 / 2.0);
+            move_favorite(&dragged, &channel_clone, after);
+            // Rebuilt once the drop is over, as this row is among those replaced
+            let list_weak = list_weak.clone();
+            let tab_view = tab_view_clone.clone();
+            let tabs = tabs_clone.clone();
+            let web_context = web_context_clone.clone();
+            glib::idle_add_local_once(move || {
+                if let Some(list) = list_weak.upgrade() {
+                    load_and_display_favorites(&list, &tab_view, &tabs, &web_context);
+                }
+            });
+            true
+        });
+        action_row.add_controller(drop_target);
+    }
+
+    action_row.add_prefix(&channel_avatar(channel, alias.as_deref().unwrap_or(channel), 32));
+
+    let live_label = gtk::Label::new(None);
+    live_label.add_css_class("caption");
+    live_label.add_css_class("error");
+    live_label.set_valign(gtk::Align::Center);
+    set_live_label(&live_label, live_viewer_counts().get(channel).copied());
+    action_row.add_suffix(&live_label);
+    FAVORITE_LIVE_LABELS.with(|labels| {
+        labels.borrow_mut().insert(channel.to_string(), live_label.downgrade());
+    });
+
+    // Create suffix button box
+    let suffix_box = Box::new(Orientation::Horizontal, 6);
+
+    // Rename button, editing the alias in place
+    let alias_entry = Entry::builder()
+        .placeholder_text(channel)
+        .text(alias.as_deref().unwrap_or_default())
+        .build();
+    let alias_popover = Popover::builder()
+        .child(&alias_entry)
+        .build();
+    let rename_button = gtk::MenuButton::builder()
+        .icon_name("document-edit-symbolic")
+        .tooltip_text("Rename")
+        .valign(gtk::Align::Center)
+        .popover(&alias_popover)
+        .build();
+    rename_button.add_css_class("flat");
+
+    // Star button
+    let star_icon = if is_starred { "starred-symbolic" } else { "non-starred-symbolic" };
+    let star_tooltip = if is_starred { "Unstar channel" } else { "Star channel" };
+    let star_button = Button::builder()
+        .icon_name(star_icon)
+        .tooltip_text(star_tooltip)
         .valign(gtk::Align::Center)
         .build();
     star_button.add_css_class("flat");
@@ -984,175 +2743,1427 @@ fn create_favorite_row(
         .build();
     trash_button.add_css_class("flat");
 
+    suffix_box.append(&rename_button);
     suffix_box.append(&star_button);
     suffix_box.append(&trash_button);
     action_row.add_suffix(&suffix_box);
 
+    // Starred channels can be opened automatically on a weekly schedule
+    if is_starred {
+        let schedule = get_channel_schedule(channel);
+        if let Some(schedule) = &schedule {
+            subtitle.push(schedule.describe());
+        }
+        let schedule_button = Button::builder()
+            .icon_name("alarm-symbolic")
+            .tooltip_text("Schedule")
+            .valign(gtk::Align::Center)
+            .build();
+        schedule_button.add_css_class("flat");
+        if schedule.is_some() {
+            schedule_button.add_css_class("accent");
+        }
+        suffix_box.prepend(&schedule_button);
+
+        let channel_clone = channel.to_string();
+        let list_weak = list.downgrade();
+        let row_weak = action_row.downgrade();
+        let tab_view_clone = tab_view.clone();
+        let tabs_clone = tabs.clone();
+        let web_context_clone = web_context.clone();
+        schedule_button.connect_clicked(move |button| {
+            let list_weak = list_weak.clone();
+            let row_weak = row_weak.clone();
+            let channel = channel_clone.clone();
+            let tab_view = tab_view_clone.clone();
+            let tabs = tabs_clone.clone();
+            let web_context = web_context_clone.clone();
+            show_schedule_dialog(button, &channel_clone, move || {
+                if let (Some(list), Some(row)) = (list_weak.upgrade(), row_weak.upgrade()) {
+                    replace_favorite_row(&list, &row, &channel, &tab_view, &tabs, &web_context);
+                }
+            });
+        });
+    }
+
+    if !subtitle.is_empty() {
+        action_row.set_subtitle(&subtitle.join(" · "));
+    }
+
+    // An empty alias goes back to the login
+    let channel_clone = channel.to_string();
+    let list_weak = list.downgrade();
+    let row_weak = action_row.downgrade();
+    let tab_view_clone = tab_view.clone();
+    let tabs_clone = tabs.clone();
+    let web_context_clone = web_context.clone();
+    alias_entry.connect_activate(move |entry| {
+        set_favorite_alias(&channel_clone, Some(&entry.text()));
+        if let Some(tab_data) = tab_for_channel(&tabs_clone, &channel_clone) {
+            tab_data.page.set_title(&channel_display_name(&channel_clone));
+        }
+        if let Some(popover) = entry.ancestor(Popover::static_type()).and_downcast::<Popover>() {
+            popover.popdown();
+        }
+        if let (Some(list), Some(row)) = (list_weak.upgrade(), row_weak.upgrade()) {
+            replace_favorite_row(&list, &row, &channel_clone, &tab_view_clone, &tabs_clone, &web_context_clone);
+        }
+    });
+
     // Handle row activation (clicking the row itself)
     let channel_clone = channel.to_string();
     let tab_view_clone = tab_view.clone();
     let tabs_clone = tabs.clone();
     let web_context_clone = web_context.clone();
     action_row.connect_activated(move |_| {
+        if FAVORITES_SELECTING.get() {
+            select_check.set_active(!select_check.is_active());
+            return;
+        }
         println!("Row clicked for channel: {}", channel_clone);
-        create_new_tab(&channel_clone, &tab_view_clone, &tabs_clone, &web_context_clone);
-        let tab_view_clone2 = tab_view_clone.clone();
-        let tabs_clone2 = tabs_clone.clone();
-        let channel_clone2 = channel_clone.clone();
-        glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
-            println!("Attempting to connect to channel: {}", channel_clone2);
-            if let Some(selected_page) = tab_view_clone2.selected_page() {
-                let tabs_guard = tabs_clone2.lock().unwrap();
-                for (_, tab_data) in tabs_guard.iter() {
-                    if tab_data.page == selected_page {
-                        println!("Found matching tab, setting entry and connecting");
-                        tab_data.entry.set_text(&channel_clone2);
-                        let tab_data_clone = Arc::clone(tab_data);
-                        let channel_for_connection = channel_clone2.clone();
-                        start_connection_for_tab(&channel_for_connection, &tab_data_clone);
-                        break;
-                    }
-                }
-            } else {
-                println!("No selected page found!");
-            }
-        });
+        open_channel_tab(&channel_clone, &tab_view_clone, &tabs_clone, &web_context_clone);
     });
 
     // Handle star button click
     let channel_clone = channel.to_string();
-    let favorites_list_clone = favorites_list.clone();
-    let favorites_entry_clone = favorites_entry.clone();
+    // Weak, as the list and row own these handlers
+    let list_weak = list.downgrade();
+    let row_weak = action_row.downgrade();
     let tab_view_clone = tab_view.clone();
     let tabs_clone = tabs.clone();
     let web_context_clone = web_context.clone();
     star_button.connect_clicked(move |_| {
         toggle_star(&channel_clone);
-        load_and_display_favorites(
-            &favorites_list_clone,
-            &favorites_entry_clone,
-            &favorites_list_clone,
-            &tab_view_clone,
-            &tabs_clone,
-            &web_context_clone,
-        );
+        if let (Some(list), Some(row)) = (list_weak.upgrade(), row_weak.upgrade()) {
+            replace_favorite_row(&list, &row, &channel_clone, &tab_view_clone, &tabs_clone, &web_context_clone);
+        }
+    });
+
+    // Handle trash button click
+    let channel_clone = channel.to_string();
+    let list_weak = list.downgrade();
+    let row_weak = action_row.downgrade();
+    trash_button.connect_clicked(move |_| {
+        remove_favorite(&channel_clone);
+        let (Some(list), Some(row)) = (list_weak.upgrade(), row_weak.upgrade()) else {
+            return;
+        };
+        list.remove(&row);
+        if list.first_child().is_none() {
+            list.append(&favorites_empty_row());
+        }
+    });
+
+    action_row
+}
+
+fn cleanup_webview(webview: &WebView) {
+    // Evaluate JavaScript to force garbage collection and cleanup before reloading
+    let cleanup_js = r#"
+        // Clean up event listeners
+        if (typeof cleanupEventListeners === 'function') {
+            cleanupEventListeners();
+        }
+
+        // Drop stored and pending messages
+        if (typeof entries !== 'undefined') {
+            entries = [];
+            pendingHtml = [];
+            windowStart = windowEnd = 0;
+        }
+
+        // Clear all messages from DOM
+        const chatBody = document.getElementById('chat-body');
+        if (chatBody) {
+            chatBody.innerHTML = '<div class="scroll-buffer"></div>';
+        }
+
+        // Force garbage collection if available
+        if (window.gc) {
+            window.gc();
+        }
+
+        // Clear any timers
+        if (typeof scrollTimeout !== 'undefined') {
+            clearTimeout(scrollTimeout);
+        }
+
+        // Stop the keepRendering loop
+        if (typeof frameCount !== 'undefined') {
+            frameCount = Infinity;
+        }
+    "#;
+
+    webview.evaluate_javascript(
+        cleanup_js,
+        None,
+        None,
+        None::<&adw::gio::Cancellable>,
+        |_| {},
+    );
+}
+
+fn cleanup_all_webviews(tabs: &HashMap<String, Arc<TabData>>) {
+    println!("Cleaning up all WebViews");
+    for (tab_id, tab_data) in tabs.iter() {
+        println!("Cleaning up WebView for tab: {}", tab_id);
+        cleanup_webview(&tab_data.webview);
+        // Load blank page to force cleanup
+        tab_data.webview.load_uri("about:blank");
+        tab_data.chat_page_loaded.store(false, Ordering::Relaxed);
+    }
+    println!("All WebViews cleaned up");
+}
+
+// Stops the tab's connection and any playback, leaving its chat view as it is
+fn end_session(tab_data: &TabData) {
+    *tab_data.connection_state.locked() = ConnectionState::Disconnected;
+    tab_data.client_state.locked().disconnect();
+    stop_playback(tab_data);
+    tab_data.queue.clear();
+}
+
+// Loads the chat template unless it's already up. Content comes separately: load_changed
+// replays message_buffer into a fresh page, and a loaded page keeps what it shows.
+fn load_chat_page(tab_data: &TabData) {
+    // Lightweight tabs leave WebKit out until switched back
+    if tab_data.lightweight.load(Ordering::Relaxed) {
+        return;
+    }
+    if !tab_data.chat_page_loaded.swap(true, Ordering::Relaxed) {
+        tab_data.webview.load_html(get_chat_html_template(), None);
+    }
+}
+
+// Empties the chat view for a new session without reloading the page
+fn clear_chat_content(tab_data: &TabData) {
+    tab_data.chat_generation.fetch_add(1, Ordering::Relaxed);
+    tab_data.message_buffer.locked().clear();
+    tab_data.pending_messages.locked().clear();
+    *tab_data.scroll_anchor.locked() = None;
+    tab_data.lightweight_view.clear();
+    if tab_data.lightweight.load(Ordering::Relaxed) {
+        return;
+    }
+    tab_data.webview.evaluate_javascript(
+        "if (typeof replaceAllMessages === 'function') { replaceAllMessages(''); }",
+        None,
+        None,
+        None::<&adw::gio::Cancellable>,
+        |_| {},
+    );
+}
+
+fn disconnect_tab_handler(tab_data: &Arc<TabData>) {
+    println!("Disconnecting tab...");
+    end_session(tab_data);
+
+    // Aggressive cleanup before clearing WebView
+    cleanup_webview(&tab_data.webview);
+    tab_data.chat_page_loaded.store(false, Ordering::Relaxed);
+    tab_data.notes_pane.load(None);
+
+    // Load a data URI to clear content without fetching anything
+    tab_data.webview.load_uri("about:blank");
+
+    // Alternatively, reload empty HTML to force a fresh context
+    tab_data.webview.load_html("<!DOCTYPE html><html><head></head><body></body></html>", None);
+
+    tab_data.send_bar.set_visible(false);
+    tab_data.stack.set_visible_child_name("placeholder");
+    tab_data.page.set_title("New Tab");
+    tab_data.page.set_loading(false);
+    *tab_data.channel_name.locked() = None;
+}
+
+// Tab views in windows opened by moving tabs out of the main one
+type DetachedViews = Rc<RefCell<Vec<TabView>>>;
+
+// Pages currently on screen: the selected one in each window
+fn shown_pages(tab_view: &TabView, detached: &DetachedViews) -> Vec<TabPage> {
+    let mut pages: Vec<TabPage> = tab_view.selected_page().into_iter().collect();
+    pages.extend(detached.borrow().iter().filter_map(|view| view.selected_page()));
+    pages
+}
+
+// A bare window for tabs moved out of the main one. Their chats keep being fed by the
+// main window's message pump, and closing the window closes its tabs.
+fn create_tab_window(
+    app: &Application,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+    detached: &DetachedViews,
+) -> TabView {
+    let tab_view = TabView::builder()
+        .vexpand(true)
+        .build();
+    let tab_bar = TabBar::builder()
+        .view(&tab_view)
+        .autohide(true)
+        .build();
+    tab_bar.add_css_class("inline");
+    add_tab_bar_gestures(&tab_bar, &tab_view);
+
+    let content = Box::new(Orientation::Vertical, 0);
+    content.append(&HeaderBar::new());
+    content.append(&tab_bar);
+    content.append(&tab_view);
+
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Admiral")
+        .default_width(400)
+        .default_height(600)
+        .content(&content)
+        .build();
+
+    let tabs_clone = tabs.clone();
+    let window_clone = window.clone();
+    tab_view.connect_selected_page_notify(move |tab_view| {
+        let Some(page) = tab_view.selected_page() else {
+            return;
+        };
+        window_clone.set_title(Some(page.title().as_str()));
+        if let Some(tab_data) = tab_for_page(&tabs_clone, &page) {
+            restore_chat_view(&tab_data);
+        }
+    });
+
+    let tabs_clone = tabs.clone();
+    tab_view.connect_close_page(move |_, page| {
+        remove_tab(&tabs_clone, page);
+        glib::Propagation::Proceed
+    });
+
+    // The last tab closing or moving elsewhere takes the window with it
+    let window_clone = window.clone();
+    tab_view.connect_page_detached(move |tab_view, _, _| {
+        if tab_view.n_pages() == 0 {
+            window_clone.close();
+        }
+    });
+
+    let app_clone = app.clone();
+    let tabs_clone = tabs.clone();
+    let detached_clone = detached.clone();
+    tab_view.connect_create_window(move |_| {
+        Some(create_tab_window(&app_clone, &tabs_clone, &detached_clone))
+    });
+
+    let tabs_clone = tabs.clone();
+    let detached_clone = detached.clone();
+    let tab_view_clone = tab_view.clone();
+    window.connect_close_request(move |_| {
+        for index in 0..tab_view_clone.n_pages() {
+            remove_tab(&tabs_clone, &tab_view_clone.nth_page(index));
+        }
+        detached_clone.borrow_mut().retain(|view| view != &tab_view_clone);
+        glib::Propagation::Proceed
+    });
+
+    detached.borrow_mut().push(tab_view.clone());
+    window.present();
+    tab_view
+}
+
+// The side of the main window that `:split` moves tabs into, hidden while it has none.
+// Like the tab views of other windows, it is counted among the detached views.
+fn build_side_pane(
+    app: &Application,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+    detached: &DetachedViews,
+) -> (Box, TabView) {
+    let side_view = TabView::builder()
+        .vexpand(true)
+        .build();
+    let side_bar = TabBar::builder()
+        .view(&side_view)
+        .autohide(false)
+        .build();
+    side_bar.add_css_class("inline");
+    add_tab_bar_gestures(&side_bar, &side_view);
+
+    let pane = Box::new(Orientation::Vertical, 0);
+    pane.append(&side_bar);
+    pane.append(&side_view);
+    pane.set_visible(false);
+
+    let tabs_clone = tabs.clone();
+    side_view.connect_selected_page_notify(move |side_view| {
+        if let Some(tab_data) = side_view.selected_page().and_then(|page| tab_for_page(&tabs_clone, &page)) {
+            restore_chat_view(&tab_data);
+        }
+    });
+
+    let tabs_clone = tabs.clone();
+    side_view.connect_close_page(move |_, page| {
+        remove_tab(&tabs_clone, page);
+        glib::Propagation::Proceed
+    });
+
+    let pane_clone = pane.clone();
+    side_view.connect_page_attached(move |_, _, _| {
+        pane_clone.set_visible(true);
+    });
+
+    // The window goes back to a single pane once the last tab here closes or moves away
+    let pane_clone = pane.clone();
+    side_view.connect_page_detached(move |side_view, _, _| {
+        if side_view.n_pages() == 0 {
+            pane_clone.set_visible(false);
+        }
+    });
+
+    let app_clone = app.clone();
+    let tabs_clone = tabs.clone();
+    let detached_clone = detached.clone();
+    side_view.connect_create_window(move |_| {
+        Some(create_tab_window(&app_clone, &tabs_clone, &detached_clone))
+    });
+
+    detached.borrow_mut().push(side_view.clone());
+    (pane, side_view)
+}
+
+// Joins the tab's channel again from scratch
+fn reconnect_tab(tab_data: &Arc<TabData>) {
+    let Some(channel) = tab_data.channel_name.locked().clone() else {
+        return;
+    };
+    // The chat view stays as it is; new messages continue below the old ones
+    end_session(tab_data);
+    start_connection_for_tab(&channel, tab_data);
+}
+
+fn tab_for_page(tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>, page: &TabPage) -> Option<Arc<TabData>> {
+    tabs.locked()
+        .values()
+        .find(|tab_data| &tab_data.page == page)
+        .cloned()
+}
+
+// Disconnects the tab shown in a page that is closing and forgets about it
+fn remove_tab(tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>, page: &TabPage) {
+    let tabs_map = tabs.locked();
+    let mut tab_id_to_remove = None;
+    for (tab_id, tab_data) in tabs_map.iter() {
+        if &tab_data.page == page {
+            println!("Found tab to disconnect: {}", tab_id);
+            disconnect_tab_handler(tab_data);
+            if let Some(pid) = *tab_data.web_process.locked() {
+                release_web_process(pid);
+            }
+            tab_id_to_remove = Some(tab_id.clone());
+            break;
+        }
+    }
+    drop(tabs_map);
+    if let Some(tab_id) = tab_id_to_remove {
+        tabs.locked().remove(&tab_id);
+        println!("Removed tab from HashMap: {}", tab_id);
+    }
+}
+
+// Follows a refill of the chat page, putting back where the user was reading
+fn push_scroll_anchor(calls: &mut PageCalls, scroll_anchor: &Mutex<Option<String>>) {
+    if let Some(message_id) = scroll_anchor.locked().as_deref() {
+        calls.push("restoreScrollAnchor", &[message_id.to_variant()]);
+    }
+}
+
+// Brings a tab that was in the background up to date once it's shown again
+fn restore_chat_view(tab_data: &TabData) {
+    tab_data.pending_messages.locked().clear();
+    // The text view is kept current in the background
+    if tab_data.lightweight.load(Ordering::Relaxed) {
+        return;
+    }
+
+    // Reloading replays message_buffer once the page finishes loading
+    if tab_data.hibernated.swap(false, Ordering::Relaxed) {
+        reload_chat_view(tab_data);
+        return;
+    }
+
+    let buf = tab_data.message_buffer.locked();
+    if buf.is_empty() {
+        return;
+    }
+    let all_html: String = buf.joined();
+    drop(buf);
+
+    let mut calls = PageCalls::new();
+    calls.push("replaceAllMessages", &[all_html.to_variant()]);
+    push_scroll_anchor(&mut calls, &tab_data.scroll_anchor);
+    let last_js_execution = tab_data.last_js_execution.clone();
+    calls.run_then(&tab_data.webview, move |result| {
+        match result {
+            Ok(()) => {
+                *last_js_execution.locked() = Instant::now();
+            }
+            Err(e) => {
+                eprintln!("Error restoring messages on tab switch: {}", e);
+            }
+        }
+    });
+}
+
+// Reflects slow mode, followers-only and similar restrictions in the send controls
+fn apply_send_hint(entry: &Entry, send_button: &Button, room_state: &RoomState) {
+    match room_state.send_hint() {
+        Some(hint) => {
+            entry.set_placeholder_text(Some(&hint.placeholder));
+            send_button.set_sensitive(hint.can_send);
+            send_button.set_tooltip_text(Some(&hint.placeholder));
+        }
+        None => {
+            entry.set_placeholder_text(Some("Send a message"));
+            send_button.set_sensitive(true);
+            send_button.set_tooltip_text(Some("Send message"));
+        }
+    }
+}
+
+// Shown only while connected, since a stale ROOMSTATE says nothing about the channel now
+fn update_room_modes(tab_data: &TabData) {
+    let modes = if tab_data.send_bar.is_visible() {
+        tab_data.room_state.locked().active_modes()
+    } else {
+        Vec::new()
+    };
+    let text = modes.join(" · ");
+    if tab_data.room_modes.text() != text {
+        tab_data.room_modes.set_text(&text);
+    }
+    tab_data.room_modes.set_visible(!modes.is_empty());
+}
+
+// Signed out, the input says so; signed in, it shows the channel's restrictions or the
+// message being replied to
+fn update_send_controls(tab_data: &TabData) {
+    update_identity(tab_data);
+    if !tab_data.signed_in.load(Ordering::Relaxed) {
+        tab_data.message_entry.set_sensitive(false);
+        tab_data.message_entry.set_placeholder_text(Some("Log in to send messages"));
+        tab_data.send_button.set_sensitive(false);
+        return;
+    }
+    tab_data.message_entry.set_sensitive(true);
+    apply_send_hint(&tab_data.message_entry, &tab_data.send_button, &tab_data.room_state.locked());
+    if let Some(reply) = tab_data.reply_target.locked().as_ref() {
+        if tab_data.send_button.is_sensitive() {
+            tab_data.message_entry.set_placeholder_text(Some(&format!("Reply to @{}", reply.login)));
+        }
+    }
+}
+
+// The name the user chats as, in their color: from the channel's USERSTATE, or the login
+// from Helix until that arrives
+fn update_identity(tab_data: &TabData) {
+    if !tab_data.signed_in.load(Ordering::Relaxed) {
+        tab_data.identity.show(None, None);
+        return;
+    }
+    let room_state = tab_data.room_state.locked();
+    let name = room_state.own_name.clone().or_else(cached_own_login);
+    tab_data.identity.show(name.as_deref(), room_state.own_color.as_deref());
+}
+
+// Sets the user's chat color on Twitch. This tab shows it right away; the others once
+// their channel's next USERSTATE arrives.
+fn change_chat_color(tab_data: &Arc<TabData>, color: String) {
+    let tab_data = tab_data.clone();
+    glib::MainContext::default().spawn_local(async move {
+        let picked = color.clone();
+        let result = adw::gio::spawn_blocking(move || {
+            update_chat_color(&http_client(), &picked).map_err(|e| e.to_string())
+        })
+        .await
+        .unwrap_or_else(|_| Err("Chat color request panicked".to_string()));
+        match result {
+            Ok(()) => {
+                println!("Chat color changed to {}", color);
+                tab_data.room_state.locked().own_color = color_hex(&color);
+                update_send_controls(&tab_data);
+            }
+            Err(e) => eprintln!("Failed to change chat color: {}", e),
+        }
+    });
+}
+
+// The user's login and token for chat, if signed in. Blocking, since the login may
+// have to be looked up first.
+fn chat_login() -> Option<StaticLoginCredentials> {
+    let token = auth::load_token()?;
+    let login = own_login(&http_client())
+        .map_err(|e| eprintln!("Failed to look up login for chat, joining read-only: {}", e))
+        .ok()?;
+    Some(StaticLoginCredentials::new(login, Some(token)))
+}
+
+// Sends what's in the tab's message input to its channel, as a reply if one was picked
+fn send_chat_message(tab_data: &Arc<TabData>) {
+    let text = tab_data.message_entry.text().trim().to_string();
+    if text.is_empty() || !tab_data.send_button.is_sensitive() {
+        return;
+    }
+    let ConnectionState::Connected(channel) = tab_data.connection_state.locked().clone() else {
+        eprintln!("Not connected yet, message not sent");
+        return;
+    };
+    let Some(client) = tab_data.client_state.locked().client.clone() else {
+        return;
+    };
+    let reply = tab_data.reply_target.locked().take();
+    tab_data.send_history.locked().push(&text);
+    tab_data.message_entry.set_text("");
+    update_send_controls(tab_data);
+
+    let tab_data = tab_data.clone();
+    glib::MainContext::default().spawn_local(async move {
+        let result = match &reply {
+            Some(reply) => client.say_in_reply_to(&channel, &reply.message_id, text.clone()).await,
+            None => client.say(&channel, text.clone()).await,
+        };
+        match result {
+            Ok(()) => {
+                tab_data.room_state.locked().record_sent();
+                let color = tab_data.room_state.locked().own_color.clone();
+                if let Some(msg) = own_message(&channel, &text, color.as_deref()) {
+                    tab_data.queue.push(msg);
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to send message to {}: {}", channel, e);
+                // Given back rather than lost, unless something new was typed meanwhile
+                if tab_data.message_entry.text().is_empty() {
+                    tab_data.message_entry.set_text(&text);
+                    *tab_data.reply_target.locked() = reply;
+                }
+            }
+        }
+        update_send_controls(&tab_data);
+    });
+}
+
+// Twitch doesn't echo our own messages back, so one is made up to show in the tab
+fn own_message(channel: &str, text: &str, color: Option<&str>) -> Option<twitch_irc::message::PrivmsgMessage> {
+    // Twitch runs commands itself; of those, only /me shows up in chat
+    let body = match text.strip_prefix("/me ") {
+        Some(action) => format!("\u{1}ACTION {}\u{1}", action),
+        None if text.starts_with('/') => return None,
+        None => text.to_string(),
+    };
+    let (user_id, login) = cached_own_user()?;
+    let sent_at = chrono::Utc::now().timestamp_millis();
+    let raw = format!(
+        "@badge-info=;badges=;color={};display-name={};emotes=;id=local-{};room-id={};tmi-sent-ts={};user-id={} :{login}!{login}@{login}.tmi.twitch.tv PRIVMSG #{} :{}",
+        escape_tag(color.unwrap_or_default()),
+        escape_tag(&login),
+        sent_at,
+        escape_tag(&known_channel_id(channel).unwrap_or_default()),
+        sent_at,
+        escape_tag(&user_id),
+        channel,
+        body,
+        login = login,
+    );
+    let irc = twitch_irc::message::IRCMessage::parse(&raw)
+        .map_err(|e| eprintln!("Failed to show sent message: {}", e))
+        .ok()?;
+    twitch_irc::message::PrivmsgMessage::try_from(irc)
+        .map_err(|e| eprintln!("Failed to show sent message: {}", e))
+        .ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChatCopyFormat {
+    Text,
+    Html,
+    Image,
+}
+
+fn selected_tab(
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+) -> Option<Arc<TabData>> {
+    let selected_page = tab_view.selected_page()?;
+    let tabs_map = tabs.locked();
+    tabs_map
+        .values()
+        .find(|tab_data| tab_data.page == selected_page)
+        .cloned()
+}
+
+// What's under the pointer on the tab bar. The bar's tab widgets are private, but each
+// carries its page as a property.
+enum TabBarTarget {
+    Tab(TabPage),
+    Control, // One of the bar's own buttons
+    Empty,
+}
+
+fn tab_bar_target(tab_bar: &TabBar, x: f64, y: f64) -> TabBarTarget {
+    let mut widget = tab_bar.pick(x, y, gtk::PickFlags::DEFAULT);
+    while let Some(current) = widget {
+        if current == *tab_bar.upcast_ref::<gtk::Widget>() {
+            break;
+        }
+        if current.find_property("page").is_some() {
+            if let Ok(page) = current.property_value("page").get::<TabPage>() {
+                return TabBarTarget::Tab(page);
+            }
+        }
+        if current.is::<gtk::Button>() {
+            return TabBarTarget::Control;
+        }
+        widget = current.parent();
+    }
+    TabBarTarget::Empty
+}
+
+// Browser habits: middle-click closes a tab, double-clicking empty space opens one
+fn add_tab_bar_gestures(tab_bar: &TabBar, tab_view: &TabView) {
+    let middle_click = gtk::GestureClick::builder()
+        .button(gdk::BUTTON_MIDDLE)
+        .propagation_phase(gtk::PropagationPhase::Capture)
+        .build();
+    let tab_view_clone = tab_view.clone();
+    middle_click.connect_pressed(move |gesture, _, x, y| {
+        let Some(tab_bar) = gesture.widget().and_downcast::<TabBar>() else {
+            return;
+        };
+        if let TabBarTarget::Tab(page) = tab_bar_target(&tab_bar, x, y) {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            tab_view_clone.close_page(&page);
+        }
+    });
+    tab_bar.add_controller(middle_click);
+
+    let double_click = gtk::GestureClick::builder()
+        .button(gdk::BUTTON_PRIMARY)
+        .build();
+    double_click.connect_pressed(|gesture, n_press, x, y| {
+        let Some(tab_bar) = gesture.widget().and_downcast::<TabBar>() else {
+            return;
+        };
+        if n_press == 2 && matches!(tab_bar_target(&tab_bar, x, y), TabBarTarget::Empty) {
+            let _ = tab_bar.activate_action("win.new-tab", None);
+        }
+    });
+    tab_bar.add_controller(double_click);
+}
+
+// Copies the selected messages, or the visible ones, of the active tab to the clipboard
+fn copy_chat_from_tab(tab_data: &TabData, format: ChatCopyFormat) {
+    let Some(display) = gdk::Display::default() else {
+        return;
+    };
+    let clipboard = display.clipboard();
+
+    if format == ChatCopyFormat::Image {
+        copy_chat_image(&tab_data.webview, clipboard);
+        return;
+    }
+
+    let js = match format {
+        ChatCopyFormat::Html => "collectChatExport('html')",
+        _ => "collectChatExport('text')",
+    };
+    tab_data.webview.evaluate_javascript(
+        js,
+        None,
+        None,
+        None::<&adw::gio::Cancellable>,
+        move |result| match result {
+            Ok(value) => {
+                let exported = value.to_str().to_string();
+                if exported.is_empty() {
+                    return;
+                }
+                if format == ChatCopyFormat::Html {
+                    // Offer plain text alongside so text-only targets still get something useful
+                    let html_provider = gdk::ContentProvider::for_bytes(
+                        "text/html",
+                        &glib::Bytes::from_owned(exported.clone().into_bytes()),
+                    );
+                    let text_provider = gdk::ContentProvider::for_value(&exported.to_value());
+                    let provider = gdk::ContentProvider::new_union(&[html_provider, text_provider]);
+                    if let Err(e) = clipboard.set_content(Some(&provider)) {
+                        eprintln!("Failed to copy chat as HTML: {}", e);
+                    }
+                } else {
+                    clipboard.set_text(&exported);
+                }
+            }
+            Err(e) => eprintln!("Failed to collect chat for copying: {}", e),
+        },
+    );
+}
+
+// With a selection, the image shows just the selected messages, laid over the top of the
+// view while it's captured; without one, the chat as it looks. A selection taller than the
+// view is cut off at its bottom edge.
+fn copy_chat_image(webview: &WebView, clipboard: gdk::Clipboard) {
+    let webview_clone = webview.clone();
+    webview.call_async_javascript_function(
+        "return await showSelectionSnapshot();",
+        None,
+        None,
+        None,
+        None::<&adw::gio::Cancellable>,
+        move |result| {
+            let selection_height = match result {
+                Ok(value) => value.to_double(),
+                Err(e) => {
+                    eprintln!("Failed to prepare the selection for an image: {}", e);
+                    0.0
+                }
+            };
+            let webview = webview_clone.clone();
+            webview_clone.snapshot(
+                webkit6::SnapshotRegion::Visible,
+                webkit6::SnapshotOptions::NONE,
+                None::<&adw::gio::Cancellable>,
+                move |result| {
+                    if selection_height > 0.0 {
+                        call_page(&webview, "hideSelectionSnapshot", &[], "Failed to clear the selection image");
+                    }
+                    let texture = match result {
+                        Ok(texture) => texture,
+                        Err(e) => {
+                            eprintln!("Failed to snapshot chat: {}", e);
+                            return;
+                        }
+                    };
+                    if selection_height > 0.0 && webview.height() > 0 {
+                        // CSS pixels to the texture's, which also counts the display scale
+                        let scale = texture.height() as f64 / webview.height() as f64;
+                        let rows = (selection_height * webview.zoom_level() * scale).ceil() as i32;
+                        clipboard.set_texture(&crop_texture_top(&texture, rows));
+                    } else {
+                        clipboard.set_texture(&texture);
+                    }
+                },
+            );
+        },
+    );
+}
+
+// The top `height` rows of `texture`
+fn crop_texture_top(texture: &gdk::Texture, height: i32) -> gdk::Texture {
+    if height <= 0 || height >= texture.height() {
+        return texture.clone();
+    }
+    let mut downloader = gdk::TextureDownloader::new(texture);
+    downloader.set_format(gdk::MemoryFormat::R8g8b8a8Premultiplied);
+    let (bytes, stride) = downloader.download_bytes();
+    let cropped = glib::Bytes::from(&bytes[..stride * height as usize]);
+    gdk::MemoryTexture::new(texture.width(), height, gdk::MemoryFormat::R8g8b8a8Premultiplied, &cropped, stride).upcast()
+}
+
+// Channel id of the tab's channel once one of its messages has been seen
+fn tab_channel_id(tab_data: &TabData) -> Option<String> {
+    let channel = tab_data.channel_name.locked().clone()?;
+    known_channel_id(&channel)
+}
+
+fn refresh_emotes_for_tab(tab_data: &TabData) {
+    match tab_channel_id(tab_data) {
+        Some(channel_id) => {
+            refresh_emotes(&channel_id);
+        }
+        None => eprintln!("No messages seen in this tab yet, nothing to refresh emotes for"),
+    }
+}
+
+// Lists what's loaded for the tab's channel, for working out why an emote doesn't show
+fn show_emote_info(window: &ApplicationWindow, tab_data: &Arc<TabData>) {
+    let Some(channel) = tab_data.channel_name.locked().clone() else {
+        return;
+    };
+    let seventv_line = match tab_channel_id(tab_data) {
+        None => "7TV: waiting for the channel's first message".to_string(),
+        Some(channel_id) => {
+            let info = emote_cache_info(&channel_id);
+            let state = if info.fetching {
+                " (fetching)".to_string()
+            } else if info.from_disk {
+                " (saved copy, 7TV unreachable)".to_string()
+            } else if let Some(loaded_at) = info.loaded_at {
+                format!(", loaded {} min ago", loaded_at.elapsed().as_secs() / 60)
+            } else {
+                " (not loaded)".to_string()
+            };
+            format!(
+                "7TV: {} emotes, {} zero-width{}\nAll channels: {} emotes in {} channels",
+                info.seventv, info.zero_width, state, info.total_emotes, info.total_channels
+            )
+        }
+    };
+    let dialog = adw::AlertDialog::builder()
+        .heading(format!("Emotes in #{}", channel))
+        .body(format!("{}\nTwitch: sent along with each message, nothing cached", seventv_line))
+        .build();
+    dialog.add_responses(&[("close", "Close"), ("refresh", "Refresh Emotes")]);
+    dialog.set_close_response("close");
+    let tab_data = tab_data.clone();
+    dialog.connect_response(Some("refresh"), move |_, _| refresh_emotes_for_tab(&tab_data));
+    dialog.present(Some(window));
+}
+
+// Saves the tab's session as a standalone HTML page or as JSON, depending on the file name
+fn export_chat_from_tab(window: &ApplicationWindow, tab_data: &TabData) {
+    let Some(channel) = tab_data.channel_name.locked().clone() else {
+        return;
+    };
+    let html_filter = gtk::FileFilter::new();
+    html_filter.set_name(Some("HTML Page"));
+    html_filter.add_suffix("html");
+    let json_filter = gtk::FileFilter::new();
+    json_filter.set_name(Some("JSON"));
+    json_filter.add_suffix("json");
+    let filters = adw::gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&html_filter);
+    filters.append(&json_filter);
+
+    let dialog = gtk::FileDialog::builder()
+        .title("Export Chat")
+        .initial_name(format!("{}-{}.html", channel, chrono::Local::now().format("%Y%m%d-%H%M")))
+        .filters(&filters)
+        .build();
+    let message_buffer = tab_data.message_buffer.clone();
+    let recent_messages = tab_data.recent_messages.clone();
+    // Taken now, while the theme colors can still be read off the window
+    let setup_js = page_settings_js(window);
+    dialog.save(Some(window), None::<&adw::gio::Cancellable>, move |result| {
+        let Some(path) = result.ok().and_then(|file| file.path()) else {
+            return;
+        };
+        let contents = match ExportFormat::from_path(&path) {
+            ExportFormat::Html => {
+                let template = get_chat_html_template();
+                let style = template
+                    .split_once("<style>")
+                    .and_then(|(_, rest)| rest.split_once("</style>"))
+                    .map(|(style, _)| style)
+                    .unwrap_or_default();
+                let messages: Vec<String> = message_buffer.locked().iter().cloned().collect();
+                session_html(&channel, style, &setup_js, &messages)
+            }
+            ExportFormat::Json => {
+                let messages: Vec<_> = recent_messages.locked().iter().cloned().collect();
+                let emote_map = messages
+                    .first()
+                    .map(|msg| get_emote_map(&msg.channel_id))
+                    .unwrap_or_default();
+                session_json(&channel, &messages, &emote_map)
+            }
+        };
+        if let Err(e) = fs::write(&path, contents) {
+            eprintln!("Failed to export chat to {}: {}", path.display(), e);
+        }
     });
+}
 
-    // Handle trash button click
-    let channel_clone = channel.to_string();
-    let favorites_list_clone = favorites_list.clone();
-    let favorites_entry_clone = favorites_entry.clone();
-    let tab_view_clone = tab_view.clone();
-    let tabs_clone = tabs.clone();
-    let web_context_clone = web_context.clone();
-    trash_button.connect_clicked(move |_| {
-        remove_favorite(&channel_clone);
-        load_and_display_favorites(
-            &favorites_list_clone,
-            &favorites_entry_clone,
-            &favorites_list_clone,
-            &tab_view_clone,
-            &tabs_clone,
-            &web_context_clone,
-        );
-    });
+fn render_options_for(
+    msg: &twitch_irc::message::PrivmsgMessage,
+    bot_settings: &BotSettings,
+    appearance: &AppearanceSettings,
+) -> RenderOptions {
+    RenderOptions {
+        bot_display: bot_settings.display_for(&msg.channel_login, &msg.sender.login),
+        enlarge_emote_only: appearance.enlarge_emote_only,
+        emoji_images: appearance.emoji_images,
+    }
+}
 
-    list.append(&action_row);
+const HISTORY_PAGE_SIZE: usize = 100;
+const MAX_DRAIN_PER_TAB: usize = 50;
+const MAX_PENDING_BUFFER: usize = 2000;
+const LIVE_POLL_INTERVAL_SECS: u32 = 120;
+const CRASH_CHANNELS_INTERVAL_SECS: u32 = 5;
+
+// Keeps a rendered message for replaying into the tab's chat page
+fn push_message_html(message_buffer: &Mutex<MessageBuffer>, html: String) {
+    message_buffer.locked().push(html);
 }
 
-fn cleanup_webview(webview: &WebView) {
-    // Evaluate JavaScript to force garbage collection and cleanup before reloading
-    let cleanup_js = r#"
-        // Clean up event listeners
-        if (typeof cleanupEventListeners === 'function') {
-            cleanupEventListeners();
+// Background tabs keep the skipped notice in their buffer until they are shown
+fn buffer_skipped_notice(tab_data: &TabData) {
+    let skipped = tab_data.queue.take_skipped();
+    if skipped > 0 {
+        push_message_html(&tab_data.message_buffer, skipped_notice_html(skipped));
+    }
+}
+
+// One pass of the message pump for a tab: rendered batches, moderator actions, new
+// messages, then late translations and account ages
+fn pump_tab(
+    tab_data: &TabData,
+    is_active_tab: bool,
+    bot_settings: &mut Option<BotSettings>,
+    appearance: &mut Option<AppearanceSettings>,
+    moderation: &mut Option<ModerationSettings>,
+    translation: &mut Option<TranslationConfig>,
+) {
+    apply_rendered_batches(tab_data, is_active_tab, moderation, translation);
+    apply_chat_events(tab_data, is_active_tab, moderation);
+    render_new_messages(tab_data, is_active_tab, bot_settings, appearance);
+    if tab_data.error_rx.locked().try_recv().is_ok() {
+        let channel = tab_data.channel_name.locked().clone().unwrap_or_default();
+        record_activity(ActivityEvent::new(ActivityKind::ConnectionError, &channel, "Failed to join channel"));
+    }
+    apply_translations(tab_data, is_active_tab);
+    apply_account_ages(tab_data, is_active_tab);
+}
+
+// Sends newly arrived messages to the render thread. A tab has one batch out at a time,
+// so batches come back in order and a busy chat waits in the queue, where overflow is
+// counted as skipped, rather than piling up behind the worker.
+fn render_new_messages(
+    tab_data: &TabData,
+    is_active_tab: bool,
+    bot_settings: &mut Option<BotSettings>,
+    appearance: &mut Option<AppearanceSettings>,
+) {
+    if tab_data.render_in_flight.load(Ordering::Relaxed) {
+        return;
+    }
+    let pacing = tab_data.pacing.locked().clone();
+    if is_active_tab && tab_data.last_js_execution.locked().elapsed() < pacing.interval() {
+        return;
+    }
+
+    let mut messages = tab_data.queue.drain(if is_active_tab { pacing.batch_size() } else { MAX_DRAIN_PER_TAB });
+    record_received(tab_data, &messages);
+    if !messages.is_empty() {
+        remove_hidden_messages(&mut messages, bot_settings.get_or_insert_with(get_bot_settings), &tab_data.filters.locked());
+    }
+    if messages.is_empty() {
+        // Shown tabs keep the count until there's something to show after it
+        if !is_active_tab {
+            buffer_skipped_notice(tab_data);
         }
+        return;
+    }
+
+    let bot_settings = bot_settings.get_or_insert_with(get_bot_settings);
+    let appearance = appearance.get_or_insert_with(get_appearance_settings);
+    let options = messages
+        .iter()
+        .map(|msg| render_options_for(msg, bot_settings, appearance))
+        .collect();
+    let skipped = tab_data.queue.take_skipped();
+    let job = RenderJob {
+        generation: tab_data.chat_generation.load(Ordering::Relaxed),
+        notice: (skipped > 0).then(|| skipped_notice_html(skipped)),
+        emote_map: get_emote_map(&messages[0].channel_id),
+        messages,
+        options,
+        reply: tab_data.render_tx.clone(),
+    };
+    tab_data.render_in_flight.store(true, Ordering::Relaxed);
+    if !submit_render(job) {
+        tab_data.render_in_flight.store(false, Ordering::Relaxed);
+    }
+}
 
-        // Clear message queue
-        if (typeof messageQueue !== 'undefined') {
-            messageQueue = [];
+// Stores what the render thread finished and shows it if the tab is on screen now
+fn apply_rendered_batches(
+    tab_data: &TabData,
+    is_active_tab: bool,
+    moderation: &mut Option<ModerationSettings>,
+    translation: &mut Option<TranslationConfig>,
+) {
+    let batches: Vec<RenderedBatch> = tab_data.render_rx.locked().try_iter().collect();
+    if batches.is_empty() {
+        return;
+    }
+    tab_data.render_in_flight.store(false, Ordering::Relaxed);
+
+    let generation = tab_data.chat_generation.load(Ordering::Relaxed);
+    let lightweight = tab_data.lightweight.load(Ordering::Relaxed);
+    let mut injected_html = String::new();
+    let mut injected = 0;
+    let mut rendered_timestamps = Vec::new();
+    for batch in batches {
+        // Rendered for a session the view has since been cleared of
+        if batch.generation != generation {
+            continue;
         }
+        let Some(first) = batch.messages.first() else {
+            continue;
+        };
+        // Queued now rather than when sent off, so an answer can't arrive before the
+        // message it belongs to is in the buffer
+        let emote_map = get_emote_map(&first.channel_id);
+        queue_translations(tab_data, &batch.messages, &emote_map, translation);
+        queue_account_ages(tab_data, &batch.messages, moderation.get_or_insert_with(get_moderation_settings));
+
+        // Rendered all the same, for exports and switching back to the WebView
+        let has_notice = batch.html.len() > batch.messages.len();
+        let mut buf = tab_data.message_buffer.locked();
+        for html in batch.html {
+            buf.push(html);
+        }
+        drop(buf);
 
-        // Clear all messages from DOM
-        const chatBody = document.getElementById('chat-body');
-        if (chatBody) {
-            chatBody.innerHTML = '<div class="scroll-buffer"></div>';
+        // Text is cheap enough to keep current while the tab is in the background
+        if lightweight {
+            if has_notice {
+                tab_data.lightweight_view.append_notice("Messages skipped (chat too fast)");
+            }
+            tab_data.lightweight_view.append_messages(&batch.messages);
+        } else if is_active_tab {
+            if !injected_html.is_empty() {
+                injected_html.push('\n');
+            }
+            injected_html.push_str(&batch.joined);
+            injected += batch.messages.len();
+            if is_benchmarking() {
+                rendered_timestamps.extend(message_timestamps(&batch.messages));
+            }
+        } else {
+            let mut pending = tab_data.pending_messages.locked();
+            for msg in batch.messages {
+                if pending.len() >= MAX_PENDING_BUFFER {
+                    pending.pop_front();
+                }
+                pending.push_back(msg);
+            }
         }
+    }
+    if injected_html.is_empty() {
+        return;
+    }
 
-        // Force garbage collection if available
-        if (window.gc) {
-            window.gc();
+    let mut calls = PageCalls::new();
+    calls.push("appendMessages", &[injected_html.to_variant()]);
+    let last_js_execution = tab_data.last_js_execution.clone();
+    let pacing = tab_data.pacing.clone();
+    let started = Instant::now();
+    calls.run_then(&tab_data.webview, move |result| {
+        match result {
+            Ok(()) => {
+                *last_js_execution.locked() = Instant::now();
+                pacing.locked().record(started.elapsed(), injected);
+                if !rendered_timestamps.is_empty() {
+                    record_rendered(&rendered_timestamps);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error running JS: {}", e);
+            }
         }
+    });
+}
 
-        // Clear any timers
-        if (typeof scrollTimeout !== 'undefined') {
-            clearTimeout(scrollTimeout);
+// Reflects moderator actions on the chat in the view
+fn apply_chat_events(tab_data: &TabData, is_active_tab: bool, moderation: &mut Option<ModerationSettings>) {
+    let events: Vec<ChatEvent> = tab_data.chat_event_rx.locked().try_iter().collect();
+    let mut calls = PageCalls::new();
+    for event in events {
+        match event {
+            ChatEvent::ChatCleared => {
+                if moderation.get_or_insert_with(get_moderation_settings).clear_on_chat_clear {
+                    clear_chat_content(tab_data);
+                    calls.clear();
+                }
+                show_tab_notice(tab_data, is_active_tab, chat_cleared_notice_html(), CHAT_CLEARED_TEXT);
+            }
+            ChatEvent::MessageDeleted { message_id } => {
+                let keep = moderation.get_or_insert_with(get_moderation_settings).show_deleted_messages;
+                let marker = format!(r#"data-msg-id="{}""#, glib::markup_escape_text(&message_id));
+                let mut buf = tab_data.message_buffer.locked();
+                if keep {
+                    buf.update_newest(&marker, mark_deleted_html);
+                } else {
+                    buf.remove_newest(&marker);
+                }
+                drop(buf);
+                if is_active_tab {
+                    calls.push("deleteMessage", &[message_id.to_variant(), keep.to_variant()]);
+                }
+            }
+            ChatEvent::UserModerated { login, timeout } => {
+                let label = moderation_label(timeout);
+                let marker = format!(r#"data-login="{}""#, glib::markup_escape_text(&login));
+                tab_data
+                    .message_buffer
+                    .locked()
+                    .update_all(&marker, |html| mark_moderated_html(html, &label));
+                if tab_data.lightweight.load(Ordering::Relaxed) {
+                    tab_data.lightweight_view.append_notice(&format!("{} was {}", login, label));
+                } else if is_active_tab {
+                    calls.push("markUserModerated", &[login.to_variant(), label.to_variant()]);
+                }
+            }
+            ChatEvent::GiftBomb { gift_id, gifter, count } => {
+                let html = gift_card_html(&gift_id, &gifter, count);
+                show_tab_notice(tab_data, is_active_tab, html, &gift_bomb_text(&gifter, count));
+            }
+            ChatEvent::GiftRecipient { gift_id, recipient } => {
+                tab_data
+                    .message_buffer
+                    .locked()
+                    .update_newest(&gift_card_marker(&gift_id), |html| add_gift_recipient_html(html, &recipient));
+                if is_active_tab {
+                    calls.push("addGiftRecipient", &[gift_id.to_variant(), recipient.to_variant()]);
+                }
+            }
+            ChatEvent::Celebration { kind, text, message } => {
+                let html = celebration_notice_html(kind, &text, message.as_deref());
+                show_tab_notice(tab_data, is_active_tab, html, &celebration_notice_text(&text, message.as_deref()));
+            }
         }
+    }
 
-        // Stop the keepRendering loop
-        if (typeof frameCount !== 'undefined') {
-            frameCount = Infinity;
+    // Background tabs pick the changes up from the buffer when selected
+    if !tab_data.lightweight.load(Ordering::Relaxed) {
+        calls.run(&tab_data.webview, "Error applying chat events");
+    }
+}
+
+// Adds a line from Admiral to whichever view the tab uses; background tabs show it
+// from the buffer once selected
+fn show_tab_notice(tab_data: &TabData, is_active_tab: bool, html: String, text: &str) {
+    if tab_data.lightweight.load(Ordering::Relaxed) {
+        tab_data.lightweight_view.append_notice(text);
+        push_message_html(&tab_data.message_buffer, html);
+    } else if is_active_tab {
+        append_notice(&tab_data.webview, &tab_data.message_buffer, html);
+    } else {
+        push_message_html(&tab_data.message_buffer, html);
+    }
+}
+
+// Messages a pass left in the queue get another pass: right away, once a shown tab's
+// interval is up, or when the batch with the render thread comes back
+fn wake_for_leftover(tab_data: &TabData, is_active_tab: bool) {
+    if tab_data.render_in_flight.load(Ordering::Relaxed) || tab_data.queue.is_empty() {
+        return;
+    }
+    let interval = tab_data.pacing.locked().interval();
+    let elapsed = tab_data.last_js_execution.locked().elapsed();
+    if is_active_tab && elapsed < interval {
+        tab_data.waker.wake_after(interval - elapsed);
+    } else {
+        tab_data.waker.wake();
+    }
+}
+
+// Takes a tab out of the message pump after it panicked, so one bad message or tab
+// can't take the rest of the app down with it
+fn stall_tab(tab_data: &TabData) {
+    let channel = tab_data.channel_name.locked().clone().unwrap_or_default();
+    eprintln!("Stopped updating the tab for {} after an internal error", channel);
+    tab_data.stalled.store(true, Ordering::Relaxed);
+    end_session(tab_data);
+    discard_crash_report();
+    tab_data.crash_banner.set_title("Chat stopped after an internal error. Reconnect to try again.");
+    tab_data.crash_banner.set_revealed(true);
+}
+
+// Adds a line from one of the chat tools to the view, kept in the buffer like messages
+fn append_notice(webview: &WebView, message_buffer: &Mutex<MessageBuffer>, html: String) {
+    call_page(webview, "appendMessages", &[html.to_variant()], "Error running JS");
+    push_message_html(message_buffer, html);
+}
+
+// Counts every received message, including ones hidden from display, and keeps
+// them around for the moderation tools
+fn record_received(tab_data: &TabData, messages: &[twitch_irc::message::PrivmsgMessage]) {
+    let Some(first) = messages.first() else {
+        return;
+    };
+    remember_channel_id(&first.channel_login, &first.channel_id);
+    request_badges(&first.channel_id);
+    request_cheermotes(&first.channel_id);
+    // Shared chat brings in messages from other channels, with their own badges
+    for room_id in messages.iter().filter_map(source_room_id).collect::<HashSet<_>>() {
+        request_badges(room_id);
+    }
+    let emote_map = get_emote_map(&first.channel_id);
+    let mut stats = tab_data.stats.locked();
+    let mut giveaway = tab_data.giveaway.locked();
+    let mut poll = tab_data.poll.locked();
+    let mut recent = tab_data.recent_messages.locked();
+    let own_login = cached_own_login();
+    for msg in messages {
+        stats.record(msg, &emote_map);
+        giveaway.record(msg);
+        poll.record(msg);
+        recent.push_back(msg.clone());
+        if recent.len() > max_retained_messages() {
+            recent.pop_front();
         }
-    "#;
+        if !tab_data.muted.load(Ordering::Relaxed) && record_message_activity(msg, own_login.as_deref()) {
+            *tab_data.unread_mentions.locked() += 1;
+        }
+    }
+    drop(recent);
+    drop(poll);
+    drop(giveaway);
+    drop(stats);
+    // Replayed VODs and the preview channel aren't live chat worth keeping
+    if first.channel_login != DEMO_CHANNEL && tab_data.replay.locked().is_none() {
+        record_history(messages);
+    }
+}
 
-    webview.evaluate_javascript(
-        cleanup_js,
-        None,
-        None,
-        None::<&adw::gio::Cancellable>,
-        |_| {},
-    );
+// Adds mentions of the signed-in user and highlighted messages to the notification center,
+// returning whether the message was recorded
+fn record_message_activity(msg: &twitch_irc::message::PrivmsgMessage, own_login: Option<&str>) -> bool {
+    // Synthetic preview chat shouldn't end up in the history
+    if msg.channel_login == DEMO_CHANNEL {
+        return false;
+    }
+    let highlighted = msg
+        .source
+        .tags
+        .0
+        .get("msg-id")
+        .and_then(|value| value.as_deref())
+        == Some("highlighted-message")
+        || matches_highlight_rule(&msg.message_text);
+    let kind = if highlighted {
+        ActivityKind::Highlight
+    } else if own_login.is_some_and(|login| mentions(&msg.message_text, login)) {
+        ActivityKind::Mention
+    } else {
+        return false;
+    };
+    let mut event = ActivityEvent::new(kind, &msg.channel_login, &msg.message_text);
+    event.sender = Some(msg.sender.name.clone());
+    event.message_id = Some(msg.message_id.clone());
+    event.unread = true;
+    record_activity(event);
+    true
 }
 
-fn cleanup_all_webviews(tabs: &HashMap<String, Arc<TabData>>) {
-    println!("Cleaning up all WebViews");
-    for (tab_id, tab_data) in tabs.iter() {
-        println!("Cleaning up WebView for tab: {}", tab_id);
-        cleanup_webview(&tab_data.webview);
-        // Load blank page to force cleanup
-        tab_data.webview.load_uri("about:blank");
+fn mentions(text: &str, login: &str) -> bool {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word.eq_ignore_ascii_case(login))
+}
+
+// Looks up account ages for chatters seen for the first time in this session
+fn queue_account_ages(
+    tab_data: &TabData,
+    messages: &[twitch_irc::message::PrivmsgMessage],
+    moderation: &ModerationSettings,
+) {
+    if messages.is_empty() || !moderation.show_account_age {
+        return;
+    }
+    let mut seen = tab_data.seen_chatters.locked();
+    for msg in messages {
+        if seen.insert(msg.sender.id.clone()) {
+            request_account_age(&msg.sender.id, &msg.message_id, &tab_data.account_age_tx);
+        }
     }
-    println!("All WebViews cleaned up");
 }
 
-fn disconnect_tab_handler(tab_data: &Arc<TabData>) {
-    println!("Disconnecting tab...");
-    *tab_data.connection_state.lock().unwrap() = ConnectionState::Disconnected;
-    tab_data.client_state.lock().unwrap().disconnect();
+fn apply_account_ages(tab_data: &TabData, is_active_tab: bool) {
+    let ages: Vec<AccountAge> = tab_data.account_age_rx.locked().try_iter().collect();
+    if ages.is_empty() {
+        return;
+    }
 
-    // Aggressive cleanup before clearing WebView
-    cleanup_webview(&tab_data.webview);
+    let mut calls = PageCalls::new();
+    {
+        let mut buf = tab_data.message_buffer.locked();
+        for age in &ages {
+            let marker = format!(r#"data-msg-id="{}""#, glib::markup_escape_text(&age.message_id));
+            buf.update_newest(&marker, |html| insert_account_age_html(html, age.created_at));
+            if is_active_tab {
+                calls.push(
+                    "appendAccountAge",
+                    &[age.message_id.to_variant(), account_age_html(age.created_at).to_variant()],
+                );
+            }
+        }
+    }
 
-    // Load a data URI to clear content without fetching anything
-    tab_data.webview.load_uri("about:blank");
+    calls.run(&tab_data.webview, "Error injecting account ages");
+}
 
-    // Alternatively, reload empty HTML to force a fresh context
-    tab_data.webview.load_html("<!DOCTYPE html><html><head></head><body></body></html>", None);
+fn queue_translations(
+    tab_data: &TabData,
+    messages: &[twitch_irc::message::PrivmsgMessage],
+    emote_map: &HashMap<String, (String, bool)>,
+    translation: &mut Option<TranslationConfig>,
+) {
+    if messages.is_empty() || !tab_data.translate_enabled.load(Ordering::Relaxed) {
+        return;
+    }
+    let config = translation.get_or_insert_with(get_translation_config);
+    for msg in messages {
+        // Emote names only confuse language detection
+        let text = msg
+            .message_text
+            .split_whitespace()
+            .filter(|word| find_emote(emote_map, word).is_none())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if is_translatable(&text) {
+            request_translation(config, &msg.message_id, &text, &tab_data.translation_tx);
+        }
+    }
+}
 
-    tab_data.stack.set_visible_child_name("placeholder");
-    tab_data.page.set_title("New Tab");
-    *tab_data.channel_name.lock().unwrap() = None;
+fn apply_translations(tab_data: &TabData, is_active_tab: bool) {
+    let translated: Vec<TranslatedMessage> = tab_data.translation_rx.locked().try_iter().collect();
+    if translated.is_empty() {
+        return;
+    }
 
-    // Drain message queue
-    let rx = tab_data.rx.lock().unwrap();
-    while rx.try_recv().is_ok() {
-        // Discard messages
+    let mut calls = PageCalls::new();
+    {
+        let mut buf = tab_data.message_buffer.locked();
+        for item in &translated {
+            let marker = format!(r#"data-msg-id="{}""#, glib::markup_escape_text(&item.message_id));
+            buf.update_newest(&marker, |html| insert_translation_html(html, &item.translation));
+            if is_active_tab {
+                calls.push(
+                    "appendTranslation",
+                    &[item.message_id.to_variant(), translation_html(&item.translation).to_variant()],
+                );
+            }
+        }
     }
-    drop(rx);
+
+    // Inactive tabs pick the translations up from the buffer when selected
+    calls.run(&tab_data.webview, "Error injecting translations");
 }
 
 fn build_ui(app: &Application) {
+    // Launching again while running, e.g. in the background, brings the window back
+    if let Some(window) = app.windows().first() {
+        window.present();
+        return;
+    }
+
     // Create a shared WebContext to limit process creation and resource usage
     // This becomes the default context for all WebViews in this process
-    let web_context = webkit6::WebContext::new();
-    web_context.set_automation_allowed(false);
-    web_context.set_cache_model(webkit6::CacheModel::WebBrowser);
-    web_context.set_spell_checking_enabled(false);
-    println!("WebKit HTTP cache enabled with WebBrowser model for emote caching");
+    let web_context = create_web_context();
+    configure_history(&get_history_settings());
+    configure_message_budget(&get_message_budget_settings());
+    configure_network(&get_network_settings());
+    configure_rules(&get_rule_settings());
+    configure_emote_matching(&get_emote_settings());
+    configure_quiet_hours(&get_quiet_hours_settings());
+    if get_startup_settings().prefetch_emotes && benchmark_config().is_none() {
+        prefetch_starred_emotes();
+    }
 
+    // Names the profile, so two instances side by side can be told apart
+    let title = match profile() {
+        Some(name) => format!("Admiral ({})", name),
+        None => "Admiral".to_string(),
+    };
     let window = ApplicationWindow::builder()
         .application(app)
-        .title("Admiral")
+        .title(title)
         .default_width(700)
         .default_height(600)
         .build();
@@ -1178,15 +4189,11 @@ fn build_ui(app: &Application) {
         .autohide(true)
         .build();
     tab_bar.add_css_class("inline");
+    add_tab_bar_gestures(&tab_bar, &tab_view);
 
     let header = HeaderBar::builder()
         .build();
 
-    let favorites_button = GtkButton::builder()
-        .icon_name("non-starred-symbolic")
-        .tooltip_text("Favorites")
-        .build();
-
     let popover = Popover::builder()
         .autohide(true)
         .build();
@@ -1215,6 +4222,28 @@ fn build_ui(app: &Application) {
         color_entry.set_text(&color);
     }
 
+    // Previews what's typed before it's applied to the chats
+    let color_swatch = gtk::DrawingArea::builder()
+        .content_width(20)
+        .content_height(20)
+        .valign(gtk::Align::Center)
+        .build();
+    let entry_for_swatch = color_entry.downgrade();
+    color_swatch.set_draw_func(move |area, cr, width, height| {
+        let text = entry_for_swatch.upgrade().map(|entry| entry.text().to_string()).unwrap_or_default();
+        let (width, height) = (width as f64, height as f64);
+        cr.arc(width / 2.0, height / 2.0, width.min(height) / 2.0 - 1.0, 0.0, std::f64::consts::TAU);
+        if let Some(rgba) = Some(text.as_str()).filter(|text| validate_hex_color(text)).and_then(|text| gdk::RGBA::parse(text).ok()) {
+            cr.set_source_rgb(rgba.red() as f64, rgba.green() as f64, rgba.blue() as f64);
+            let _ = cr.fill_preserve();
+        }
+        let outline = area.color();
+        cr.set_source_rgba(outline.red() as f64, outline.green() as f64, outline.blue() as f64, 0.3);
+        cr.set_line_width(1.0);
+        let _ = cr.stroke();
+    });
+
+    color_row.add_suffix(&color_swatch);
     color_row.add_suffix(&color_entry);
     popover_content.append(&color_row);
 
@@ -1235,8 +4264,16 @@ fn build_ui(app: &Application) {
     add_favorite_button.add_css_class("circular");
     add_favorite_button.add_css_class("suggested-action");
 
+    let sort_favorites_button = gtk::ToggleButton::builder()
+        .icon_name("view-sort-ascending-symbolic")
+        .tooltip_text("Keep favorites sorted alphabetically")
+        .active(load_favorites().auto_sort)
+        .build();
+    sort_favorites_button.add_css_class("flat");
+
     let favorites_entry_box = Box::new(Orientation::Horizontal, 6);
     favorites_entry_box.append(&favorites_entry);
+    favorites_entry_box.append(&sort_favorites_button);
     favorites_entry_box.append(&add_favorite_button);
     popover_content.append(&favorites_entry_box);
 
@@ -1254,14 +4291,71 @@ fn build_ui(app: &Application) {
     favorites_scrolled.set_margin_top(6);
     popover_content.append(&favorites_scrolled);
 
+    // Bulk actions on the channels ticked in selection mode
+    let select_favorites_button = gtk::ToggleButton::builder()
+        .icon_name("selection-mode-symbolic")
+        .tooltip_text("Select channels")
+        .build();
+    select_favorites_button.add_css_class("flat");
+    let open_selected_button = GtkButton::with_label("Open");
+    let star_selected_button = GtkButton::builder()
+        .icon_name("starred-symbolic")
+        .tooltip_text("Star selected")
+        .build();
+    let unstar_selected_button = GtkButton::builder()
+        .icon_name("non-starred-symbolic")
+        .tooltip_text("Unstar selected")
+        .build();
+    let remove_selected_button = GtkButton::builder()
+        .icon_name("user-trash-symbolic")
+        .tooltip_text("Remove selected from favorites")
+        .build();
+    remove_selected_button.add_css_class("destructive-action");
+    let open_starred_button = GtkButton::builder()
+        .label("Open Starred")
+        .tooltip_text("Open every starred channel in a tab")
+        .action_name("win.open-starred")
+        .build();
+    let bulk_box = Box::new(Orientation::Horizontal, 6);
+    bulk_box.set_visible(false);
+    bulk_box.append(&open_selected_button);
+    bulk_box.append(&star_selected_button);
+    bulk_box.append(&unstar_selected_button);
+    bulk_box.append(&remove_selected_button);
+    let favorites_action_box = Box::new(Orientation::Horizontal, 6);
+    favorites_action_box.append(&select_favorites_button);
+    favorites_action_box.append(&bulk_box);
+    let spacer = Box::new(Orientation::Horizontal, 0);
+    spacer.set_hexpand(true);
+    favorites_action_box.append(&spacer);
+    favorites_action_box.append(&open_starred_button);
+    popover_content.append(&favorites_action_box);
+
+    select_favorites_button.connect_toggled(clone!(
+        #[strong]
+        bulk_box,
+        move |button| {
+            set_favorites_selection_mode(button.is_active());
+            bulk_box.set_visible(button.is_active());
+        }
+    ));
+    // Leaving the popover ends selection mode
+    popover.connect_closed(clone!(
+        #[strong]
+        select_favorites_button,
+        move |_| {
+            select_favorites_button.set_active(false);
+        }
+    ));
+
     popover.set_child(Some(&popover_content));
 
-    let favorites_button_clone = favorites_button.clone();
-    let popover_clone = popover.clone();
-    favorites_button.connect_clicked(move |_| {
-        popover_clone.set_parent(&favorites_button_clone);
-        popover_clone.popup();
-    });
+    // The menu button owns the popover, so it's parented once and stays usable
+    let favorites_button = gtk::MenuButton::builder()
+        .icon_name("non-starred-symbolic")
+        .tooltip_text("Favorites")
+        .popover(&popover)
+        .build();
 
     header.pack_start(&favorites_button);
 
@@ -1275,6 +4369,45 @@ fn build_ui(app: &Application) {
         .tooltip_text("Tab overview")
         .build();
 
+    let primary_menu = adw::gio::Menu::new();
+    let copy_section = adw::gio::Menu::new();
+    copy_section.append(Some("Copy Chat as Text"), Some("win.copy-chat-text"));
+    copy_section.append(Some("Copy Chat as HTML"), Some("win.copy-chat-html"));
+    copy_section.append(Some("Copy Chat as Image"), Some("win.copy-chat-image"));
+    copy_section.append(Some("Export Chat…"), Some("win.export-chat"));
+    primary_menu.append_section(None, &copy_section);
+    let emote_section = adw::gio::Menu::new();
+    emote_section.append(Some("Refresh Emotes"), Some("win.refresh-emotes"));
+    emote_section.append(Some("Emote Info…"), Some("win.emote-info"));
+    primary_menu.append_section(None, &emote_section);
+    let moderation_section = adw::gio::Menu::new();
+    moderation_section.append(Some("Mass Moderation…"), Some("win.mass-moderation"));
+    primary_menu.append_section(None, &moderation_section);
+    let quiet_section = adw::gio::Menu::new();
+    quiet_section.append(Some("Do Not Disturb"), Some("win.do-not-disturb"));
+    primary_menu.append_section(None, &quiet_section);
+    primary_menu.append(Some("Preferences"), Some("win.preferences"));
+    let menu_button = gtk::MenuButton::builder()
+        .icon_name("open-menu-symbolic")
+        .tooltip_text("Main menu")
+        .menu_model(&primary_menu)
+        .primary(true)
+        .build();
+
+    let activity_button = gtk::ToggleButton::builder()
+        .icon_name("preferences-system-notifications-symbolic")
+        .tooltip_text("Activity")
+        .build();
+
+    let pause_button = gtk::ToggleButton::builder()
+        .icon_name("media-playback-pause-symbolic")
+        .tooltip_text("Pause chat in all tabs")
+        .action_name("win.pause-chat")
+        .build();
+
+    header.pack_end(&menu_button);
+    header.pack_end(&activity_button);
+    header.pack_end(&pause_button);
     header.pack_end(&add_tab_button);
     header.pack_end(&overview_button);
 
@@ -1285,12 +4418,80 @@ fn build_ui(app: &Application) {
         .show_end_title_buttons(false)
         .build();
 
+    let offline_banner = adw::Banner::new("Offline. Chats pick up again when the network is back.");
+
+    let tabs: Arc<Mutex<HashMap<String, Arc<TabData>>>> = Arc::new(Mutex::new(HashMap::new()));
+    let detached_views: DetachedViews = Rc::new(RefCell::new(Vec::new()));
+
+    let main_pane = Box::new(Orientation::Vertical, 0);
+    main_pane.append(&tab_bar);
+    main_pane.append(&tab_overview);
+    let (side_pane, side_view) = build_side_pane(app, &tabs, &detached_views);
+    let panes = gtk::Paned::builder()
+        .orientation(Orientation::Horizontal)
+        .start_child(&main_pane)
+        .end_child(&side_pane)
+        .shrink_start_child(false)
+        .shrink_end_child(false)
+        .vexpand(true)
+        .build();
+    // Opening the side pane splits the window evenly
+    let panes_clone = panes.clone();
+    side_view.connect_page_attached(move |side_view, _, _| {
+        if side_view.n_pages() == 1 {
+            panes_clone.set_position(panes_clone.width() / 2);
+        }
+    });
+
     let content = Box::new(Orientation::Vertical, 0);
     content.append(&header);
-    content.append(&tab_bar);
-    content.append(&tab_overview);
+    content.append(&offline_banner);
+    content.append(&panes);
+    // While set, no tab draws new messages; chats stay joined and buffer as if in the background
+    let rendering_paused = Rc::new(std::cell::Cell::new(false));
+
+    // Dragging a tab out of the bar opens it in a window of its own
+    let app_for_windows = app.clone();
+    let tabs_for_windows = tabs.clone();
+    let detached_for_windows = detached_views.clone();
+    tab_view.connect_create_window(move |_| {
+        Some(create_tab_window(&app_for_windows, &tabs_for_windows, &detached_for_windows))
+    });
 
-    let tabs: Arc<Mutex<HashMap<String, Arc<TabData>>>> = Arc::new(Mutex::new(HashMap::new()));
+    // Vim-style command bar, opened with ':'
+    let command_entry = Entry::builder()
+        .placeholder_text(":join <channel>, :close, :filter add <regex>, :help")
+        .hexpand(true)
+        .build();
+    let command_status = gtk::Label::new(None);
+    command_status.add_css_class("dim-label");
+    command_status.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    let command_box = Box::new(Orientation::Horizontal, 6);
+    command_box.set_margin_top(6);
+    command_box.set_margin_bottom(6);
+    command_box.set_margin_start(6);
+    command_box.set_margin_end(6);
+    command_box.append(&command_entry);
+    command_box.append(&command_status);
+    let command_revealer = gtk::Revealer::builder()
+        .transition_type(gtk::RevealerTransitionType::SlideUp)
+        .child(&command_box)
+        .build();
+    content.append(&command_revealer);
+
+    let escape_controller = gtk::EventControllerKey::new();
+    escape_controller.connect_key_pressed(clone!(
+        #[strong]
+        command_revealer,
+        move |_, key, _, _| {
+            if key == gdk::Key::Escape {
+                command_revealer.set_reveal_child(false);
+                return glib::Propagation::Stop;
+            }
+            glib::Propagation::Proceed
+        }
+    ));
+    command_entry.add_controller(escape_controller);
 
     add_tab_button.connect_clicked(clone!(
         #[strong]
@@ -1307,26 +4508,78 @@ fn build_ui(app: &Application) {
     let tabs_clone = tabs.clone();
     let favorites_list_clone = favorites_list.clone();
     let favorites_entry_clone = favorites_entry.clone();
-    let tab_view_for_color = tab_view.clone();
     let tabs_for_color = tabs.clone();
 
-    // Color entry change handler
+    // Typing only updates the swatch; the color is saved and applied once typing pauses,
+    // on Enter, or when the popover closes
+    let color_commit: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    let commit_color = Rc::new(clone!(
+        #[strong]
+        tabs_for_color,
+        #[strong]
+        color_commit,
+        #[weak]
+        color_entry,
+        move || {
+            if let Some(source) = color_commit.borrow_mut().take() {
+                source.remove();
+            }
+            let color_text = color_entry.text().to_string();
+            let color = if color_text.is_empty() || color_text == "#" {
+                None
+            } else if validate_hex_color(&color_text) {
+                Some(color_text)
+            } else {
+                return;
+            };
+            if color != get_background_color() {
+                set_background_color(color.as_deref());
+                apply_background_color_to_tabs(&tabs_for_color, color.as_deref());
+            }
+        }
+    ));
+
     color_entry.connect_changed(clone!(
         #[strong]
-        tab_view_for_color,
+        color_commit,
         #[strong]
-        tabs_for_color,
+        commit_color,
+        #[weak]
+        color_swatch,
         move |entry| {
-            let color_text = entry.text().to_string();
-            if color_text.is_empty() || color_text == "#" {
-                set_background_color(None);
-                apply_background_color_to_tabs(&tab_view_for_color, &tabs_for_color, None);
-            } else if validate_hex_color(&color_text) {
-                set_background_color(Some(&color_text));
-                apply_background_color_to_tabs(&tab_view_for_color, &tabs_for_color, Some(&color_text));
+            color_swatch.queue_draw();
+            let text = entry.text();
+            let valid = text.is_empty() || text == "#" || validate_hex_color(&text);
+            // Still being typed until it's as long as a hex code can be
+            if valid || text.len() < 7 {
+                entry.remove_css_class("error");
+            } else {
+                entry.add_css_class("error");
+            }
+            if let Some(source) = color_commit.borrow_mut().take() {
+                source.remove();
+            }
+            if valid {
+                let commit_color = commit_color.clone();
+                let color_commit_for_timer = color_commit.clone();
+                *color_commit.borrow_mut() = Some(glib::timeout_add_local_once(std::time::Duration::from_millis(500), move || {
+                    // The source is done once it fires, so it mustn't be removed again
+                    color_commit_for_timer.borrow_mut().take();
+                    commit_color();
+                }));
             }
         }
     ));
+    color_entry.connect_activate(clone!(
+        #[strong]
+        commit_color,
+        move |_| commit_color()
+    ));
+    popover.connect_closed(clone!(
+        #[strong]
+        commit_color,
+        move |_| commit_color()
+    ));
 
     add_favorite_button.connect_clicked(clone!(
         #[strong]
@@ -1344,7 +4597,7 @@ fn build_ui(app: &Application) {
             if !channel.is_empty() {
                 add_favorite(&channel);
                 favorites_entry_clone.set_text("");
-                load_and_display_favorites(&favorites_list_clone, &favorites_entry_clone, &favorites_list_clone, &tab_view, &tabs_clone, &web_context);
+                load_and_display_favorites(&favorites_list_clone, &tab_view, &tabs_clone, &web_context);
             }
         }
     ));
@@ -1355,9 +4608,53 @@ fn build_ui(app: &Application) {
         move |_| {
             add_favorite_button.emit_clicked();
         }
-    ));
+    ));
+
+    load_and_display_favorites(&favorites_list, &tab_view, &tabs, &web_context);
+
+    let tab_view_clone = tab_view.clone();
+    let tabs_clone = tabs.clone();
+    let web_context_clone = web_context.clone();
+    let select_clone = select_favorites_button.clone();
+    open_selected_button.connect_clicked(move |_| {
+        open_channel_tabs(&selected_favorites(), &tab_view_clone, &tabs_clone, &web_context_clone);
+        select_clone.set_active(false);
+    });
+
+    for (button, starred) in [(&star_selected_button, true), (&unstar_selected_button, false)] {
+        let favorites_list_clone = favorites_list.clone();
+        let tab_view_clone = tab_view.clone();
+        let tabs_clone = tabs.clone();
+        let web_context_clone = web_context.clone();
+        let select_clone = select_favorites_button.clone();
+        button.connect_clicked(move |_| {
+            set_starred(&selected_favorites(), starred);
+            select_clone.set_active(false);
+            load_and_display_favorites(&favorites_list_clone, &tab_view_clone, &tabs_clone, &web_context_clone);
+        });
+    }
+
+    let favorites_list_clone = favorites_list.clone();
+    let tab_view_clone = tab_view.clone();
+    let tabs_clone = tabs.clone();
+    let web_context_clone = web_context.clone();
+    let select_clone = select_favorites_button.clone();
+    remove_selected_button.connect_clicked(move |_| {
+        for channel in selected_favorites() {
+            remove_favorite(&channel);
+        }
+        select_clone.set_active(false);
+        load_and_display_favorites(&favorites_list_clone, &tab_view_clone, &tabs_clone, &web_context_clone);
+    });
 
-    load_and_display_favorites(&favorites_list, &favorites_entry, &favorites_list, &tab_view, &tabs, &web_context);
+    let favorites_list_clone = favorites_list.clone();
+    let tab_view_clone = tab_view.clone();
+    let tabs_clone = tabs.clone();
+    let web_context_clone = web_context.clone();
+    sort_favorites_button.connect_toggled(move |button| {
+        set_favorites_auto_sort(button.is_active());
+        load_and_display_favorites(&favorites_list_clone, &tab_view_clone, &tabs_clone, &web_context_clone);
+    });
 
     overview_button.connect_clicked(clone!(
         #[strong]
@@ -1367,234 +4664,137 @@ fn build_ui(app: &Application) {
         }
     ));
 
-    create_new_tab("New Tab", &tab_view, &tabs, &web_context);
+    let startup = get_startup_settings();
+    if startup.status_icon {
+        set_status_icon_visible(app, true);
+    }
+    let behavior = if benchmark_config().is_some() {
+        StartupBehavior::BlankTab
+    } else {
+        startup.behavior
+    };
+    let startup_channels = match behavior {
+        StartupBehavior::BlankTab => Vec::new(),
+        StartupBehavior::RestoreSession => startup.last_session,
+        StartupBehavior::ConnectStarred | StartupBehavior::Background => load_favorites().starred,
+    };
+    if startup_channels.is_empty() {
+        create_new_tab("New Tab", &tab_view, &tabs, &web_context);
+    }
+    for channel in &startup_channels {
+        open_channel_tab(channel, &tab_view, &tabs, &web_context);
+    }
+
+    // Keeps the crash handler's list of open channels current
+    let tab_view_crash = tab_view.clone();
+    let tabs_crash = tabs.clone();
+    glib::timeout_add_seconds_local(CRASH_CHANNELS_INTERVAL_SECS, move || {
+        remember_open_channels(session_channels(&tab_view_crash, &tabs_crash.locked()));
+        glib::ControlFlow::Continue
+    });
+
+    // Channels opened by a schedule, mapped to whether Admiral opened the tab itself
+    let scheduled_channels: Rc<RefCell<HashMap<String, bool>>> = Rc::new(RefCell::new(HashMap::new()));
+    apply_schedules(&tab_view, &tabs, &web_context, &scheduled_channels);
+    let tab_view_schedule = tab_view.clone();
+    let tabs_schedule = tabs.clone();
+    let web_context_schedule = web_context.clone();
+    glib::timeout_add_seconds_local(SCHEDULE_CHECK_INTERVAL_SECS, move || {
+        apply_schedules(&tab_view_schedule, &tabs_schedule, &web_context_schedule, &scheduled_channels);
+        glib::ControlFlow::Continue
+    });
 
     // Apply any saved background color to existing tabs
     if let Some(color) = get_background_color() {
-        apply_background_color_to_tabs(&tab_view, &tabs, Some(&color));
+        apply_background_color_to_tabs(&tabs, Some(&color));
     }
 
-    // Apply GTK theme colors to emote popovers and listen for theme changes
-    apply_theme_to_popovers(&tabs, &window);
-    let tabs_for_theme = tabs.clone();
-    let window_for_theme = window.clone();
-    adw::StyleManager::default().connect_color_scheme_notify(move |_| {
-        apply_theme_to_popovers(&tabs_for_theme, &window_for_theme);
-    });
+    // Apply GTK theme colors to the chats and follow theme changes. The window's
+    // style is only recomputed after the style manager notifies, hence the idle.
+    apply_theme_to_tabs(&tabs, &window);
+    let style_manager = adw::StyleManager::default();
+    let on_theme_changed = Rc::new(clone!(
+        #[strong]
+        tabs,
+        #[weak]
+        window,
+        move || {
+            glib::idle_add_local_once(clone!(
+                #[strong]
+                tabs,
+                #[weak]
+                window,
+                move || apply_theme_to_tabs(&tabs, &window)
+            ));
+        }
+    ));
+    let on_dark_changed = on_theme_changed.clone();
+    style_manager.connect_dark_notify(move |_| on_dark_changed());
+    let on_contrast_changed = on_theme_changed.clone();
+    style_manager.connect_high_contrast_notify(move |_| on_contrast_changed());
+    style_manager.connect_accent_color_rgba_notify(move |_| on_theme_changed());
 
     // Flush pending messages when switching tabs and check WebView health
     let tabs_for_selection = tabs.clone();
-    let tab_view_for_selection = tab_view.clone();
-    tab_view.connect_selected_page_notify(move |_| {
-        if let Some(selected_page) = tab_view_for_selection.selected_page() {
-            let tabs_map = tabs_for_selection.lock().unwrap();
-            for (_, tab_data) in tabs_map.iter() {
-                if tab_data.page == selected_page {
-                    {
-                        let mut pending = tab_data.pending_messages.lock().unwrap();
-                        pending.clear();
-                    }
-
-                    let buf = tab_data.message_buffer.lock().unwrap();
-                    if buf.is_empty() {
-                        drop(buf);
-                        continue;
-                    }
-                    let all_html: String = buf.iter().cloned().collect::<Vec<_>>().join("\n");
-                    drop(buf);
-
-                    let escaped_html = escape_js_string(&all_html);
-                    let js_code = format!(
-                        r#"if (typeof replaceAllMessages === 'function') {{ replaceAllMessages('{}'); }}"#,
-                        escaped_html
-                    );
-                    let webview_flush = tab_data.webview.clone();
-                    let last_js_execution = tab_data.last_js_execution.clone();
-                    webview_flush.evaluate_javascript(
-                        &js_code,
-                        None,
-                        None,
-                        None::<&adw::gio::Cancellable>,
-                        move |result| {
-                            match result {
-                                Ok(_) => {
-                                    *last_js_execution.lock().unwrap() = Instant::now();
-                                }
-                                Err(e) => {
-                                    eprintln!("Error restoring messages on tab switch: {}", e);
-                                }
-                            }
-                        },
-                    );
-                }
-            }
+    tab_view.connect_selected_page_notify(move |tab_view| {
+        if let Some(tab_data) = tab_view.selected_page().and_then(|page| tab_for_page(&tabs_for_selection, &page)) {
+            restore_chat_view(&tab_data);
         }
     });
 
     let tabs_for_close = tabs.clone();
     tab_view.connect_close_page(move |_tab_view, page| {
         println!("Tab close requested");
-        let tabs_map = tabs_for_close.lock().unwrap();
-        let mut tab_id_to_remove = None;
-        for (tab_id, tab_data) in tabs_map.iter() {
-            if &tab_data.page == page {
-                println!("Found tab to disconnect: {}", tab_id);
-                disconnect_tab_handler(tab_data);
-                tab_id_to_remove = Some(tab_id.clone());
-                break;
-            }
-        }
-        drop(tabs_map);
-        if let Some(tab_id) = tab_id_to_remove {
-            tabs_for_close.lock().unwrap().remove(&tab_id);
-            println!("Removed tab from HashMap: {}", tab_id);
-        }
+        remove_tab(&tabs_for_close, page);
         glib::Propagation::Proceed
     });
 
+    // Tabs are woken by whatever feeds them (new messages, finished renders, lookups)
+    // rather than polled, so quiet tabs cost nothing
     let tabs_clone = tabs.clone();
     let tab_view_for_processing = tab_view.clone();
-    glib::timeout_add_local(std::time::Duration::from_millis(200), move || {
-        let tabs_map = tabs_clone.lock().unwrap();
-
-        const MAX_BATCH_SIZE: usize = 30;
-        const MAX_DRAIN_PER_TAB: usize = 50;
-        const MAX_PENDING_BUFFER: usize = 2000;
-        const MAX_MESSAGE_BUFFER: usize = 2000;
-
-        if let Some(selected_page) = tab_view_for_processing.selected_page() {
-            for (_, tab_data) in tabs_map.iter() {
-                let is_active_tab = tab_data.page == selected_page;
-
-                if is_active_tab {
-                    let last_execution = *tab_data.last_js_execution.lock().unwrap();
-                    if last_execution.elapsed() < std::time::Duration::from_millis(30) {
-                        continue;
-                    }
-
-                    let mut messages_to_process = Vec::new();
-                    let rx = tab_data.rx.lock().unwrap();
-
-                    while messages_to_process.len() < MAX_BATCH_SIZE {
-                        match rx.try_recv() {
-                            Ok(msg) => messages_to_process.push(msg),
-                            Err(_) => break,
-                        }
-                    }
-                    drop(rx);
-
-                    if !messages_to_process.is_empty() {
-                        let webview = tab_data.webview.clone();
-                        let message_buffer = tab_data.message_buffer.clone();
-                        let channel_id_for_closure = messages_to_process
-                            .first()
-                            .map(|msg| msg.channel_id.clone());
-                        let last_js_execution = tab_data.last_js_execution.clone();
-
-                        if let Some(channel_id_str) = channel_id_for_closure {
-                            let emote_map = get_emote_map(&channel_id_str);
-                            let mut html_content = String::new();
-                            for msg in &messages_to_process {
-                                let msg_html = parse_message_html(msg, &emote_map);
-                                {
-                                    let mut buf = message_buffer.lock().unwrap();
-                                    buf.push_back(msg_html.clone());
-                                    if buf.len() > MAX_MESSAGE_BUFFER {
-                                        buf.pop_front();
-                                    }
-                                }
-                                html_content.push_str(&msg_html);
-                                html_content.push('\n');
-                            }
-
-                            let escaped_html = escape_js_string(&html_content);
-                            let js_code = format!(
-                                r#"if (typeof appendMessages === 'function') {{ appendMessages('{}'); }}"#,
-                                escaped_html
-                            );
-
-                            webview.evaluate_javascript(
-                                &js_code,
-                                None,
-                                None,
-                                None::<&adw::gio::Cancellable>,
-                                move |result| {
-                                    match result {
-                                        Ok(_) => {
-                                            *last_js_execution.lock().unwrap() = Instant::now();
-                                        }
-                                        Err(e) => {
-                                            eprintln!("Error running JS: {}", e);
-                                        }
-                                    }
-                                },
-                            );
-                        }
-                    }
-                } else {
-                    let mut messages_to_buffer = Vec::new();
-                    {
-                        let rx = tab_data.rx.lock().unwrap();
-                        while messages_to_buffer.len() < MAX_DRAIN_PER_TAB {
-                            match rx.try_recv() {
-                                Ok(msg) => messages_to_buffer.push(msg),
-                                Err(_) => break,
-                            }
-                        }
-                    }
+    let detached_for_processing = detached_views.clone();
+    let paused_for_processing = rendering_paused.clone();
+    set_pump_handler(move |tab_id| {
+        // The tab may have closed since it was woken
+        let Some(tab_data) = tabs_clone.locked().get(tab_id).cloned() else {
+            return;
+        };
+        if tab_data.stalled.load(Ordering::Relaxed) {
+            return;
+        }
 
-                    if !messages_to_buffer.is_empty() {
-                        let channel_id_str = messages_to_buffer[0].channel_id.clone();
-                        let emote_map = get_emote_map(&channel_id_str);
-                        let mut buf = tab_data.message_buffer.lock().unwrap();
-                        let mut pending = tab_data.pending_messages.lock().unwrap();
-                        for msg in messages_to_buffer {
-                            let msg_html = parse_message_html(&msg, &emote_map);
-                            buf.push_back(msg_html);
-                            if buf.len() > MAX_MESSAGE_BUFFER {
-                                buf.pop_front();
-                            }
-                            if pending.len() >= MAX_PENDING_BUFFER {
-                                pending.pop_front();
-                            }
-                            pending.push_back(msg);
-                        }
-                    }
-                }
-            }
-        } else {
-            for (_, tab_data) in tabs_map.iter() {
-                let mut messages_to_buffer = Vec::new();
-                {
-                    let rx = tab_data.rx.lock().unwrap();
-                    while messages_to_buffer.len() < MAX_DRAIN_PER_TAB {
-                        match rx.try_recv() {
-                            Ok(msg) => messages_to_buffer.push(msg),
-                            Err(_) => break,
-                        }
-                    }
-                }
+        // Loaded on first use so passes with nothing to render don't touch the config file
+        let mut bot_settings: Option<BotSettings> = None;
+        let mut appearance: Option<AppearanceSettings> = None;
+        let mut moderation: Option<ModerationSettings> = None;
+        let mut translation: Option<TranslationConfig> = None;
+
+        // Lightweight tabs have no WebView to pace for or inject into
+        let is_active_tab = !paused_for_processing.get()
+            && !tab_data.lightweight.load(Ordering::Relaxed)
+            && shown_pages(&tab_view_for_processing, &detached_for_processing).contains(&tab_data.page);
+        // A panic here stops only this tab; the others keep updating
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pump_tab(&tab_data, is_active_tab, &mut bot_settings, &mut appearance, &mut moderation, &mut translation);
+        }));
+        if result.is_err() {
+            stall_tab(&tab_data);
+            return;
+        }
+        wake_for_leftover(&tab_data, is_active_tab);
+    });
 
-                if !messages_to_buffer.is_empty() {
-                    let channel_id_str = messages_to_buffer[0].channel_id.clone();
-                    let emote_map = get_emote_map(&channel_id_str);
-                    let mut buf = tab_data.message_buffer.lock().unwrap();
-                    let mut pending = tab_data.pending_messages.lock().unwrap();
-                    for msg in messages_to_buffer {
-                        let msg_html = parse_message_html(&msg, &emote_map);
-                        buf.push_back(msg_html);
-                        if buf.len() > MAX_MESSAGE_BUFFER {
-                            buf.pop_front();
-                        }
-                        if pending.len() >= MAX_PENDING_BUFFER {
-                            pending.pop_front();
-                        }
-                        pending.push_back(msg);
-                    }
-                }
+    // Keeps slow mode countdowns and the channel's restrictions current in the send inputs
+    // and the chat mode strips
+    let tabs_for_send = tabs.clone();
+    glib::timeout_add_local(std::time::Duration::from_secs(1), move || {
+        for tab_data in tabs_for_send.locked().values() {
+            if tab_data.send_bar.is_visible() {
+                update_send_controls(tab_data);
             }
+            update_room_modes(tab_data);
         }
-
         glib::ControlFlow::Continue
     });
 
@@ -1611,10 +4811,10 @@ fn build_ui(app: &Application) {
     glib::timeout_add_local(std::time::Duration::from_secs(300), move || {
         // Force garbage collection on all tabs to prevent memory leaks
         if let Some(selected_page) = tab_view_gc.selected_page() {
-            let tabs_map = tabs_gc.lock().unwrap();
+            let tabs_map = tabs_gc.locked();
             for (_, tab_data) in tabs_map.iter() {
                 // Only garbage collect the active tab to save CPU
-                if tab_data.page == selected_page {
+                if tab_data.page == selected_page && !tab_data.lightweight.load(Ordering::Relaxed) {
                     let webview = tab_data.webview.clone();
                     webview.evaluate_javascript(
                         r#"
@@ -1655,44 +4855,33 @@ fn build_ui(app: &Application) {
         if !win.is_active() {
             return;
         }
-        let last_focus = *focus_debounce_clone.lock().unwrap();
+        let last_focus = *focus_debounce_clone.locked();
         if last_focus.elapsed() < std::time::Duration::from_millis(100) {
             return;
         }
-        *focus_debounce_clone.lock().unwrap() = Instant::now();
-        let tabs_map = tabs_focus.lock().unwrap();
+        *focus_debounce_clone.locked() = Instant::now();
+        let tabs_map = tabs_focus.locked();
         for (_, tab_data) in tabs_map.iter() {
-            let conn_state = tab_data.connection_state.lock().unwrap();
+            let conn_state = tab_data.connection_state.locked();
             let is_connected = matches!(*conn_state, ConnectionState::Connected(_));
             drop(conn_state);
             if !is_connected {
                 continue;
             }
 
-            let buf = tab_data.message_buffer.lock().unwrap();
+            let buf = tab_data.message_buffer.locked();
             if buf.is_empty() {
                 drop(buf);
                 continue;
             }
-            let all_html: String = buf.iter().cloned().collect::<Vec<_>>().join("\n");
+            let all_html: String = buf.joined();
             drop(buf);
 
-            let escaped_html = escape_js_string(&all_html);
-            let js = format!(
-                "if (typeof replaceAllMessages === 'function') {{ replaceAllMessages('{}'); }}",
-                escaped_html
-            );
-            let webview = tab_data.webview.clone();
-            webview.evaluate_javascript(
-                &js,
-                None,
-                None,
-                None::<&adw::gio::Cancellable>,
-                |result| {
-                    if let Err(e) = result {
-                        eprintln!("Failed to re-inject messages on focus regain: {:?}", e);
-                    }
-                },
+            call_page(
+                &tab_data.webview,
+                "replaceAllMessages",
+                &[all_html.to_variant()],
+                "Failed to re-inject messages on focus regain",
             );
         }
     });
@@ -1706,6 +4895,15 @@ fn build_ui(app: &Application) {
     });
     window.add_action(&new_tab_action);
 
+    let open_starred_action = SimpleAction::new("open-starred", None);
+    let tab_view_clone = tab_view.clone();
+    let tabs_clone = tabs.clone();
+    let web_context_clone = web_context.clone();
+    open_starred_action.connect_activate(move |_, _| {
+        open_channel_tabs(&load_favorites().starred, &tab_view_clone, &tabs_clone, &web_context_clone);
+    });
+    window.add_action(&open_starred_action);
+
     let close_tab_action = SimpleAction::new("close-tab", None);
     let tab_view_close = tab_view.clone();
     close_tab_action.connect_activate(move |_, _| {
@@ -1715,17 +4913,592 @@ fn build_ui(app: &Application) {
     });
     window.add_action(&close_tab_action);
 
+    // Right-click menu on a tab; the actions apply to the tab it was opened on
+    let tab_menu = adw::gio::Menu::new();
+    let connection_section = adw::gio::Menu::new();
+    connection_section.append(Some("Reconnect"), Some("tab.reconnect"));
+    connection_section.append(Some("Disconnect"), Some("tab.disconnect"));
+    tab_menu.append_section(None, &connection_section);
+    let channel_section = adw::gio::Menu::new();
+    channel_section.append(Some("Add to Favorites"), Some("tab.add-favorite"));
+    channel_section.append(Some("Mute Mentions"), Some("tab.mute"));
+    channel_section.append(Some("Text-Only Mode"), Some("tab.lightweight"));
+    tab_menu.append_section(None, &channel_section);
+    let arrange_section = adw::gio::Menu::new();
+    arrange_section.append(Some("Duplicate"), Some("tab.duplicate"));
+    arrange_section.append(Some("Move to New Window"), Some("tab.move-to-window"));
+    arrange_section.append(Some("Close Other Tabs"), Some("tab.close-others"));
+    tab_menu.append_section(None, &arrange_section);
+    tab_view.set_menu_model(Some(&tab_menu));
+
+    let tab_actions = adw::gio::SimpleActionGroup::new();
+    let menu_page: Rc<RefCell<Option<TabPage>>> = Rc::new(RefCell::new(None));
+    let menu_tab = {
+        let menu_page = menu_page.clone();
+        let tabs = tabs.clone();
+        move || menu_page.borrow().as_ref().and_then(|page| tab_for_page(&tabs, page))
+    };
+    let menu_channel = {
+        let menu_tab = menu_tab.clone();
+        move || menu_tab().and_then(|tab_data| tab_data.channel_name.locked().clone())
+    };
+
+    let reconnect_action = SimpleAction::new("reconnect", None);
+    let menu_tab_clone = menu_tab.clone();
+    reconnect_action.connect_activate(move |_, _| {
+        if let Some(tab_data) = menu_tab_clone() {
+            reconnect_tab(&tab_data);
+        }
+    });
+    tab_actions.add_action(&reconnect_action);
+
+    let disconnect_action = SimpleAction::new("disconnect", None);
+    let menu_tab_clone = menu_tab.clone();
+    disconnect_action.connect_activate(move |_, _| {
+        if let Some(tab_data) = menu_tab_clone() {
+            disconnect_tab_handler(&tab_data);
+        }
+    });
+    tab_actions.add_action(&disconnect_action);
+
+    let add_favorite_action = SimpleAction::new("add-favorite", None);
+    let menu_channel_clone = menu_channel.clone();
+    let favorites_list_for_menu = favorites_list.clone();
+    let tab_view_clone = tab_view.clone();
+    let tabs_clone = tabs.clone();
+    let web_context_clone = web_context.clone();
+    add_favorite_action.connect_activate(move |_, _| {
+        if let Some(channel) = menu_channel_clone() {
+            add_favorite(&channel);
+            load_and_display_favorites(&favorites_list_for_menu, &tab_view_clone, &tabs_clone, &web_context_clone);
+        }
+    });
+    tab_actions.add_action(&add_favorite_action);
+
+    let mute_action = SimpleAction::new_stateful("mute", None, &false.to_variant());
+    let menu_tab_clone = menu_tab.clone();
+    mute_action.connect_activate(move |action, _| {
+        if let Some(tab_data) = menu_tab_clone() {
+            let muted = !tab_data.muted.load(Ordering::Relaxed);
+            tab_data.muted.store(muted, Ordering::Relaxed);
+            if muted {
+                *tab_data.unread_mentions.locked() = 0;
+            }
+            action.set_state(&muted.to_variant());
+        }
+    });
+    tab_actions.add_action(&mute_action);
+
+    // Remembered for the channel, so it opens the same way next time
+    let lightweight_action = SimpleAction::new_stateful("lightweight", None, &false.to_variant());
+    let menu_tab_clone = menu_tab.clone();
+    lightweight_action.connect_activate(move |action, _| {
+        if let Some(tab_data) = menu_tab_clone() {
+            let lightweight = !tab_data.lightweight.load(Ordering::Relaxed);
+            set_tab_lightweight(&tab_data, lightweight);
+            let channel = tab_data.channel_name.locked().clone();
+            if let Some(channel) = channel.filter(|channel| channel != DEMO_CHANNEL) {
+                set_lightweight_channel(&channel, lightweight);
+            }
+            action.set_state(&lightweight.to_variant());
+        }
+    });
+    tab_actions.add_action(&lightweight_action);
+
+    let duplicate_action = SimpleAction::new("duplicate", None);
+    let menu_channel_clone = menu_channel.clone();
+    let tab_view_clone = tab_view.clone();
+    let tabs_clone = tabs.clone();
+    let web_context_clone = web_context.clone();
+    duplicate_action.connect_activate(move |_, _| {
+        if let Some(channel) = menu_channel_clone() {
+            open_channel_tab(&channel, &tab_view_clone, &tabs_clone, &web_context_clone);
+        }
+    });
+    tab_actions.add_action(&duplicate_action);
+
+    let move_to_window_action = SimpleAction::new("move-to-window", None);
+    let menu_page_clone = menu_page.clone();
+    let tab_view_clone = tab_view.clone();
+    let tabs_clone = tabs.clone();
+    let app_clone = app.clone();
+    let detached_clone = detached_views.clone();
+    move_to_window_action.connect_activate(move |_, _| {
+        if let Some(page) = menu_page_clone.borrow().clone() {
+            let new_view = create_tab_window(&app_clone, &tabs_clone, &detached_clone);
+            tab_view_clone.transfer_page(&page, &new_view, 0);
+        }
+    });
+    tab_actions.add_action(&move_to_window_action);
+
+    let close_others_action = SimpleAction::new("close-others", None);
+    let menu_page_clone = menu_page.clone();
+    let tab_view_clone = tab_view.clone();
+    close_others_action.connect_activate(move |_, _| {
+        if let Some(page) = menu_page_clone.borrow().clone() {
+            // Pinned tabs stay, as with the tab view's own bulk closing
+            tab_view_clone.close_other_pages(&page);
+        }
+    });
+    tab_actions.add_action(&close_others_action);
+
+    let tabs_clone = tabs.clone();
+    tab_view.connect_setup_menu(move |tab_view, page| {
+        *menu_page.borrow_mut() = page.cloned();
+        let Some(page) = page else {
+            return;
+        };
+        let tab_data = tab_for_page(&tabs_clone, page);
+        let channel = tab_data.as_ref().and_then(|tab_data| tab_data.channel_name.locked().clone());
+        let is_favorite = channel.as_ref().is_some_and(|channel| load_favorites().channels.contains(channel));
+        let muted = tab_data.as_ref().is_some_and(|tab_data| tab_data.muted.load(Ordering::Relaxed));
+        let lightweight = tab_data.as_ref().is_some_and(|tab_data| tab_data.lightweight.load(Ordering::Relaxed));
+        let has_others = tab_view.n_pages() > 1;
+        reconnect_action.set_enabled(channel.is_some());
+        disconnect_action.set_enabled(channel.is_some());
+        add_favorite_action.set_enabled(channel.is_some() && !is_favorite);
+        mute_action.set_enabled(tab_data.is_some());
+        mute_action.set_state(&muted.to_variant());
+        lightweight_action.set_enabled(tab_data.is_some());
+        lightweight_action.set_state(&lightweight.to_variant());
+        duplicate_action.set_enabled(channel.is_some());
+        move_to_window_action.set_enabled(has_others);
+        close_others_action.set_enabled(has_others);
+    });
+    window.insert_action_group("tab", Some(&tab_actions));
+
+    for (action_name, format) in [
+        ("copy-chat-text", ChatCopyFormat::Text),
+        ("copy-chat-html", ChatCopyFormat::Html),
+        ("copy-chat-image", ChatCopyFormat::Image),
+    ] {
+        let copy_action = SimpleAction::new(action_name, None);
+        let tab_view_copy = tab_view.clone();
+        let tabs_copy = tabs.clone();
+        copy_action.connect_activate(move |_, _| {
+            if let Some(tab_data) = selected_tab(&tab_view_copy, &tabs_copy) {
+                copy_chat_from_tab(&tab_data, format);
+            }
+        });
+        window.add_action(&copy_action);
+    }
+
+    let export_action = SimpleAction::new("export-chat", None);
+    let tab_view_export = tab_view.clone();
+    let tabs_export = tabs.clone();
+    let window_export = window.clone();
+    export_action.connect_activate(move |_, _| {
+        if let Some(tab_data) = selected_tab(&tab_view_export, &tabs_export) {
+            export_chat_from_tab(&window_export, &tab_data);
+        }
+    });
+    window.add_action(&export_action);
+
+    let refresh_emotes_action = SimpleAction::new("refresh-emotes", None);
+    let tab_view_refresh = tab_view.clone();
+    let tabs_refresh = tabs.clone();
+    refresh_emotes_action.connect_activate(move |_, _| {
+        if let Some(tab_data) = selected_tab(&tab_view_refresh, &tabs_refresh) {
+            refresh_emotes_for_tab(&tab_data);
+        }
+    });
+    window.add_action(&refresh_emotes_action);
+
+    let emote_info_action = SimpleAction::new("emote-info", None);
+    let tab_view_emote_info = tab_view.clone();
+    let tabs_emote_info = tabs.clone();
+    let window_emote_info = window.clone();
+    emote_info_action.connect_activate(move |_, _| {
+        if let Some(tab_data) = selected_tab(&tab_view_emote_info, &tabs_emote_info) {
+            show_emote_info(&window_emote_info, &tab_data);
+        }
+    });
+    window.add_action(&emote_info_action);
+
+    let mass_moderation_action = SimpleAction::new("mass-moderation", None);
+    let tab_view_moderation = tab_view.clone();
+    let tabs_moderation = tabs.clone();
+    let window_moderation = window.clone();
+    mass_moderation_action.connect_activate(move |_, _| {
+        let Some(tab_data) = selected_tab(&tab_view_moderation, &tabs_moderation) else {
+            return;
+        };
+        let Some(channel) = tab_data.channel_name.locked().clone() else {
+            return;
+        };
+        mod_tools::show_mass_ban_dialog(&window_moderation, &channel, &tab_data.recent_messages);
+    });
+    window.add_action(&mass_moderation_action);
+
+    let preview_channel_action = SimpleAction::new("preview-channel", None);
+    let tab_view_preview = tab_view.clone();
+    let tabs_preview = tabs.clone();
+    let web_context_preview = web_context.clone();
+    preview_channel_action.connect_activate(move |_, _| {
+        let tab_data = create_new_tab("Preview", &tab_view_preview, &tabs_preview, &web_context_preview);
+        // Same delay as open_channel_tab, the WebView needs a moment before loading
+        glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
+            start_demo_for_tab(DEFAULT_DEMO_RATE, &tab_data);
+        });
+    });
+    window.add_action(&preview_channel_action);
+
+    let command_bar_action = SimpleAction::new("command-bar", None);
+    command_bar_action.connect_activate(clone!(
+        #[strong]
+        command_revealer,
+        #[strong]
+        command_entry,
+        #[strong]
+        command_status,
+        move |_, _| {
+            command_status.set_text("");
+            command_revealer.set_reveal_child(true);
+            command_entry.set_text(":");
+            command_entry.grab_focus();
+            command_entry.set_position(-1);
+        }
+    ));
+    window.add_action(&command_bar_action);
+
+    // ':' anywhere that doesn't consume text input opens the command bar
+    let colon_controller = gtk::EventControllerKey::new();
+    colon_controller.connect_key_pressed(|controller, key, _, modifiers| {
+        let plain = !modifiers.intersects(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK);
+        if key == gdk::Key::colon && plain {
+            if let Some(widget) = controller.widget() {
+                let _ = widget.activate_action("win.command-bar", None);
+            }
+            return glib::Propagation::Stop;
+        }
+        glib::Propagation::Proceed
+    });
+    window.add_controller(colon_controller);
+
+    command_entry.connect_activate(clone!(
+        #[strong]
+        command_revealer,
+        #[strong]
+        command_status,
+        #[strong]
+        tab_view,
+        #[strong]
+        side_view,
+        #[strong]
+        tabs,
+        #[strong]
+        web_context,
+        move |entry| {
+            let result = parse_command(&entry.text())
+                .and_then(|command| run_command(command, &tab_view, &side_view, &tabs, &web_context));
+            match result {
+                Ok(Some(message)) => {
+                    command_status.set_text(&message);
+                    entry.set_text(":");
+                    entry.set_position(-1);
+                }
+                Ok(None) => {
+                    entry.set_text("");
+                    command_revealer.set_reveal_child(false);
+                }
+                Err(e) => command_status.set_text(&e),
+            }
+        }
+    ));
+
+    let preferences_action = SimpleAction::new("preferences", None);
+    let window_for_preferences = window.clone();
+    let tabs_for_preferences = tabs.clone();
+    preferences_action.connect_activate(move |_, _| {
+        preferences::show_preferences(&window_for_preferences, &tabs_for_preferences);
+    });
+    window.add_action(&preferences_action);
+
+    let join_channel_action = SimpleAction::new("join-channel", Some(glib::VariantTy::STRING));
+    let tab_view_join = tab_view.clone();
+    let tabs_join = tabs.clone();
+    let web_context_join = web_context.clone();
+    join_channel_action.connect_activate(move |_, parameter| {
+        if let Some(channel) = parameter.and_then(|p| p.get::<String>()) {
+            open_channel_tab(&channel, &tab_view_join, &tabs_join, &web_context_join);
+        }
+    });
+    window.add_action(&join_channel_action);
+
+    let switch_tab_action = SimpleAction::new("switch-tab", Some(glib::VariantTy::STRING));
+    let tab_view_switch = tab_view.clone();
+    let tabs_switch = tabs.clone();
+    switch_tab_action.connect_activate(move |_, parameter| {
+        let Some(tab_id) = parameter.and_then(|p| p.get::<String>()) else {
+            return;
+        };
+        let page = tabs_switch.locked().get(&tab_id).map(|tab_data| tab_data.page.clone());
+        if let Some(page) = page {
+            tab_view_switch.set_selected_page(&page);
+        }
+    });
+    window.add_action(&switch_tab_action);
+
+    // Resuming brings the shown tabs up to date from what was buffered meanwhile
+    let pause_action = SimpleAction::new_stateful("pause-chat", None, &false.to_variant());
+    let tab_view_clone = tab_view.clone();
+    let tabs_clone = tabs.clone();
+    let detached_clone = detached_views.clone();
+    let pause_button_clone = pause_button.clone();
+    pause_action.connect_activate(move |action, _| {
+        let paused = !rendering_paused.get();
+        rendering_paused.set(paused);
+        action.set_state(&paused.to_variant());
+        if paused {
+            pause_button_clone.add_css_class("accent");
+            pause_button_clone.set_tooltip_text(Some("Resume chat"));
+            return;
+        }
+        pause_button_clone.remove_css_class("accent");
+        pause_button_clone.set_tooltip_text(Some("Pause chat in all tabs"));
+        for page in shown_pages(&tab_view_clone, &detached_clone) {
+            if let Some(tab_data) = tab_for_page(&tabs_clone, &page) {
+                restore_chat_view(&tab_data);
+            }
+        }
+    });
+    window.add_action(&pause_action);
+
+    let dnd_action = SimpleAction::new_stateful("do-not-disturb", None, &get_quiet_hours_settings().do_not_disturb.to_variant());
+    dnd_action.connect_activate(|action, _| {
+        let mut settings = get_quiet_hours_settings();
+        settings.do_not_disturb = !settings.do_not_disturb;
+        set_quiet_hours_settings(&settings);
+        action.set_state(&settings.do_not_disturb.to_variant());
+    });
+    window.add_action(&dnd_action);
+
+    let toggle_theme_action = SimpleAction::new("toggle-theme", None);
+    toggle_theme_action.connect_activate(|_, _| {
+        let style_manager = adw::StyleManager::default();
+        let scheme = if style_manager.is_dark() {
+            adw::ColorScheme::ForceLight
+        } else {
+            adw::ColorScheme::ForceDark
+        };
+        style_manager.set_color_scheme(scheme);
+    });
+    window.add_action(&toggle_theme_action);
+
+    let open_channel_action = SimpleAction::new("open-channel", Some(glib::VariantTy::STRING));
+    let tab_view_open = tab_view.clone();
+    let tabs_open = tabs.clone();
+    let web_context_open = web_context.clone();
+    open_channel_action.connect_activate(move |_, parameter| {
+        let Some(channel) = parameter.and_then(|p| p.get::<String>()) else {
+            return;
+        };
+        // Focus an existing tab for the channel before opening a new one
+        let existing = tabs_open
+            .locked()
+            .values()
+            .find(|tab_data| tab_data.channel_name.locked().as_deref() == Some(channel.as_str()))
+            .map(|tab_data| tab_data.page.clone());
+        match existing {
+            Some(page) => tab_view_open.set_selected_page(&page),
+            None => open_channel_tab(&channel, &tab_view_open, &tabs_open, &web_context_open),
+        }
+    });
+    window.add_action(&open_channel_action);
+
+    let quick_switcher_action = SimpleAction::new("quick-switcher", None);
+    let window_switcher = window.clone();
+    let tab_view_switcher = tab_view.clone();
+    let tabs_switcher = tabs.clone();
+    quick_switcher_action.connect_activate(move |_, _| {
+        let items = quick_switcher_items(&tab_view_switcher, &tabs_switcher);
+        show_palette(&window_switcher, "Switch Channel", "Open tabs, favorites and followed channels", items);
+        refresh_followed_channels();
+    });
+    window.add_action(&quick_switcher_action);
+    refresh_token_info();
+    refresh_followed_channels();
+    refresh_own_user();
+
+    // Go-live events for favorites and followed channels end up in the notification center
+    glib::timeout_add_seconds_local(LIVE_POLL_INTERVAL_SECS, || {
+        if is_offline() {
+            return glib::ControlFlow::Continue;
+        }
+        let mut channels = load_favorites().channels;
+        channels.extend(cached_followed_channels());
+        channels.sort();
+        channels.dedup();
+        check_live_channels(channels);
+        glib::ControlFlow::Continue
+    });
+
+    // Polls finish on a worker thread; the favorites list follows once they're in
+    let favorites_list_live = favorites_list.clone();
+    let seen_generation = std::cell::Cell::new(live_status_generation());
+    glib::timeout_add_seconds_local(2, move || {
+        let generation = live_status_generation();
+        if seen_generation.replace(generation) != generation {
+            refresh_favorites_live_status(&favorites_list_live);
+        }
+        glib::ControlFlow::Continue
+    });
+
+    // Tabs keep their chat while offline and rejoin as soon as the network returns,
+    // without waiting out the client's own reconnect backoff
+    let tabs_network = tabs.clone();
+    watch_network(move |offline| {
+        offline_banner.set_revealed(offline);
+        let tabs: Vec<Arc<TabData>> = tabs_network.locked().values().cloned().collect();
+        for tab_data in &tabs {
+            let ConnectionState::Connected(channel) = tab_data.connection_state.locked().clone() else {
+                continue;
+            };
+            tab_data.page.set_loading(offline);
+            if offline {
+                continue;
+            }
+            if let Some(client) = tab_data.client_state.locked().client.as_ref() {
+                if let Err(e) = client.join(channel.clone()) {
+                    eprintln!("Failed to rejoin channel '{}': {}", channel, e);
+                }
+            }
+        }
+        if !offline {
+            forget_saved_emote_maps();
+            refresh_token_info();
+            refresh_followed_channels();
+            refresh_own_user();
+        }
+    });
+
+    let command_palette_action = SimpleAction::new("command-palette", None);
+    let window_palette = window.clone();
+    let tab_view_palette = tab_view.clone();
+    let tabs_palette = tabs.clone();
+    let app_palette = app.clone();
+    command_palette_action.connect_activate(move |_, _| {
+        let items = command_palette_items(&app_palette, &tab_view_palette, &tabs_palette);
+        show_palette(&window_palette, "Commands", "Search commands, tabs and favorites", items);
+    });
+    window.add_action(&command_palette_action);
+
     app.set_accels_for_action("win.new-tab", &["<Control>t"]);
     app.set_accels_for_action("win.close-tab", &["<Control>w"]);
+    app.set_accels_for_action("win.preferences", &["<Control>comma"]);
+    app.set_accels_for_action("win.copy-chat-text", &["<Control><Shift>c"]);
+    app.set_accels_for_action("win.command-palette", &["<Control>k"]);
+    app.set_accels_for_action("win.quick-switcher", &["<Control>p"]);
+
+    // Notification center slides over the chat from the right
+    let (activity_panel, activity_list) = build_activity_panel();
+    let split_view = adw::OverlaySplitView::builder()
+        .sidebar(&activity_panel)
+        .content(&content)
+        .sidebar_position(gtk::PackType::End)
+        .collapsed(true)
+        .show_sidebar(false)
+        .build();
+    split_view
+        .bind_property("show-sidebar", &activity_button, "active")
+        .bidirectional()
+        .sync_create()
+        .build();
+    split_view.connect_show_sidebar_notify(move |split_view| {
+        if split_view.shows_sidebar() {
+            refresh_activity_list(&activity_list);
+        }
+    });
+
+    let jump_action = SimpleAction::new("jump-to-message", Some(glib::VariantTy::new("(ss)").unwrap()));
+    let tabs_jump = tabs.clone();
+    let window_jump = window.clone();
+    let split_view_jump = split_view.clone();
+    jump_action.connect_activate(move |_, parameter| {
+        let Some((channel, message_id)) = parameter.and_then(|p| p.get::<(String, String)>()) else {
+            return;
+        };
+        let _ = window_jump.activate_action("open-channel", Some(&channel.to_variant()));
+        split_view_jump.set_show_sidebar(false);
+        let tab_data = tabs_jump
+            .locked()
+            .values()
+            .find(|tab_data| tab_data.channel_name.locked().as_deref() == Some(channel.as_str()))
+            .cloned();
+        if let Some(tab_data) = tab_data {
+            call_page(&tab_data.webview, "scrollToMessage", &[message_id.to_variant()], "Failed to jump to the message");
+        }
+    });
+    window.add_action(&jump_action);
+
+    // Mentions stay unread until their tab is shown while the user is actually present,
+    // not just selected while the session sat idle or the window was in the background
+    watch_session_idle();
+    let tabs_unread = tabs.clone();
+    let tab_view_unread = tab_view.clone();
+    let detached_unread = detached_views.clone();
+    let window_unread = window.clone();
+    glib::timeout_add_seconds_local(1, move || {
+        let user_present = window_unread.is_active() && !is_session_idle();
+        let shown = shown_pages(&tab_view_unread, &detached_unread);
+        let tabs_map = tabs_unread.locked();
+        for (_, tab_data) in tabs_map.iter() {
+            let mut unread = tab_data.unread_mentions.locked();
+            if user_present && shown.contains(&tab_data.page) {
+                if *unread > 0 {
+                    *unread = 0;
+                    if let Some(channel) = tab_data.channel_name.locked().as_deref() {
+                        mark_channel_read(channel);
+                    }
+                }
+            }
+            update_unread_indicator(&tab_data.page, *unread);
+        }
+        drop(tabs_map);
+        if unread_activity_count() > 0 {
+            activity_button.add_css_class("accent");
+        } else {
+            activity_button.remove_css_class("accent");
+        }
+        glib::ControlFlow::Continue
+    });
+
+    let toast_overlay = adw::ToastOverlay::new();
+    toast_overlay.set_child(Some(&split_view));
+    window.set_content(Some(&toast_overlay));
+    if get_startup_settings().check_for_updates && benchmark_config().is_none() {
+        check_for_updates(&window, &toast_overlay);
+    }
+    watch_consent_requests(&window, &toast_overlay);
+
+    // Samples each tab's web process, the usual culprit when memory use runs away
+    let tabs_watchdog = tabs.clone();
+    let tab_view_watchdog = tab_view.clone();
+    let window_watchdog = window.clone();
+    glib::timeout_add_seconds_local(WATCHDOG_INTERVAL_SECS, move || {
+        let settings = get_watchdog_settings();
+        if settings.enabled {
+            let tabs: Vec<Arc<TabData>> = tabs_watchdog.locked().values().cloned().collect();
+            for tab_data in &tabs {
+                check_tab_memory(&window_watchdog, &tab_view_watchdog, tab_data, &settings);
+            }
+        }
+        glib::ControlFlow::Continue
+    });
 
-    window.set_content(Some(&content));
+    // Set by the quit action so closing the window really exits in background mode
+    let quitting = Rc::new(std::cell::Cell::new(false));
 
     let quit_action = SimpleAction::new("quit", None);
     let tabs_quit = tabs.clone();
+    let tab_view_quit = tab_view.clone();
+    let quitting_quit = quitting.clone();
     let window_quit = window.clone();
     quit_action.connect_activate(move |_, _| {
         println!("Quit action triggered");
-        let tabs_map = tabs_quit.lock().unwrap();
+        quitting_quit.set(true);
+        let tabs_map = tabs_quit.locked();
+        save_session(&tab_view_quit, &tabs_map);
         // First cleanup all WebViews
         cleanup_all_webviews(&tabs_map);
         // Then disconnect all tabs
@@ -1734,7 +5507,7 @@ fn build_ui(app: &Application) {
             disconnect_tab_handler(tab_data);
         }
         drop(tabs_map);
-        tabs_quit.lock().unwrap().clear();
+        tabs_quit.locked().clear();
         println!("All tabs disconnected and cleared");
 
         // Close the window after all tabs are disconnected
@@ -1744,9 +5517,19 @@ fn build_ui(app: &Application) {
     app.set_accels_for_action("win.quit", &["<Control>q"]);
 
     let tabs_for_window_close = tabs.clone();
-    window.connect_close_request(move |_window| {
+    let tab_view_for_window_close = tab_view.clone();
+    window.connect_close_request(move |window| {
         println!("Window close button clicked");
-        let tabs_map = tabs_for_window_close.lock().unwrap();
+        // Chats stay connected and activity is reported through desktop notifications
+        if !quitting.get() && get_startup_settings().run_in_background {
+            window.set_visible(false);
+            return glib::Propagation::Stop;
+        }
+        let tabs_map = tabs_for_window_close.locked();
+        // Quitting already saved the session and cleared the tabs
+        if !tabs_map.is_empty() {
+            save_session(&tab_view_for_window_close, &tabs_map);
+        }
         // First cleanup all WebViews
         cleanup_all_webviews(&tabs_map);
         // Then disconnect all tabs
@@ -1755,22 +5538,116 @@ fn build_ui(app: &Application) {
             disconnect_tab_handler(tab_data);
         }
         drop(tabs_map);
-        tabs_for_window_close.lock().unwrap().clear();
+        tabs_for_window_close.locked().clear();
         println!("All tabs disconnected on window close");
         glib::Propagation::Proceed
     });
 
-    window.present();
+    if behavior != StartupBehavior::Background {
+        window.present();
+        auth::unlock_saved_token(&window);
+        // Left for the next launch that shows the window otherwise
+        offer_crash_recovery(&window, &tab_view, &tabs, &web_context);
+    }
+
+    if let Some(config) = benchmark_config() {
+        run_benchmark(app, config, &tab_view, &tabs, &web_context);
+    }
+}
+
+// Drives the preview channel at the benchmark rate, then prints the report and quits
+fn run_benchmark(
+    app: &Application,
+    config: benchmark::BenchmarkConfig,
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+    web_context: &webkit6::WebContext,
+) {
+    println!("Starting benchmark at {} msg/s for {}s", config.rate, config.duration.as_secs());
+    let tab_data = create_new_tab("Benchmark", tab_view, tabs, web_context);
+    let app = app.clone();
+    glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
+        start_demo_for_tab(config.rate, &tab_data);
+        // Give the chat page time to load before measuring
+        glib::timeout_add_local_once(std::time::Duration::from_secs(1), move || {
+            tab_data.webview.evaluate_javascript(
+                "startFrameMonitor();",
+                None,
+                None,
+                None::<&adw::gio::Cancellable>,
+                |_| {},
+            );
+            start_benchmark();
+            glib::timeout_add_local_once(config.duration, move || {
+                tab_data.webview.evaluate_javascript(
+                    "JSON.stringify(frameStats)",
+                    None,
+                    None,
+                    None::<&adw::gio::Cancellable>,
+                    move |result| {
+                        let frames: FrameStats = match result {
+                            Ok(value) => serde_json::from_str(&value.to_str()).unwrap_or_default(),
+                            Err(e) => {
+                                eprintln!("Failed to read frame statistics: {}", e);
+                                FrameStats::default()
+                            }
+                        };
+                        finish_benchmark(&config, &frames);
+                        match app.active_window() {
+                            Some(window) => {
+                                let _ = window.activate_action("win.quit", None);
+                            }
+                            None => app.quit(),
+                        }
+                    },
+                );
+            });
+        });
+    });
+}
+
+// Configuration shared by every chat view. WebKitGTK 6 always runs a sandboxed web
+// process per unrelated view, which the memory watchdog relies on to attribute usage.
+fn create_web_context() -> webkit6::WebContext {
+    let web_context = webkit6::WebContext::new();
+    web_context.set_automation_allowed(false);
+    // Keeps emotes and badges in the HTTP cache across tabs and restarts
+    web_context.set_cache_model(webkit6::CacheModel::WebBrowser);
+    web_context.set_spell_checking_enabled(false);
+    web_context
+}
+
+thread_local! {
+    // Cookies and the HTTP cache live in Admiral's own directories rather than WebKit's defaults
+    static NETWORK_SESSION: webkit6::NetworkSession = {
+        let data_dir = data_dir().join("webkit");
+        let cache_dir = cache_dir().join("webkit");
+        let network_session = webkit6::NetworkSession::new(data_dir.to_str(), cache_dir.to_str());
+        apply_proxy_settings(&network_session, &get_network_settings());
+        network_session
+    };
+}
+
+// Sends the chat view's own traffic (emote images, the template's requests) through the proxy
+fn apply_proxy_settings(network_session: &webkit6::NetworkSession, settings: &NetworkSettings) {
+    match settings.proxy_mode {
+        ProxyMode::System => network_session.set_proxy_settings(webkit6::NetworkProxyMode::Default, None),
+        ProxyMode::Direct => network_session.set_proxy_settings(webkit6::NetworkProxyMode::NoProxy, None),
+        ProxyMode::Custom => {
+            let proxy = webkit6::NetworkProxySettings::new(Some(&settings.proxy_url), &[]);
+            network_session.set_proxy_settings(webkit6::NetworkProxyMode::Custom, Some(&proxy));
+        }
+    }
 }
 
 fn create_new_tab(
     label: &str,
     tab_view: &TabView,
     tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
-    _web_context: &webkit6::WebContext
-) {
+    web_context: &webkit6::WebContext
+) -> Arc<TabData> {
     let tab_content = Box::new(Orientation::Vertical, 0);
-    let message_buffer: Arc<Mutex<VecDeque<String>>> = Arc::new(Mutex::new(VecDeque::new()));
+    let message_buffer: Arc<Mutex<MessageBuffer>> = Arc::new(Mutex::new(MessageBuffer::default()));
 
     let entry_box = Box::new(Orientation::Horizontal, 6);
     entry_box.set_margin_top(6);
@@ -1784,30 +5661,79 @@ fn create_new_tab(
     let connect_button = GtkButton::builder()
         .label("Connect")
         .build();
+    let translate_button = gtk::ToggleButton::builder()
+        .icon_name("preferences-desktop-locale-symbolic")
+        .tooltip_text("Translate foreign-language messages")
+        .build();
+    let stats = Arc::new(Mutex::new(ChannelStats::default()));
+    let stats_button = gtk::MenuButton::builder()
+        .icon_name("utilities-system-monitor-symbolic")
+        .tooltip_text("Session statistics")
+        .popover(&build_stats_popover(&stats))
+        .build();
+    let notes_button = gtk::ToggleButton::builder()
+        .icon_name("accessories-text-editor-symbolic")
+        .tooltip_text("Channel notes")
+        .build();
+    let giveaway = Arc::new(Mutex::new(Giveaway::default()));
+    let poll = Arc::new(Mutex::new(Poll::default()));
+    // Their popovers are added once the WebView exists, since results are announced there
+    let giveaway_button = gtk::MenuButton::builder()
+        .icon_name("starred-symbolic")
+        .tooltip_text("Giveaway")
+        .build();
+    let poll_button = gtk::MenuButton::builder()
+        .icon_name("view-list-bullet-symbolic")
+        .tooltip_text("Chat poll")
+        .build();
     entry_box.append(&entry);
+    entry_box.append(&notes_button);
+    entry_box.append(&giveaway_button);
+    entry_box.append(&poll_button);
+    entry_box.append(&stats_button);
+    entry_box.append(&translate_button);
     entry_box.append(&connect_button);
 
+    let translate_enabled = Arc::new(AtomicBool::new(false));
+    let translate_enabled_clone = translate_enabled.clone();
+    translate_button.connect_toggled(move |button| {
+        translate_enabled_clone.store(button.is_active(), Ordering::Relaxed);
+    });
+
     // Create WebView for chat display
     // Note: Visibility override will be injected via JS after load
-    let webview = WebView::new();
+    // Lets the chat page talk back through window.webkit.messageHandlers.admiral
+    let user_content_manager = webkit6::UserContentManager::new();
+    user_content_manager.register_script_message_handler("admiral", None);
+    let webview = NETWORK_SESSION.with(|network_session| {
+        WebView::builder()
+            .web_context(web_context)
+            .network_session(network_session)
+            .user_content_manager(&user_content_manager)
+            .build()
+    });
     webview.set_vexpand(true);
     webview.set_hexpand(true);
 
-    // Set webview background to saved color or default (transparent to let GTK background show)
-    let bg_color = if let Some(color_hex) = get_background_color() {
-        // Parse hex color to RGBA
-        if let Ok(rgb) = u32::from_str_radix(&color_hex[1..], 16) {
-            let r = ((rgb >> 16) & 0xFF) as f32 / 255.0;
-            let g = ((rgb >> 8) & 0xFF) as f32 / 255.0;
-            let b = (rgb & 0xFF) as f32 / 255.0;
-            gdk::RGBA::new(r, g, b, 0.95)
-        } else {
-            gdk::RGBA::new(0.0, 0.0, 0.0, 0.95) // Fallback to black
-        }
-    } else {
-        gdk::RGBA::new(0.0, 0.0, 0.0, 0.0) // Default transparent (alpha = 0)
-    };
-    webview.set_background_color(&bg_color);
+    webview.set_background_color(&webview_background(get_background_color().as_deref()));
+    apply_page_styles(&webview, &webview);
+
+    let giveaway_popover = build_giveaway_popover(&giveaway, glib::clone!(
+        #[weak]
+        webview,
+        #[strong]
+        message_buffer,
+        move |html| append_notice(&webview, &message_buffer, html)
+    ));
+    giveaway_button.set_popover(Some(&giveaway_popover));
+    let poll_popover = build_poll_popover(&poll, glib::clone!(
+        #[weak]
+        webview,
+        #[strong]
+        message_buffer,
+        move |html| append_notice(&webview, &message_buffer, html)
+    ));
+    poll_button.set_popover(Some(&poll_popover));
 
     // Configure WebView for aggressive resource management and chat optimization
     let settings = webkit6::Settings::new();
@@ -1854,10 +5780,11 @@ fn create_new_tab(
         true // Consume the event
     });
 
-    // Inject initial HTML and JavaScript with custom background color
-    let html_template = get_chat_html_template_with_color(get_background_color().as_deref());
-    webview.load_html(&html_template, None);
+    // The chat page loads once here; colors and settings are applied once it's finished
+    webview.load_html(get_chat_html_template(), None);
 
+    let web_process: Arc<Mutex<Option<i32>>> = Arc::new(Mutex::new(None));
+    let scroll_anchor: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
     webview.connect_load_changed(clone!(
         #[strong]
         webview,
@@ -1865,55 +5792,70 @@ fn create_new_tab(
         tab_content,
         #[strong]
         message_buffer,
+        #[strong]
+        web_process,
+        #[strong]
+        scroll_anchor,
         move |webview, event| {
             use webkit6::LoadEvent;
+            // The web process exists by now, so the memory watchdog can find it
+            if event == LoadEvent::Committed {
+                let mut pid = web_process.locked();
+                *pid = claim_web_process(*pid);
+            }
             if event == LoadEvent::Finished {
-            let (popover_bg, popover_border, popover_text) = get_theme_popover_colors(&tab_content);
-            let theme_js = format!(
-                "document.documentElement.style.setProperty('--popover-bg', '{}');\
-                 document.documentElement.style.setProperty('--popover-border', '{}');\
-                 document.documentElement.style.setProperty('--popover-text', '{}');",
-                popover_bg, popover_border, popover_text,
-            );
+            apply_page_styles(webview, &tab_content);
+            let settings_js = format!("{}{}", APPLY_SETTINGS_JS, get_appearance_settings().apply_js());
             webview.evaluate_javascript(
-                &theme_js,
+                &settings_js,
                 None,
                 None,
                 None::<&adw::gio::Cancellable>,
                 |result| {
                 if let Err(e) = result {
-                    eprintln!("Failed to apply theme popover colors: {:?}", e);
+                    eprintln!("Failed to apply page settings: {:?}", e);
                 }
             });
 
-            let buf = message_buffer.lock().unwrap();
+            let buf = message_buffer.locked();
             if !buf.is_empty() {
-                let all_html: String = buf.iter().cloned().collect::<Vec<_>>().join("\n");
-                let escaped_html = all_html
-                    .replace('\\', "\\\\")
-                    .replace('\'', "\\'")
-                    .replace('\n', "\\n")
-                    .replace('\r', "\\r");
-                let js = format!(
-                    "if (typeof replaceAllMessages === 'function') {{ replaceAllMessages('{}'); }}",
-                    escaped_html
-                );
-                webview.evaluate_javascript(
-                    &js,
-                    None,
-                    None,
-                    None::<&adw::gio::Cancellable>,
-                    |result| {
-                    if let Err(e) = result {
-                        eprintln!("Failed to restore buffered messages: {:?}", e);
-                    }
-                });
+                let mut calls = PageCalls::new();
+                calls.push("replaceAllMessages", &[buf.joined().to_variant()]);
+                push_scroll_anchor(&mut calls, &scroll_anchor);
+                calls.run(webview, "Failed to restore buffered messages");
             }
             drop(buf);
             }
         }
     ));
 
+    // Shown after WebKit's web process dies and the chat view had to be rebuilt
+    let crash_banner = adw::Banner::builder()
+        .button_label("Dismiss")
+        .revealed(false)
+        .build();
+    crash_banner.connect_button_clicked(|banner| banner.set_revealed(false));
+
+    // Without this a killed web process leaves the tab blank for good. Reloading the
+    // template replays message_buffer through the load_changed handler above.
+    webview.connect_web_process_terminated(clone!(
+        #[strong]
+        crash_banner,
+        move |webview, reason| {
+            let title = match reason {
+                webkit6::WebProcessTerminationReason::TerminatedByApi => return,
+                webkit6::WebProcessTerminationReason::ExceededMemoryLimit => {
+                    "Chat view ran out of memory and was reloaded"
+                }
+                _ => "Chat view crashed and was reloaded",
+            };
+            eprintln!("WebView process terminated ({:?}), reloading chat", reason);
+            crash_banner.set_title(title);
+            crash_banner.set_revealed(true);
+            webview.load_html(get_chat_html_template(), None);
+        }
+    ));
+
     let scrolled_window = ScrolledWindow::builder()
         .vexpand(true)
         .hexpand(true)
@@ -1936,31 +5878,97 @@ fn create_new_tab(
     placeholder_box.append(&main_label);
     placeholder_box.append(&subtitle_label);
 
+    let notes_pane = build_notes_pane();
+    notes_button
+        .bind_property("active", &notes_pane.revealer, "reveal-child")
+        .sync_create()
+        .build();
+    let sparkline = build_activity_sparkline(&stats, glib::clone!(
+        #[weak]
+        webview,
+        move |time| {
+            call_page(&webview, "scrollToTime", &[time.to_variant()], "Failed to jump to the chart's time");
+        }
+    ));
+    sparkline.set_margin_start(6);
+    sparkline.set_margin_end(6);
+    let lightweight_view = build_lightweight_view(&scrolled_window);
+    let chat_box = Box::new(Orientation::Horizontal, 0);
+    chat_box.append(&lightweight_view.views);
+    chat_box.append(&notes_pane.revealer);
+    let message_entry = Entry::builder()
+        .placeholder_text("Send a message")
+        .hexpand(true)
+        .build();
+    let send_button = Button::builder()
+        .icon_name("document-send-symbolic")
+        .tooltip_text("Send message")
+        .build();
+    let send_bar = Box::new(Orientation::Horizontal, 6);
+    send_bar.set_margin_top(6);
+    send_bar.set_margin_bottom(6);
+    send_bar.set_margin_start(6);
+    send_bar.set_margin_end(6);
+    let identity = build_identity_button();
+    send_bar.append(&identity.button);
+    send_bar.append(&message_entry);
+    send_bar.append(&send_button);
+    send_bar.set_visible(false);
+    let send_history = Arc::new(Mutex::new(SendHistory::default()));
+    attach_send_history(&message_entry, &send_history);
+    attach_paste_upload(&message_entry);
+
+    let room_modes = gtk::Label::builder()
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .margin_start(6)
+        .margin_end(6)
+        .margin_top(2)
+        .margin_bottom(2)
+        .css_classes(["caption", "dim-label"])
+        .tooltip_text("Chat modes set by the channel")
+        .visible(false)
+        .build();
+
+    let chat_view = Box::new(Orientation::Vertical, 0);
+    chat_view.append(&sparkline);
+    chat_view.append(&room_modes);
+    chat_view.append(&chat_box);
+    chat_view.append(&send_bar);
+
     let stack = Stack::builder()
         .vexpand(true)
         .hexpand(true)
         .build();
     stack.add_named(&placeholder_box, Some("placeholder"));
-    stack.add_named(&scrolled_window, Some("chat")); // Show WebView in chat view
+    stack.add_named(&chat_view, Some("chat")); // Show WebView in chat view
     stack.set_visible_child_name("placeholder");
 
+    let replay = Arc::new(Mutex::new(None));
+    let replay_bar = build_replay_bar(&replay);
+
     tab_content.append(&entry_box);
+    tab_content.append(&crash_banner);
+    tab_content.append(&replay_bar.revealer);
     tab_content.append(&stack);
 
     let page = tab_view.append(&tab_content);
     page.set_title(label);
 
-    let (tx, rx) = mpsc::sync_channel(500);
-    let (error_tx, error_rx) = mpsc::channel();
-
-    let tab_count = tabs.lock().unwrap().len();
+    let tab_count = tabs.locked().len();
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
         .as_millis();
     let tab_id = format!("tab_{}_{}", timestamp, tab_count);
+
+    let waker = TabWaker::new(&tab_id);
+    let (error_tx, error_rx) = pump_channel(&waker);
+    let (translation_tx, translation_rx) = pump_channel(&waker);
+    let (account_age_tx, account_age_rx) = pump_channel(&waker);
+    let (render_tx, render_rx) = pump_channel(&waker);
+    let (chat_event_tx, chat_event_rx) = pump_channel(&waker);
     let client_state = Arc::new(Mutex::new(ClientState::new()));
-    let shutdown_flag = client_state.lock().unwrap().shutdown_flag.clone();
     let tab_data = TabData {
         page: page.clone(),
         webview: webview.clone(),
@@ -1969,19 +5977,89 @@ fn create_new_tab(
         channel_name: Arc::new(Mutex::new(None)),
         client_state: client_state.clone(),
         connection_state: Arc::new(Mutex::new(ConnectionState::Disconnected)),
-        tx,
-        rx: Arc::new(Mutex::new(rx)),
+        queue: Arc::new(MessageQueue::new(DEFAULT_QUEUE_CAPACITY, waker.clone())),
+        waker,
         error_tx,
         error_rx: Arc::new(Mutex::new(error_rx)),
         last_js_execution: Arc::new(Mutex::new(Instant::now())),
-        shutdown_flag,
+        pacing: Arc::new(Mutex::new(RenderPacing::default())),
         message_buffer,
         pending_messages: Arc::new(Mutex::new(VecDeque::new())),
+        translate_enabled,
+        translation_tx,
+        translation_rx: Arc::new(Mutex::new(translation_rx)),
+        room_state: Arc::new(Mutex::new(RoomState::default())),
+        stats,
+        giveaway,
+        poll,
+        seen_chatters: Arc::new(Mutex::new(HashSet::new())),
+        recent_messages: Arc::new(Mutex::new(VecDeque::new())),
+        reply_target: Arc::new(Mutex::new(None)),
+        send_history,
+        send_bar: send_bar.clone(),
+        identity,
+        room_modes: room_modes.clone(),
+        message_entry: message_entry.clone(),
+        send_button: send_button.clone(),
+        signed_in: Arc::new(AtomicBool::new(false)),
+        notes_pane,
+        filters: Arc::new(Mutex::new(Vec::new())),
+        unread_mentions: Arc::new(Mutex::new(0)),
+        muted: Arc::new(AtomicBool::new(false)),
+        replay,
+        replay_bar,
+        demo_stop: Arc::new(Mutex::new(None)),
+        web_process,
+        memory_warned: Arc::new(AtomicBool::new(false)),
+        hibernated: Arc::new(AtomicBool::new(false)),
+        scroll_anchor,
+        chat_page_loaded: Arc::new(AtomicBool::new(true)),
+        render_tx,
+        render_rx: Arc::new(Mutex::new(render_rx)),
+        render_in_flight: Arc::new(AtomicBool::new(false)),
+        chat_generation: Arc::new(AtomicU64::new(0)),
+        stalled: Arc::new(AtomicBool::new(false)),
+        crash_banner: crash_banner.clone(),
+        lightweight: Arc::new(AtomicBool::new(false)),
+        lightweight_view,
+        account_age_tx,
+        account_age_rx: Arc::new(Mutex::new(account_age_rx)),
+        chat_event_tx,
+        chat_event_rx: Arc::new(Mutex::new(chat_event_rx)),
     };
     let tab_data_arc = Arc::new(tab_data);
-    tabs.lock().unwrap().insert(tab_id.clone(), tab_data_arc.clone());
+    tabs.locked().insert(tab_id.clone(), tab_data_arc.clone());
     println!("Created new tab with id: {}", tab_id);
 
+    let tab_data_weak = Arc::downgrade(&tab_data_arc);
+    message_entry.connect_activate(move |_| {
+        if let Some(tab_data) = tab_data_weak.upgrade() {
+            send_chat_message(&tab_data);
+        }
+    });
+    let tab_data_weak = Arc::downgrade(&tab_data_arc);
+    send_button.connect_clicked(move |_| {
+        if let Some(tab_data) = tab_data_weak.upgrade() {
+            send_chat_message(&tab_data);
+        }
+    });
+    let tab_data_weak = Arc::downgrade(&tab_data_arc);
+    tab_data_arc.identity.connect_color_picked(move |color| {
+        if let Some(tab_data) = tab_data_weak.upgrade() {
+            change_chat_color(&tab_data, color);
+        }
+    });
+
+    let tab_data_weak = Arc::downgrade(&tab_data_arc);
+    user_content_manager.connect_script_message_received(Some("admiral"), move |_, value| {
+        let Some(tab_data) = tab_data_weak.upgrade() else {
+            return;
+        };
+        if let Some(message) = parse_script_message(&value.to_str()) {
+            handle_script_message(&tab_data, message);
+        }
+    });
+
     connect_button.connect_clicked(clone!(
         #[strong]
         tab_data_arc,
@@ -1991,10 +6069,14 @@ fn create_new_tab(
                 disconnect_tab_handler(&tab_data_arc);
                 return;
             }
-            let current_state = tab_data_arc.connection_state.lock().unwrap().clone();
+            if let Some(video_id) = parse_vod_id(&channel_name, false) {
+                start_vod_replay_for_tab(&video_id, &tab_data_arc);
+                return;
+            }
+            let current_state = tab_data_arc.connection_state.locked().clone();
             match current_state {
                 ConnectionState::Connected(_) => {
-                    disconnect_tab_handler(&tab_data_arc);
+                    end_session(&tab_data_arc);
                     start_connection_for_tab(&channel_name, &tab_data_arc);
                 },
                 ConnectionState::Disconnected | ConnectionState::Connecting => {
@@ -2013,110 +6095,614 @@ fn create_new_tab(
     ));
 
     tab_view.set_selected_page(&page);
+    tab_data_arc
 }
 
-fn start_connection_for_tab(
+// Window actions listed in the command palette, in display order
+const PALETTE_ACTIONS: &[(&str, &str)] = &[
+    ("win.new-tab", "New Tab"),
+    ("win.close-tab", "Close Tab"),
+    ("win.open-starred", "Open All Starred Channels"),
+    ("win.command-bar", "Open Command Bar"),
+    ("win.quick-switcher", "Quick Switcher"),
+    ("win.toggle-theme", "Toggle Dark/Light Theme"),
+    ("win.pause-chat", "Pause/Resume Chat"),
+    ("win.do-not-disturb", "Toggle Do Not Disturb"),
+    ("win.preferences", "Preferences"),
+    ("win.copy-chat-text", "Copy Chat as Text"),
+    ("win.copy-chat-html", "Copy Chat as HTML"),
+    ("win.copy-chat-image", "Copy Chat as Image"),
+    ("win.export-chat", "Export Chat"),
+    ("win.refresh-emotes", "Refresh Emotes"),
+    ("win.emote-info", "Emote Info"),
+    ("win.mass-moderation", "Mass Moderation"),
+    ("win.preview-channel", "Open Preview Channel"),
+    ("win.quit", "Quit"),
+];
+
+fn command_palette_items(
+    app: &Application,
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+) -> Vec<PaletteItem> {
+    let mut items: Vec<PaletteItem> = PALETTE_ACTIONS
+        .iter()
+        .map(|(action, title)| {
+            let accel = app
+                .accels_for_action(action)
+                .first()
+                .and_then(|accel| gtk::accelerator_parse(accel))
+                .map(|(key, modifiers)| gtk::accelerator_get_label(key, modifiers).to_string())
+                .unwrap_or_default();
+            PaletteItem::new(title, &accel, action, None)
+        })
+        .collect();
+    items.extend(tab_palette_items(tab_view, tabs));
+    items.extend(favorite_palette_items());
+    items
+}
+
+fn tab_palette_items(
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+) -> Vec<PaletteItem> {
+    let tabs_map = tabs.locked();
+    let mut items: Vec<(i32, PaletteItem)> = tabs_map
+        .iter()
+        .map(|(tab_id, tab_data)| {
+            let title = format!("Switch to {}", tab_data.page.title());
+            (tab_view.page_position(&tab_data.page), PaletteItem::new(&title, "Open tab", "win.switch-tab", Some(tab_id)))
+        })
+        .collect();
+    items.sort_by_key(|(position, _)| *position);
+    items.into_iter().map(|(_, item)| item).collect()
+}
+
+fn favorite_palette_items() -> Vec<PaletteItem> {
+    let favorites = load_favorites();
+    favorites
+        .channels
+        .iter()
+        .map(|channel| {
+            let label = match favorites.aliases.get(channel) {
+                Some(alias) => format!("Join {} ({})", alias, channel),
+                None => format!("Join {}", channel),
+            };
+            PaletteItem::new(&label, "Favorite", "win.join-channel", Some(channel))
+        })
+        .collect()
+}
+
+// Open tabs first, then favorites and followed channels that aren't open yet
+fn quick_switcher_items(
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+) -> Vec<PaletteItem> {
+    let mut items = tab_palette_items(tab_view, tabs);
+    let mut seen: HashSet<String> = tabs
+        .locked()
+        .values()
+        .filter_map(|tab_data| tab_data.channel_name.locked().clone())
+        .collect();
+    for channel in load_favorites().channels {
+        if seen.insert(channel.clone()) {
+            items.push(PaletteItem::new(&channel, "Favorite", "win.open-channel", Some(&channel)));
+        }
+    }
+    for channel in cached_followed_channels() {
+        if seen.insert(channel.clone()) {
+            items.push(PaletteItem::new(&channel, "Followed", "win.open-channel", Some(&channel)));
+        }
+    }
+    items
+}
+
+// Runs a command bar command. Ok(Some) keeps the bar open to show the message.
+fn run_command(
+    command: Command,
+    tab_view: &TabView,
+    side_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+    web_context: &webkit6::WebContext,
+) -> Result<Option<String>, String> {
+    match command {
+        Command::Join(channel) => {
+            open_channel_tab(&channel, tab_view, tabs, web_context);
+            Ok(None)
+        }
+        Command::Help => Ok(Some(HELP_TEXT.to_string())),
+        Command::Split => {
+            let page = tab_view.selected_page().ok_or("No tab selected")?;
+            // The main pane can't be left empty
+            if tab_view.n_pages() < 2 {
+                return Err("Open another tab to split with".to_string());
+            }
+            tab_view.transfer_page(&page, side_view, side_view.n_pages());
+            side_view.set_selected_page(&page);
+            Ok(None)
+        }
+        Command::Only => {
+            while side_view.n_pages() > 0 {
+                side_view.transfer_page(&side_view.nth_page(0), tab_view, tab_view.n_pages());
+            }
+            Ok(None)
+        }
+        command => {
+            let tab_data = selected_tab(tab_view, tabs).ok_or("No tab selected")?;
+            match command {
+                Command::Close => {
+                    tab_view.close_page(&tab_data.page);
+                    Ok(None)
+                }
+                Command::Disconnect => {
+                    disconnect_tab_handler(&tab_data);
+                    Ok(None)
+                }
+                Command::FilterAdd(pattern) => {
+                    let filter = Regex::new(&pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+                    tab_data.filters.locked().push(filter);
+                    Ok(Some(format!("Hiding messages matching {}", pattern)))
+                }
+                Command::FilterRemove(pattern) => {
+                    let mut filters = tab_data.filters.locked();
+                    let before = filters.len();
+                    filters.retain(|filter| filter.as_str() != pattern);
+                    if filters.len() == before {
+                        return Err(format!("No filter {}", pattern));
+                    }
+                    Ok(Some(format!("Removed filter {}", pattern)))
+                }
+                Command::FilterClear => {
+                    tab_data.filters.locked().clear();
+                    Ok(Some("Filters cleared".to_string()))
+                }
+                Command::FilterList => {
+                    let filters = tab_data.filters.locked();
+                    if filters.is_empty() {
+                        return Ok(Some("No filters in this tab".to_string()));
+                    }
+                    let list: Vec<&str> = filters.iter().map(|filter| filter.as_str()).collect();
+                    Ok(Some(format!("Filters: {}", list.join(", "))))
+                }
+                Command::Demo(rate) => {
+                    start_demo_for_tab(rate, &tab_data);
+                    Ok(Some(format!("Preview channel at {} messages per second", rate)))
+                }
+                Command::Vod(video_id) => {
+                    start_vod_replay_for_tab(&video_id, &tab_data);
+                    Ok(Some(format!("Loading VOD {}", video_id)))
+                }
+                Command::Join(_) | Command::Help | Command::Split | Command::Only => unreachable!(),
+            }
+        }
+    }
+}
+
+// Opens a new tab and connects it to `channel`
+fn tab_for_channel(tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>, channel: &str) -> Option<Arc<TabData>> {
+    tabs.locked()
+        .values()
+        .find(|tab_data| tab_data.channel_name.locked().as_deref() == Some(channel))
+        .cloned()
+}
+
+// Opens starred channels whose schedule just started and closes the tabs it opened once
+// the window ends. Tabs the user opened themselves are never closed.
+fn apply_schedules(
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+    web_context: &webkit6::WebContext,
+    scheduled: &RefCell<HashMap<String, bool>>,
+) {
+    let favorites = load_favorites();
+    let now = chrono::Local::now().naive_local();
+    let is_active = |channel: &str| {
+        favorites.starred.iter().any(|starred| starred == channel)
+            && favorites.schedules.get(channel).is_some_and(|schedule| schedule.is_active(now))
+    };
+    let mut scheduled = scheduled.borrow_mut();
+    scheduled.retain(|channel, opened_here| {
+        if is_active(channel) {
+            return true;
+        }
+        if *opened_here {
+            if let Some(tab_data) = tab_for_channel(tabs, channel) {
+                println!("Schedule for {} ended, closing its tab", channel);
+                tab_view.close_page(&tab_data.page);
+            }
+        }
+        false
+    });
+    for channel in favorites.schedules.keys() {
+        // Marked as handled even when the user closes the tab early, so it isn't reopened
+        if !is_active(channel) || scheduled.contains_key(channel) {
+            continue;
+        }
+        let already_open = tab_for_channel(tabs, channel).is_some();
+        if !already_open {
+            println!("Schedule for {} started, opening it", channel);
+            open_channel_tab(channel, tab_view, tabs, web_context);
+        }
+        scheduled.insert(channel.clone(), !already_open);
+    }
+}
+
+fn open_channel_tab(
     channel: &str,
-    tab_data: &Arc<TabData>
+    tab_view: &TabView,
+    tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>,
+    web_context: &webkit6::WebContext,
 ) {
-    // Convert channel name to lowercase as Twitch requires lowercase channel names
-    let channel = channel.to_lowercase();
+    let tab_data = create_new_tab(channel, tab_view, tabs, web_context);
+    let channel = channel.to_string();
+    // Give the freshly created WebView a moment before loading the chat page
+    glib::timeout_add_local_once(std::time::Duration::from_millis(50), move || {
+        println!("Attempting to connect to channel: {}", channel);
+        tab_data.entry.set_text(&channel);
+        start_connection_for_tab(&channel, &tab_data);
+    });
+}
 
-    *tab_data.connection_state.lock().unwrap() = ConnectionState::Connecting;
-    *tab_data.channel_name.lock().unwrap() = Some(channel.clone());
+fn handle_script_message(tab_data: &TabData, message: ScriptMessage) {
+    match message {
+        ScriptMessage::UserCard { login } => {
+            let Some(channel) = tab_data.channel_name.locked().clone() else {
+                return;
+            };
+            let context = UserCardContext {
+                channel,
+                recent_messages: tab_data.recent_messages.clone(),
+                timeout_durations: get_moderation_settings().timeout_durations,
+                // Helix rejects the request anyway if we turn out not to be a moderator
+                can_moderate: auth::load_token().is_some(),
+            };
+            show_user_card(&tab_data.webview, &context, &login);
+        }
+        ScriptMessage::Reply { login, message_id } => {
+            *tab_data.reply_target.locked() = Some(ReplyTarget { login, message_id });
+            update_send_controls(tab_data);
+            tab_data.message_entry.grab_focus();
+        }
+        ScriptMessage::InsertEmote { name } => {
+            let entry = &tab_data.message_entry;
+            let text = entry.text();
+            let separator = if text.is_empty() || text.ends_with(' ') { "" } else { " " };
+            entry.set_text(&format!("{}{}{} ", text, separator, name));
+            entry.grab_focus();
+            entry.set_position(-1);
+        }
+        ScriptMessage::CommandBar => {
+            let _ = tab_data.webview.activate_action("win.command-bar", None);
+        }
+        ScriptMessage::Copy { text } => {
+            if let Some(display) = gdk::Display::default() {
+                display.clipboard().set_text(&text);
+            }
+        }
+        ScriptMessage::LoadOlder { before } => load_older_messages(tab_data, before),
+        ScriptMessage::ScrollAnchor { message_id } => *tab_data.scroll_anchor.locked() = message_id,
+    }
+}
 
-    // Aggressive cleanup before loading new content
-    cleanup_webview(&tab_data.webview);
+// Answers the page's request for scrollback past what it holds, from the saved history
+fn load_older_messages(tab_data: &TabData, before: i64) {
+    let webview = tab_data.webview.clone();
+    let Some(channel) = tab_data.channel_name.locked().clone() else {
+        call_page(&webview, "prependMessages", &["".to_variant(), false.to_variant()], "Failed to prepend older messages");
+        return;
+    };
+    let filters = tab_data.filters.locked().clone();
+    let bot_settings = get_bot_settings();
+    let appearance = get_appearance_settings();
+    glib::MainContext::default().spawn_local(async move {
+        // Rendered alongside the query, off the main loop
+        let (html, has_more) = adw::gio::spawn_blocking(move || {
+            let mut messages = messages_before(&channel, before, HISTORY_PAGE_SIZE);
+            let has_more = messages.len() == HISTORY_PAGE_SIZE;
+            remove_hidden_messages(&mut messages, &bot_settings, &filters);
+            let html = messages
+                .iter()
+                .map(|msg| {
+                    let emote_map = emote_map_for(msg, &get_emote_map(&msg.channel_id));
+                    parse_message_html(msg, &emote_map, &render_options_for(msg, &bot_settings, &appearance))
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            (html, has_more)
+        })
+        .await
+        .unwrap_or_default();
+        call_page(
+            &webview,
+            "prependMessages",
+            &[html.to_variant(), has_more.to_variant()],
+            "Failed to prepend older messages",
+        );
+    });
+}
 
-    // Clear WebView content and show chat view with custom background color
-    let html_template = get_chat_html_template_with_color(get_background_color().as_deref());
-    tab_data.webview.load_html(&html_template, None);
-    tab_data.stack.set_visible_child_name("chat");
-    tab_data.page.set_title(&channel);
-    let connection_state = tab_data.connection_state.clone();
-    let client_state_thread = tab_data.client_state.clone();
-    let client_state_store = tab_data.client_state.clone();
-    let shutdown_flag = tab_data.shutdown_flag.clone();
-    let tx = tab_data.tx.clone();
-    let error_tx = tab_data.error_tx.clone();
+// Clears everything tied to the previous channel session of a tab
+fn reset_session_state(tab_data: &TabData, channel: &str) {
+    *tab_data.room_state.locked() = RoomState::default();
+    *tab_data.stats.locked() = ChannelStats::new(channel);
+    *tab_data.giveaway.locked() = Giveaway::default();
+    tab_data.poll.locked().end();
+    tab_data.seen_chatters.locked().clear();
+    tab_data.recent_messages.locked().clear();
+    *tab_data.reply_target.locked() = None;
+}
+
+// Swaps the tab's web process for a fresh one; load_changed replays message_buffer
+fn reload_chat_view(tab_data: &TabData) {
+    tab_data.memory_warned.store(false, Ordering::Relaxed);
+    tab_data.webview.terminate_web_process();
+    tab_data.webview.load_html(get_chat_html_template(), None);
+}
+
+// Switches a tab between the WebView and plain text. The text starts from the messages
+// kept for the moderation tools; the WebView reloads and replays message_buffer.
+fn set_tab_lightweight(tab_data: &TabData, lightweight: bool) {
+    if tab_data.lightweight.swap(lightweight, Ordering::Relaxed) == lightweight {
+        return;
+    }
+    tab_data.lightweight_view.clear();
+    tab_data.lightweight_view.show_text(lightweight);
+    if !lightweight {
+        if tab_data.channel_name.locked().is_some() {
+            load_chat_page(tab_data);
+        }
+        return;
+    }
+
+    let bot_settings = get_bot_settings();
+    let filters = tab_data.filters.locked();
+    let shown: Vec<_> = tab_data
+        .recent_messages
+        .locked()
+        .iter()
+        .filter(|msg| !is_hidden(msg, &bot_settings, &filters))
+        .cloned()
+        .collect();
+    drop(filters);
+    tab_data.lightweight_view.append_messages(&shown);
+
+    tab_data.pending_messages.locked().clear();
+    tab_data.memory_warned.store(false, Ordering::Relaxed);
+    tab_data.hibernated.store(false, Ordering::Relaxed);
+    tab_data.chat_page_loaded.store(false, Ordering::Relaxed);
+    tab_data.webview.terminate_web_process();
+}
+
+// Frees a background tab's web process until the tab is selected again
+fn hibernate_chat_view(tab_data: &TabData) {
+    tab_data.memory_warned.store(false, Ordering::Relaxed);
+    tab_data.hibernated.store(true, Ordering::Relaxed);
+    tab_data.webview.terminate_web_process();
+}
+
+fn check_tab_memory(window: &ApplicationWindow, tab_view: &TabView, tab_data: &Arc<TabData>, settings: &WatchdogSettings) {
+    if tab_data.hibernated.load(Ordering::Relaxed) || tab_data.memory_warned.load(Ordering::Relaxed) {
+        return;
+    }
+    let Some(pid) = *tab_data.web_process.locked() else {
+        return;
+    };
+    let Some(used_mb) = resident_mb(pid) else {
+        return;
+    };
+    if used_mb < settings.threshold_mb as u64 {
+        return;
+    }
+    let is_selected = tab_view.selected_page().as_ref() == Some(&tab_data.page);
+    println!("Chat view for tab {:?} uses {} MB", tab_data.channel_name.locked(), used_mb);
+    match settings.action {
+        WatchdogAction::Reload => reload_chat_view(tab_data),
+        WatchdogAction::Hibernate if !is_selected => hibernate_chat_view(tab_data),
+        WatchdogAction::Hibernate | WatchdogAction::Ask => {
+            tab_data.memory_warned.store(true, Ordering::Relaxed);
+            let channel = tab_data
+                .channel_name
+                .locked()
+                .clone()
+                .map(|channel| format!("#{}", channel))
+                .unwrap_or_else(|| "a tab".to_string());
+            let dialog = adw::AlertDialog::builder()
+                .heading("Chat View Using a Lot of Memory")
+                .body(format!(
+                    "The chat view for {} is using {} MB. Reloading it frees the memory and keeps recent messages.",
+                    channel, used_mb
+                ))
+                .build();
+            dialog.add_responses(&[("ignore", "Ignore"), ("reload", "Reload")]);
+            dialog.set_response_appearance("reload", adw::ResponseAppearance::Suggested);
+            dialog.set_default_response(Some("reload"));
+            dialog.set_close_response("ignore");
+            let tab_data = tab_data.clone();
+            dialog.connect_response(Some("reload"), move |_, _| {
+                reload_chat_view(&tab_data);
+            });
+            dialog.present(Some(window));
+        }
+    }
+}
 
-    let mut state = tab_data.client_state.lock().unwrap();
-    // Create a new runtime if one doesn't exist (e.g., after reconnect)
-    if state.runtime.is_none() {
-        state.runtime = Some(Runtime::new().unwrap());
+// Stops a VOD replay or preview channel feeding the tab
+fn stop_playback(tab_data: &TabData) {
+    if let Some(control) = tab_data.replay.locked().take() {
+        control.locked().stopped = true;
     }
-    let runtime = state.runtime.take().unwrap();
-    drop(state);
+    if let Some(stop) = tab_data.demo_stop.locked().take() {
+        stop.store(true, Ordering::Relaxed);
+    }
+    tab_data.replay_bar.revealer.set_reveal_child(false);
+}
 
-    let handle = thread::spawn(move || {
-        runtime.block_on(async move {
-            let config = ClientConfig::default();
-            let (mut incoming_messages, client) = TwitchIRCClient::<SecureTCPTransport, StaticLoginCredentials>::new(config);
+// Fills the tab with synthetic chat for tuning appearance without joining a channel
+fn start_demo_for_tab(rate: u32, tab_data: &Arc<TabData>) {
+    end_session(tab_data);
+    *tab_data.channel_name.locked() = Some(DEMO_CHANNEL.to_string());
+    reset_session_state(tab_data, DEMO_CHANNEL);
 
-            if let Err(e) = client.join(channel.clone()) {
-                eprintln!("Failed to join channel '{}': {}", channel, e);
-                let _ = error_tx.send(());
+    clear_chat_content(tab_data);
+    load_chat_page(tab_data);
+    tab_data.notes_pane.load(None);
+    tab_data.send_bar.set_visible(false);
+    tab_data.stack.set_visible_child_name("chat");
+    tab_data.page.set_title("Preview");
+    tab_data.page.set_tooltip(&format!("Synthetic chat, {} messages per second", rate));
+
+    let stop = Arc::new(AtomicBool::new(false));
+    *tab_data.demo_stop.locked() = Some(stop.clone());
+    start_demo(tab_data.queue.clone(), rate, stop);
+}
+
+// Replays a VOD's chat in the tab instead of a live channel
+fn start_vod_replay_for_tab(video_id: &str, tab_data: &Arc<TabData>) {
+    end_session(tab_data);
+    tab_data.page.set_title(&format!("VOD {}", video_id));
+    tab_data.page.set_loading(true);
+
+    let video_id = video_id.to_string();
+    let tab_data = tab_data.clone();
+    glib::MainContext::default().spawn_local(async move {
+        let id_for_fetch = video_id.clone();
+        let result = adw::gio::spawn_blocking(move || {
+            fetch_vod_info(&http_client(), &id_for_fetch).map_err(|e| e.to_string())
+        })
+        .await
+        .unwrap_or_else(|_| Err("VOD lookup panicked".to_string()));
+        tab_data.page.set_loading(false);
+        let info = match result {
+            Ok(info) => info,
+            Err(e) => {
+                eprintln!("Failed to load VOD {}: {}", video_id, e);
+                tab_data.page.set_title("New Tab");
                 return;
             }
+        };
 
-            {
-                let mut state = client_state_thread.lock().unwrap();
-                state.client = Some(client);
-            }
+        // Drop anything still queued from the previous session
+        tab_data.queue.clear();
+        *tab_data.channel_name.locked() = Some(info.channel_login.clone());
+        reset_session_state(&tab_data, &info.channel_login);
+
+        clear_chat_content(&tab_data);
+        load_chat_page(&tab_data);
+        tab_data.notes_pane.load(Some(&info.channel_login));
+        tab_data.send_bar.set_visible(false);
+        tab_data.stack.set_visible_child_name("chat");
+        tab_data.page.set_title(&format!("{} (VOD)", info.channel_login));
+        tab_data.page.set_tooltip(&glib::markup_escape_text(&info.title));
+
+        let control = Arc::new(Mutex::new(ReplayControl::new(info.length_secs)));
+        *tab_data.replay.locked() = Some(control.clone());
+        tab_data.replay_bar.reset();
+        tab_data.replay_bar.revealer.set_reveal_child(true);
+        start_replay(info, tab_data.queue.clone(), control);
+    });
+}
 
-            {
-                let mut state = connection_state.lock().unwrap();
-                *state = ConnectionState::Connected(channel.clone());
-            }
+fn start_connection_for_tab(
+    channel: &str,
+    tab_data: &Arc<TabData>
+) {
+    // Convert channel name to lowercase as Twitch requires lowercase channel names
+    let channel = channel.to_lowercase();
 
-            // Message reception loop - process all tabs regardless of activity
-            while let Some(message) = incoming_messages.recv().await {
-                if let twitch_irc::message::ServerMessage::Privmsg(msg) = message {
-                    if shutdown_flag.load(Ordering::Acquire) {
-                        println!("Shutdown flag set, exiting message loop");
-                        break;
-                    }
+    // Reconnecting to the channel already shown keeps its messages; anything else,
+    // including a replay or preview of it, starts from an empty view
+    let was_playback = tab_data.replay.locked().is_some() || tab_data.demo_stop.locked().is_some();
+    let previous_channel = tab_data.channel_name.locked().replace(channel.clone());
+    let same_session = previous_channel.as_deref() == Some(channel.as_str()) && !was_playback;
+    *tab_data.connection_state.locked() = ConnectionState::Connecting;
+    tab_data.stalled.store(false, Ordering::Relaxed);
+    tab_data.crash_banner.set_revealed(false);
+    stop_playback(tab_data);
+    if !same_session {
+        clear_chat_content(tab_data);
+        reset_session_state(tab_data, &channel);
+    }
+    set_tab_lightweight(tab_data, is_lightweight_channel(&channel));
+    load_chat_page(tab_data);
+    tab_data.notes_pane.load(Some(&channel));
+    tab_data.stack.set_visible_child_name("chat");
+    tab_data.page.set_title(&channel_display_name(&channel));
+    // Connects as soon as the network is back, shown as loading until then
+    tab_data.page.set_loading(is_offline());
+    let connection_state = tab_data.connection_state.clone();
+    let client_state_task = tab_data.client_state.clone();
+    let client_state_store = tab_data.client_state.clone();
+    let queue = tab_data.queue.clone();
+    let error_tx = tab_data.error_tx.clone();
+    let room_state = tab_data.room_state.clone();
+    let chat_event_tx = tab_data.chat_event_tx.clone();
+    let signed_in = tab_data.signed_in.clone();
+    tab_data.signed_in.store(false, Ordering::Relaxed);
+    tab_data.send_bar.set_visible(true);
+    update_send_controls(tab_data);
+
+    let transport = get_network_settings().chat_transport;
+
+    let handle = connections::spawn_connection(async move {
+        // Signed in, the tab can send as the user; otherwise it only reads. The keyring
+        // lookup blocks, so it stays off the runtime's workers.
+        let login = tokio::task::spawn_blocking(chat_login).await.ok().flatten();
+        signed_in.store(login.is_some(), Ordering::Relaxed);
+        let credentials = login.unwrap_or_else(StaticLoginCredentials::anonymous);
+        let (mut incoming_messages, client) = ChatClient::connect(transport, credentials);
+
+        if let Err(e) = client.join(channel.clone()) {
+            eprintln!("Failed to join channel '{}': {}", channel, e);
+            let _ = error_tx.send(());
+            return;
+        }
 
-                    // Always send messages to UI thread (no pausing)
-                    let send_result = tx.try_send(msg.clone());
-
-                    match send_result {
-                        Ok(_) => {},
-                        Err(std::sync::mpsc::TrySendError::Full(_)) => {
-                            // Channel is full - UI thread is overwhelmed
-                            use std::time::{SystemTime, UNIX_EPOCH};
-                            let now = SystemTime::now()
-                                .duration_since(UNIX_EPOCH)
-                                .unwrap()
-                                .as_secs();
-
-                            static LAST_WARNING: AtomicU64 = AtomicU64::new(0);
-                            let last_warning = LAST_WARNING.load(Ordering::Relaxed);
-
-                            if now.saturating_sub(last_warning) >= 5 {
-                                eprintln!("UI thread message queue full, dropping messages to prevent freeze");
-                                LAST_WARNING.store(now, Ordering::Relaxed);
-                            }
-                        }
-                        Err(std::sync::mpsc::TrySendError::Disconnected(_)) => {
-                            eprintln!("UI thread disconnected, stopping message processing");
-                            break;
-                        }
-                    }
+        {
+            let mut state = client_state_task.locked();
+            state.client = Some(client);
+        }
+
+        {
+            let mut state = connection_state.locked();
+            *state = ConnectionState::Connected(channel.clone());
+        }
+
+        // Message reception loop - process all tabs regardless of activity
+        while let Some(message) = incoming_messages.recv().await {
+            if let Some(event) = ChatEvent::from_server_message(&message) {
+                let _ = chat_event_tx.send(event);
+            }
+            match &message {
+                twitch_irc::message::ServerMessage::RoomState(msg) => {
+                    room_state.locked().apply_roomstate(msg);
                 }
+                twitch_irc::message::ServerMessage::UserState(msg) => {
+                    room_state.locked().apply_userstate(msg);
+                }
+                twitch_irc::message::ServerMessage::Notice(msg) => {
+                    room_state.locked().apply_notice(msg);
+                }
+                _ => {}
             }
+            if let twitch_irc::message::ServerMessage::Privmsg(msg) = message {
+                // Never blocks; the oldest queued message is dropped if the UI falls behind
+                queue.push(msg);
+            }
+        }
 
-            {
-                let mut state = connection_state.lock().unwrap();
-                if matches!(*state, ConnectionState::Connected(ref c) if c == &channel) {
-                    *state = ConnectionState::Disconnected;
-                }
+        {
+            let mut state = connection_state.locked();
+            if matches!(*state, ConnectionState::Connected(ref c) if c == &channel) {
+                *state = ConnectionState::Disconnected;
             }
-        });
+        }
     });
 
     {
-        let mut state = client_state_store.lock().unwrap();
-        state.join_handle = Some(handle);
+        let mut state = client_state_store.locked();
+        // A connection still running for this tab would otherwise keep feeding it
+        if let Some(previous) = state.connection.replace(handle) {
+            previous.abort();
+        }
     }
 }
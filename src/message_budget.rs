@@ -0,0 +1,167 @@
+// message_budget.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+// A tab never evicts below this many messages for the global budget, so a quiet tab
+// still shows some context after a busy one has used up the budget
+const MIN_RETAINED: usize = 100;
+const BYTES_PER_MB: usize = 1024 * 1024;
+
+static MAX_MESSAGES: AtomicUsize = AtomicUsize::new(2000);
+static MAX_TAB_BYTES: AtomicUsize = AtomicUsize::new(16 * BYTES_PER_MB);
+static MAX_TOTAL_BYTES: AtomicUsize = AtomicUsize::new(128 * BYTES_PER_MB);
+static TOTAL_BYTES: AtomicUsize = AtomicUsize::new(0);
+static TOTAL_EVICTED: AtomicU64 = AtomicU64::new(0);
+
+// Stored under [message_budget] in favorites.toml
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MessageBudgetSettings {
+    pub max_messages: u32, // Per tab
+    pub max_tab_mb: u32,
+    pub max_total_mb: u32, // Across all tabs
+}
+
+impl Default for MessageBudgetSettings {
+    fn default() -> Self {
+        Self {
+            max_messages: 2000,
+            max_tab_mb: 16,
+            max_total_mb: 128,
+        }
+    }
+}
+
+/// Applies changed settings; called at startup and from preferences. Buffers over the
+/// new limits shrink as their next message arrives.
+pub fn configure_message_budget(settings: &MessageBudgetSettings) {
+    MAX_MESSAGES.store(settings.max_messages.max(MIN_RETAINED as u32) as usize, Ordering::Relaxed);
+    MAX_TAB_BYTES.store(settings.max_tab_mb.max(1) as usize * BYTES_PER_MB, Ordering::Relaxed);
+    MAX_TOTAL_BYTES.store(settings.max_total_mb.max(1) as usize * BYTES_PER_MB, Ordering::Relaxed);
+}
+
+/// Per-tab message count limit, also used for the messages kept for the moderation tools
+pub fn max_retained_messages() -> usize {
+    MAX_MESSAGES.load(Ordering::Relaxed)
+}
+
+/// Bytes held by all tabs' buffers and messages evicted from them this run
+pub fn retained_totals() -> (usize, u64) {
+    (TOTAL_BYTES.load(Ordering::Relaxed), TOTAL_EVICTED.load(Ordering::Relaxed))
+}
+
+/// Rendered messages a tab keeps to replay into a fresh chat page, oldest first,
+/// within the per-tab and global budgets. Eviction drops the oldest messages; with
+/// chat history saved they stay reachable by scrolling back.
+#[derive(Default)]
+pub struct MessageBuffer {
+    messages: VecDeque<String>,
+    bytes: usize,
+    evicted: u64,
+}
+
+impl MessageBuffer {
+    pub fn push(&mut self, html: String) {
+        self.bytes += html.len();
+        TOTAL_BYTES.fetch_add(html.len(), Ordering::Relaxed);
+        self.messages.push_back(html);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let max_messages = MAX_MESSAGES.load(Ordering::Relaxed);
+        let max_tab_bytes = MAX_TAB_BYTES.load(Ordering::Relaxed);
+        let max_total_bytes = MAX_TOTAL_BYTES.load(Ordering::Relaxed);
+        while self.messages.len() > 1 {
+            let over_tab = self.messages.len() > max_messages || self.bytes > max_tab_bytes;
+            let over_total = self.messages.len() > MIN_RETAINED && TOTAL_BYTES.load(Ordering::Relaxed) > max_total_bytes;
+            if !over_tab && !over_total {
+                break;
+            }
+            let Some(oldest) = self.messages.pop_front() else {
+                break;
+            };
+            self.bytes -= oldest.len();
+            TOTAL_BYTES.fetch_sub(oldest.len(), Ordering::Relaxed);
+            self.evicted += 1;
+            TOTAL_EVICTED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Rewrites the newest message containing `marker`, for details that arrive later
+    pub fn update_newest(&mut self, marker: &str, update: impl FnOnce(&str) -> Option<String>) {
+        let Some(entry) = self.messages.iter_mut().rev().find(|html| html.contains(marker)) else {
+            return;
+        };
+        let Some(updated) = update(entry) else {
+            return;
+        };
+        TOTAL_BYTES.fetch_add(updated.len(), Ordering::Relaxed);
+        TOTAL_BYTES.fetch_sub(entry.len(), Ordering::Relaxed);
+        self.bytes = self.bytes + updated.len() - entry.len();
+        *entry = updated;
+    }
+
+    /// Rewrites every message containing `marker`, like all of a timed out user's
+    pub fn update_all(&mut self, marker: &str, update: impl Fn(&str) -> Option<String>) {
+        for entry in self.messages.iter_mut().filter(|html| html.contains(marker)) {
+            let Some(updated) = update(entry) else {
+                continue;
+            };
+            TOTAL_BYTES.fetch_add(updated.len(), Ordering::Relaxed);
+            TOTAL_BYTES.fetch_sub(entry.len(), Ordering::Relaxed);
+            self.bytes = self.bytes + updated.len() - entry.len();
+            *entry = updated;
+        }
+    }
+
+    /// Drops the newest message containing `marker`, like one deleted by a moderator
+    pub fn remove_newest(&mut self, marker: &str) {
+        let Some(index) = self.messages.iter().rposition(|html| html.contains(marker)) else {
+            return;
+        };
+        if let Some(removed) = self.messages.remove(index) {
+            self.bytes -= removed.len();
+            TOTAL_BYTES.fetch_sub(removed.len(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        TOTAL_BYTES.fetch_sub(self.bytes, Ordering::Relaxed);
+        self.messages.clear();
+        self.bytes = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.messages.iter()
+    }
+
+    /// Every message, one per line, as the chat page takes them
+    pub fn joined(&self) -> String {
+        self.messages.iter().cloned().collect::<Vec<_>>().join("\n")
+    }
+}
+
+impl Drop for MessageBuffer {
+    fn drop(&mut self) {
+        TOTAL_BYTES.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,73 @@
+// message_queue.rs
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::pump::TabWaker;
+use crate::state::MutexExt;
+
+pub const DEFAULT_QUEUE_CAPACITY: usize = 500;
+
+/// Bounded queue between a message source and the UI thread. Pushing never blocks:
+/// when full, the oldest message is dropped and counted, so slow rendering can't
+/// back up the network task. Each push wakes the tab's message pump.
+pub struct MessageQueue {
+    messages: Mutex<VecDeque<PrivmsgMessage>>,
+    capacity: usize,
+    skipped: AtomicU64,
+    waker: TabWaker,
+}
+
+impl MessageQueue {
+    pub fn new(capacity: usize, waker: TabWaker) -> Self {
+        Self {
+            messages: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            skipped: AtomicU64::new(0),
+            waker,
+        }
+    }
+
+    pub fn push(&self, msg: PrivmsgMessage) {
+        let mut messages = self.messages.locked();
+        if messages.len() >= self.capacity {
+            messages.pop_front();
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        messages.push_back(msg);
+        drop(messages);
+        self.waker.wake();
+    }
+
+    /// Up to `max` messages, oldest first
+    pub fn drain(&self, max: usize) -> Vec<PrivmsgMessage> {
+        let mut messages = self.messages.locked();
+        let count = messages.len().min(max);
+        messages.drain(..count).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.locked().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.messages.locked().clear();
+        self.skipped.store(0, Ordering::Relaxed);
+    }
+
+    /// Messages dropped since the last call
+    pub fn take_skipped(&self) -> u64 {
+        self.skipped.swap(0, Ordering::Relaxed)
+    }
+}
+
+// Divider marking the gap left by dropped messages in the chat view
+pub fn skipped_notice_html(count: u64) -> String {
+    format!(
+        r#"<div class="skipped-notice" role="separator" title="Messages arrived faster than they could be shown">{} message{} skipped (chat too fast)</div>"#,
+        count,
+        if count == 1 { "" } else { "s" }
+    )
+}
@@ -0,0 +1,300 @@
+// mod_tools.rs
+
+use adw::prelude::*;
+use adw::{AlertDialog, ComboRow, Dialog, EntryRow, HeaderBar, PreferencesGroup, SpinRow, ToolbarView};
+use chrono::{Duration as ChronoDuration, Utc};
+use gtk::{Align, Box as GtkBox, Button, Label, ListBox, Orientation, ScrolledWindow};
+use regex::Regex;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::helix::ban_user;
+use crate::network::http_client;
+use crate::state::MutexExt;
+
+// A chatter whose recent messages matched the mass action pattern
+#[derive(Debug, Clone)]
+pub struct MassActionTarget {
+    pub user_id: String,
+    pub login: String,
+    pub sample: String, // Most recent matching message
+    pub matches: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MassAction {
+    Timeout(u32),
+    Ban,
+}
+
+impl MassAction {
+    const ALL: [MassAction; 4] = [
+        MassAction::Timeout(60),
+        MassAction::Timeout(600),
+        MassAction::Timeout(3600),
+        MassAction::Ban,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            MassAction::Timeout(60) => "Timeout 1 minute",
+            MassAction::Timeout(600) => "Timeout 10 minutes",
+            MassAction::Timeout(3600) => "Timeout 1 hour",
+            MassAction::Timeout(_) => "Timeout",
+            MassAction::Ban => "Ban",
+        }
+    }
+
+    fn duration_secs(self) -> Option<u32> {
+        match self {
+            MassAction::Timeout(secs) => Some(secs),
+            MassAction::Ban => None,
+        }
+    }
+}
+
+/// Senders of messages from the last `window_minutes` that match `pattern`, newest first.
+/// Broadcaster and moderators are never included.
+pub fn find_mass_action_targets(
+    messages: &VecDeque<PrivmsgMessage>,
+    pattern: &Regex,
+    window_minutes: i64,
+) -> Vec<MassActionTarget> {
+    let cutoff = Utc::now() - ChronoDuration::minutes(window_minutes);
+    let mut targets: Vec<MassActionTarget> = Vec::new();
+    for msg in messages.iter().rev() {
+        if msg.server_timestamp < cutoff {
+            break;
+        }
+        let protected = msg
+            .badges
+            .iter()
+            .any(|badge| badge.name == "broadcaster" || badge.name == "moderator");
+        if protected || !pattern.is_match(&msg.message_text) {
+            continue;
+        }
+        match targets.iter_mut().find(|t| t.user_id == msg.sender.id) {
+            Some(target) => target.matches += 1,
+            None => targets.push(MassActionTarget {
+                user_id: msg.sender.id.clone(),
+                login: msg.sender.login.clone(),
+                sample: msg.message_text.clone(),
+                matches: 1,
+            }),
+        }
+    }
+    targets
+}
+
+// --- Mass Ban Dialog ---
+
+pub fn show_mass_ban_dialog(
+    parent: &impl IsA<gtk::Widget>,
+    channel: &str,
+    recent_messages: &Arc<Mutex<VecDeque<PrivmsgMessage>>>,
+) {
+    let dialog = Dialog::builder()
+        .title(format!("Mass Moderation — {}", channel))
+        .content_width(480)
+        .content_height(560)
+        .build();
+
+    let content = GtkBox::new(Orientation::Vertical, 12);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let group = PreferencesGroup::builder()
+        .description("Preview who matches before applying, nothing is sent until you confirm")
+        .build();
+    let pattern_row = EntryRow::builder().title("Pattern (regular expression)").build();
+    let window_row = SpinRow::with_range(1.0, 60.0, 1.0);
+    window_row.set_title("Time Window (minutes)");
+    window_row.set_value(5.0);
+    let action_labels: Vec<&str> = MassAction::ALL.iter().map(|a| a.label()).collect();
+    let action_row = ComboRow::builder()
+        .title("Action")
+        .model(&gtk::StringList::new(&action_labels))
+        .selected(1)
+        .build();
+    let reason_row = EntryRow::builder().title("Reason").text("Mass moderation").build();
+    group.add(&pattern_row);
+    group.add(&window_row);
+    group.add(&action_row);
+    group.add(&reason_row);
+
+    let status_label = Label::new(Some("Enter a pattern and press Preview"));
+    status_label.add_css_class("dim-label");
+    status_label.set_halign(Align::Start);
+    status_label.set_wrap(true);
+
+    let results_list = ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .build();
+    results_list.add_css_class("boxed-list");
+    let results_scrolled = ScrolledWindow::builder()
+        .vexpand(true)
+        .child(&results_list)
+        .build();
+
+    let button_box = GtkBox::new(Orientation::Horizontal, 6);
+    button_box.set_halign(Align::End);
+    let preview_button = Button::with_label("Preview");
+    let apply_button = Button::with_label("Apply");
+    apply_button.add_css_class("destructive-action");
+    apply_button.set_sensitive(false);
+    button_box.append(&preview_button);
+    button_box.append(&apply_button);
+
+    content.append(&group);
+    content.append(&status_label);
+    content.append(&results_scrolled);
+    content.append(&button_box);
+
+    let toolbar_view = ToolbarView::new();
+    toolbar_view.add_top_bar(&HeaderBar::new());
+    toolbar_view.set_content(Some(&content));
+    dialog.set_child(Some(&toolbar_view));
+
+    let targets: Rc<RefCell<Vec<MassActionTarget>>> = Rc::new(RefCell::new(Vec::new()));
+
+    // Any change invalidates the preview, so Apply always acts on what was shown
+    let invalidate = {
+        let targets = targets.clone();
+        let apply_button = apply_button.clone();
+        move || {
+            targets.borrow_mut().clear();
+            apply_button.set_sensitive(false);
+            apply_button.set_label("Apply");
+        }
+    };
+    let invalidate_clone = invalidate.clone();
+    pattern_row.connect_changed(move |_| invalidate_clone());
+    let invalidate_clone = invalidate.clone();
+    window_row.connect_value_notify(move |_| invalidate_clone());
+
+    let recent_for_preview = recent_messages.clone();
+    let targets_for_preview = targets.clone();
+    let status_for_preview = status_label.clone();
+    let apply_for_preview = apply_button.clone();
+    let pattern_for_preview = pattern_row.clone();
+    let window_for_preview = window_row.clone();
+    preview_button.connect_clicked(move |_| {
+        while let Some(child) = results_list.first_child() {
+            results_list.remove(&child);
+        }
+        let pattern = match Regex::new(&pattern_for_preview.text()) {
+            Ok(pattern) if !pattern_for_preview.text().is_empty() => pattern,
+            Ok(_) => {
+                status_for_preview.set_text("Enter a pattern first");
+                return;
+            }
+            Err(e) => {
+                status_for_preview.set_text(&format!("Invalid pattern: {}", e));
+                return;
+            }
+        };
+        let found = find_mass_action_targets(
+            &recent_for_preview.locked(),
+            &pattern,
+            window_for_preview.value() as i64,
+        );
+        for target in &found {
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&target.login).as_str())
+                .subtitle(glib::markup_escape_text(&target.sample).as_str())
+                .subtitle_lines(1)
+                .build();
+            let count_label = Label::new(Some(&format!("{}×", target.matches)));
+            count_label.add_css_class("dim-label");
+            row.add_suffix(&count_label);
+            results_list.append(&row);
+        }
+        status_for_preview.set_text(&format!(
+            "{} matching chatter{} (dry run, nothing sent)",
+            found.len(),
+            if found.len() == 1 { "" } else { "s" }
+        ));
+        apply_for_preview.set_sensitive(!found.is_empty());
+        apply_for_preview.set_label(&format!("Apply to {}", found.len()));
+        *targets_for_preview.borrow_mut() = found;
+    });
+
+    let channel_id = recent_messages
+        .locked()
+        .back()
+        .map(|msg| msg.channel_id.clone());
+    let dialog_weak = dialog.downgrade();
+    apply_button.connect_clicked(move |button| {
+        let selected = targets.borrow().clone();
+        let Some(broadcaster_id) = channel_id.clone() else {
+            status_label.set_text("No messages received yet, channel unknown");
+            return;
+        };
+        if selected.is_empty() {
+            return;
+        }
+        let action = MassAction::ALL
+            .get(action_row.selected() as usize)
+            .copied()
+            .unwrap_or(MassAction::Ban);
+        let reason = reason_row.text().to_string();
+
+        let confirm = AlertDialog::builder()
+            .heading(format!("{} {} chatters?", action.label(), selected.len()))
+            .body("This is sent to Twitch immediately and cannot be undone from here.")
+            .build();
+        confirm.add_responses(&[("cancel", "Cancel"), ("apply", "Apply")]);
+        confirm.set_response_appearance("apply", adw::ResponseAppearance::Destructive);
+        confirm.set_default_response(Some("cancel"));
+        confirm.set_close_response("cancel");
+
+        let status_label = status_label.clone();
+        let button = button.clone();
+        confirm.connect_response(Some("apply"), move |_, _| {
+            button.set_sensitive(false);
+            status_label.set_text("Applying…");
+            let selected = selected.clone();
+            let broadcaster_id = broadcaster_id.clone();
+            let reason = reason.clone();
+            let status_label = status_label.clone();
+            glib::MainContext::default().spawn_local(async move {
+                let total = selected.len();
+                let failures = adw::gio::spawn_blocking(move || {
+                    let client = http_client();
+                    let mut failures = 0;
+                    for target in &selected {
+                        if let Err(e) = ban_user(
+                            &client,
+                            &broadcaster_id,
+                            &target.user_id,
+                            action.duration_secs(),
+                            &reason,
+                        ) {
+                            eprintln!("Failed to moderate {}: {}", target.login, e);
+                            failures += 1;
+                        }
+                    }
+                    failures
+                })
+                .await
+                .unwrap_or(total);
+                status_label.set_text(&format!(
+                    "Applied to {} of {} chatters",
+                    total - failures,
+                    total
+                ));
+            });
+        });
+
+        if let Some(dialog) = dialog_weak.upgrade() {
+            confirm.present(Some(&dialog));
+        }
+    });
+
+    dialog.present(Some(parent));
+}
@@ -0,0 +1,240 @@
+// notes.rs
+
+use adw::prelude::*;
+use chrono::Local;
+use gtk::{Box as GtkBox, Label, Orientation, Revealer, RevealerTransitionType, ScrolledWindow, TextBuffer, TextView};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::profile::data_dir;
+
+// Typing pauses this long before the scratchpad is written out
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+// A local moderation note about a chatter, never sent anywhere
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UserNote {
+    pub created: String, // "YYYY-MM-DD HH:MM", local time
+    pub text: String,
+}
+
+// One file per channel: login -> notes, oldest first
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct ChannelNotes {
+    #[serde(default)]
+    scratchpad: String, // Free-form running notes from the tab's notes pane
+    #[serde(default)]
+    users: HashMap<String, Vec<UserNote>>,
+}
+
+fn notes_path(channel: &str) -> PathBuf {
+    data_dir()
+        .join("notes")
+        .join(format!("{}.toml", channel.to_lowercase()))
+}
+
+fn load_channel_notes(channel: &str) -> ChannelNotes {
+    let path = notes_path(channel);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return ChannelNotes::default();
+    };
+    toml::from_str(&contents).unwrap_or_else(|e| {
+        eprintln!("Failed to parse notes file {}: {}", path.display(), e);
+        ChannelNotes::default()
+    })
+}
+
+fn save_channel_notes(channel: &str, notes: &ChannelNotes) {
+    let path = notes_path(channel);
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            eprintln!("Failed to create notes directory: {}", e);
+            return;
+        }
+    }
+    match toml::to_string(notes) {
+        Ok(contents) => {
+            if let Err(e) = fs::write(&path, contents) {
+                eprintln!("Failed to write notes file {}: {}", path.display(), e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize notes: {}", e),
+    }
+}
+
+pub fn get_user_notes(channel: &str, login: &str) -> Vec<UserNote> {
+    load_channel_notes(channel)
+        .users
+        .remove(&login.to_lowercase())
+        .unwrap_or_default()
+}
+
+pub fn add_user_note(channel: &str, login: &str, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    let mut notes = load_channel_notes(channel);
+    notes
+        .users
+        .entry(login.to_lowercase())
+        .or_default()
+        .push(UserNote {
+            created: Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            text: text.to_string(),
+        });
+    save_channel_notes(channel, &notes);
+}
+
+pub fn remove_user_note(channel: &str, login: &str, index: usize) {
+    let mut notes = load_channel_notes(channel);
+    let login = login.to_lowercase();
+    if let Some(user_notes) = notes.users.get_mut(&login) {
+        if index < user_notes.len() {
+            user_notes.remove(index);
+        }
+        if user_notes.is_empty() {
+            notes.users.remove(&login);
+        }
+    }
+    save_channel_notes(channel, &notes);
+}
+
+pub fn get_scratchpad(channel: &str) -> String {
+    load_channel_notes(channel).scratchpad
+}
+
+pub fn set_scratchpad(channel: &str, text: &str) {
+    let mut notes = load_channel_notes(channel);
+    if notes.scratchpad == text {
+        return;
+    }
+    notes.scratchpad = text.to_string();
+    save_channel_notes(channel, &notes);
+}
+
+// --- Notes Pane ---
+
+/// Collapsible pane beside a tab's chat with running notes about its channel
+pub struct NotesPane {
+    pub revealer: Revealer,
+    text_view: TextView,
+    channel: Rc<RefCell<Option<String>>>,
+    pending_save: Rc<RefCell<Option<glib::SourceId>>>,
+    loading: Rc<Cell<bool>>, // Set while text is swapped in, which isn't an edit
+}
+
+pub fn build_notes_pane() -> NotesPane {
+    let heading = Label::new(Some("Notes"));
+    heading.add_css_class("heading");
+    heading.set_halign(gtk::Align::Start);
+    let hint = Label::new(Some("Kept for this channel, only on this computer"));
+    hint.add_css_class("dim-label");
+    hint.add_css_class("caption");
+    hint.set_halign(gtk::Align::Start);
+    hint.set_wrap(true);
+    hint.set_xalign(0.0);
+
+    let text_view = TextView::builder()
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .top_margin(6)
+        .bottom_margin(6)
+        .left_margin(6)
+        .right_margin(6)
+        .sensitive(false)
+        .build();
+    text_view.add_css_class("card");
+    let scrolled_window = ScrolledWindow::builder()
+        .child(&text_view)
+        .vexpand(true)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .build();
+
+    let content = GtkBox::new(Orientation::Vertical, 6);
+    content.set_width_request(240);
+    content.set_margin_top(6);
+    content.set_margin_bottom(6);
+    content.set_margin_start(6);
+    content.set_margin_end(6);
+    content.append(&heading);
+    content.append(&hint);
+    content.append(&scrolled_window);
+
+    let pane = GtkBox::new(Orientation::Horizontal, 0);
+    pane.append(&gtk::Separator::new(Orientation::Vertical));
+    pane.append(&content);
+
+    let revealer = Revealer::builder()
+        .transition_type(RevealerTransitionType::SlideLeft)
+        .child(&pane)
+        .reveal_child(false)
+        .build();
+
+    let notes_pane = NotesPane {
+        revealer,
+        text_view,
+        channel: Rc::new(RefCell::new(None)),
+        pending_save: Rc::new(RefCell::new(None)),
+        loading: Rc::new(Cell::new(false)),
+    };
+
+    let channel = notes_pane.channel.clone();
+    let pending_save = notes_pane.pending_save.clone();
+    let loading = notes_pane.loading.clone();
+    notes_pane.text_view.buffer().connect_changed(move |buffer| {
+        if loading.get() {
+            return;
+        }
+        let Some(channel) = channel.borrow().clone() else {
+            return;
+        };
+        if let Some(source) = pending_save.borrow_mut().take() {
+            source.remove();
+        }
+        let buffer = buffer.clone();
+        let pending_save_for_timer = pending_save.clone();
+        *pending_save.borrow_mut() = Some(glib::timeout_add_local_once(SAVE_DELAY, move || {
+            pending_save_for_timer.borrow_mut().take();
+            set_scratchpad(&channel, &buffer_text(&buffer));
+        }));
+    });
+
+    notes_pane
+}
+
+fn buffer_text(buffer: &TextBuffer) -> String {
+    let (start, end) = buffer.bounds();
+    buffer.text(&start, &end, false).to_string()
+}
+
+impl NotesPane {
+    /// Shows the notes of `channel`, saving those of the previous one first.
+    /// Without a channel the pane is empty and can't be edited.
+    pub fn load(&self, channel: Option<&str>) {
+        if self.channel.borrow().as_deref() == channel {
+            return;
+        }
+        self.save_now();
+        *self.channel.borrow_mut() = channel.map(str::to_string);
+        self.loading.set(true);
+        self.text_view.buffer().set_text(&channel.map(get_scratchpad).unwrap_or_default());
+        self.loading.set(false);
+        self.text_view.set_sensitive(channel.is_some());
+    }
+
+    /// Writes out an edit that's still waiting for typing to pause
+    pub fn save_now(&self) {
+        let Some(source) = self.pending_save.borrow_mut().take() else {
+            return;
+        };
+        source.remove();
+        if let Some(channel) = self.channel.borrow().as_deref() {
+            set_scratchpad(channel, &buffer_text(&self.text_view.buffer()));
+        }
+    }
+}
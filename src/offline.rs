@@ -0,0 +1,26 @@
+// offline.rs
+
+use adw::gio;
+use adw::prelude::*;
+
+use crate::network::set_offline;
+pub use crate::network::is_offline;
+
+/// Follows the system's network state; `on_change` runs on the main thread with
+/// `true` when the network goes away and `false` when it's back. The monitor
+/// reports every route change, so only actual transitions get through.
+pub fn watch_network(on_change: impl Fn(bool) + 'static) {
+    let monitor = gio::NetworkMonitor::default();
+    let offline = !monitor.is_network_available();
+    set_offline(offline);
+    if offline {
+        on_change(true);
+    }
+    monitor.connect_network_changed(move |_, available| {
+        let offline = !available;
+        if set_offline(offline) {
+            println!("Network {}", if offline { "lost, going offline" } else { "is back" });
+            on_change(offline);
+        }
+    });
+}
@@ -0,0 +1,66 @@
+// pacing.rs
+
+use std::time::Duration;
+
+// One frame at 60 Hz. Injections that take longer than this make scrolling stutter.
+const FRAME_BUDGET: Duration = Duration::from_millis(16);
+const MIN_BATCH: usize = 5;
+const MAX_BATCH: usize = 200;
+const START_BATCH: usize = 30;
+const MIN_INTERVAL: Duration = Duration::from_millis(8);
+const MAX_INTERVAL: Duration = Duration::from_millis(250);
+const START_INTERVAL: Duration = Duration::from_millis(30);
+
+/// How much a shown tab injects at once and how often, tuned from how long its recent
+/// injections took: slow WebViews get smaller, sparser batches, fast ones larger and
+/// more frequent
+#[derive(Debug, Clone)]
+pub struct RenderPacing {
+    batch_size: usize,
+    interval: Duration,
+    latency: Option<Duration>, // Smoothed over recent injections
+}
+
+impl Default for RenderPacing {
+    fn default() -> Self {
+        Self {
+            batch_size: START_BATCH,
+            interval: START_INTERVAL,
+            latency: None,
+        }
+    }
+}
+
+impl RenderPacing {
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Least time between the end of one injection and the start of the next
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Takes in how long an injection of `messages` messages took to complete
+    pub fn record(&mut self, latency: Duration, messages: usize) {
+        let smoothed = match self.latency {
+            Some(previous) => (previous * 3 + latency) / 4,
+            None => latency,
+        };
+        self.latency = Some(smoothed);
+
+        if smoothed > FRAME_BUDGET {
+            // Back off quickly: scale the batch down to what fits the budget
+            let per_message = smoothed / messages.max(1) as u32;
+            let fits = FRAME_BUDGET.as_nanos() / per_message.as_nanos().max(1);
+            self.batch_size = (fits as usize).clamp(MIN_BATCH, self.batch_size.max(MIN_BATCH));
+            self.interval = (self.interval * 2).min(MAX_INTERVAL);
+        } else if smoothed < FRAME_BUDGET / 2 {
+            // Only full batches say anything about room for bigger ones
+            if messages >= self.batch_size {
+                self.batch_size = (self.batch_size + self.batch_size / 4 + 1).min(MAX_BATCH);
+            }
+            self.interval = (self.interval * 3 / 4).max(MIN_INTERVAL);
+        }
+    }
+}
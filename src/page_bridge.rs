@@ -0,0 +1,75 @@
+// page_bridge.rs
+
+use glib::prelude::*;
+use glib::Variant;
+use webkit6::prelude::*;
+use webkit6::WebView;
+
+// Runs each [name, args] pair against the page's global functions, skipping any the page
+// doesn't define (yet), like a template that is still loading
+const RUN_CALLS: &str = r#"
+for (const [name, args] of calls) {
+  const func = window[name];
+  if (typeof func === 'function') {
+    func(...args);
+  }
+}
+"#;
+
+/// Calls into the chat page's functions, sent together in one round trip. Arguments go
+/// over as GVariants and arrive as plain JS values, so HTML and chatter names need no
+/// escaping into script source.
+#[derive(Default)]
+pub struct PageCalls {
+    calls: Vec<(String, Vec<Variant>)>,
+}
+
+impl PageCalls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, function: &str, args: &[Variant]) {
+        self.calls.push((function.to_string(), args.to_vec()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.calls.clear();
+    }
+
+    /// Runs the calls, reporting a failure under `context`
+    pub fn run(self, webview: &WebView, context: &'static str) {
+        self.run_then(webview, move |result| {
+            if let Err(e) = result {
+                eprintln!("{}: {}", context, e);
+            }
+        });
+    }
+
+    pub fn run_then(self, webview: &WebView, done: impl FnOnce(Result<(), glib::Error>) + 'static) {
+        if self.calls.is_empty() {
+            return;
+        }
+        let arguments = glib::VariantDict::new(None);
+        arguments.insert_value("calls", &self.calls.to_variant());
+        webview.call_async_javascript_function(
+            RUN_CALLS,
+            Some(&arguments.end()),
+            None,
+            None,
+            None::<&adw::gio::Cancellable>,
+            move |result| done(result.map(|_| ())),
+        );
+    }
+}
+
+/// Calls one of the page's functions, if it's defined
+pub fn call_page(webview: &WebView, function: &str, args: &[Variant], context: &'static str) {
+    let mut calls = PageCalls::new();
+    calls.push(function, args);
+    calls.run(webview, context);
+}
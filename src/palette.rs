@@ -0,0 +1,178 @@
+// palette.rs
+
+use adw::prelude::*;
+use adw::{ActionRow, ApplicationWindow, Dialog};
+use gtk::{Box as GtkBox, ListBox, Orientation, ScrolledWindow, SearchEntry};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const MAX_RESULTS: usize = 50;
+
+// One selectable entry, activating `action` (with an optional string target) on the window
+#[derive(Debug, Clone)]
+pub struct PaletteItem {
+    pub title: String,
+    pub subtitle: String,
+    pub action: String,
+    pub target: Option<String>,
+}
+
+impl PaletteItem {
+    pub fn new(title: &str, subtitle: &str, action: &str, target: Option<&str>) -> Self {
+        Self {
+            title: title.to_string(),
+            subtitle: subtitle.to_string(),
+            action: action.to_string(),
+            target: target.map(str::to_string),
+        }
+    }
+}
+
+/// Subsequence match with bonuses for consecutive and word-start hits; None if `query` doesn't match
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|&i| text[i] == query_char)?;
+        score += 1;
+        if previous_match == Some(found.wrapping_sub(1)) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - position) as i32 / 4; // Gaps count against the match
+        previous_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+fn ranked_items(items: &[PaletteItem], query: &str) -> Vec<PaletteItem> {
+    let mut scored: Vec<(i32, &PaletteItem)> = items
+        .iter()
+        .filter_map(|item| {
+            let title_score = fuzzy_score(query, &item.title);
+            let subtitle_score = fuzzy_score(query, &item.subtitle).map(|s| s - 2);
+            title_score.max(subtitle_score).map(|score| (score, item))
+        })
+        .collect();
+    // Stable sort keeps the caller's order for ties
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(_, item)| item.clone())
+        .collect()
+}
+
+/// Shows a fuzzy-searchable list of `items`; Enter or a click activates the item's action
+pub fn show_palette(window: &ApplicationWindow, title: &str, placeholder: &str, items: Vec<PaletteItem>) {
+    let dialog = Dialog::builder()
+        .title(title)
+        .content_width(420)
+        .content_height(420)
+        .build();
+
+    let content = GtkBox::new(Orientation::Vertical, 6);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+
+    let search_entry = SearchEntry::builder()
+        .placeholder_text(placeholder)
+        .build();
+    let list = ListBox::builder()
+        .selection_mode(gtk::SelectionMode::Browse)
+        .build();
+    list.add_css_class("boxed-list");
+    let scrolled = ScrolledWindow::builder()
+        .vexpand(true)
+        .child(&list)
+        .build();
+    content.append(&search_entry);
+    content.append(&scrolled);
+    dialog.set_child(Some(&content));
+
+    let shown: Rc<RefCell<Vec<PaletteItem>>> = Rc::new(RefCell::new(Vec::new()));
+    let populate = {
+        let list = list.clone();
+        let shown = shown.clone();
+        move |query: &str| {
+            list.remove_all();
+            let ranked = ranked_items(&items, query);
+            for item in &ranked {
+                let row = ActionRow::builder()
+                    .title(glib::markup_escape_text(&item.title).as_str())
+                    .subtitle(glib::markup_escape_text(&item.subtitle).as_str())
+                    .activatable(true)
+                    .build();
+                list.append(&row);
+            }
+            if let Some(first) = list.row_at_index(0) {
+                list.select_row(Some(&first));
+            }
+            *shown.borrow_mut() = ranked;
+        }
+    };
+    populate("");
+    search_entry.connect_search_changed(move |entry| populate(&entry.text()));
+
+    let activate = {
+        let window = window.clone();
+        let dialog = dialog.clone();
+        move |index: i32| {
+            let Some(item) = shown.borrow().get(index as usize).cloned() else {
+                return;
+            };
+            dialog.close();
+            let target = item.target.as_ref().map(|t| t.to_variant());
+            let _ = window.activate_action(&item.action, target.as_ref());
+        }
+    };
+
+    let activate_row = activate.clone();
+    list.connect_row_activated(move |_, row| activate_row(row.index()));
+
+    let list_for_enter = list.clone();
+    search_entry.connect_activate(move |_| {
+        if let Some(row) = list_for_enter.selected_row() {
+            activate(row.index());
+        }
+    });
+
+    // Arrow keys move through results without leaving the search entry
+    let list_for_keys = list.clone();
+    search_entry.connect_next_match(move |_| move_selection(&list_for_keys, 1));
+    let list_for_keys = list.clone();
+    search_entry.connect_previous_match(move |_| move_selection(&list_for_keys, -1));
+    let key_controller = gtk::EventControllerKey::new();
+    key_controller.connect_key_pressed(move |_, key, _, _| match key {
+        gtk::gdk::Key::Down => {
+            move_selection(&list, 1);
+            glib::Propagation::Stop
+        }
+        gtk::gdk::Key::Up => {
+            move_selection(&list, -1);
+            glib::Propagation::Stop
+        }
+        _ => glib::Propagation::Proceed,
+    });
+    search_entry.add_controller(key_controller);
+
+    dialog.present(Some(window));
+    search_entry.grab_focus();
+}
+
+fn move_selection(list: &ListBox, step: i32) {
+    let current = list.selected_row().map(|row| row.index()).unwrap_or(-1);
+    if let Some(row) = list.row_at_index((current + step).max(0)) {
+        list.select_row(Some(&row));
+    }
+}
@@ -0,0 +1,282 @@
+// poll.rs
+
+use adw::prelude::*;
+use gtk::{Align, Box as GtkBox, Button, Entry, Label, LevelBar, Orientation, Popover, SpinButton};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::state::MutexExt;
+
+const MAX_OPTIONS: usize = 9; // Votes are single digits
+const DEFAULT_DURATION_MINUTES: f64 = 2.0;
+const MAX_DURATION_MINUTES: f64 = 30.0;
+
+/// Local poll for one tab: counts one vote per user, by option number or name,
+/// while the poll runs. A user voting again moves their vote.
+#[derive(Default)]
+pub struct Poll {
+    options: Vec<String>,
+    closes_at: Option<Instant>,
+    votes: HashMap<String, usize>, // Login to option index
+    round: u64, // Bumped on every start, so a stale end timer can tell
+}
+
+impl Poll {
+    pub fn start(&mut self, options: Vec<String>, duration: Duration) -> u64 {
+        *self = Self {
+            options,
+            closes_at: Some(Instant::now() + duration),
+            round: self.round + 1,
+            ..Self::default()
+        };
+        self.round
+    }
+
+    /// Ends the poll if it is still running, returning whether it was
+    pub fn end(&mut self) -> bool {
+        self.closes_at.take().is_some()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.closes_at.is_some_and(|closes_at| Instant::now() < closes_at)
+    }
+
+    pub fn time_left(&self) -> Option<Duration> {
+        self.closes_at
+            .map(|closes_at| closes_at.saturating_duration_since(Instant::now()))
+            .filter(|left| !left.is_zero())
+    }
+
+    // "2" votes for the second option, as does its name; anything else is chatter
+    fn option_for(&self, text: &str) -> Option<usize> {
+        let text = text.trim();
+        if let Ok(number) = text.parse::<usize>() {
+            return (1..=self.options.len()).contains(&number).then(|| number - 1);
+        }
+        self.options.iter().position(|option| option.eq_ignore_ascii_case(text))
+    }
+
+    pub fn record(&mut self, msg: &PrivmsgMessage) {
+        if !self.is_running() {
+            return;
+        }
+        if let Some(option) = self.option_for(&msg.message_text) {
+            self.votes.insert(msg.sender.login.clone(), option);
+        }
+    }
+
+    /// Each option with its vote count, in the order given
+    pub fn tally(&self) -> Vec<(String, usize)> {
+        let mut counts = vec![0; self.options.len()];
+        for option in self.votes.values() {
+            counts[*option] += 1;
+        }
+        self.options.iter().cloned().zip(counts).collect()
+    }
+}
+
+// Options are separated by commas, like "yes, no" or "1v1, 2v2, 3v3"
+fn parse_options(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Line in the chat view with the final tally, styled like the other tool notices
+pub fn results_notice_html(tally: &[(String, usize)]) -> String {
+    let total: usize = tally.iter().map(|(_, count)| count).sum();
+    let mut html = format!(
+        r#"<div class="tool-notice" role="status">Poll ended with {} vote{}:"#,
+        total,
+        if total == 1 { "" } else { "s" }
+    );
+    for (number, (option, count)) in tally.iter().enumerate() {
+        html.push_str(&format!(
+            " <b>{}. {}</b> {} ({}%)",
+            number + 1,
+            glib::markup_escape_text(option),
+            count,
+            (count * 100).checked_div(total).unwrap_or(0)
+        ));
+    }
+    html.push_str("</div>");
+    html
+}
+
+fn build_result_row(number: usize, option: &str, count: usize, total: usize) -> GtkBox {
+    let row = GtkBox::new(Orientation::Vertical, 2);
+    let label_box = GtkBox::new(Orientation::Horizontal, 6);
+    let name_label = Label::new(Some(&format!("{}. {}", number, option)));
+    name_label.set_halign(Align::Start);
+    name_label.set_hexpand(true);
+    name_label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    let count_label = Label::new(Some(&count.to_string()));
+    count_label.add_css_class("numeric");
+    label_box.append(&name_label);
+    label_box.append(&count_label);
+
+    let bar = LevelBar::for_interval(0.0, total.max(1) as f64);
+    bar.set_value(count as f64);
+    // The default offsets color low values as warnings, which means nothing here
+    bar.remove_offset_value(Some("low"));
+    bar.remove_offset_value(Some("high"));
+    bar.remove_offset_value(Some("full"));
+
+    row.append(&label_box);
+    row.append(&bar);
+    row
+}
+
+/// Popover for running a poll; `announce` gets the notice HTML once a poll ends
+pub fn build_poll_popover(poll: &Arc<Mutex<Poll>>, announce: impl Fn(String) + 'static) -> Popover {
+    let popover = Popover::builder().autohide(true).build();
+
+    let content = GtkBox::new(Orientation::Vertical, 6);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.set_width_request(260);
+
+    let title = Label::new(Some("Chat Poll"));
+    title.add_css_class("heading");
+    title.set_halign(Align::Start);
+
+    let options_entry = Entry::builder().placeholder_text("Options, separated by commas").build();
+
+    let duration_box = GtkBox::new(Orientation::Horizontal, 6);
+    let duration_label = Label::new(Some("Runs for (minutes)"));
+    duration_label.set_hexpand(true);
+    duration_label.set_halign(Align::Start);
+    let duration_spin = SpinButton::with_range(1.0, MAX_DURATION_MINUTES, 1.0);
+    duration_spin.set_value(DEFAULT_DURATION_MINUTES);
+    duration_box.append(&duration_label);
+    duration_box.append(&duration_spin);
+
+    let status_label = Label::new(None);
+    status_label.set_halign(Align::Start);
+    status_label.set_wrap(true);
+
+    let results = GtkBox::new(Orientation::Vertical, 6);
+
+    let button_box = GtkBox::new(Orientation::Horizontal, 6);
+    button_box.set_halign(Align::End);
+    button_box.set_margin_top(6);
+    let start_button = Button::with_label("Start");
+    start_button.add_css_class("suggested-action");
+    button_box.append(&start_button);
+
+    content.append(&title);
+    content.append(&options_entry);
+    content.append(&duration_box);
+    content.append(&status_label);
+    content.append(&results);
+    content.append(&button_box);
+    popover.set_child(Some(&content));
+
+    let refresh = {
+        let poll = poll.clone();
+        let status_label = status_label.clone();
+        let results = results.clone();
+        let start_button = start_button.clone();
+        move || {
+            let poll = poll.locked();
+            let tally = poll.tally();
+            let total: usize = tally.iter().map(|(_, count)| count).sum();
+            let status = match poll.time_left() {
+                Some(left) => format!(
+                    "Vote by typing a number or option: {} vote{}, {}:{:02} left",
+                    total,
+                    if total == 1 { "" } else { "s" },
+                    left.as_secs() / 60,
+                    left.as_secs() % 60
+                ),
+                None if tally.is_empty() => "Not running".to_string(),
+                None => format!("Ended with {} vote{}", total, if total == 1 { "" } else { "s" }),
+            };
+            status_label.set_text(&status);
+            while let Some(child) = results.first_child() {
+                results.remove(&child);
+            }
+            for (index, (option, count)) in tally.iter().enumerate() {
+                results.append(&build_result_row(index + 1, option, *count, total));
+            }
+            let running = poll.is_running();
+            start_button.set_label(if running { "End Poll" } else { "Start" });
+            if running {
+                start_button.remove_css_class("suggested-action");
+                start_button.add_css_class("destructive-action");
+            } else {
+                start_button.remove_css_class("destructive-action");
+                start_button.add_css_class("suggested-action");
+            }
+        }
+    };
+
+    // Refresh while visible; the timer stops itself once the popover closes
+    let refresh_on_show = refresh.clone();
+    popover.connect_show(move |popover| {
+        refresh_on_show();
+        let refresh = refresh_on_show.clone();
+        let popover_weak = popover.downgrade();
+        glib::timeout_add_local(Duration::from_secs(1), move || {
+            match popover_weak.upgrade() {
+                Some(popover) if popover.is_visible() => {
+                    refresh();
+                    glib::ControlFlow::Continue
+                }
+                _ => glib::ControlFlow::Break,
+            }
+        });
+    });
+
+    // Posts the results once, whether the poll is ended by hand or runs out
+    let finish = {
+        let poll = poll.clone();
+        let refresh = refresh.clone();
+        move |round: Option<u64>| {
+            let mut poll = poll.locked();
+            if round.is_some_and(|round| round != poll.round) || !poll.end() {
+                return;
+            }
+            let tally = poll.tally();
+            drop(poll);
+            announce(results_notice_html(&tally));
+            refresh();
+        }
+    };
+    let finish = Rc::new(finish);
+
+    let poll_for_start = poll.clone();
+    let refresh_on_start = refresh.clone();
+    let options_entry_for_start = options_entry.clone();
+    start_button.connect_clicked(move |_| {
+        if poll_for_start.locked().is_running() {
+            finish(None);
+            return;
+        }
+        let options = parse_options(&options_entry_for_start.text());
+        if !(2..=MAX_OPTIONS).contains(&options.len()) {
+            options_entry_for_start.add_css_class("error");
+            return;
+        }
+        options_entry_for_start.remove_css_class("error");
+        let duration = Duration::from_secs(duration_spin.value_as_int().max(1) as u64 * 60);
+        let round = poll_for_start.locked().start(options, duration);
+        let finish = finish.clone();
+        glib::timeout_add_local_once(duration, move || finish(Some(round)));
+        refresh_on_start();
+    });
+    options_entry.connect_activate(glib::clone!(
+        #[weak]
+        start_button,
+        move |_| start_button.emit_clicked()
+    ));
+
+    popover
+}
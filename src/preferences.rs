@@ -0,0 +1,1370 @@
+// preferences.rs
+
+use adw::prelude::*;
+use adw::{ActionRow, ApplicationWindow, ComboRow, EntryRow, ExpanderRow, PasswordEntryRow, PreferencesDialog, PreferencesGroup, PreferencesPage, SpinRow, SwitchRow};
+use chrono::{DateTime, Utc};
+use glib::clone;
+use gtk::Button;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::appearance::{AnimationLimit, Density, NameColors};
+use crate::avatars::channel_avatar;
+use crate::auth::{forget_token, load_token, reauthorize, revoke_and_forget_token};
+use crate::bots::{parse_bot_list, BotDisplay};
+use crate::message_budget::retained_totals;
+use crate::moderation::{format_timeout, parse_timeout_list};
+use crate::network::{is_valid_chat_host, is_valid_proxy_url, ProxyMode};
+use crate::rules::{is_valid_pattern, RulePreset, RuleSettings};
+use crate::schedule::ChannelSchedule;
+use crate::scopes::{is_granted, token_info, Feature};
+use crate::startup::StartupBehavior;
+use crate::state::MutexExt;
+use crate::status_icon::set_status_icon_visible;
+use crate::translate::TranslationBackend;
+use crate::transport::ChatTransport;
+use crate::upload::UploadHost;
+use crate::watchdog::WatchdogAction;
+use crate::{apply_appearance_to_tabs, get_appearance_settings, get_bot_settings, get_emote_settings, get_history_settings, get_message_budget_settings, get_moderation_settings, get_network_settings, get_quiet_hours_settings, get_rule_settings, get_startup_settings, get_translation_config, get_upload_settings, get_watchdog_settings, set_appearance_settings, set_bot_settings, set_emote_settings, set_history_settings, set_message_budget_settings, set_moderation_settings, set_network_settings, set_quiet_hours_settings, set_rule_settings, set_startup_settings, set_translation_config, set_upload_settings, set_watchdog_settings, TabData};
+
+pub fn show_preferences(window: &ApplicationWindow, tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>) {
+    let dialog = PreferencesDialog::builder()
+        .title("Preferences")
+        .build();
+
+    let general_page = PreferencesPage::builder()
+        .title("General")
+        .icon_name("preferences-system-symbolic")
+        .build();
+    general_page.add(&build_startup_group());
+    general_page.add(&build_quiet_hours_group());
+    general_page.add(&build_appearance_group(tabs));
+    general_page.add(&build_emotes_group());
+    general_page.add(&build_bots_group());
+    general_page.add(&build_rules_group(window));
+    general_page.add(&build_moderation_group());
+    general_page.add(&build_translation_group());
+    general_page.add(&build_history_group());
+    general_page.add(&build_memory_group(tabs));
+    general_page.add(&build_network_group());
+    general_page.add(&build_upload_group());
+
+    let accounts_page = PreferencesPage::builder()
+        .title("Accounts")
+        .icon_name("system-users-symbolic")
+        .build();
+    accounts_page.add(&build_account_group(window));
+    accounts_page.add(&build_permissions_group(window));
+
+    dialog.add(&general_page);
+    dialog.add(&accounts_page);
+    dialog.present(Some(window));
+}
+
+fn build_startup_group() -> PreferencesGroup {
+    let settings = get_startup_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Startup")
+        .build();
+
+    let behavior_labels: Vec<&str> = StartupBehavior::ALL.iter().map(|b| b.label()).collect();
+    let behavior_row = ComboRow::builder()
+        .title("On Launch")
+        .subtitle("In the background, starred channels connect without opening the window")
+        .model(&gtk::StringList::new(&behavior_labels))
+        .selected(settings.behavior.index())
+        .build();
+
+    behavior_row.connect_selected_notify(|row| {
+        let mut settings = get_startup_settings();
+        settings.behavior = StartupBehavior::from_index(row.selected());
+        set_startup_settings(&settings);
+    });
+
+    let background_row = SwitchRow::builder()
+        .title("Keep Running in the Background")
+        .subtitle("Closing the window keeps chats connected and sends notifications for mentions and streams going live")
+        .active(settings.run_in_background)
+        .build();
+
+    background_row.connect_active_notify(|row| {
+        let mut settings = get_startup_settings();
+        settings.run_in_background = row.is_active();
+        set_startup_settings(&settings);
+    });
+
+    let status_icon_row = SwitchRow::builder()
+        .title("Show Status Icon")
+        .subtitle("Needs a system tray, on GNOME the AppIndicator extension")
+        .active(settings.status_icon)
+        .build();
+
+    status_icon_row.connect_active_notify(|row| {
+        let mut settings = get_startup_settings();
+        settings.status_icon = row.is_active();
+        set_startup_settings(&settings);
+        if let Some(app) = adw::gio::Application::default().and_downcast::<gtk::Application>() {
+            set_status_icon_visible(&app, settings.status_icon);
+        }
+    });
+
+    let prefetch_row = SwitchRow::builder()
+        .title("Preload Starred Emotes")
+        .subtitle("Fetch emotes of starred channels at launch so they show from the first message")
+        .active(settings.prefetch_emotes)
+        .build();
+
+    prefetch_row.connect_active_notify(|row| {
+        let mut settings = get_startup_settings();
+        settings.prefetch_emotes = row.is_active();
+        set_startup_settings(&settings);
+    });
+
+    let updates_row = SwitchRow::builder()
+        .title("Check for Updates")
+        .subtitle("Look for a newer release on GitHub at launch, for installs outside a package manager")
+        .active(settings.check_for_updates)
+        .build();
+
+    updates_row.connect_active_notify(|row| {
+        let mut settings = get_startup_settings();
+        settings.check_for_updates = row.is_active();
+        set_startup_settings(&settings);
+    });
+
+    group.add(&behavior_row);
+    group.add(&background_row);
+    group.add(&status_icon_row);
+    group.add(&prefetch_row);
+    group.add(&updates_row);
+    group
+}
+
+fn token_expiry_text(expires_at: Option<DateTime<Utc>>) -> String {
+    let Some(expires_at) = expires_at else {
+        return "The token doesn't expire".to_string();
+    };
+    match (expires_at - Utc::now()).num_minutes() {
+        minutes if minutes <= 0 => "The token has expired".to_string(),
+        minutes if minutes < 60 => format!("The token expires in {} minutes", minutes),
+        minutes if minutes < 48 * 60 => format!("The token expires in {} hours", minutes / 60),
+        minutes => format!("The token expires in {} days", minutes / (24 * 60)),
+    }
+}
+
+fn build_account_group(window: &ApplicationWindow) -> PreferencesGroup {
+    let group = PreferencesGroup::builder()
+        .title("Twitch Account")
+        .build();
+
+    let logged_in = load_token().is_some();
+    let account_row = ActionRow::new();
+    let scopes_row = ActionRow::builder()
+        .title("Scopes")
+        .subtitle_selectable(true)
+        .visible(false)
+        .build();
+    match token_info() {
+        _ if !logged_in => {
+            account_row.set_title("Not logged in");
+            account_row.set_subtitle("Log in to chat and moderate as yourself");
+        }
+        Some(info) if info.valid => {
+            account_row.set_title(&info.login);
+            account_row.set_subtitle(&token_expiry_text(info.expires_at));
+            account_row.add_prefix(&channel_avatar(&info.login, &info.login, 40));
+            let mut scopes: Vec<&str> = info.scopes.iter().map(String::as_str).collect();
+            scopes.sort();
+            scopes_row.set_subtitle(&scopes.join(", "));
+            scopes_row.set_visible(true);
+        }
+        Some(_) => {
+            account_row.set_title("Login expired");
+            account_row.set_subtitle("Twitch no longer accepts the saved token");
+        }
+        None => {
+            account_row.set_title("Logged in");
+            account_row.set_subtitle("Checking the token with Twitch…");
+        }
+    }
+
+    let login_button = Button::builder()
+        .label(if logged_in { "Log In Again" } else { "Log In" })
+        .valign(gtk::Align::Center)
+        .build();
+    let window_login = window.clone();
+    login_button.connect_clicked(move |_| reauthorize(&window_login, &[]));
+    account_row.add_suffix(&login_button);
+
+    let revoke_row = ActionRow::builder()
+        .title("Revoke Token")
+        .subtitle("Log out and have Twitch invalidate the token, so no copy of it keeps working")
+        .sensitive(logged_in)
+        .build();
+    let revoke_button = Button::builder()
+        .label("Revoke")
+        .valign(gtk::Align::Center)
+        .css_classes(["destructive-action"])
+        .build();
+    revoke_row.add_suffix(&revoke_button);
+
+    let forget_row = ActionRow::builder()
+        .title("Remove Saved Login")
+        .subtitle("Delete the token from the keyring and the encrypted token file; it stays valid at Twitch")
+        .sensitive(logged_in)
+        .build();
+    let forget_button = Button::builder()
+        .label("Remove")
+        .valign(gtk::Align::Center)
+        .build();
+    forget_row.add_suffix(&forget_button);
+
+    // Open chats keep their connection, signed in, until they reconnect
+    let show_logged_out = {
+        let account_row = account_row.clone();
+        let scopes_row = scopes_row.clone();
+        let login_button = login_button.clone();
+        let revoke_row = revoke_row.clone();
+        let forget_row = forget_row.clone();
+        move || {
+            account_row.set_title("Not logged in");
+            account_row.set_subtitle("Open chats stay signed in until they reconnect");
+            scopes_row.set_visible(false);
+            login_button.set_label("Log In");
+            revoke_row.set_sensitive(false);
+            forget_row.set_sensitive(false);
+        }
+    };
+
+    let revoke_logged_out = show_logged_out.clone();
+    revoke_button.connect_clicked(move |button| {
+        let confirm = adw::AlertDialog::builder()
+            .heading("Revoke Token?")
+            .body("You'll be logged out here and anywhere else this token is used.")
+            .build();
+        confirm.add_responses(&[("cancel", "Cancel"), ("revoke", "Revoke")]);
+        confirm.set_response_appearance("revoke", adw::ResponseAppearance::Destructive);
+        confirm.set_close_response("cancel");
+        let button_weak = button.downgrade();
+        let show_logged_out = revoke_logged_out.clone();
+        confirm.connect_response(Some("revoke"), move |_, _| {
+            let Some(button) = button_weak.upgrade() else {
+                return;
+            };
+            button.set_sensitive(false);
+            let show_logged_out = show_logged_out.clone();
+            glib::MainContext::default().spawn_local(async move {
+                let result = adw::gio::spawn_blocking(revoke_and_forget_token)
+                    .await
+                    .unwrap_or_else(|_| Err("Token revocation panicked".to_string()));
+                button.set_sensitive(true);
+                match result {
+                    Ok(()) => show_logged_out(),
+                    Err(e) => eprintln!("{}", e),
+                }
+            });
+        });
+        confirm.present(Some(button));
+    });
+
+    forget_button.connect_clicked(move |_| match forget_token() {
+        Ok(()) => show_logged_out(),
+        Err(e) => eprintln!("{}", e),
+    });
+
+    group.add(&account_row);
+    group.add(&scopes_row);
+    group.add(&revoke_row);
+    group.add(&forget_row);
+    group
+}
+
+fn build_permissions_group(window: &ApplicationWindow) -> PreferencesGroup {
+    let group = PreferencesGroup::builder()
+        .title("Twitch Permissions")
+        .description(if load_token().is_some() {
+            "What your Twitch login allows. Granting more asks Twitch again, keeping what's already allowed."
+        } else {
+            "Log in to chat and moderate as yourself."
+        })
+        .build();
+
+    for feature in Feature::ALL {
+        let row = ActionRow::builder()
+            .title(feature.label())
+            .subtitle(feature.description())
+            .build();
+        match is_granted(feature) {
+            Some(true) => {
+                let granted = gtk::Label::new(Some("Allowed"));
+                granted.add_css_class("dim-label");
+                row.add_suffix(&granted);
+            }
+            // Unknown until Twitch has been asked, so granting is offered either way
+            _ => {
+                let grant_button = Button::builder()
+                    .label("Grant")
+                    .valign(gtk::Align::Center)
+                    .build();
+                let window = window.clone();
+                grant_button.connect_clicked(move |_| reauthorize(&window, &[feature]));
+                row.add_suffix(&grant_button);
+            }
+        }
+        group.add(&row);
+    }
+    group
+}
+
+fn build_quiet_hours_group() -> PreferencesGroup {
+    let settings = get_quiet_hours_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Quiet Hours")
+        .description("No desktop notifications, while mentions still count as unread. Do Not Disturb in the main menu does the same right away.")
+        .build();
+
+    let scheduled_row = SwitchRow::builder()
+        .title("Scheduled Quiet Hours")
+        .active(settings.scheduled)
+        .build();
+
+    let hours_row = EntryRow::builder()
+        .title("Hours (e.g. “daily 22:00-07:00” or “weekdays 09:00-17:00”)")
+        .text(settings.hours.describe())
+        .show_apply_button(true)
+        .sensitive(settings.scheduled)
+        .build();
+
+    let hours_row_clone = hours_row.clone();
+    scheduled_row.connect_active_notify(move |row| {
+        let mut settings = get_quiet_hours_settings();
+        settings.scheduled = row.is_active();
+        hours_row_clone.set_sensitive(settings.scheduled);
+        set_quiet_hours_settings(&settings);
+    });
+
+    hours_row.connect_apply(|row| {
+        let text = row.text();
+        if let Some(hours) = ChannelSchedule::parse(&text) {
+            row.remove_css_class("error");
+            row.set_text(&hours.describe());
+            let mut settings = get_quiet_hours_settings();
+            settings.hours = hours;
+            set_quiet_hours_settings(&settings);
+        } else {
+            row.add_css_class("error");
+            eprintln!("Ignoring invalid quiet hours: {}", text);
+        }
+    });
+
+    group.add(&scheduled_row);
+    group.add(&hours_row);
+    group
+}
+
+fn build_appearance_group(tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>) -> PreferencesGroup {
+    let settings = get_appearance_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Appearance")
+        .build();
+
+    let enlarge_row = SwitchRow::builder()
+        .title("Enlarge Emote-Only Messages")
+        .subtitle("Show emotes at double size when a message has no text")
+        .active(settings.enlarge_emote_only)
+        .build();
+
+    enlarge_row.connect_active_notify(|row| {
+        let mut settings = get_appearance_settings();
+        settings.enlarge_emote_only = row.is_active();
+        set_appearance_settings(&settings);
+    });
+
+    let density_labels: Vec<&str> = Density::ALL.iter().map(|d| d.label()).collect();
+    let density_row = ComboRow::builder()
+        .title("Message Density")
+        .model(&gtk::StringList::new(&density_labels))
+        .selected(settings.density.index())
+        .build();
+
+    let tabs_clone = tabs.clone();
+    density_row.connect_selected_notify(move |row| {
+        let mut settings = get_appearance_settings();
+        settings.density = Density::from_index(row.selected());
+        set_appearance_settings(&settings);
+        apply_appearance_to_tabs(&tabs_clone);
+    });
+
+    let timestamps_row = SwitchRow::builder()
+        .title("Timestamps on Hover")
+        .subtitle("Only show a message's time while the pointer is over it")
+        .active(settings.timestamps_on_hover)
+        .build();
+
+    let tabs_clone = tabs.clone();
+    timestamps_row.connect_active_notify(move |row| {
+        let mut settings = get_appearance_settings();
+        settings.timestamps_on_hover = row.is_active();
+        set_appearance_settings(&settings);
+        apply_appearance_to_tabs(&tabs_clone);
+    });
+
+    let font_size_row = SpinRow::with_range(10.0, 32.0, 1.0);
+    font_size_row.set_title("Text Size");
+    font_size_row.set_value(settings.font_size as f64);
+
+    let tabs_clone = tabs.clone();
+    font_size_row.connect_value_notify(move |row| {
+        let mut settings = get_appearance_settings();
+        settings.font_size = row.value() as u32;
+        set_appearance_settings(&settings);
+        apply_appearance_to_tabs(&tabs_clone);
+    });
+
+    let emote_size_row = SpinRow::with_range(16.0, 64.0, 2.0);
+    emote_size_row.set_title("Emote Size");
+    emote_size_row.set_value(settings.emote_size as f64);
+
+    let tabs_clone = tabs.clone();
+    emote_size_row.connect_value_notify(move |row| {
+        let mut settings = get_appearance_settings();
+        settings.emote_size = row.value() as u32;
+        set_appearance_settings(&settings);
+        apply_appearance_to_tabs(&tabs_clone);
+    });
+
+    let accent_row = SwitchRow::builder()
+        .title("Use Accent Color")
+        .subtitle("Color highlighted messages, mentions and links with the system accent color")
+        .active(settings.accent_colors)
+        .build();
+
+    let tabs_clone = tabs.clone();
+    accent_row.connect_active_notify(move |row| {
+        let mut settings = get_appearance_settings();
+        settings.accent_colors = row.is_active();
+        set_appearance_settings(&settings);
+        apply_appearance_to_tabs(&tabs_clone);
+    });
+
+    let name_color_labels: Vec<&str> = NameColors::ALL.iter().map(|c| c.label()).collect();
+    let name_colors_row = ComboRow::builder()
+        .title("Name Colors")
+        .model(&gtk::StringList::new(&name_color_labels))
+        .selected(settings.name_colors.index())
+        .build();
+
+    let tabs_clone = tabs.clone();
+    name_colors_row.connect_selected_notify(move |row| {
+        let mut settings = get_appearance_settings();
+        settings.name_colors = NameColors::from_index(row.selected());
+        set_appearance_settings(&settings);
+        apply_appearance_to_tabs(&tabs_clone);
+    });
+
+    // Synthetic chat to judge changes without joining a live channel
+    let preview_button = Button::builder()
+        .label("Preview")
+        .tooltip_text("Open a preview channel with sample messages")
+        .valign(gtk::Align::Center)
+        .build();
+    preview_button.add_css_class("flat");
+    preview_button.connect_clicked(|button| {
+        let _ = button.activate_action("win.preview-channel", None);
+    });
+    group.set_header_suffix(Some(&preview_button));
+
+    let emoji_row = SwitchRow::builder()
+        .title("Emoji Images")
+        .subtitle("Draw emoji as Twemoji pictures at emote size instead of with the system font")
+        .active(settings.emoji_images)
+        .build();
+
+    emoji_row.connect_active_notify(|row| {
+        let mut settings = get_appearance_settings();
+        settings.emoji_images = row.is_active();
+        set_appearance_settings(&settings);
+    });
+
+    let animation_labels: Vec<&str> = AnimationLimit::ALL.iter().map(|l| l.label()).collect();
+    let animation_row = ComboRow::builder()
+        .title("Emote Animation")
+        .subtitle("Cap how often animated emotes redraw to lighten busy chats on older hardware")
+        .model(&gtk::StringList::new(&animation_labels))
+        .selected(settings.animation_limit.index())
+        .build();
+
+    let tabs_clone = tabs.clone();
+    animation_row.connect_selected_notify(move |row| {
+        let mut settings = get_appearance_settings();
+        settings.animation_limit = AnimationLimit::from_index(row.selected());
+        set_appearance_settings(&settings);
+        apply_appearance_to_tabs(&tabs_clone);
+    });
+
+    group.add(&density_row);
+    group.add(&font_size_row);
+    group.add(&emote_size_row);
+    group.add(&timestamps_row);
+    group.add(&accent_row);
+    group.add(&name_colors_row);
+    group.add(&enlarge_row);
+    group.add(&emoji_row);
+    group.add(&animation_row);
+    group
+}
+
+fn build_moderation_group() -> PreferencesGroup {
+    let settings = get_moderation_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Moderation")
+        .build();
+
+    let account_age_row = SwitchRow::builder()
+        .title("Show Account Age")
+        .subtitle("Tag first-time chatters with their account age (requires login)")
+        .active(settings.show_account_age)
+        .build();
+
+    account_age_row.connect_active_notify(|row| {
+        let mut settings = get_moderation_settings();
+        settings.show_account_age = row.is_active();
+        set_moderation_settings(&settings);
+    });
+
+    let format_durations = |durations: &[u32]| {
+        durations
+            .iter()
+            .map(|secs| format_timeout(*secs))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let timeouts_row = EntryRow::builder()
+        .title("Quick Timeouts (e.g. 1m, 10m, 1h, 24h)")
+        .text(format_durations(&settings.timeout_durations))
+        .show_apply_button(true)
+        .build();
+
+    timeouts_row.connect_apply(move |row| {
+        let durations = parse_timeout_list(&row.text());
+        if durations.is_empty() {
+            return;
+        }
+        let mut settings = get_moderation_settings();
+        row.set_text(&format_durations(&durations));
+        settings.timeout_durations = durations;
+        set_moderation_settings(&settings);
+    });
+
+    let clear_row = SwitchRow::builder()
+        .title("Clear View With Chat")
+        .subtitle("When a moderator clears chat, remove earlier messages instead of marking where it happened")
+        .active(settings.clear_on_chat_clear)
+        .build();
+
+    clear_row.connect_active_notify(|row| {
+        let mut settings = get_moderation_settings();
+        settings.clear_on_chat_clear = row.is_active();
+        set_moderation_settings(&settings);
+    });
+
+    let deleted_row = SwitchRow::builder()
+        .title("Show Deleted Messages")
+        .subtitle("Keep messages moderators delete visible, struck through and tagged as deleted")
+        .active(settings.show_deleted_messages)
+        .build();
+
+    deleted_row.connect_active_notify(|row| {
+        let mut settings = get_moderation_settings();
+        settings.show_deleted_messages = row.is_active();
+        set_moderation_settings(&settings);
+    });
+
+    group.add(&account_age_row);
+    group.add(&timeouts_row);
+    group.add(&clear_row);
+    group.add(&deleted_row);
+    group
+}
+
+fn build_translation_group() -> PreferencesGroup {
+    let config = get_translation_config();
+
+    let group = PreferencesGroup::builder()
+        .title("Translation")
+        .description("Used by tabs with translation turned on")
+        .build();
+
+    let backend_row = ComboRow::builder()
+        .title("Backend")
+        .model(&gtk::StringList::new(&["LibreTranslate", "DeepL"]))
+        .selected(match config.backend {
+            TranslationBackend::LibreTranslate => 0,
+            TranslationBackend::DeepL => 1,
+        })
+        .build();
+
+    let url_row = EntryRow::builder()
+        .title("LibreTranslate URL")
+        .text(config.libretranslate_url.as_str())
+        .show_apply_button(true)
+        .sensitive(config.backend == TranslationBackend::LibreTranslate)
+        .build();
+
+    let key_row = PasswordEntryRow::builder()
+        .title("API Key")
+        .text(config.api_key.as_deref().unwrap_or(""))
+        .show_apply_button(true)
+        .build();
+
+    let target_row = EntryRow::builder()
+        .title("Target Language (e.g. en, de, ja)")
+        .text(config.target_language.as_str())
+        .show_apply_button(true)
+        .build();
+
+    let url_row_clone = url_row.clone();
+    backend_row.connect_selected_notify(move |row| {
+        let mut config = get_translation_config();
+        config.backend = if row.selected() == 1 {
+            TranslationBackend::DeepL
+        } else {
+            TranslationBackend::LibreTranslate
+        };
+        url_row_clone.set_sensitive(config.backend == TranslationBackend::LibreTranslate);
+        set_translation_config(&config);
+    });
+
+    url_row.connect_apply(|row| {
+        let url = row.text().trim().to_string();
+        if url.starts_with("http://") || url.starts_with("https://") {
+            let mut config = get_translation_config();
+            config.libretranslate_url = url;
+            set_translation_config(&config);
+        } else {
+            eprintln!("Ignoring invalid LibreTranslate URL: {}", url);
+        }
+    });
+
+    key_row.connect_apply(|row| {
+        let key = row.text().trim().to_string();
+        let mut config = get_translation_config();
+        config.api_key = if key.is_empty() { None } else { Some(key) };
+        set_translation_config(&config);
+    });
+
+    target_row.connect_apply(|row| {
+        let language = row.text().trim().to_lowercase();
+        if !language.is_empty() {
+            let mut config = get_translation_config();
+            config.target_language = language;
+            set_translation_config(&config);
+        }
+    });
+
+    group.add(&backend_row);
+    group.add(&url_row);
+    group.add(&key_row);
+    group.add(&target_row);
+    group
+}
+
+fn build_history_group() -> PreferencesGroup {
+    let settings = get_history_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("History")
+        .description("Saved messages back scrollback and chatter history beyond the current session")
+        .build();
+
+    let enabled_row = SwitchRow::builder()
+        .title("Save Chat History")
+        .subtitle("Store received messages on disk")
+        .active(settings.enabled)
+        .build();
+
+    let retention_row = SpinRow::with_range(0.0, 3650.0, 1.0);
+    retention_row.set_title("Keep Messages for Days");
+    retention_row.set_subtitle("0 keeps them forever");
+    retention_row.set_value(settings.retention_days as f64);
+    retention_row.set_sensitive(settings.enabled);
+
+    let retention_row_clone = retention_row.clone();
+    enabled_row.connect_active_notify(move |row| {
+        let mut settings = get_history_settings();
+        settings.enabled = row.is_active();
+        retention_row_clone.set_sensitive(settings.enabled);
+        set_history_settings(&settings);
+    });
+
+    retention_row.connect_value_notify(|row| {
+        let mut settings = get_history_settings();
+        settings.retention_days = row.value() as u32;
+        set_history_settings(&settings);
+    });
+
+    group.add(&enabled_row);
+    group.add(&retention_row);
+    group
+}
+
+fn format_mb(bytes: usize) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn build_memory_group(tabs: &Arc<Mutex<HashMap<String, Arc<TabData>>>>) -> PreferencesGroup {
+    let settings = get_watchdog_settings();
+    let budget = get_message_budget_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Memory")
+        .description("Watch how much memory each tab's chat view uses. Tabs keep recent messages to redraw their chat; past the limits the oldest are dropped, and stay reachable by scrolling back when chat history is saved.")
+        .build();
+
+    let enabled_row = SwitchRow::builder()
+        .title("Memory Watchdog")
+        .active(settings.enabled)
+        .build();
+
+    let threshold_row = SpinRow::with_range(256.0, 16384.0, 128.0);
+    threshold_row.set_title("Limit per Tab (MB)");
+    threshold_row.set_value(settings.threshold_mb as f64);
+    threshold_row.set_sensitive(settings.enabled);
+
+    let action_labels: Vec<&str> = WatchdogAction::ALL.iter().map(|a| a.label()).collect();
+    let action_row = ComboRow::builder()
+        .title("When Over the Limit")
+        .model(&gtk::StringList::new(&action_labels))
+        .selected(settings.action.index())
+        .sensitive(settings.enabled)
+        .build();
+
+    let threshold_row_clone = threshold_row.clone();
+    let action_row_clone = action_row.clone();
+    enabled_row.connect_active_notify(move |row| {
+        let mut settings = get_watchdog_settings();
+        settings.enabled = row.is_active();
+        threshold_row_clone.set_sensitive(settings.enabled);
+        action_row_clone.set_sensitive(settings.enabled);
+        set_watchdog_settings(&settings);
+    });
+
+    threshold_row.connect_value_notify(|row| {
+        let mut settings = get_watchdog_settings();
+        settings.threshold_mb = row.value() as u32;
+        set_watchdog_settings(&settings);
+    });
+
+    action_row.connect_selected_notify(|row| {
+        let mut settings = get_watchdog_settings();
+        settings.action = WatchdogAction::from_index(row.selected());
+        set_watchdog_settings(&settings);
+    });
+
+    let max_messages_row = SpinRow::with_range(100.0, 20000.0, 100.0);
+    max_messages_row.set_title("Messages Kept per Tab");
+    max_messages_row.set_value(budget.max_messages as f64);
+    max_messages_row.connect_value_notify(|row| {
+        let mut settings = get_message_budget_settings();
+        settings.max_messages = row.value() as u32;
+        set_message_budget_settings(&settings);
+    });
+
+    let max_tab_row = SpinRow::with_range(1.0, 512.0, 1.0);
+    max_tab_row.set_title("Message Memory per Tab (MB)");
+    max_tab_row.set_value(budget.max_tab_mb as f64);
+    max_tab_row.connect_value_notify(|row| {
+        let mut settings = get_message_budget_settings();
+        settings.max_tab_mb = row.value() as u32;
+        set_message_budget_settings(&settings);
+    });
+
+    let max_total_row = SpinRow::with_range(8.0, 4096.0, 8.0);
+    max_total_row.set_title("Message Memory for All Tabs (MB)");
+    max_total_row.set_value(budget.max_total_mb as f64);
+    max_total_row.connect_value_notify(|row| {
+        let mut settings = get_message_budget_settings();
+        settings.max_total_mb = row.value() as u32;
+        set_message_budget_settings(&settings);
+    });
+
+    // Snapshot of what each tab holds right now, for telling which one grows
+    let (total_bytes, total_evicted) = retained_totals();
+    let tabs = tabs.locked();
+    let usage_row = ExpanderRow::builder()
+        .title("Retained Messages")
+        .subtitle(format!("{} in {} tabs, {} dropped this session", format_mb(total_bytes), tabs.len(), total_evicted))
+        .build();
+    let mut tab_usage: Vec<(String, usize, usize, u64)> = tabs
+        .values()
+        .map(|tab_data| {
+            let buf = tab_data.message_buffer.locked();
+            let channel = tab_data.channel_name.locked().clone().unwrap_or_else(|| "New tab".to_string());
+            (channel, buf.len(), buf.bytes(), buf.evicted())
+        })
+        .collect();
+    drop(tabs);
+    tab_usage.sort_by(|a, b| b.2.cmp(&a.2));
+    for (channel, count, bytes, evicted) in tab_usage {
+        let row = adw::ActionRow::builder()
+            .title(&channel)
+            .subtitle(format!("{} messages, {}, {} dropped", count, format_mb(bytes), evicted))
+            .build();
+        usage_row.add_row(&row);
+    }
+
+    group.add(&enabled_row);
+    group.add(&threshold_row);
+    group.add(&action_row);
+    group.add(&max_messages_row);
+    group.add(&max_tab_row);
+    group.add(&max_total_row);
+    group.add(&usage_row);
+    group
+}
+
+fn build_network_group() -> PreferencesGroup {
+    let settings = get_network_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Network")
+        .description("Used for API requests, chat views and the chat connection; some background lookups pick up changes after a restart, chats when they next connect. Chat goes through HTTP, HTTPS and SOCKS5 proxies.")
+        .build();
+
+    let transport_labels: Vec<&str> = ChatTransport::ALL.iter().map(|t| t.label()).collect();
+    let transport_row = ComboRow::builder()
+        .title("Chat Connection")
+        .subtitle("WebSocket gets through networks that only allow web traffic. Applies when a chat next connects.")
+        .model(&gtk::StringList::new(&transport_labels))
+        .selected(settings.chat_transport.index())
+        .build();
+
+    let host_row = EntryRow::builder()
+        .title("Chat Server (empty for Twitch)")
+        .text(settings.chat_host.as_str())
+        .show_apply_button(true)
+        .build();
+
+    let port_row = SpinRow::with_range(0.0, 65535.0, 1.0);
+    port_row.set_title("Chat Port");
+    port_row.set_subtitle("0 uses the chat connection's usual port");
+    port_row.set_value(settings.chat_port as f64);
+
+    let mode_labels: Vec<&str> = ProxyMode::ALL.iter().map(|m| m.label()).collect();
+    let mode_row = ComboRow::builder()
+        .title("Proxy")
+        .model(&gtk::StringList::new(&mode_labels))
+        .selected(settings.proxy_mode.index())
+        .build();
+
+    let url_row = EntryRow::builder()
+        .title("Proxy URL (http:// or socks5://)")
+        .text(settings.proxy_url.as_str())
+        .show_apply_button(true)
+        .sensitive(settings.proxy_mode == ProxyMode::Custom)
+        .build();
+
+    let doh_row = SwitchRow::builder()
+        .title("DNS over HTTPS")
+        .subtitle("Look up hosts through an encrypted resolver instead of the network's DNS")
+        .active(settings.dns_over_https)
+        .build();
+
+    let doh_url_row = EntryRow::builder()
+        .title("Resolver URL")
+        .text(settings.doh_url.as_str())
+        .show_apply_button(true)
+        .sensitive(settings.dns_over_https)
+        .build();
+
+    transport_row.connect_selected_notify(|row| {
+        let mut settings = get_network_settings();
+        settings.chat_transport = ChatTransport::from_index(row.selected());
+        set_network_settings(&settings);
+    });
+
+    host_row.connect_apply(|row| {
+        let host = row.text().trim().to_string();
+        if host.is_empty() || is_valid_chat_host(&host) {
+            row.remove_css_class("error");
+            let mut settings = get_network_settings();
+            settings.chat_host = host;
+            set_network_settings(&settings);
+        } else {
+            row.add_css_class("error");
+            eprintln!("Ignoring invalid chat server: {}", host);
+        }
+    });
+
+    port_row.connect_value_notify(|row| {
+        let mut settings = get_network_settings();
+        settings.chat_port = row.value() as u16;
+        set_network_settings(&settings);
+    });
+
+    let url_row_clone = url_row.clone();
+    mode_row.connect_selected_notify(move |row| {
+        let mut settings = get_network_settings();
+        settings.proxy_mode = ProxyMode::from_index(row.selected());
+        url_row_clone.set_sensitive(settings.proxy_mode == ProxyMode::Custom);
+        set_network_settings(&settings);
+    });
+
+    url_row.connect_apply(|row| {
+        let url = row.text().trim().to_string();
+        if is_valid_proxy_url(&url) {
+            row.remove_css_class("error");
+            let mut settings = get_network_settings();
+            settings.proxy_url = url;
+            set_network_settings(&settings);
+        } else {
+            row.add_css_class("error");
+            eprintln!("Ignoring invalid proxy URL: {}", url);
+        }
+    });
+
+    let doh_url_row_clone = doh_url_row.clone();
+    doh_row.connect_active_notify(move |row| {
+        let mut settings = get_network_settings();
+        settings.dns_over_https = row.is_active();
+        doh_url_row_clone.set_sensitive(settings.dns_over_https);
+        set_network_settings(&settings);
+    });
+
+    doh_url_row.connect_apply(|row| {
+        let url = row.text().trim().to_string();
+        if url.starts_with("https://") {
+            row.remove_css_class("error");
+            let mut settings = get_network_settings();
+            settings.doh_url = url;
+            set_network_settings(&settings);
+        } else {
+            row.add_css_class("error");
+            eprintln!("Ignoring invalid DNS over HTTPS URL: {}", url);
+        }
+    });
+
+    group.add(&transport_row);
+    group.add(&host_row);
+    group.add(&port_row);
+    group.add(&mode_row);
+    group.add(&url_row);
+    group.add(&doh_row);
+    group.add(&doh_url_row);
+    group
+}
+
+fn build_emotes_group() -> PreferencesGroup {
+    let settings = get_emote_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Emotes")
+        .description("Twitch only shows an emote when its name is typed exactly")
+        .build();
+
+    let case_row = SwitchRow::builder()
+        .title("Ignore Case")
+        .subtitle("Show emotes typed in any case, like kekw for KEKW")
+        .active(settings.case_insensitive)
+        .build();
+
+    let original_names_row = SwitchRow::builder()
+        .title("Match Original 7TV Names")
+        .subtitle("Show emotes a channel renamed under their original name too")
+        .active(settings.match_original_names)
+        .build();
+
+    case_row.connect_active_notify(|row| {
+        let mut settings = get_emote_settings();
+        settings.case_insensitive = row.is_active();
+        set_emote_settings(&settings);
+    });
+
+    original_names_row.connect_active_notify(|row| {
+        let mut settings = get_emote_settings();
+        settings.match_original_names = row.is_active();
+        set_emote_settings(&settings);
+    });
+
+    group.add(&case_row);
+    group.add(&original_names_row);
+    group
+}
+
+fn bot_display_model() -> gtk::StringList {
+    let labels: Vec<&str> = BotDisplay::ALL.iter().map(|d| d.label()).collect();
+    gtk::StringList::new(&labels)
+}
+
+fn build_bots_group() -> PreferencesGroup {
+    let settings = get_bot_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Bots")
+        .description("Messages from known bots can be dimmed, collapsed or hidden")
+        .build();
+
+    let bots_row = EntryRow::builder()
+        .title("Known Bots (comma separated)")
+        .text(settings.known_bots.join(", "))
+        .show_apply_button(true)
+        .build();
+
+    let display_row = ComboRow::builder()
+        .title("Bot Messages")
+        .model(&bot_display_model())
+        .selected(settings.display.index())
+        .build();
+
+    let overrides_row = ExpanderRow::builder()
+        .title("Channel Overrides")
+        .subtitle("Use a different style in specific channels")
+        .build();
+
+    let add_override_row = EntryRow::builder()
+        .title("Add channel")
+        .show_apply_button(true)
+        .build();
+    overrides_row.add_row(&add_override_row);
+
+    let mut channels: Vec<(&String, &BotDisplay)> = settings.channel_overrides.iter().collect();
+    channels.sort_by_key(|(channel, _)| *channel);
+    for (channel, display) in channels {
+        add_bot_override_row(&overrides_row, channel, *display);
+    }
+
+    bots_row.connect_apply(|row| {
+        let mut settings = get_bot_settings();
+        settings.known_bots = parse_bot_list(&row.text());
+        row.set_text(&settings.known_bots.join(", "));
+        set_bot_settings(&settings);
+    });
+
+    display_row.connect_selected_notify(|row| {
+        let mut settings = get_bot_settings();
+        settings.display = BotDisplay::from_index(row.selected());
+        set_bot_settings(&settings);
+    });
+
+    let overrides_row_clone = overrides_row.clone();
+    add_override_row.connect_apply(move |row| {
+        let channel = row.text().trim().trim_start_matches('#').to_lowercase();
+        if channel.is_empty() {
+            return;
+        }
+        let mut settings = get_bot_settings();
+        if !settings.channel_overrides.contains_key(&channel) {
+            let display = settings.display;
+            settings.channel_overrides.insert(channel.clone(), display);
+            set_bot_settings(&settings);
+            add_bot_override_row(&overrides_row_clone, &channel, display);
+        }
+        row.set_text("");
+    });
+
+    group.add(&bots_row);
+    group.add(&display_row);
+    group.add(&overrides_row);
+    group
+}
+
+fn add_bot_override_row(overrides_row: &ExpanderRow, channel: &str, display: BotDisplay) {
+    let row = ComboRow::builder()
+        .title(channel)
+        .model(&bot_display_model())
+        .selected(display.index())
+        .build();
+
+    let remove_button = Button::builder()
+        .icon_name("user-trash-symbolic")
+        .tooltip_text("Remove override")
+        .valign(gtk::Align::Center)
+        .build();
+    remove_button.add_css_class("flat");
+    row.add_suffix(&remove_button);
+
+    let channel_clone = channel.to_string();
+    row.connect_selected_notify(move |row| {
+        let mut settings = get_bot_settings();
+        settings
+            .channel_overrides
+            .insert(channel_clone.clone(), BotDisplay::from_index(row.selected()));
+        set_bot_settings(&settings);
+    });
+
+    let channel_clone = channel.to_string();
+    remove_button.connect_clicked(clone!(
+        #[weak]
+        overrides_row,
+        #[weak]
+        row,
+        move |_| {
+            let mut settings = get_bot_settings();
+            settings.channel_overrides.remove(&channel_clone);
+            set_bot_settings(&settings);
+            overrides_row.remove(&row);
+        }
+    ));
+
+    overrides_row.add_row(&row);
+}
+
+// The two pattern lists under [rules]
+#[derive(Clone, Copy)]
+enum RuleList {
+    Highlights,
+    Filters,
+}
+
+impl RuleList {
+    fn patterns(self, settings: &mut RuleSettings) -> &mut Vec<String> {
+        match self {
+            RuleList::Highlights => &mut settings.highlights,
+            RuleList::Filters => &mut settings.filters,
+        }
+    }
+}
+
+fn build_rules_group(window: &ApplicationWindow) -> PreferencesGroup {
+    let settings = get_rule_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Rules")
+        .description("Regular expressions checked against every message in every channel")
+        .build();
+
+    let highlights_row = ExpanderRow::builder()
+        .title("Highlights")
+        .subtitle("Matching messages are highlighted and show up in notifications")
+        .build();
+    let filters_row = ExpanderRow::builder()
+        .title("Filters")
+        .subtitle("Matching messages are hidden")
+        .build();
+
+    for (expander, list, patterns) in [
+        (&highlights_row, RuleList::Highlights, &settings.highlights),
+        (&filters_row, RuleList::Filters, &settings.filters),
+    ] {
+        let add_row = EntryRow::builder()
+            .title("Add pattern")
+            .show_apply_button(true)
+            .build();
+        expander.add_row(&add_row);
+        for pattern in patterns {
+            add_rule_row(expander, list, pattern);
+        }
+
+        add_row.connect_apply(clone!(
+            #[weak]
+            expander,
+            move |row| {
+                let pattern = row.text().trim().to_string();
+                if !is_valid_pattern(&pattern) {
+                    row.add_css_class("error");
+                    return;
+                }
+                row.remove_css_class("error");
+                let mut settings = get_rule_settings();
+                if !list.patterns(&mut settings).contains(&pattern) {
+                    list.patterns(&mut settings).push(pattern.clone());
+                    set_rule_settings(&settings);
+                    add_rule_row(&expander, list, &pattern);
+                }
+                row.set_text("");
+            }
+        ));
+    }
+
+    // Presets carry only the rules, so they can be handed around a mod team as is
+    let export_button = Button::builder()
+        .label("Export…")
+        .tooltip_text("Save these rules as a preset file")
+        .build();
+    export_button.add_css_class("flat");
+    let import_button = Button::builder()
+        .label("Import…")
+        .tooltip_text("Add the rules from a preset file")
+        .build();
+    import_button.add_css_class("flat");
+    let preset_buttons = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .valign(gtk::Align::Center)
+        .build();
+    preset_buttons.append(&import_button);
+    preset_buttons.append(&export_button);
+    group.set_header_suffix(Some(&preset_buttons));
+
+    export_button.connect_clicked(clone!(
+        #[weak]
+        window,
+        move |_| {
+            let dialog = gtk::FileDialog::builder()
+                .title("Export Rules")
+                .initial_name("admiral-rules.toml")
+                .filters(&preset_file_filters())
+                .build();
+            dialog.save(Some(&window), None::<&adw::gio::Cancellable>, |result| {
+                let Some(path) = result.ok().and_then(|file| file.path()) else {
+                    return;
+                };
+                let name = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let preset = RulePreset::from_settings(&name, &get_rule_settings());
+                match toml::to_string(&preset) {
+                    Ok(contents) => {
+                        if let Err(e) = std::fs::write(&path, contents) {
+                            eprintln!("Failed to export rules to {}: {}", path.display(), e);
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to serialize rules: {}", e),
+                }
+            });
+        }
+    ));
+
+    import_button.connect_clicked(clone!(
+        #[weak]
+        window,
+        #[weak]
+        highlights_row,
+        #[weak]
+        filters_row,
+        move |_| {
+            let dialog = gtk::FileDialog::builder()
+                .title("Import Rules")
+                .filters(&preset_file_filters())
+                .build();
+            dialog.open(Some(&window), None::<&adw::gio::Cancellable>, move |result| {
+                let Some(path) = result.ok().and_then(|file| file.path()) else {
+                    return;
+                };
+                let preset: RulePreset = match std::fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|contents| toml::from_str(&contents).map_err(|e| e.to_string()))
+                {
+                    Ok(preset) => preset,
+                    Err(e) => {
+                        eprintln!("Failed to import rules from {}: {}", path.display(), e);
+                        return;
+                    }
+                };
+                // A preset that doesn't fully apply is turned away whole, rather than
+                // leaving a team's shared setup half imported
+                let invalid = preset.invalid_patterns();
+                if !invalid.is_empty() {
+                    eprintln!("Not importing rules from {}, invalid patterns: {}", path.display(), invalid.join(", "));
+                    return;
+                }
+                let mut before = get_rule_settings();
+                let mut settings = before.clone();
+                settings.merge(&preset);
+                set_rule_settings(&settings);
+                // Merged rules go after the existing ones, so only the tail needs rows
+                for (expander, list) in [(&highlights_row, RuleList::Highlights), (&filters_row, RuleList::Filters)] {
+                    let existing = list.patterns(&mut before).len();
+                    for pattern in &list.patterns(&mut settings)[existing..] {
+                        add_rule_row(expander, list, pattern);
+                    }
+                }
+                println!(
+                    "Imported {} highlight and {} filter rules from {}",
+                    preset.highlights.len(),
+                    preset.filters.len(),
+                    path.display()
+                );
+            });
+        }
+    ));
+
+    group.add(&highlights_row);
+    group.add(&filters_row);
+    group
+}
+
+fn preset_file_filters() -> adw::gio::ListStore {
+    let toml_filter = gtk::FileFilter::new();
+    toml_filter.set_name(Some("Rule presets (TOML)"));
+    toml_filter.add_suffix("toml");
+    let filters = adw::gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&toml_filter);
+    filters
+}
+
+fn add_rule_row(expander: &ExpanderRow, list: RuleList, pattern: &str) {
+    let row = adw::ActionRow::builder()
+        .title(glib::markup_escape_text(pattern).as_str())
+        .build();
+    row.add_css_class("monospace");
+
+    let remove_button = Button::builder()
+        .icon_name("user-trash-symbolic")
+        .tooltip_text("Remove rule")
+        .valign(gtk::Align::Center)
+        .build();
+    remove_button.add_css_class("flat");
+    row.add_suffix(&remove_button);
+
+    let pattern = pattern.to_string();
+    remove_button.connect_clicked(clone!(
+        #[weak]
+        expander,
+        #[weak]
+        row,
+        move |_| {
+            let mut settings = get_rule_settings();
+            list.patterns(&mut settings).retain(|existing| *existing != pattern);
+            set_rule_settings(&settings);
+            expander.remove(&row);
+        }
+    ));
+
+    expander.add_row(&row);
+}
+
+fn build_upload_group() -> PreferencesGroup {
+    let settings = get_upload_settings();
+
+    let group = PreferencesGroup::builder()
+        .title("Image Upload")
+        .description("Images pasted into the message input can be uploaded, with their link added to the message")
+        .build();
+
+    let host_labels: Vec<&str> = UploadHost::ALL.iter().map(|h| h.label()).collect();
+    let host_row = ComboRow::builder()
+        .title("Upload To")
+        .model(&gtk::StringList::new(&host_labels))
+        .selected(settings.host.index())
+        .build();
+
+    let client_id_row = EntryRow::builder()
+        .title("Imgur Client ID")
+        .text(settings.imgur_client_id.as_str())
+        .show_apply_button(true)
+        .sensitive(settings.host == UploadHost::Imgur)
+        .build();
+
+    let client_id_row_clone = client_id_row.clone();
+    host_row.connect_selected_notify(move |row| {
+        let mut settings = get_upload_settings();
+        settings.host = UploadHost::from_index(row.selected());
+        client_id_row_clone.set_sensitive(settings.host == UploadHost::Imgur);
+        set_upload_settings(&settings);
+    });
+
+    client_id_row.connect_apply(|row| {
+        let mut settings = get_upload_settings();
+        settings.imgur_client_id = row.text().trim().to_string();
+        set_upload_settings(&settings);
+    });
+
+    group.add(&host_row);
+    group.add(&client_id_row);
+    group
+}
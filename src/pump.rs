@@ -0,0 +1,89 @@
+// pump.rs
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Tabs are woken from network, render and lookup threads, but their work has to run on
+// the main loop, where the handler lives
+thread_local! {
+    static HANDLER: RefCell<Option<Rc<dyn Fn(&str)>>> = RefCell::new(None);
+}
+
+/// Sets what a woken tab runs on the main loop, given its tab id. Call from the main thread.
+pub fn set_pump_handler(handler: impl Fn(&str) + 'static) {
+    HANDLER.with(|slot| *slot.borrow_mut() = Some(Rc::new(handler)));
+}
+
+/// Schedules a pass of the message pump for one tab. Any number of wakes before the
+/// pass runs collapse into it, so a busy chat costs one pass per idle cycle and a
+/// quiet one costs nothing.
+#[derive(Clone)]
+pub struct TabWaker {
+    tab_id: Arc<str>,
+    scheduled: Arc<AtomicBool>,
+}
+
+impl TabWaker {
+    pub fn new(tab_id: &str) -> Self {
+        Self {
+            tab_id: Arc::from(tab_id),
+            scheduled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Callable from any thread
+    pub fn wake(&self) {
+        if self.scheduled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let waker = self.clone();
+        // Idle priority, so a flood of messages yields to input and redraws
+        glib::idle_add_once(move || waker.run());
+    }
+
+    pub fn wake_after(&self, delay: Duration) {
+        let waker = self.clone();
+        glib::timeout_add_once(delay, move || waker.wake());
+    }
+
+    fn run(&self) {
+        // Cleared first, so anything arriving during the pass schedules another
+        self.scheduled.store(false, Ordering::Release);
+        let handler = HANDLER.with(|slot| slot.borrow().clone());
+        if let Some(handler) = handler {
+            handler(&self.tab_id);
+        }
+    }
+}
+
+/// Sending half of a channel read by the message pump; each send wakes the tab
+pub struct PumpSender<T> {
+    tx: Sender<T>,
+    waker: TabWaker,
+}
+
+impl<T> Clone for PumpSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            waker: self.waker.clone(),
+        }
+    }
+}
+
+impl<T> PumpSender<T> {
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.tx.send(value)?;
+        self.waker.wake();
+        Ok(())
+    }
+}
+
+pub fn pump_channel<T>(waker: &TabWaker) -> (PumpSender<T>, Receiver<T>) {
+    let (tx, rx) = mpsc::channel();
+    (PumpSender { tx, waker: waker.clone() }, rx)
+}
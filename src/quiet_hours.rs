@@ -0,0 +1,46 @@
+// quiet_hours.rs
+
+use chrono::Local;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+use crate::schedule::ChannelSchedule;
+use crate::state::RwLockExt;
+
+// Stored under [quiet_hours] in favorites.toml
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct QuietHoursSettings {
+    pub scheduled: bool,
+    pub hours: ChannelSchedule, // Same weekly window form as channel schedules
+    pub do_not_disturb: bool, // Turned on from the menu, quiet regardless of the time
+}
+
+impl Default for QuietHoursSettings {
+    fn default() -> Self {
+        Self {
+            scheduled: false,
+            hours: ChannelSchedule {
+                days: (0..7).collect(),
+                start: "22:00".to_string(),
+                end: "07:00".to_string(),
+            },
+            do_not_disturb: false,
+        }
+    }
+}
+
+static SETTINGS: Lazy<RwLock<QuietHoursSettings>> = Lazy::new(|| RwLock::new(QuietHoursSettings::default()));
+
+/// Applies changed settings; called at startup, from preferences and from the menu toggle
+pub fn configure_quiet_hours(settings: &QuietHoursSettings) {
+    *SETTINGS.write_locked() = settings.clone();
+}
+
+/// Whether notifications should be held back right now. Mentions still count as
+/// unread and land in the notification center. Safe to call from worker threads.
+pub fn is_quiet() -> bool {
+    let settings = SETTINGS.read_locked();
+    settings.do_not_disturb || (settings.scheduled && settings.hours.is_active(Local::now().naive_local()))
+}
@@ -0,0 +1,85 @@
+// render.rs
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::emotes::{emote_map_for, parse_message_html, RenderOptions};
+use crate::pump::PumpSender;
+use crate::state::MutexExt;
+
+/// Messages for one tab to turn into HTML, in order
+pub struct RenderJob {
+    pub generation: u64, // The tab's chat generation when the job was made
+    pub notice: Option<String>, // Shown ahead of the messages, like the skipped divider
+    pub messages: Vec<PrivmsgMessage>,
+    pub options: Vec<RenderOptions>, // One per message
+    pub emote_map: Arc<HashMap<String, (String, bool)>>,
+    pub reply: PumpSender<RenderedBatch>,
+}
+
+/// A job's HTML, ready to store and inject from the main loop
+pub struct RenderedBatch {
+    pub generation: u64,
+    pub messages: Vec<PrivmsgMessage>,
+    pub html: Vec<String>, // The notice, if any, then one entry per message
+    pub joined: String, // All of `html` on separate lines, as the page takes it
+}
+
+// One thread is plenty for batches of a few dozen messages, and keeps each tab's
+// batches in order without further bookkeeping
+static WORKER: Lazy<Mutex<Sender<RenderJob>>> = Lazy::new(|| {
+    let (tx, rx) = channel::<RenderJob>();
+    thread::Builder::new()
+        .name("render".to_string())
+        .spawn(move || {
+            for job in rx {
+                let reply = job.reply.clone();
+                let generation = job.generation;
+                // An empty batch still answers the tab, which waits for it before sending more
+                let batch = catch_unwind(AssertUnwindSafe(|| render(job))).unwrap_or_else(|_| {
+                    eprintln!("Rendering a batch of messages panicked; dropping it");
+                    RenderedBatch {
+                        generation,
+                        messages: Vec::new(),
+                        html: Vec::new(),
+                        joined: String::new(),
+                    }
+                });
+                // The tab may have closed meanwhile
+                let _ = reply.send(batch);
+            }
+        })
+        .expect("Failed to start the render thread");
+    Mutex::new(tx)
+});
+
+fn render(job: RenderJob) -> RenderedBatch {
+    let mut html: Vec<String> = job.notice.into_iter().collect();
+    for (msg, options) in job.messages.iter().zip(&job.options) {
+        html.push(parse_message_html(msg, &emote_map_for(msg, &job.emote_map), options));
+    }
+    let joined = html.join("\n");
+    RenderedBatch {
+        generation: job.generation,
+        messages: job.messages,
+        html,
+        joined,
+    }
+}
+
+/// Queues a job for the render thread; the result arrives on the job's `reply` channel.
+/// Returns false if the job couldn't be queued.
+pub fn submit_render(job: RenderJob) -> bool {
+    match WORKER.locked().send(job) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Render thread is gone: {}", e);
+            false
+        }
+    }
+}
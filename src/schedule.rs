@@ -0,0 +1,137 @@
+// schedule.rs
+
+use adw::prelude::*;
+use adw::AlertDialog;
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use glib::clone;
+use serde::{Deserialize, Serialize};
+
+use crate::{get_channel_schedule, set_channel_schedule};
+
+pub const SCHEDULE_CHECK_INTERVAL_SECS: u32 = 30;
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+// Weekly window in which a starred channel is opened automatically.
+// Stored under [schedules] in favorites.toml, keyed by channel.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChannelSchedule {
+    pub days: Vec<u32>, // 0 is Monday
+    pub start: String,  // "19:00", local time
+    pub end: String,    // Earlier than start for windows that run past midnight
+}
+
+fn parse_time(text: &str) -> Option<u32> {
+    let (hours, minutes) = text.trim().split_once(':')?;
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+fn parse_days(text: &str) -> Option<Vec<u32>> {
+    let mut days: Vec<u32> = match text {
+        "daily" | "everyday" => (0..7).collect(),
+        "weekdays" => (0..5).collect(),
+        "weekends" => vec![5, 6],
+        _ => text
+            .split(',')
+            .map(|day| {
+                let day = day.trim();
+                DAY_NAMES
+                    .iter()
+                    .position(|name| day.get(..3) == Some(*name))
+                    .map(|index| index as u32)
+            })
+            .collect::<Option<_>>()?,
+    };
+    days.sort_unstable();
+    days.dedup();
+    (!days.is_empty()).then_some(days)
+}
+
+impl ChannelSchedule {
+    /// Parses "weekdays 19:00-23:00", "sat,sun 14:00-18:00" or "daily 22:00-02:00"
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        let (days, range) = text.rsplit_once(char::is_whitespace)?;
+        let (start, end) = range.split_once('-')?;
+        let (start_minutes, end_minutes) = (parse_time(start)?, parse_time(end)?);
+        if start_minutes == end_minutes {
+            return None;
+        }
+        Some(Self {
+            days: parse_days(days.trim())?,
+            start: format!("{:02}:{:02}", start_minutes / 60, start_minutes % 60),
+            end: format!("{:02}:{:02}", end_minutes / 60, end_minutes % 60),
+        })
+    }
+
+    /// The same form `parse` accepts
+    pub fn describe(&self) -> String {
+        let days = match self.days.as_slice() {
+            [0, 1, 2, 3, 4, 5, 6] => "daily".to_string(),
+            [0, 1, 2, 3, 4] => "weekdays".to_string(),
+            [5, 6] => "weekends".to_string(),
+            days => days
+                .iter()
+                .filter_map(|day| DAY_NAMES.get(*day as usize).copied())
+                .collect::<Vec<_>>()
+                .join(","),
+        };
+        format!("{} {}-{}", days, self.start, self.end)
+    }
+
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        let (Some(start), Some(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        let minute = now.hour() * 60 + now.minute();
+        let today = now.weekday().num_days_from_monday();
+        let yesterday = (today + 6) % 7;
+        if start < end {
+            self.days.contains(&today) && minute >= start && minute < end
+        } else {
+            // Past midnight the window still belongs to the day it started on
+            (self.days.contains(&today) && minute >= start) || (self.days.contains(&yesterday) && minute < end)
+        }
+    }
+}
+
+/// Lets the user set or clear the schedule of `channel`; `on_saved` runs after saving
+pub fn show_schedule_dialog(parent: &impl IsA<gtk::Widget>, channel: &str, on_saved: impl Fn() + 'static) {
+    let entry = gtk::Entry::builder()
+        .placeholder_text("weekdays 19:00-23:00")
+        .text(get_channel_schedule(channel).map(|schedule| schedule.describe()).unwrap_or_default())
+        .activates_default(true)
+        .build();
+    let dialog = AlertDialog::builder()
+        .heading(format!("Schedule #{}", channel))
+        .body("The channel opens when the window starts and its tab closes when it ends. Days can be “daily”, “weekdays”, “weekends” or a list like “mon,wed,fri”. Leave empty to remove the schedule.")
+        .extra_child(&entry)
+        .build();
+    dialog.add_responses(&[("cancel", "Cancel"), ("save", "Save")]);
+    dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("save"));
+    dialog.set_close_response("cancel");
+
+    entry.connect_changed(clone!(
+        #[weak]
+        dialog,
+        move |entry| {
+            let text = entry.text();
+            let valid = text.trim().is_empty() || ChannelSchedule::parse(&text).is_some();
+            dialog.set_response_enabled("save", valid);
+            if valid {
+                entry.remove_css_class("error");
+            } else {
+                entry.add_css_class("error");
+            }
+        }
+    ));
+
+    let channel = channel.to_string();
+    dialog.connect_response(Some("save"), move |_, _| {
+        set_channel_schedule(&channel, ChannelSchedule::parse(&entry.text()));
+        on_saved();
+    });
+    dialog.present(Some(parent));
+}
@@ -0,0 +1,157 @@
+// scopes.rs
+
+use adw::prelude::*;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use crate::auth::{reauthorize, CLIENT_ID, REDIRECT_URI};
+use crate::state::{MutexExt, RwLockExt};
+
+/// What a Twitch login can be used for. Logging in asks only for chat; the others are
+/// granted on top when first needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    Chat,
+    Moderation,
+    Whispers,
+    Follows,
+    ChatColor,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 5] = [
+        Feature::Chat,
+        Feature::Moderation,
+        Feature::Whispers,
+        Feature::Follows,
+        Feature::ChatColor,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Feature::Chat => "Chat",
+            Feature::Moderation => "Moderation",
+            Feature::Whispers => "Whispers",
+            Feature::Follows => "Followed Channels",
+            Feature::ChatColor => "Chat Color",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Feature::Chat => "Send messages as yourself",
+            Feature::Moderation => "Ban and time out chatters from user cards and mod tools",
+            Feature::Whispers => "Send whispers",
+            Feature::Follows => "List followed channels in the quick switcher and live alerts",
+            Feature::ChatColor => "Change the color of your name from the message input",
+        }
+    }
+
+    pub fn scopes(self) -> &'static [&'static str] {
+        match self {
+            Feature::Chat => &["chat:read", "chat:edit"],
+            Feature::Moderation => &["moderator:manage:banned_users"],
+            Feature::Whispers => &["user:manage:whispers"],
+            Feature::Follows => &["user:read:follows"],
+            Feature::ChatColor => &["user:manage:chat_color"],
+        }
+    }
+}
+
+/// The saved token as Twitch's validate endpoint describes it
+#[derive(Debug, Clone)]
+pub struct TokenInfo {
+    pub valid: bool, // False once expired or revoked; the rest is then empty
+    pub login: String,
+    pub scopes: HashSet<String>,
+    pub expires_at: Option<DateTime<Utc>>, // None for tokens that don't expire
+}
+
+impl TokenInfo {
+    pub fn invalid() -> Self {
+        Self {
+            valid: false,
+            login: String::new(),
+            scopes: HashSet::new(),
+            expires_at: None,
+        }
+    }
+}
+
+// None until validated or without a token
+static TOKEN_INFO: Lazy<RwLock<Option<TokenInfo>>> = Lazy::new(|| RwLock::new(None));
+// Features a request failed for lack of scopes, waiting to be offered to the user
+static CONSENT_REQUESTS: Lazy<Mutex<Vec<Feature>>> = Lazy::new(|| Mutex::new(Vec::new()));
+// Offered once per run, so a batch of failed requests doesn't stack up toasts
+static CONSENT_OFFERED: Lazy<Mutex<HashSet<Feature>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+pub fn set_token_info(info: Option<TokenInfo>) {
+    *TOKEN_INFO.write_locked() = info;
+    CONSENT_OFFERED.locked().clear();
+}
+
+pub fn token_info() -> Option<TokenInfo> {
+    TOKEN_INFO.read_locked().clone()
+}
+
+/// Whether the saved token covers `feature`; None when that isn't known yet
+pub fn is_granted(feature: Feature) -> Option<bool> {
+    let info = TOKEN_INFO.read_locked();
+    let info = info.as_ref()?;
+    Some(feature.scopes().iter().all(|scope| info.scopes.contains(*scope)))
+}
+
+/// Queues a prompt to grant `feature`, for when Twitch turned a request down. Callable
+/// from any thread.
+pub fn request_consent(feature: Feature) {
+    if !CONSENT_OFFERED.locked().insert(feature) {
+        return;
+    }
+    eprintln!("The Twitch login lacks permission for {}", feature.label());
+    CONSENT_REQUESTS.locked().push(feature);
+}
+
+/// The authorize page asking for everything already granted plus `features`, so granting
+/// one more doesn't drop the others
+pub fn authorize_url(features: &[Feature]) -> String {
+    let mut scopes: Vec<String> = TOKEN_INFO.read_locked().iter().flat_map(|info| info.scopes.iter().cloned()).collect();
+    for feature in [Feature::Chat].iter().chain(features) {
+        scopes.extend(feature.scopes().iter().map(|scope| scope.to_string()));
+    }
+    scopes.sort();
+    scopes.dedup();
+    format!(
+        "https://id.twitch.tv/oauth2/authorize?client_id={}&redirect_uri={}&response_type=code&scope={}{}",
+        CLIENT_ID,
+        REDIRECT_URI,
+        scopes.join("+"),
+        // Otherwise Twitch skips the consent screen for an app it has seen before
+        if features.is_empty() { "" } else { "&force_verify=true" }
+    )
+}
+
+/// Shows a toast for each queued consent request, leading to the login window with the
+/// missing scopes added
+pub fn watch_consent_requests(window: &adw::ApplicationWindow, toast_overlay: &adw::ToastOverlay) {
+    let window_weak = window.downgrade();
+    let overlay_weak = toast_overlay.downgrade();
+    glib::timeout_add_local(Duration::from_secs(1), move || {
+        let (Some(window), Some(overlay)) = (window_weak.upgrade(), overlay_weak.upgrade()) else {
+            return glib::ControlFlow::Break;
+        };
+        for feature in std::mem::take(&mut *CONSENT_REQUESTS.locked()) {
+            let toast = adw::Toast::builder()
+                .title(format!("{} needs more permissions from Twitch", feature.label()))
+                .button_label("Grant")
+                .timeout(0) // Stays until dismissed
+                .build();
+            let window = window.clone();
+            toast.connect_button_clicked(move |_| reauthorize(&window, &[feature]));
+            overlay.add_toast(toast);
+        }
+        glib::ControlFlow::Continue
+    });
+}
@@ -0,0 +1,22 @@
+// script_messages.rs
+
+use serde::Deserialize;
+
+// Messages posted by the chat page via window.webkit.messageHandlers.admiral
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ScriptMessage {
+    UserCard { login: String },
+    Reply { login: String, message_id: String },
+    Copy { text: String },
+    InsertEmote { name: String }, // From the emote popover, for the message entry
+    CommandBar,
+    LoadOlder { before: i64 }, // Unix milliseconds of the oldest message the page holds
+    ScrollAnchor { message_id: Option<String> }, // Top message while reading back, None while following
+}
+
+pub fn parse_script_message(json: &str) -> Option<ScriptMessage> {
+    serde_json::from_str(json)
+        .map_err(|e| eprintln!("Ignoring unknown script message {}: {}", json, e))
+        .ok()
+}
@@ -0,0 +1,86 @@
+// send_history.rs
+
+use adw::prelude::*;
+use gtk::{gdk, Entry};
+use std::sync::{Arc, Mutex};
+
+use crate::state::MutexExt;
+
+const MAX_ENTRIES: usize = 100;
+
+/// Messages sent from one tab this session, recalled in the send input like shell history
+#[derive(Default)]
+pub struct SendHistory {
+    entries: Vec<String>, // Oldest first
+    position: Option<usize>, // Entry shown while browsing, None when at the draft
+    draft: String, // What was typed before browsing started
+}
+
+impl SendHistory {
+    /// Remembers a sent message and stops browsing. Repeats of the last one are kept once.
+    pub fn push(&mut self, text: &str) {
+        self.position = None;
+        self.draft.clear();
+        let text = text.trim();
+        if text.is_empty() || self.entries.last().is_some_and(|last| last == text) {
+            return;
+        }
+        self.entries.push(text.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    /// The message before the one shown; `current` is kept as the draft when browsing starts
+    pub fn older(&mut self, current: &str) -> Option<String> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(0) => return None,
+            Some(position) => position - 1,
+        };
+        self.position = Some(position);
+        self.entries.get(position).cloned()
+    }
+
+    /// The message after the one shown, or the draft once past the newest
+    pub fn newer(&mut self) -> Option<String> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            self.entries.get(position + 1).cloned()
+        } else {
+            self.position = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+}
+
+/// Lets Up and Down in `entry` step through `history`
+pub fn attach_send_history(entry: &Entry, history: &Arc<Mutex<SendHistory>>) {
+    let controller = gtk::EventControllerKey::new();
+    let history = history.clone();
+    let entry_weak = entry.downgrade();
+    controller.connect_key_pressed(move |_, key, _, modifiers| {
+        let Some(entry) = entry_weak.upgrade() else {
+            return glib::Propagation::Proceed;
+        };
+        if !modifiers.is_empty() {
+            return glib::Propagation::Proceed;
+        }
+        let recalled = match key {
+            gdk::Key::Up => history.locked().older(&entry.text()),
+            gdk::Key::Down => history.locked().newer(),
+            _ => return glib::Propagation::Proceed,
+        };
+        if let Some(text) = recalled {
+            entry.set_text(&text);
+            entry.set_position(-1);
+        }
+        glib::Propagation::Stop
+    });
+    entry.add_controller(controller);
+}
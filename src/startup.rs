@@ -0,0 +1,50 @@
+// startup.rs
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum StartupBehavior {
+    #[default]
+    BlankTab,
+    RestoreSession,
+    ConnectStarred,
+    Background, // Starred channels connect without showing the window
+}
+
+impl StartupBehavior {
+    pub const ALL: [StartupBehavior; 4] = [
+        StartupBehavior::BlankTab,
+        StartupBehavior::RestoreSession,
+        StartupBehavior::ConnectStarred,
+        StartupBehavior::Background,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StartupBehavior::BlankTab => "Open a New Tab",
+            StartupBehavior::RestoreSession => "Restore Last Session",
+            StartupBehavior::ConnectStarred => "Connect Starred Channels",
+            StartupBehavior::Background => "Start in the Background",
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        Self::ALL.iter().position(|b| *b == self).unwrap_or(0) as u32
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+}
+
+// Stored under [startup] in favorites.toml
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StartupSettings {
+    pub behavior: StartupBehavior,
+    pub last_session: Vec<String>, // Channels open when Admiral last quit, in tab order
+    pub run_in_background: bool, // Closing the window hides it and keeps chats connected
+    pub status_icon: bool,
+    pub prefetch_emotes: bool, // Loads starred channels' emotes before they're opened
+    pub check_for_updates: bool, // Asks GitHub for a newer release at launch
+}
@@ -0,0 +1,465 @@
+// stats.rs
+
+use adw::prelude::*;
+use chrono::{DateTime, Local, TimeZone};
+use gtk::{Align, Box as GtkBox, Button, DrawingArea, Image, Label, Orientation, Popover};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::rc::Rc;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::emotes::{find_emote, load_emote_image_bytes};
+use crate::state::MutexExt;
+
+const MAX_MINUTE_BUCKETS: usize = 12 * 60; // Enough for the sparkline to span a marathon stream
+const GRAPH_MINUTES: usize = 30;
+const TOP_EMOTES: usize = 5;
+const SPARKLINE_REFRESH_SECS: u64 = 10;
+
+// Per-session statistics for one tab, reset whenever the tab connects
+#[derive(Debug, Clone)]
+pub struct ChannelStats {
+    pub channel: Option<String>,
+    pub started_at: DateTime<Local>,
+    pub message_count: u64,
+    pub chatters: HashSet<String>,
+    pub emote_counts: HashMap<String, u64>,
+    pub emote_urls: HashMap<String, String>,
+    pub minute_counts: VecDeque<(i64, u32)>, // (minutes since epoch, messages)
+}
+
+impl Default for ChannelStats {
+    fn default() -> Self {
+        Self {
+            channel: None,
+            started_at: Local::now(),
+            message_count: 0,
+            chatters: HashSet::new(),
+            emote_counts: HashMap::new(),
+            emote_urls: HashMap::new(),
+            minute_counts: VecDeque::new(),
+        }
+    }
+}
+
+impl ChannelStats {
+    pub fn new(channel: &str) -> Self {
+        Self {
+            channel: Some(channel.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self {
+            channel: self.channel.take(),
+            ..Self::default()
+        };
+    }
+
+    pub fn record(&mut self, msg: &PrivmsgMessage, emote_map: &HashMap<String, (String, bool)>) {
+        self.message_count += 1;
+        if !self.chatters.contains(&msg.sender.login) {
+            self.chatters.insert(msg.sender.login.clone());
+        }
+        for word in msg.message_text.split_whitespace() {
+            if let Some((url, _)) = find_emote(emote_map, word) {
+                *self.emote_counts.entry(word.to_string()).or_insert(0) += 1;
+                if !self.emote_urls.contains_key(word) {
+                    self.emote_urls.insert(word.to_string(), url.clone());
+                }
+            }
+        }
+
+        let minute = msg.server_timestamp.timestamp().div_euclid(60);
+        match self.minute_counts.back_mut() {
+            Some((last, count)) if *last == minute => *count += 1,
+            Some((last, _)) if *last > minute => {} // Late message for an older bucket
+            _ => {
+                self.minute_counts.push_back((minute, 1));
+                if self.minute_counts.len() > MAX_MINUTE_BUCKETS {
+                    self.minute_counts.pop_front();
+                }
+            }
+        }
+    }
+
+    pub fn top_emotes(&self, limit: usize) -> Vec<(String, u64)> {
+        let mut emotes: Vec<(String, u64)> = self
+            .emote_counts
+            .iter()
+            .map(|(name, count)| (name.clone(), *count))
+            .collect();
+        emotes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        emotes.truncate(limit);
+        emotes
+    }
+
+    pub fn messages_per_minute(&self) -> f64 {
+        let elapsed = Local::now()
+            .signed_duration_since(self.started_at)
+            .num_seconds()
+            .max(60);
+        self.message_count as f64 * 60.0 / elapsed as f64
+    }
+
+    /// Message counts for the last `minutes` minutes, oldest first, with gaps filled in
+    pub fn recent_minutes(&self, minutes: usize) -> Vec<u32> {
+        let now = Local::now().timestamp().div_euclid(60);
+        self.minutes_between(now - minutes as i64 + 1, now)
+    }
+
+    /// Message counts for every minute of the session that is still kept, oldest first,
+    /// along with the minute (since epoch) the first count belongs to
+    pub fn session_minutes(&self) -> (i64, Vec<u32>) {
+        let now = Local::now().timestamp().div_euclid(60);
+        let start = self
+            .started_at
+            .timestamp()
+            .div_euclid(60)
+            .max(now - MAX_MINUTE_BUCKETS as i64 + 1);
+        (start, self.minutes_between(start, now))
+    }
+
+    fn minutes_between(&self, first: i64, last: i64) -> Vec<u32> {
+        let counts: HashMap<i64, u32> = self.minute_counts.iter().copied().collect();
+        (first..=last)
+            .map(|minute| counts.get(&minute).copied().unwrap_or(0))
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("type,key,value\n");
+        let _ = writeln!(csv, "summary,channel,{}", csv_field(self.channel.as_deref().unwrap_or("")));
+        let _ = writeln!(csv, "summary,started_at,{}", self.started_at.format("%Y-%m-%d %H:%M:%S"));
+        let _ = writeln!(csv, "summary,messages,{}", self.message_count);
+        let _ = writeln!(csv, "summary,unique_chatters,{}", self.chatters.len());
+        let _ = writeln!(csv, "summary,messages_per_minute,{:.2}", self.messages_per_minute());
+        for (name, count) in self.top_emotes(usize::MAX) {
+            let _ = writeln!(csv, "emote,{},{}", csv_field(&name), count);
+        }
+        for (minute, count) in &self.minute_counts {
+            let time = Local
+                .timestamp_opt(minute * 60, 0)
+                .single()
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            let _ = writeln!(csv, "minute,{},{}", time, count);
+        }
+        csv
+    }
+}
+
+// Quotes a CSV field when it contains separators or quotes
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// --- Activity Sparkline ---
+
+// Which minute of `len` a point `x` across the sparkline falls on
+fn sparkline_index(x: f64, width: i32, len: usize) -> usize {
+    let last = len.saturating_sub(1) as f64;
+    (x / width.max(1) as f64 * last).round().clamp(0.0, last) as usize
+}
+
+/// Thin messages-per-minute graph of the whole session. Clicking it passes the
+/// chosen minute, in milliseconds since epoch, to `on_jump`.
+pub fn build_activity_sparkline(stats: &Arc<Mutex<ChannelStats>>, on_jump: impl Fn(i64) + 'static) -> DrawingArea {
+    let sparkline = DrawingArea::builder()
+        .content_height(20)
+        .hexpand(true)
+        .has_tooltip(true)
+        .build();
+    sparkline.set_cursor_from_name(Some("pointer"));
+
+    let stats_for_draw = stats.clone();
+    sparkline.set_draw_func(move |area, cr, width, height| {
+        let (_, counts) = stats_for_draw.locked().session_minutes();
+        if counts.len() < 2 {
+            return;
+        }
+        let max = counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+        let step = width as f64 / (counts.len() - 1) as f64;
+        let y = |count: u32| height as f64 - count as f64 / max * (height as f64 - 2.0) - 1.0;
+        let color = area.color();
+        let (red, green, blue) = (color.red() as f64, color.green() as f64, color.blue() as f64);
+
+        cr.move_to(0.0, y(counts[0]));
+        for (i, count) in counts.iter().enumerate().skip(1) {
+            cr.line_to(i as f64 * step, y(*count));
+        }
+        cr.set_source_rgba(red, green, blue, 0.7);
+        cr.set_line_width(1.0);
+        let _ = cr.stroke_preserve();
+        cr.line_to(width as f64, height as f64);
+        cr.line_to(0.0, height as f64);
+        cr.close_path();
+        cr.set_source_rgba(red, green, blue, 0.2);
+        let _ = cr.fill();
+    });
+
+    let stats_for_tooltip = stats.clone();
+    sparkline.connect_query_tooltip(move |area, x, _, _, tooltip| {
+        let (start, counts) = stats_for_tooltip.locked().session_minutes();
+        if counts.len() < 2 {
+            return false;
+        }
+        let index = sparkline_index(x as f64, area.width(), counts.len());
+        let Some(time) = Local.timestamp_opt((start + index as i64) * 60, 0).single() else {
+            return false;
+        };
+        let count = counts[index];
+        tooltip.set_text(Some(&format!(
+            "{}: {} message{}",
+            time.format("%H:%M"),
+            count,
+            if count == 1 { "" } else { "s" }
+        )));
+        true
+    });
+
+    let click = gtk::GestureClick::new();
+    let stats_for_click = stats.clone();
+    click.connect_released(move |gesture, _, x, _| {
+        let Some(area) = gesture.widget() else {
+            return;
+        };
+        let (start, counts) = stats_for_click.locked().session_minutes();
+        if counts.len() < 2 {
+            return;
+        }
+        let index = sparkline_index(x, area.width(), counts.len());
+        on_jump((start + index as i64) * 60_000);
+    });
+    sparkline.add_controller(click);
+
+    // Redraws until the tab is closed, as the newest minute fills in
+    let sparkline_weak = sparkline.downgrade();
+    glib::timeout_add_local(Duration::from_secs(SPARKLINE_REFRESH_SECS), move || {
+        match sparkline_weak.upgrade() {
+            Some(sparkline) => {
+                if sparkline.is_mapped() {
+                    sparkline.queue_draw();
+                }
+                glib::ControlFlow::Continue
+            }
+            None => glib::ControlFlow::Break,
+        }
+    });
+
+    sparkline
+}
+
+// --- Statistics Popover ---
+
+fn build_leaderboard_row(rank: usize, name: &str, count: u64, url: Option<&str>) -> GtkBox {
+    let row = GtkBox::new(Orientation::Horizontal, 8);
+
+    let rank_label = Label::new(Some(&format!("{}.", rank)));
+    rank_label.add_css_class("dim-label");
+    rank_label.set_width_chars(2);
+
+    let image = Image::builder().pixel_size(28).build();
+    if let Some(url) = url {
+        let url = url.to_string();
+        let image_weak = image.downgrade();
+        glib::MainContext::default().spawn_local(async move {
+            let bytes = adw::gio::spawn_blocking(move || load_emote_image_bytes(&url))
+                .await
+                .ok()
+                .flatten();
+            let (Some(bytes), Some(image)) = (bytes, image_weak.upgrade()) else {
+                return;
+            };
+            match gtk::gdk::Texture::from_bytes(&glib::Bytes::from_owned(bytes.to_vec())) {
+                Ok(texture) => image.set_paintable(Some(&texture)),
+                Err(e) => eprintln!("Failed to decode emote image: {}", e),
+            }
+        });
+    }
+
+    let name_label = Label::new(Some(name));
+    name_label.set_hexpand(true);
+    name_label.set_halign(Align::Start);
+    name_label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+
+    let count_label = Label::new(Some(&count.to_string()));
+    count_label.add_css_class("numeric");
+
+    row.append(&rank_label);
+    row.append(&image);
+    row.append(&name_label);
+    row.append(&count_label);
+    row
+}
+
+pub fn build_stats_popover(stats: &Arc<Mutex<ChannelStats>>) -> Popover {
+    let popover = Popover::builder().autohide(true).build();
+
+    let content = GtkBox::new(Orientation::Vertical, 6);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.set_width_request(260);
+
+    let title = Label::new(Some("Session Statistics"));
+    title.add_css_class("heading");
+    title.set_halign(Align::Start);
+
+    let summary_label = Label::new(None);
+    summary_label.set_halign(Align::Start);
+    summary_label.set_xalign(0.0);
+
+    let graph_title = Label::new(Some("Messages per minute"));
+    graph_title.add_css_class("dim-label");
+    graph_title.set_halign(Align::Start);
+
+    let graph = DrawingArea::builder()
+        .content_height(60)
+        .hexpand(true)
+        .build();
+    let stats_for_graph = stats.clone();
+    graph.set_draw_func(move |area, cr, width, height| {
+        let counts = stats_for_graph.locked().recent_minutes(GRAPH_MINUTES);
+        let max = counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+        let bar_width = width as f64 / counts.len().max(1) as f64;
+        let color = area.color();
+        cr.set_source_rgba(
+            color.red() as f64,
+            color.green() as f64,
+            color.blue() as f64,
+            0.6,
+        );
+        for (i, count) in counts.iter().enumerate() {
+            let bar_height = *count as f64 / max * height as f64;
+            cr.rectangle(
+                i as f64 * bar_width + 1.0,
+                height as f64 - bar_height,
+                (bar_width - 2.0).max(1.0),
+                bar_height,
+            );
+        }
+        let _ = cr.fill();
+    });
+
+    let emotes_title = Label::new(Some("Top emotes"));
+    emotes_title.add_css_class("dim-label");
+    emotes_title.set_halign(Align::Start);
+
+    let leaderboard = GtkBox::new(Orientation::Vertical, 4);
+
+    let button_box = GtkBox::new(Orientation::Horizontal, 6);
+    button_box.set_halign(Align::End);
+    button_box.set_margin_top(6);
+    let reset_button = Button::with_label("Reset");
+    let export_button = Button::with_label("Export CSV");
+    button_box.append(&reset_button);
+    button_box.append(&export_button);
+
+    content.append(&title);
+    content.append(&summary_label);
+    content.append(&graph_title);
+    content.append(&graph);
+    content.append(&emotes_title);
+    content.append(&leaderboard);
+    content.append(&button_box);
+    popover.set_child(Some(&content));
+
+    let refresh = {
+        let stats = stats.clone();
+        let summary_label = summary_label.clone();
+        let leaderboard = leaderboard.clone();
+        let graph = graph.clone();
+        let shown_emotes: Rc<RefCell<Option<Vec<(String, u64)>>>> = Rc::new(RefCell::new(None));
+        move || {
+            let stats = stats.locked();
+            summary_label.set_text(&format!(
+                "Messages: {}\nUnique chatters: {}\nAverage: {:.1} msg/min",
+                stats.message_count,
+                stats.chatters.len(),
+                stats.messages_per_minute(),
+            ));
+            let top = stats.top_emotes(TOP_EMOTES);
+            // Rebuilding the rows reloads images, so skip it when nothing moved
+            if shown_emotes.borrow().as_ref() != Some(&top) {
+                while let Some(child) = leaderboard.first_child() {
+                    leaderboard.remove(&child);
+                }
+                if top.is_empty() {
+                    let empty_label = Label::new(Some("No emotes yet"));
+                    empty_label.set_halign(Align::Start);
+                    leaderboard.append(&empty_label);
+                }
+                for (rank, (name, count)) in top.iter().enumerate() {
+                    leaderboard.append(&build_leaderboard_row(
+                        rank + 1,
+                        name,
+                        *count,
+                        stats.emote_urls.get(name).map(String::as_str),
+                    ));
+                }
+                *shown_emotes.borrow_mut() = Some(top);
+            }
+            graph.queue_draw();
+        }
+    };
+
+    // Refresh while visible; the timer stops itself once the popover closes
+    let refresh_on_show = refresh.clone();
+    popover.connect_show(move |popover| {
+        refresh_on_show();
+        let refresh = refresh_on_show.clone();
+        let popover_weak = popover.downgrade();
+        glib::timeout_add_local(Duration::from_secs(2), move || {
+            match popover_weak.upgrade() {
+                Some(popover) if popover.is_visible() => {
+                    refresh();
+                    glib::ControlFlow::Continue
+                }
+                _ => glib::ControlFlow::Break,
+            }
+        });
+    });
+
+    let stats_for_reset = stats.clone();
+    let refresh_on_reset = refresh.clone();
+    reset_button.connect_clicked(move |_| {
+        stats_for_reset.locked().reset();
+        refresh_on_reset();
+    });
+
+    let stats_for_export = stats.clone();
+    export_button.connect_clicked(move |button| {
+        let stats = stats_for_export.locked().clone();
+        let file_name = format!(
+            "{}-{}.csv",
+            stats.channel.as_deref().unwrap_or("chat"),
+            stats.started_at.format("%Y%m%d-%H%M")
+        );
+        let dialog = gtk::FileDialog::builder()
+            .title("Export Statistics")
+            .initial_name(file_name)
+            .build();
+        let window = button.root().and_downcast::<gtk::Window>();
+        dialog.save(window.as_ref(), None::<&adw::gio::Cancellable>, move |result| {
+            if let Ok(file) = result {
+                if let Some(path) = file.path() {
+                    if let Err(e) = std::fs::write(&path, stats.to_csv()) {
+                        eprintln!("Failed to export statistics to {}: {}", path.display(), e);
+                    }
+                }
+            }
+        });
+    });
+
+    popover
+}
@@ -0,0 +1,118 @@
+// status_icon.rs
+
+use adw::gio;
+use adw::prelude::*;
+use std::cell::RefCell;
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const ITEM_XML: &str = r#"
+<node>
+  <interface name="org.kde.StatusNotifierItem">
+    <property name="Category" type="s" access="read"/>
+    <property name="Id" type="s" access="read"/>
+    <property name="Title" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconName" type="s" access="read"/>
+    <property name="ItemIsMenu" type="b" access="read"/>
+    <method name="Activate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="SecondaryActivate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="ContextMenu">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="Scroll">
+      <arg name="delta" type="i" direction="in"/>
+      <arg name="orientation" type="s" direction="in"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+thread_local! {
+    static REGISTRATION: RefCell<Option<(gio::DBusConnection, gio::RegistrationId)>> = const { RefCell::new(None) };
+}
+
+/// Shows or removes the tray icon; clicking it brings the window back. Only appears
+/// where a StatusNotifierWatcher runs, which on GNOME takes the AppIndicator extension.
+pub fn set_status_icon_visible(app: &gtk::Application, visible: bool) {
+    REGISTRATION.with(|registration| {
+        let mut registration = registration.borrow_mut();
+        if !visible {
+            if let Some((connection, id)) = registration.take() {
+                if let Err(e) = connection.unregister_object(id) {
+                    eprintln!("Failed to remove status icon: {}", e);
+                }
+            }
+            return;
+        }
+        if registration.is_some() {
+            return;
+        }
+        let Some(connection) = app.dbus_connection() else {
+            return;
+        };
+        let Some(interface) = gio::DBusNodeInfo::for_xml(ITEM_XML)
+            .ok()
+            .and_then(|node| node.lookup_interface(ITEM_INTERFACE))
+        else {
+            return;
+        };
+
+        let app_weak = app.downgrade();
+        let icon_name = app.application_id().map(|id| id.to_string()).unwrap_or_default();
+        let id = connection
+            .register_object(ITEM_PATH, &interface)
+            .method_call(move |_, _, _, _, method, _, invocation| {
+                if method == "Activate" || method == "SecondaryActivate" {
+                    if let Some(app) = app_weak.upgrade() {
+                        app.activate();
+                    }
+                }
+                invocation.return_value(None);
+            })
+            .property(move |_, _, _, _, property| match property {
+                "Category" => "Communications".to_variant(),
+                "Id" => "admiral".to_variant(),
+                "Title" => "Admiral".to_variant(),
+                "Status" => "Active".to_variant(),
+                "IconName" => icon_name.to_variant(),
+                "ItemIsMenu" => false.to_variant(),
+                _ => "".to_variant(),
+            })
+            .build();
+        let id = match id {
+            Ok(id) => id,
+            Err(e) => {
+                eprintln!("Failed to export status icon: {}", e);
+                return;
+            }
+        };
+
+        if let Some(bus_name) = connection.unique_name() {
+            connection.call(
+                Some("org.kde.StatusNotifierWatcher"),
+                "/StatusNotifierWatcher",
+                "org.kde.StatusNotifierWatcher",
+                "RegisterStatusNotifierItem",
+                Some(&(bus_name.as_str(),).to_variant()),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+                None::<&gio::Cancellable>,
+                |result| {
+                    if let Err(e) = result {
+                        eprintln!("No system tray available for the status icon: {}", e);
+                    }
+                },
+            );
+        }
+        *registration = Some((connection, id));
+    });
+}
@@ -0,0 +1,105 @@
+// token_store.rs
+
+use age::secrecy::SecretString;
+use once_cell::sync::Lazy;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::profile::config_dir;
+use crate::state::RwLockExt;
+
+// Without a Secret Service (a bare window manager, a headless box) the token is kept in
+// an age file encrypted with a passphrase instead. Once unlocked it stays in memory for
+// the rest of the run, since load_token is called from worker threads that can't prompt.
+static UNLOCKED_TOKEN: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+
+fn token_file_path() -> PathBuf {
+    config_dir().join("token.age")
+}
+
+pub fn has_token_file() -> bool {
+    token_file_path().exists()
+}
+
+/// The token from the encrypted file, if it has been unlocked this run
+pub fn unlocked_token() -> Option<String> {
+    UNLOCKED_TOKEN.read_locked().clone()
+}
+
+/// Encrypts `token` with `passphrase` into the token file, replacing any earlier one
+pub fn save_token_file(token: &str, passphrase: &str) -> Result<(), String> {
+    let encryptor = age::Encryptor::with_user_passphrase(SecretString::from(passphrase.to_string()));
+    let mut encrypted = Vec::new();
+    let mut writer = encryptor
+        .wrap_output(&mut encrypted)
+        .map_err(|e| format!("Failed to encrypt token: {}", e))?;
+    writer
+        .write_all(token.as_bytes())
+        .and_then(|_| writer.finish().map(|_| ()))
+        .map_err(|e| format!("Failed to encrypt token: {}", e))?;
+
+    let path = token_file_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    open_private(&path)
+        .and_then(|mut file| file.write_all(&encrypted))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    // The mode above only applies to new files; an older one may have been created wider
+    restrict_permissions(&path);
+    *UNLOCKED_TOKEN.write_locked() = Some(token.to_string());
+    Ok(())
+}
+
+/// Decrypts the token file with `passphrase` and keeps the token for this run
+pub fn unlock_token_file(passphrase: &str) -> Result<(), String> {
+    let encrypted = fs::read(token_file_path()).map_err(|e| format!("Failed to read token file: {}", e))?;
+    let decryptor = age::Decryptor::new(&encrypted[..]).map_err(|e| format!("Token file is damaged: {}", e))?;
+    let identity = age::scrypt::Identity::new(SecretString::from(passphrase.to_string()));
+    let mut reader = decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .map_err(|_| "Wrong passphrase".to_string())?;
+    let mut token = String::new();
+    reader
+        .read_to_string(&mut token)
+        .map_err(|e| format!("Failed to decrypt token: {}", e))?;
+    *UNLOCKED_TOKEN.write_locked() = Some(token);
+    Ok(())
+}
+
+/// Deletes the token file and forgets the unlocked token
+pub fn delete_token_file() -> Result<(), String> {
+    *UNLOCKED_TOKEN.write_locked() = None;
+    let path = token_file_path();
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to delete {}: {}", path.display(), e)),
+    }
+}
+
+// Encrypted or not, the file is nobody else's business, so it's never readable by
+// others, not even between being created and written
+#[cfg(unix)]
+fn open_private(path: &Path) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn open_private(path: &Path) -> std::io::Result<fs::File> {
+    fs::File::create(path)
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(0o600)) {
+        eprintln!("Failed to restrict permissions of {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) {}
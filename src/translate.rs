@@ -0,0 +1,301 @@
+// translate.rs
+
+use once_cell::sync::Lazy;
+use reqwest::blocking::Client; // Blocking client for the worker thread
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::sync::{mpsc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::network::{http_client, http_client_builder};
+use crate::pump::PumpSender;
+use crate::state::{MutexExt, RwLockExt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum TranslationBackend {
+    #[default]
+    LibreTranslate,
+    DeepL,
+}
+
+// Stored under [translation] in favorites.toml
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TranslationConfig {
+    pub backend: TranslationBackend,
+    pub libretranslate_url: String,
+    pub api_key: Option<String>, // LibreTranslate key (optional) or DeepL auth key
+    pub target_language: String, // ISO 639-1 code, e.g. "en"
+}
+
+impl Default for TranslationConfig {
+    fn default() -> Self {
+        Self {
+            backend: TranslationBackend::LibreTranslate,
+            libretranslate_url: "https://libretranslate.com".to_string(),
+            api_key: None,
+            target_language: "en".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Translation {
+    pub source_language: String,
+    pub text: String,
+}
+
+// Delivered back to the owning tab once the backend has answered
+#[derive(Debug, Clone)]
+pub struct TranslatedMessage {
+    pub message_id: String,
+    pub translation: Translation,
+}
+
+struct TranslationJob {
+    config: TranslationConfig,
+    message_id: String,
+    text: String,
+    reply: PumpSender<TranslatedMessage>,
+}
+
+const MAX_QUEUED_JOBS: usize = 200;
+const MAX_CACHED_TRANSLATIONS: usize = 2000;
+
+// --- Global State for the Translation Worker ---
+// Keyed by "target\0text"; None records "already in the target language"
+static TRANSLATION_CACHE: Lazy<RwLock<HashMap<String, Option<Translation>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+static JOB_SENDER: Lazy<Mutex<mpsc::SyncSender<TranslationJob>>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::sync_channel::<TranslationJob>(MAX_QUEUED_JOBS);
+    thread::spawn(move || run_worker(rx));
+    Mutex::new(tx)
+});
+
+#[derive(Debug, Deserialize)]
+struct LibreTranslateResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: String,
+    #[serde(rename = "detectedLanguage")]
+    detected_language: Option<LibreDetectedLanguage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibreDetectedLanguage {
+    language: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepLTranslation {
+    detected_source_language: String,
+    text: String,
+}
+
+/// Returns true if the text has enough letters to be worth sending to a backend
+pub fn is_translatable(text: &str) -> bool {
+    if text.starts_with('!') {
+        return false; // Bot commands
+    }
+    let letters = text
+        .split_whitespace()
+        .filter(|word| !word.starts_with("http://") && !word.starts_with("https://"))
+        .flat_map(|word| word.chars())
+        .filter(|c| c.is_alphabetic())
+        .count();
+    letters >= 4
+}
+
+/// Queues a message for translation. Results arrive on `reply`; messages that are
+/// already in the target language never produce a result.
+pub fn request_translation(
+    config: &TranslationConfig,
+    message_id: &str,
+    text: &str,
+    reply: &PumpSender<TranslatedMessage>,
+) {
+    let cache_key = format!("{}\0{}", config.target_language, text);
+    if let Some(cached) = TRANSLATION_CACHE.read_locked().get(&cache_key) {
+        if let Some(translation) = cached {
+            let _ = reply.send(TranslatedMessage {
+                message_id: message_id.to_string(),
+                translation: translation.clone(),
+            });
+        }
+        return;
+    }
+
+    let job = TranslationJob {
+        config: config.clone(),
+        message_id: message_id.to_string(),
+        text: text.to_string(),
+        reply: reply.clone(),
+    };
+    if let Err(mpsc::TrySendError::Full(_)) = JOB_SENDER.locked().try_send(job) {
+        eprintln!("Translation queue full, skipping message {}", message_id);
+    }
+}
+
+// --- Worker Thread ---
+fn run_worker(rx: mpsc::Receiver<TranslationJob>) {
+    let client = http_client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_else(|_| http_client());
+
+    while let Ok(job) = rx.recv() {
+        let cache_key = format!("{}\0{}", job.config.target_language, job.text);
+        let cached = TRANSLATION_CACHE.read_locked().get(&cache_key).cloned();
+        let result = match cached {
+            Some(result) => result,
+            None => match translate_text(&client, &job.config, &job.text) {
+                Ok(result) => {
+                    let mut cache = TRANSLATION_CACHE.write_locked();
+                    if cache.len() >= MAX_CACHED_TRANSLATIONS {
+                        cache.clear();
+                    }
+                    cache.insert(cache_key, result.clone());
+                    result
+                }
+                Err(e) => {
+                    eprintln!("Failed to translate message {}: {}", job.message_id, e);
+                    continue;
+                }
+            },
+        };
+
+        if let Some(translation) = result {
+            // The tab may have been closed in the meantime
+            let _ = job.reply.send(TranslatedMessage {
+                message_id: job.message_id,
+                translation,
+            });
+        }
+    }
+}
+
+// --- Backend Requests ---
+fn translate_text(
+    client: &Client,
+    config: &TranslationConfig,
+    text: &str,
+) -> Result<Option<Translation>, Box<dyn StdError + Send + Sync>> {
+    let translation = match config.backend {
+        TranslationBackend::LibreTranslate => translate_libretranslate(client, config, text)?,
+        TranslationBackend::DeepL => translate_deepl(client, config, text)?,
+    };
+
+    if is_same_language(&translation.source_language, &config.target_language)
+        || translation.text.trim() == text.trim()
+    {
+        return Ok(None);
+    }
+    Ok(Some(translation))
+}
+
+fn translate_libretranslate(
+    client: &Client,
+    config: &TranslationConfig,
+    text: &str,
+) -> Result<Translation, Box<dyn StdError + Send + Sync>> {
+    let url = format!("{}/translate", config.libretranslate_url.trim_end_matches('/'));
+    let mut body = serde_json::json!({
+        "q": text,
+        "source": "auto",
+        "target": config.target_language,
+        "format": "text",
+    });
+    if let Some(key) = config.api_key.as_deref().filter(|k| !k.is_empty()) {
+        body["api_key"] = serde_json::Value::String(key.to_string());
+    }
+
+    let response = client.post(&url).json(&body).send()?;
+    if !response.status().is_success() {
+        return Err(format!("LibreTranslate request failed with status {}", response.status()).into());
+    }
+    let parsed: LibreTranslateResponse = response.json()?;
+    Ok(Translation {
+        source_language: parsed
+            .detected_language
+            .map(|d| d.language)
+            .unwrap_or_default(),
+        text: parsed.translated_text,
+    })
+}
+
+fn translate_deepl(
+    client: &Client,
+    config: &TranslationConfig,
+    text: &str,
+) -> Result<Translation, Box<dyn StdError + Send + Sync>> {
+    let key = config
+        .api_key
+        .as_deref()
+        .filter(|k| !k.is_empty())
+        .ok_or("DeepL requires an API key")?;
+    // Free-tier keys end in ":fx" and use a separate host
+    let url = if key.ends_with(":fx") {
+        "https://api-free.deepl.com/v2/translate"
+    } else {
+        "https://api.deepl.com/v2/translate"
+    };
+    let body = serde_json::json!({
+        "text": [text],
+        "target_lang": config.target_language.to_uppercase(),
+    });
+
+    let response = client
+        .post(url)
+        .header("Authorization", format!("DeepL-Auth-Key {}", key))
+        .json(&body)
+        .send()?;
+    if !response.status().is_success() {
+        return Err(format!("DeepL request failed with status {}", response.status()).into());
+    }
+    let parsed: DeepLResponse = response.json()?;
+    let first = parsed
+        .translations
+        .into_iter()
+        .next()
+        .ok_or("DeepL returned no translations")?;
+    Ok(Translation {
+        source_language: first.detected_source_language,
+        text: first.text,
+    })
+}
+
+// --- Helper Functions ---
+
+// Compares primary language subtags, so "EN-US" matches "en"
+fn is_same_language(a: &str, b: &str) -> bool {
+    let primary = |s: &str| s.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+    !a.is_empty() && primary(a) == primary(b)
+}
+
+pub fn translation_html(translation: &Translation) -> String {
+    format!(
+        r#"<div class="message-translation"><span class="translation-lang">{}</span> {}</div>"#,
+        glib::markup_escape_text(&translation.source_language.to_uppercase()),
+        glib::markup_escape_text(&translation.text)
+    )
+}
+
+/// Inserts a translation into an already rendered message box, so buffered
+/// history keeps the translation when it is re-injected.
+pub fn insert_translation_html(message_html: &str, translation: &Translation) -> Option<String> {
+    if message_html.contains("message-translation") {
+        return None;
+    }
+    let closing = message_html.rfind("</div>")?;
+    let mut html = String::with_capacity(message_html.len() + 128);
+    html.push_str(&message_html[..closing]);
+    html.push_str(&translation_html(translation));
+    html.push_str(&message_html[closing..]);
+    Some(html)
+}
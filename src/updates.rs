@@ -0,0 +1,135 @@
+// updates.rs
+
+use adw::prelude::*;
+use serde::Deserialize;
+use std::error::Error as StdError;
+
+use crate::network::{http_client, is_offline};
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/toasterrepairman/admiral/releases/latest";
+
+/// A published release newer than the running build
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: String,
+    pub notes: String,
+    pub url: String, // The release page, with the downloads
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    body: String,
+    html_url: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+// "v1.2.3" or "1.2.3" as comparable numbers; anything after a '-' is ignored
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split('-').next()?;
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+fn is_newer(candidate: &str, current: &str) -> bool {
+    match (parse_version(candidate), parse_version(current)) {
+        (Some(candidate), Some(current)) => candidate > current,
+        _ => false,
+    }
+}
+
+/// The latest GitHub release if it's newer than this build. Blocking.
+pub fn fetch_newer_release() -> Result<Option<Release>, Box<dyn StdError + Send + Sync>> {
+    let response = http_client()
+        .get(LATEST_RELEASE_URL)
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", concat!("admiral/", env!("CARGO_PKG_VERSION")))
+        .send()?;
+    if !response.status().is_success() {
+        return Err(format!("GitHub releases request failed with status {}", response.status()).into());
+    }
+    let release: GithubRelease = response.json()?;
+    if release.draft || release.prerelease || !is_newer(&release.tag_name, env!("CARGO_PKG_VERSION")) {
+        return Ok(None);
+    }
+    Ok(Some(Release {
+        version: release.tag_name.trim_start_matches('v').to_string(),
+        notes: release.body,
+        url: release.html_url,
+    }))
+}
+
+/// Looks for a newer release in the background and, if there is one, shows a toast
+/// that opens its release notes. Meant for installs from a tarball; package managers
+/// handle updates themselves, hence opt-in.
+pub fn check_for_updates(window: &adw::ApplicationWindow, toast_overlay: &adw::ToastOverlay) {
+    if is_offline() {
+        return;
+    }
+    let window_weak = window.downgrade();
+    let overlay_weak = toast_overlay.downgrade();
+    glib::MainContext::default().spawn_local(async move {
+        let result = adw::gio::spawn_blocking(fetch_newer_release)
+            .await
+            .unwrap_or_else(|_| Err("Update check panicked".into()));
+        let release = match result {
+            Ok(Some(release)) => release,
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("Failed to check for updates: {}", e);
+                return;
+            }
+        };
+        let (Some(window), Some(overlay)) = (window_weak.upgrade(), overlay_weak.upgrade()) else {
+            return;
+        };
+        println!("Admiral {} is available", release.version);
+        let toast = adw::Toast::builder()
+            .title(format!("Admiral {} is available", release.version))
+            .button_label("Details")
+            .timeout(0) // Stays until dismissed
+            .build();
+        toast.connect_button_clicked(move |_| show_release_notes(&window, &release));
+        overlay.add_toast(toast);
+    });
+}
+
+fn show_release_notes(window: &adw::ApplicationWindow, release: &Release) {
+    let notes = gtk::Label::builder()
+        .label(if release.notes.trim().is_empty() { "No release notes." } else { release.notes.trim() })
+        .wrap(true)
+        .xalign(0.0)
+        .selectable(true)
+        .build();
+    let scrolled = gtk::ScrolledWindow::builder()
+        .child(&notes)
+        .min_content_height(200)
+        .max_content_height(400)
+        .propagate_natural_height(true)
+        .build();
+    let dialog = adw::AlertDialog::builder()
+        .heading(format!("Admiral {}", release.version))
+        .body(format!("You're running {}.", env!("CARGO_PKG_VERSION")))
+        .extra_child(&scrolled)
+        .build();
+    dialog.add_responses(&[("close", "Close"), ("download", "Download")]);
+    dialog.set_response_appearance("download", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("download"));
+    dialog.set_close_response("close");
+
+    let window_weak = window.downgrade();
+    let url = release.url.clone();
+    dialog.connect_response(Some("download"), move |_, _| {
+        let launcher = gtk::UriLauncher::new(&url);
+        launcher.launch(window_weak.upgrade().as_ref(), None::<&adw::gio::Cancellable>, |result| {
+            if let Err(e) = result {
+                eprintln!("Failed to open the release page: {}", e);
+            }
+        });
+    });
+    dialog.present(Some(window));
+}
@@ -0,0 +1,180 @@
+// upload.rs
+
+use adw::prelude::*;
+use gtk::{gdk, Entry};
+use reqwest::blocking::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::get_upload_settings;
+use crate::network::http_client;
+
+const KAPPA_LOL_URL: &str = "https://kappa.lol/api/upload";
+const IMGUR_URL: &str = "https://api.imgur.com/3/image";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum UploadHost {
+    #[default]
+    KappaLol,
+    Imgur, // Anonymous uploads, but needs an application's client id
+}
+
+impl UploadHost {
+    pub const ALL: [UploadHost; 2] = [UploadHost::KappaLol, UploadHost::Imgur];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            UploadHost::KappaLol => "kappa.lol",
+            UploadHost::Imgur => "Imgur",
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        Self::ALL.iter().position(|h| *h == self).unwrap_or(0) as u32
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+}
+
+// Stored under [upload] in favorites.toml
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UploadSettings {
+    pub host: UploadHost,
+    pub imgur_client_id: String,
+}
+
+/// Uploads a PNG and returns the link to it. Blocking.
+pub fn upload_image(settings: &UploadSettings, png: Vec<u8>) -> Result<String, String> {
+    let part = Part::bytes(png)
+        .file_name("pasted.png")
+        .mime_str("image/png")
+        .map_err(|e| e.to_string())?;
+    let request = match settings.host {
+        UploadHost::KappaLol => http_client().post(KAPPA_LOL_URL).multipart(Form::new().part("file", part)),
+        UploadHost::Imgur => {
+            if settings.imgur_client_id.trim().is_empty() {
+                return Err("Imgur needs a client id, set one in preferences".to_string());
+            }
+            http_client()
+                .post(IMGUR_URL)
+                .header(reqwest::header::AUTHORIZATION, format!("Client-ID {}", settings.imgur_client_id.trim()))
+                .multipart(Form::new().part("image", part))
+        }
+    };
+    let response = request.send().map_err(|e| e.to_string())?;
+    // Error pages aren't always JSON, so the status goes first
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{} answered with status {}", settings.host.label(), status));
+    }
+    let body: Value = response.json().map_err(|e| e.to_string())?;
+    let link = match settings.host {
+        UploadHost::KappaLol => body.get("link"),
+        UploadHost::Imgur => body.pointer("/data/link"),
+    };
+    link.and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| format!("No link in the {} response", settings.host.label()))
+}
+
+/// Makes pasting an image into `entry` offer to upload it and insert the link instead.
+/// Pasting text is left alone.
+pub fn attach_paste_upload(entry: &Entry) {
+    let controller = gtk::EventControllerKey::new();
+    // Ahead of the entry's own paste handling, which would drop the image
+    controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let entry_weak = entry.downgrade();
+    controller.connect_key_pressed(move |_, key, _, modifiers| {
+        let is_paste = (key == gdk::Key::v && modifiers == gdk::ModifierType::CONTROL_MASK)
+            || (key == gdk::Key::Insert && modifiers == gdk::ModifierType::SHIFT_MASK);
+        let Some(entry) = entry_weak.upgrade().filter(|_| is_paste) else {
+            return glib::Propagation::Proceed;
+        };
+        let clipboard = entry.clipboard();
+        if !clipboard.formats().contains_type(gdk::Texture::static_type()) {
+            return glib::Propagation::Proceed;
+        }
+        glib::MainContext::default().spawn_local(async move {
+            match clipboard.read_texture_future().await {
+                Ok(Some(texture)) => confirm_upload(&entry, texture),
+                Ok(None) => {}
+                Err(e) => eprintln!("Failed to read pasted image: {}", e),
+            }
+        });
+        glib::Propagation::Stop
+    });
+    entry.add_controller(controller);
+}
+
+fn confirm_upload(entry: &Entry, texture: gdk::Texture) {
+    let settings = get_upload_settings();
+    let preview = gtk::Picture::builder()
+        .paintable(&texture)
+        .can_shrink(true)
+        .height_request(160)
+        .build();
+    let dialog = adw::AlertDialog::builder()
+        .heading("Upload Pasted Image?")
+        .body(format!("The image is uploaded to {} and its link is added to the message. Anyone with the link can see it.", settings.host.label()))
+        .extra_child(&preview)
+        .build();
+    dialog.add_responses(&[("cancel", "Cancel"), ("upload", "Upload")]);
+    dialog.set_response_appearance("upload", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("upload"));
+    dialog.set_close_response("cancel");
+
+    let entry_weak = entry.downgrade();
+    dialog.connect_response(Some("upload"), move |_, _| {
+        let png = texture.save_to_png_bytes().to_vec();
+        let settings = settings.clone();
+        let entry_weak = entry_weak.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let result = adw::gio::spawn_blocking(move || upload_image(&settings, png))
+                .await
+                .unwrap_or_else(|_| Err("Upload panicked".to_string()));
+            let Some(entry) = entry_weak.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(link) => insert_link(&entry, &link),
+                Err(e) => {
+                    eprintln!("Failed to upload pasted image: {}", e);
+                    show_upload_error(&entry, &e);
+                }
+            }
+        });
+    });
+    dialog.present(Some(entry));
+}
+
+fn show_upload_error(entry: &Entry, error: &str) {
+    let dialog = adw::AlertDialog::builder()
+        .heading("Upload Failed")
+        .body(format!("The image wasn't uploaded and nothing was added to the message.\n\n{}", error))
+        .build();
+    dialog.add_response("close", "Close");
+    dialog.set_default_response(Some("close"));
+    dialog.set_close_response("close");
+    dialog.present(Some(entry));
+}
+
+// At the cursor, with spaces so the link doesn't run into neighbouring words
+fn insert_link(entry: &Entry, link: &str) {
+    let text = entry.text();
+    let mut position = entry.position();
+    let byte_index = text.char_indices().nth(position.max(0) as usize).map(|(i, _)| i).unwrap_or(text.len());
+    let (before, after) = text.split_at(byte_index);
+    let mut insertion = String::new();
+    if !before.is_empty() && !before.ends_with(' ') {
+        insertion.push(' ');
+    }
+    insertion.push_str(link);
+    if !after.starts_with(' ') {
+        insertion.push(' ');
+    }
+    entry.insert_text(&insertion, &mut position);
+    entry.set_position(position);
+}
@@ -0,0 +1,225 @@
+// user_card.rs
+
+use adw::prelude::*;
+use adw::{ActionRow, Dialog, EntryRow, HeaderBar, PreferencesGroup, PreferencesPage, ToolbarView};
+use chrono::Local;
+use gtk::{Align, Box as GtkBox, Button, Label, Orientation};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::helix::ban_user;
+use crate::history::{is_history_enabled, user_history};
+use crate::moderation::format_timeout;
+use crate::network::http_client;
+use crate::notes::{add_user_note, get_user_notes, remove_user_note};
+use crate::state::MutexExt;
+
+const MAX_CARD_MESSAGES: usize = 10;
+
+// Everything the user card needs from the owning tab
+#[derive(Clone)]
+pub struct UserCardContext {
+    pub channel: String,
+    pub recent_messages: Arc<Mutex<VecDeque<PrivmsgMessage>>>,
+    pub timeout_durations: Vec<u32>,
+    pub can_moderate: bool,
+}
+
+pub fn show_user_card(parent: &impl IsA<gtk::Widget>, context: &UserCardContext, login: &str) {
+    let login = login.to_lowercase();
+    let recent: Vec<PrivmsgMessage> = context
+        .recent_messages
+        .locked()
+        .iter()
+        .rev()
+        .filter(|msg| msg.sender.login == login)
+        .take(MAX_CARD_MESSAGES)
+        .cloned()
+        .collect();
+    // Chatters who haven't spoken this session may still be in the saved history, which is
+    // read off the main loop
+    if recent.is_empty() && is_history_enabled() {
+        let parent = parent.clone().upcast::<gtk::Widget>();
+        let context = context.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let channel = context.channel.clone();
+            let history_login = login.clone();
+            let recent = adw::gio::spawn_blocking(move || user_history(&channel, &history_login, MAX_CARD_MESSAGES))
+                .await
+                .unwrap_or_default();
+            present_user_card(&parent, &context, &login, recent, true);
+        });
+        return;
+    }
+    present_user_card(parent, context, &login, recent, false);
+}
+
+fn present_user_card(
+    parent: &impl IsA<gtk::Widget>,
+    context: &UserCardContext,
+    login: &str,
+    recent: Vec<PrivmsgMessage>,
+    from_history: bool,
+) {
+    let display_name = recent
+        .first()
+        .map(|msg| msg.sender.name.clone())
+        .unwrap_or_else(|| login.to_string());
+
+    let dialog = Dialog::builder()
+        .title(&display_name)
+        .content_width(420)
+        .content_height(520)
+        .build();
+
+    let page = PreferencesPage::new();
+
+    let messages_group = PreferencesGroup::builder()
+        .title("Recent Messages")
+        .description(if recent.is_empty() {
+            "Nothing from this chatter in the current session"
+        } else if from_history {
+            "From saved history, newest first"
+        } else {
+            "From the current session, newest first"
+        })
+        .build();
+    for msg in &recent {
+        let row = ActionRow::builder()
+            .title(glib::markup_escape_text(&msg.message_text).as_str())
+            .subtitle(
+                msg.server_timestamp
+                    .with_timezone(&Local)
+                    .format(if from_history { "%b %-d, %-I:%M %p" } else { "%-I:%M:%S %p" })
+                    .to_string(),
+            )
+            .build();
+        messages_group.add(&row);
+    }
+    page.add(&messages_group);
+
+    let target = recent
+        .first()
+        .map(|msg| (msg.channel_id.clone(), msg.sender.id.clone()));
+    if let (true, Some((broadcaster_id, user_id))) = (context.can_moderate, target) {
+        page.add(&build_moderation_group(
+            &context.timeout_durations,
+            &broadcaster_id,
+            &user_id,
+        ));
+    }
+
+    let notes_group = PreferencesGroup::builder()
+        .title("Moderation Notes")
+        .description(format!("Stored locally for #{}", context.channel))
+        .build();
+    let add_note_row = EntryRow::builder()
+        .title("Add note")
+        .show_apply_button(true)
+        .build();
+    notes_group.add(&add_note_row);
+    page.add(&notes_group);
+
+    let note_rows: Rc<RefCell<Vec<ActionRow>>> = Rc::new(RefCell::new(Vec::new()));
+    populate_notes(&notes_group, &note_rows, &context.channel, login);
+
+    let channel = context.channel.clone();
+    let login_for_notes = login.to_string();
+    add_note_row.connect_apply(move |row| {
+        add_user_note(&channel, &login_for_notes, &row.text());
+        row.set_text("");
+        populate_notes(&notes_group, &note_rows, &channel, &login_for_notes);
+    });
+
+    let toolbar_view = ToolbarView::new();
+    toolbar_view.add_top_bar(&HeaderBar::new());
+    toolbar_view.set_content(Some(&page));
+    dialog.set_child(Some(&toolbar_view));
+    dialog.present(Some(parent));
+}
+
+// Rebuilds the note rows below the "Add note" entry
+fn populate_notes(
+    notes_group: &PreferencesGroup,
+    note_rows: &Rc<RefCell<Vec<ActionRow>>>,
+    channel: &str,
+    login: &str,
+) {
+    for row in note_rows.borrow_mut().drain(..) {
+        notes_group.remove(&row);
+    }
+    for (index, note) in get_user_notes(channel, login).into_iter().enumerate() {
+        let row = ActionRow::builder()
+            .title(glib::markup_escape_text(&note.text).as_str())
+            .subtitle(note.created.as_str())
+            .build();
+        let remove_button = Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text("Remove note")
+            .valign(gtk::Align::Center)
+            .build();
+        remove_button.add_css_class("flat");
+
+        let notes_group = notes_group.clone();
+        let note_rows_clone = note_rows.clone();
+        let channel = channel.to_string();
+        let login = login.to_string();
+        remove_button.connect_clicked(move |_| {
+            remove_user_note(&channel, &login, index);
+            populate_notes(&notes_group, &note_rows_clone, &channel, &login);
+        });
+        row.add_suffix(&remove_button);
+        notes_group.add(&row);
+        note_rows.borrow_mut().push(row);
+    }
+}
+
+// One-click timeouts, sent through Helix
+fn build_moderation_group(durations: &[u32], broadcaster_id: &str, user_id: &str) -> PreferencesGroup {
+    let group = PreferencesGroup::builder()
+        .title("Moderation")
+        .build();
+
+    let button_box = GtkBox::new(Orientation::Horizontal, 6);
+    button_box.set_halign(Align::Center);
+    let status_label = Label::new(None);
+    status_label.add_css_class("dim-label");
+    status_label.set_margin_top(6);
+
+    for &duration in durations {
+        let label = format_timeout(duration);
+        let button = Button::with_label(&label);
+        let broadcaster_id = broadcaster_id.to_string();
+        let user_id = user_id.to_string();
+        let status_label = status_label.clone();
+        button.connect_clicked(move |_| {
+            let broadcaster_id = broadcaster_id.clone();
+            let user_id = user_id.clone();
+            let status_label = status_label.clone();
+            let label = label.clone();
+            status_label.set_text("Sending…");
+            glib::MainContext::default().spawn_local(async move {
+                let result = adw::gio::spawn_blocking(move || {
+                    ban_user(&http_client(), &broadcaster_id, &user_id, Some(duration), "")
+                        .map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|_| Err("Moderation request panicked".to_string()));
+                match result {
+                    Ok(()) => status_label.set_text(&format!("Timed out for {}", label)),
+                    Err(e) => status_label.set_text(&e),
+                }
+            });
+        });
+        button_box.append(&button);
+    }
+
+    let content = GtkBox::new(Orientation::Vertical, 0);
+    content.append(&button_box);
+    content.append(&status_label);
+    group.add(&content);
+    group
+}
@@ -0,0 +1,524 @@
+// vod.rs
+
+use adw::prelude::*;
+use chrono::DateTime;
+use gtk::{Box as GtkBox, Button, DropDown, Label, Orientation, Revealer, Scale};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error as StdError;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use twitch_irc::message::{IRCMessage, PrivmsgMessage};
+
+use crate::message_queue::MessageQueue;
+use crate::network::http_client;
+use crate::state::MutexExt;
+
+// Public web client id; the VOD comments API is only served through Twitch's GraphQL endpoint
+const GQL_CLIENT_ID: &str = "kimne78kx3ncx6brgo4mv6wki5h1ko";
+const GQL_URL: &str = "https://gql.twitch.tv/gql";
+const COMMENTS_QUERY_HASH: &str = "b70a3591ff0f4e0313d126c6a1502d79a1c02baebb288227c582044aa76adf6a";
+const TICK: Duration = Duration::from_millis(100);
+const PREFETCH_THRESHOLD: usize = 20; // Fetch the next page once fewer comments are queued
+
+pub const SPEEDS: [f64; 5] = [0.5, 1.0, 1.5, 2.0, 4.0];
+
+static VOD_URL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)(?:twitch\.tv/(?:\w+/)?videos?/|^v)(\d+)").unwrap());
+
+/// Accepts a VOD URL, "v123456" or, when `allow_bare` is set, a plain numeric id
+pub fn parse_vod_id(input: &str, allow_bare: bool) -> Option<String> {
+    let input = input.trim();
+    if allow_bare && !input.is_empty() && input.chars().all(|c| c.is_ascii_digit()) {
+        return Some(input.to_string());
+    }
+    VOD_URL_REGEX
+        .captures(input)
+        .map(|captures| captures[1].to_string())
+}
+
+#[derive(Debug, Clone)]
+pub struct VodInfo {
+    pub id: String,
+    pub channel_id: String,
+    pub channel_login: String,
+    pub title: String,
+    pub length_secs: u32,
+}
+
+// Shared between the replay thread and the tab's replay bar
+#[derive(Debug)]
+pub struct ReplayControl {
+    pub position_secs: f64,
+    pub length_secs: u32,
+    pub speed: f64,
+    pub paused: bool,
+    pub seek_to: Option<u32>,
+    pub stopped: bool,
+}
+
+impl ReplayControl {
+    pub fn new(length_secs: u32) -> Self {
+        Self {
+            position_secs: 0.0,
+            length_secs,
+            speed: 1.0,
+            paused: false,
+            seek_to: Some(0),
+            stopped: false,
+        }
+    }
+}
+
+// --- GraphQL ---
+
+#[derive(Debug, Deserialize)]
+struct GqlResponse<T> {
+    data: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VideoData {
+    video: Option<GqlVideo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlVideo {
+    title: Option<String>,
+    length_seconds: Option<u32>,
+    owner: Option<GqlUser>,
+    comments: Option<GqlComments>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlUser {
+    id: String,
+    login: String,
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlComments {
+    edges: Vec<GqlCommentEdge>,
+    page_info: GqlPageInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlPageInfo {
+    has_next_page: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlCommentEdge {
+    cursor: Option<String>,
+    node: GqlComment,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlComment {
+    id: String,
+    commenter: Option<GqlUser>,
+    content_offset_seconds: u32,
+    created_at: String,
+    message: GqlCommentMessage,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlCommentMessage {
+    fragments: Vec<GqlFragment>,
+    user_badges: Vec<GqlBadge>,
+    user_color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GqlFragment {
+    text: String,
+    emote: Option<GqlEmote>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlEmote {
+    emote_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GqlBadge {
+    set_id: String,
+    version: String,
+}
+
+fn gql_request(client: &Client, body: serde_json::Value) -> Result<VideoData, Box<dyn StdError + Send + Sync>> {
+    let response = client
+        .post(GQL_URL)
+        .header("Client-Id", GQL_CLIENT_ID)
+        .json(&body)
+        .send()?;
+    if !response.status().is_success() {
+        return Err(format!("Twitch GraphQL request failed with status {}", response.status()).into());
+    }
+    let parsed: GqlResponse<VideoData> = response.json()?;
+    parsed.data.ok_or_else(|| "Twitch GraphQL returned no data".into())
+}
+
+/// Owner and length of a VOD. Blocking.
+pub fn fetch_vod_info(client: &Client, video_id: &str) -> Result<VodInfo, Box<dyn StdError + Send + Sync>> {
+    let query = "query($id: ID) { video(id: $id) { title lengthSeconds owner { id login displayName } } }";
+    let data = gql_request(
+        client,
+        serde_json::json!({ "query": query, "variables": { "id": video_id } }),
+    )?;
+    let video = data.video.ok_or("VOD not found")?;
+    let owner = video.owner.ok_or("VOD has no owner")?;
+    Ok(VodInfo {
+        id: video_id.to_string(),
+        channel_id: owner.id,
+        channel_login: owner.login,
+        title: video.title.unwrap_or_default(),
+        length_secs: video.length_seconds.unwrap_or(0),
+    })
+}
+
+enum CommentPage {
+    Offset(u32),
+    Cursor(String),
+}
+
+// One page of comments as (offset seconds, message), plus the cursor of the next page
+fn fetch_comments(
+    client: &Client,
+    info: &VodInfo,
+    page: &CommentPage,
+) -> Result<(Vec<(u32, PrivmsgMessage)>, Option<String>), Box<dyn StdError + Send + Sync>> {
+    let variables = match page {
+        CommentPage::Offset(offset) => serde_json::json!({ "videoID": info.id, "contentOffsetSeconds": offset }),
+        CommentPage::Cursor(cursor) => serde_json::json!({ "videoID": info.id, "cursor": cursor }),
+    };
+    let data = gql_request(
+        client,
+        serde_json::json!({
+            "operationName": "VideoCommentsByOffsetOrCursor",
+            "variables": variables,
+            "extensions": { "persistedQuery": { "version": 1, "sha256Hash": COMMENTS_QUERY_HASH } },
+        }),
+    )?;
+    let comments = data
+        .video
+        .and_then(|video| video.comments)
+        .ok_or("VOD has no comments")?;
+    let next_cursor = if comments.page_info.has_next_page {
+        comments.edges.last().and_then(|edge| edge.cursor.clone())
+    } else {
+        None
+    };
+    let messages = comments
+        .edges
+        .into_iter()
+        .filter_map(|edge| {
+            let offset = edge.node.content_offset_seconds;
+            comment_to_privmsg(edge.node, info).map(|msg| (offset, msg))
+        })
+        .collect();
+    Ok((messages, next_cursor))
+}
+
+// Escapes an IRCv3 tag value
+pub fn escape_tag(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\:")
+        .replace(' ', "\\s")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+// Rebuilds the comment as the PRIVMSG it originally was, so it goes through the same
+// render pipeline as live chat
+fn comment_to_privmsg(comment: GqlComment, info: &VodInfo) -> Option<PrivmsgMessage> {
+    let commenter = comment.commenter?;
+    let mut text = String::new();
+    let mut emotes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for fragment in &comment.message.fragments {
+        let start = text.chars().count();
+        text.push_str(&fragment.text);
+        if let Some(emote) = &fragment.emote {
+            let end = text.chars().count().saturating_sub(1);
+            emotes
+                .entry(emote.emote_id.clone())
+                .or_default()
+                .push(format!("{}-{}", start, end));
+        }
+    }
+    let text = text.replace(['\r', '\n'], " ");
+    if text.trim().is_empty() {
+        return None;
+    }
+    let emotes_tag = emotes
+        .iter()
+        .map(|(id, ranges)| format!("{}:{}", id, ranges.join(",")))
+        .collect::<Vec<_>>()
+        .join("/");
+    let badges_tag = comment
+        .message
+        .user_badges
+        .iter()
+        .filter(|badge| !badge.set_id.is_empty())
+        .map(|badge| format!("{}/{}", badge.set_id, badge.version))
+        .collect::<Vec<_>>()
+        .join(",");
+    let sent_ts = DateTime::parse_from_rfc3339(&comment.created_at)
+        .map(|time| time.timestamp_millis())
+        .unwrap_or(0);
+    let display_name = commenter.display_name.unwrap_or_else(|| commenter.login.clone());
+
+    let raw = format!(
+        "@badge-info=;badges={};color={};display-name={};emotes={};id={};room-id={};tmi-sent-ts={};user-id={} :{login}!{login}@{login}.tmi.twitch.tv PRIVMSG #{} :{}",
+        escape_tag(&badges_tag),
+        escape_tag(comment.message.user_color.as_deref().unwrap_or("")),
+        escape_tag(&display_name),
+        escape_tag(&emotes_tag),
+        escape_tag(&comment.id),
+        escape_tag(&info.channel_id),
+        sent_ts,
+        escape_tag(&commenter.id),
+        info.channel_login,
+        text,
+        login = commenter.login,
+    );
+    let irc = IRCMessage::parse(&raw)
+        .map_err(|e| eprintln!("Failed to parse VOD comment {}: {}", comment.id, e))
+        .ok()?;
+    PrivmsgMessage::try_from(irc)
+        .map_err(|e| eprintln!("Failed to convert VOD comment {}: {}", comment.id, e))
+        .ok()
+}
+
+// --- Replay ---
+
+/// Replays the VOD's chat into `queue` in (scaled) real time until `control` is stopped
+pub fn start_replay(
+    info: VodInfo,
+    queue: Arc<MessageQueue>,
+    control: Arc<Mutex<ReplayControl>>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let client = http_client();
+        let mut pending: VecDeque<(u32, PrivmsgMessage)> = VecDeque::new();
+        let mut cursor: Option<String> = None;
+        let mut last_tick = Instant::now();
+        loop {
+            thread::sleep(TICK);
+            let elapsed = last_tick.elapsed().as_secs_f64();
+            last_tick = Instant::now();
+
+            let (position, seek) = {
+                let mut control = control.locked();
+                if control.stopped {
+                    break;
+                }
+                let seek = control.seek_to.take();
+                if let Some(seek) = seek {
+                    control.position_secs = seek as f64;
+                } else if !control.paused {
+                    control.position_secs += elapsed * control.speed;
+                }
+                (control.position_secs, seek)
+            };
+
+            let page = match seek {
+                Some(offset) => {
+                    pending.clear();
+                    Some(CommentPage::Offset(offset))
+                }
+                None if pending.len() < PREFETCH_THRESHOLD => cursor.take().map(CommentPage::Cursor),
+                None => None,
+            };
+            if let Some(page) = page {
+                match fetch_comments(&client, &info, &page) {
+                    Ok((comments, next_cursor)) => {
+                        // Comments before a seek target were already passed over
+                        let skip_before = seek.unwrap_or(0);
+                        pending.extend(comments.into_iter().filter(|(offset, _)| *offset >= skip_before));
+                        cursor = next_cursor;
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to fetch VOD comments: {}", e);
+                        if let CommentPage::Cursor(previous) = page {
+                            cursor = Some(previous); // Retry on the next tick
+                        }
+                    }
+                }
+            }
+
+            while pending.front().is_some_and(|(offset, _)| *offset as f64 <= position) {
+                let (_, msg) = pending.pop_front().unwrap();
+                queue.push(msg);
+            }
+
+            if pending.is_empty() && cursor.is_none() {
+                let mut control = control.locked();
+                if control.seek_to.is_none() && control.position_secs >= control.length_secs as f64 {
+                    control.paused = true;
+                    control.position_secs = control.length_secs as f64;
+                }
+            }
+        }
+    })
+}
+
+// "1:02:03" or "2:03"
+pub fn format_position(secs: u32) -> String {
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+// --- Replay Bar ---
+
+// Playback controls shown under the channel entry while a tab replays a VOD
+#[derive(Clone)]
+pub struct ReplayBar {
+    pub revealer: Revealer,
+    play_button: Button,
+    scale: Scale,
+    position_label: Label,
+    speed_dropdown: DropDown,
+    last_seek: Rc<Cell<Option<Instant>>>,
+}
+
+pub fn build_replay_bar(replay: &Arc<Mutex<Option<Arc<Mutex<ReplayControl>>>>>) -> ReplayBar {
+    let bar = GtkBox::new(Orientation::Horizontal, 6);
+    bar.set_margin_bottom(6);
+    bar.set_margin_start(6);
+    bar.set_margin_end(6);
+
+    let play_button = Button::builder()
+        .icon_name("media-playback-pause-symbolic")
+        .tooltip_text("Pause")
+        .build();
+    play_button.add_css_class("flat");
+    let scale = Scale::with_range(Orientation::Horizontal, 0.0, 1.0, 1.0);
+    scale.set_hexpand(true);
+    scale.set_draw_value(false);
+    let position_label = Label::new(Some("0:00"));
+    position_label.add_css_class("numeric");
+    position_label.add_css_class("dim-label");
+    let speed_labels: Vec<String> = SPEEDS.iter().map(|speed| format!("{}×", speed)).collect();
+    let speed_labels: Vec<&str> = speed_labels.iter().map(String::as_str).collect();
+    let speed_dropdown = DropDown::from_strings(&speed_labels);
+    speed_dropdown.set_selected(1);
+    speed_dropdown.set_tooltip_text(Some("Playback speed"));
+
+    bar.append(&play_button);
+    bar.append(&scale);
+    bar.append(&position_label);
+    bar.append(&speed_dropdown);
+
+    let revealer = Revealer::builder()
+        .transition_type(gtk::RevealerTransitionType::SlideDown)
+        .child(&bar)
+        .build();
+
+    let replay_for_play = replay.clone();
+    play_button.connect_clicked(move |_| {
+        if let Some(control) = replay_for_play.locked().as_ref() {
+            let mut control = control.locked();
+            if control.paused && control.position_secs >= control.length_secs as f64 {
+                control.seek_to = Some(0); // Start over once finished
+            }
+            control.paused = !control.paused;
+        }
+    });
+
+    let last_seek: Rc<Cell<Option<Instant>>> = Rc::new(Cell::new(None));
+    let replay_for_seek = replay.clone();
+    let last_seek_for_scale = last_seek.clone();
+    scale.connect_change_value(move |_, _, value| {
+        last_seek_for_scale.set(Some(Instant::now()));
+        if let Some(control) = replay_for_seek.locked().as_ref() {
+            control.locked().seek_to = Some(value.max(0.0) as u32);
+        }
+        glib::Propagation::Proceed
+    });
+
+    let replay_for_speed = replay.clone();
+    speed_dropdown.connect_selected_notify(move |dropdown| {
+        let speed = SPEEDS.get(dropdown.selected() as usize).copied().unwrap_or(1.0);
+        if let Some(control) = replay_for_speed.locked().as_ref() {
+            control.locked().speed = speed;
+        }
+    });
+
+    let replay_bar = ReplayBar {
+        revealer,
+        play_button,
+        scale,
+        position_label,
+        speed_dropdown,
+        last_seek,
+    };
+
+    // Follow playback while the bar is shown
+    let replay_for_refresh = replay.clone();
+    let bar_weak = replay_bar.revealer.downgrade();
+    let replay_bar_refresh = replay_bar.clone();
+    glib::timeout_add_local(Duration::from_millis(500), move || {
+        if bar_weak.upgrade().is_none() {
+            return glib::ControlFlow::Break;
+        }
+        if replay_bar_refresh.revealer.reveals_child() {
+            if let Some(control) = replay_for_refresh.locked().as_ref() {
+                replay_bar_refresh.update(&control.locked());
+            }
+        }
+        glib::ControlFlow::Continue
+    });
+
+    replay_bar
+}
+
+impl ReplayBar {
+    fn update(&self, control: &ReplayControl) {
+        let length = control.length_secs.max(1) as f64;
+        let position = control.position_secs.min(length);
+        // Don't fight the user while they drag the slider
+        let dragging = self.last_seek.get().is_some_and(|time| time.elapsed() < Duration::from_secs(1));
+        if !dragging {
+            self.scale.set_range(0.0, length);
+            self.scale.set_value(position);
+        }
+        self.position_label.set_text(&format!(
+            "{} / {}",
+            format_position(position as u32),
+            format_position(control.length_secs)
+        ));
+        let (icon, tooltip) = if control.paused {
+            ("media-playback-start-symbolic", "Play")
+        } else {
+            ("media-playback-pause-symbolic", "Pause")
+        };
+        self.play_button.set_icon_name(icon);
+        self.play_button.set_tooltip_text(Some(tooltip));
+    }
+
+    pub fn reset(&self) {
+        self.speed_dropdown.set_selected(1);
+        self.scale.set_value(0.0);
+        self.position_label.set_text("0:00");
+    }
+}
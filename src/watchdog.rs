@@ -0,0 +1,131 @@
+// watchdog.rs
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::sync::Mutex;
+
+use crate::state::MutexExt;
+
+// WebKit names its renderer "WebKitWebProcess", which /proc truncates to 15 characters
+const WEB_PROCESS_COMM: &str = "WebKitWebProces";
+pub const WATCHDOG_INTERVAL_SECS: u32 = 10;
+
+// Web process pids already attributed to a tab
+static CLAIMED: Lazy<Mutex<HashSet<i32>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+pub enum WatchdogAction {
+    #[default]
+    Ask,
+    Reload,
+    Hibernate, // Background tabs drop their web process until selected again
+}
+
+impl WatchdogAction {
+    pub const ALL: [WatchdogAction; 3] = [WatchdogAction::Ask, WatchdogAction::Reload, WatchdogAction::Hibernate];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WatchdogAction::Ask => "Ask",
+            WatchdogAction::Reload => "Reload Automatically",
+            WatchdogAction::Hibernate => "Hibernate Background Tabs",
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        Self::ALL.iter().position(|a| *a == self).unwrap_or(0) as u32
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        Self::ALL.get(index as usize).copied().unwrap_or_default()
+    }
+}
+
+// Stored under [watchdog] in favorites.toml
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    pub threshold_mb: u32, // Resident memory of a single tab's web process
+    pub action: WatchdogAction,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_mb: 1024,
+            action: WatchdogAction::Ask,
+        }
+    }
+}
+
+struct ProcessStat {
+    comm: String,
+    ppid: i32,
+    start_time: u64,
+}
+
+fn read_stat(pid: i32) -> Option<ProcessStat> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // comm may contain spaces and parentheses, so split around the last ')'
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let fields: Vec<&str> = stat[close + 1..].split_whitespace().collect();
+    Some(ProcessStat {
+        comm: stat[open + 1..close].to_string(),
+        ppid: fields.get(1)?.parse().ok()?,
+        start_time: fields.get(19)?.parse().ok()?,
+    })
+}
+
+/// Web processes spawned by this instance, oldest first
+fn web_processes() -> Vec<i32> {
+    let own_pid = std::process::id() as i32;
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut processes: Vec<(u64, i32)> = entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<i32>().ok())
+        .filter_map(|pid| {
+            let stat = read_stat(pid)?;
+            (stat.ppid == own_pid && stat.comm.starts_with(WEB_PROCESS_COMM)).then_some((stat.start_time, pid))
+        })
+        .collect();
+    processes.sort_unstable();
+    processes.into_iter().map(|(_, pid)| pid).collect()
+}
+
+/// Attributes a web process to a tab whose page was just committed. Keeps `current`
+/// while it's still running, otherwise takes the newest process no other tab claimed.
+pub fn claim_web_process(current: Option<i32>) -> Option<i32> {
+    let running = web_processes();
+    let mut claimed = CLAIMED.locked();
+    claimed.retain(|pid| running.contains(pid));
+    if let Some(pid) = current.filter(|pid| running.contains(pid)) {
+        return Some(pid);
+    }
+    let pid = running.into_iter().rev().find(|pid| !claimed.contains(pid))?;
+    claimed.insert(pid);
+    Some(pid)
+}
+
+pub fn release_web_process(pid: i32) {
+    CLAIMED.locked().remove(&pid);
+}
+
+/// Resident set size in megabytes, None once the process is gone
+pub fn resident_mb(pid: i32) -> Option<u64> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let kb: u64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kb / 1024)
+}